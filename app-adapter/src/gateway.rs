@@ -1,12 +1,18 @@
+use crate::gateway::in_memory::InMemoryLine;
 use derive_new::new;
-use reqwest::Client;
+use reqwest::{header, Client, Response};
 use std::marker::PhantomData;
-use thiserror::Error;
 
+pub use domain::gateway::GatewayError;
+
+pub mod in_memory;
+pub mod rich_menu;
 pub mod send_message;
 pub mod user_auth;
 
 pub const LINE_MESSAGE_NUMBER_LIMIT: usize = 5;
+// リッチメニューの一括リンク・解除で一度に指定できるユーザー数の上限
+pub const LINE_RICH_MENU_BULK_LIMIT: usize = 500;
 
 #[derive(new)]
 pub struct HttpClientRepositoryImpl<T> {
@@ -14,10 +20,44 @@ pub struct HttpClientRepositoryImpl<T> {
     _marker: PhantomData<T>,
 }
 
-#[derive(Debug, Error)]
-pub enum GatewayError {
-    #[error("Failed to convert response {0} to {1}")]
-    FailedConvertResponse(String, String),
-    #[error(transparent)]
-    Other(#[from] anyhow::Error),
+// LINEのAPIを呼ばずに、送ったメッセージなどをメモリに残す。ローカルでの動作確認とテスト用
+#[derive(new)]
+pub struct InMemoryGatewayImpl<T> {
+    pub line: InMemoryLine,
+    _marker: PhantomData<T>,
+}
+
+/// リクエストを送れなかったときのエラー。タイムアウトやつながらなかったときはリトライできる
+pub(crate) fn request_error(e: reqwest::Error) -> GatewayError {
+    if e.is_timeout() || e.is_connect() {
+        GatewayError::Unavailable(e.to_string())
+    } else if let Some(status) = e.status() {
+        GatewayError::from_status(status.as_u16(), e.to_string())
+    } else {
+        GatewayError::Unexpected(e.to_string())
+    }
+}
+
+/// パスワードのハッシュ化など、APIを呼ぶ前の処理で失敗したときのエラー
+pub(crate) fn unexpected_error(e: anyhow::Error) -> GatewayError {
+    GatewayError::Unexpected(format!("{:#}", e))
+}
+
+/// 成功しなかったレスポンスを、ステータスに応じたGatewayErrorにする
+/// 429のときは、Retry-Afterがあれば待つ秒数を持たせる
+pub(crate) async fn error_response(res: Response) -> GatewayError {
+    let status = res.status().as_u16();
+    let retry_after = res
+        .headers()
+        .get(header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    let body = match res.text().await {
+        Ok(body) => body,
+        Err(e) => return request_error(e),
+    };
+    match GatewayError::from_status(status, body) {
+        GatewayError::RateLimited(_) => GatewayError::RateLimited(retry_after),
+        err => err,
+    }
 }
@@ -0,0 +1,97 @@
+use derive_new::new;
+use domain::model::{
+    line_user::LineUserProfile,
+    message::send_message::NewSendMessages,
+    rich_menu::{RichMenu, RichMenuAlias, RichMenuAliasId, RichMenuId, RichMenuImage},
+    user_auth::LineId,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::gateway::GatewayError;
+
+pub mod rich_menu;
+pub mod send_message;
+pub mod user_auth;
+
+/// LINEのAPIの代わりに、送ったメッセージやリッチメニューをメモリに持つ
+/// テストでは送ったメッセージを取り出して、LINEに何を送ったかを確かめる
+#[derive(Clone, Default)]
+pub struct InMemoryLine(Arc<Mutex<InMemoryLineState>>);
+
+impl InMemoryLine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// LINEから取得できるプロフィールを登録する
+    /// 登録していないユーザーのプロフィールは、LINEのユーザーIDを表示名にして返す
+    pub fn add_line_user_profile(&self, source: LineUserProfile) -> anyhow::Result<()> {
+        let mut state = self.lock()?;
+        state
+            .line_user_profiles
+            .insert(source.auth_id.0.clone(), source);
+        Ok(())
+    }
+
+    /// これまでに送ったメッセージを、送った順に返す
+    pub fn sent_messages(&self) -> anyhow::Result<Vec<InMemorySentMessages>> {
+        Ok(self.lock()?.sent_messages.clone())
+    }
+
+    /// 既読にしたユーザーを、既読にした順に返す
+    pub fn read_line_ids(&self) -> anyhow::Result<Vec<LineId>> {
+        Ok(self.lock()?.read_line_ids.clone())
+    }
+
+    /// ユーザーに表示されるリッチメニュー。個別にリンクしていないときはデフォルトのリッチメニューになる
+    pub fn rich_menu_id_of(&self, line_id: &LineId) -> anyhow::Result<Option<RichMenuId>> {
+        let state = self.lock()?;
+        Ok(state
+            .user_rich_menu_ids
+            .get(&line_id.0)
+            .or(state.default_rich_menu_id.as_ref())
+            .cloned())
+    }
+
+    pub(crate) fn lock(&self) -> Result<MutexGuard<'_, InMemoryLineState>, GatewayError> {
+        self.0
+            .lock()
+            .map_err(|e| GatewayError::Unexpected(e.to_string()))
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct InMemoryLineState {
+    // LINEのユーザーIDがキー
+    pub line_user_profiles: HashMap<String, LineUserProfile>,
+    pub sent_messages: Vec<InMemorySentMessages>,
+    // LINEのメッセージIDは数字の文字列なので、送るたびに数える
+    pub last_message_id: u64,
+    pub read_line_ids: Vec<LineId>,
+    pub rich_menus: Vec<RichMenu>,
+    // リッチメニューのIDがキー
+    pub rich_menu_images: HashMap<String, RichMenuImage>,
+    pub default_rich_menu_id: Option<RichMenuId>,
+    // LINEのユーザーIDがキー
+    pub user_rich_menu_ids: HashMap<String, RichMenuId>,
+    pub rich_menu_aliases: HashMap<RichMenuAliasId, RichMenuAlias>,
+}
+
+impl InMemoryLineState {
+    pub fn rich_menu_exists(&self, rich_menu_id: &RichMenuId) -> bool {
+        self.rich_menus.iter().any(|r| &r.id == rich_menu_id)
+    }
+}
+
+/// LINEに送ったメッセージと、その宛先
+#[derive(new, Clone, Debug)]
+pub struct InMemorySentMessages {
+    pub to: LineId,
+    pub messages: NewSendMessages,
+}
+
+// LINEのAPIと同じ形のエラーにする
+pub(crate) fn line_error(status: u16, message: &str) -> GatewayError {
+    GatewayError::from_status(status, format!(r#"{{"message":"{}"}}"#, message))
+}
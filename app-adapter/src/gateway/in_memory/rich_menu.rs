@@ -0,0 +1,206 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::gateway::in_memory::{line_error, InMemoryLineState};
+use crate::gateway::{GatewayError, InMemoryGatewayImpl};
+use domain::{
+    gateway::rich_menu::RichMenuGateway,
+    model::{
+        rich_menu::{
+            NewRichMenu, RichMenu, RichMenuAlias, RichMenuAliasId, RichMenuId, RichMenuImage,
+        },
+        user_auth::{LineAuthToken, LineId},
+    },
+};
+
+// LINEのAPIと同じく、存在しないリッチメニューは404、画像のないリッチメニューの利用は400にする
+#[async_trait]
+impl RichMenuGateway for InMemoryGatewayImpl<RichMenu> {
+    async fn create_rich_menu(
+        &self,
+        _auth_token: LineAuthToken,
+        source: NewRichMenu,
+    ) -> Result<RichMenu, GatewayError> {
+        let rich_menu = source.into_rich_menu(RichMenuId::new(format!(
+            "richmenu-{}",
+            Uuid::new_v4().simple()
+        )));
+        self.line.lock()?.rich_menus.push(rich_menu.clone());
+        Ok(rich_menu)
+    }
+
+    /// 画像は一度しかアップロードできない
+    async fn upload_rich_menu_image(
+        &self,
+        _auth_token: LineAuthToken,
+        rich_menu_id: RichMenuId,
+        image: RichMenuImage,
+    ) -> Result<(), GatewayError> {
+        let mut state = self.line.lock()?;
+        if !state.rich_menu_exists(&rich_menu_id) {
+            return Err(line_error(404, "Not found"));
+        }
+        if state.rich_menu_images.contains_key(&rich_menu_id.0) {
+            return Err(line_error(
+                400,
+                "An image has already been uploaded to the richmenu",
+            ));
+        }
+        state.rich_menu_images.insert(rich_menu_id.0, image);
+        Ok(())
+    }
+
+    async fn get_rich_menu_list(
+        &self,
+        _auth_token: LineAuthToken,
+    ) -> Result<Vec<RichMenu>, GatewayError> {
+        Ok(self.line.lock()?.rich_menus.clone())
+    }
+
+    async fn delete_rich_menu(
+        &self,
+        _auth_token: LineAuthToken,
+        rich_menu_id: RichMenuId,
+    ) -> Result<(), GatewayError> {
+        let mut state = self.line.lock()?;
+        if !state.rich_menu_exists(&rich_menu_id) {
+            return Err(line_error(404, "Not found"));
+        }
+        state.rich_menus.retain(|r| r.id != rich_menu_id);
+        state.rich_menu_images.remove(&rich_menu_id.0);
+        // 削除したリッチメニューはユーザーにも表示されなくなる
+        if state.default_rich_menu_id.as_ref() == Some(&rich_menu_id) {
+            state.default_rich_menu_id = None;
+        }
+        state.user_rich_menu_ids.retain(|_, id| id != &rich_menu_id);
+        Ok(())
+    }
+
+    async fn set_default_rich_menu(
+        &self,
+        _auth_token: LineAuthToken,
+        rich_menu_id: RichMenuId,
+    ) -> Result<(), GatewayError> {
+        let mut state = self.line.lock()?;
+        check_rich_menu_is_ready(&state, &rich_menu_id)?;
+        state.default_rich_menu_id = Some(rich_menu_id);
+        Ok(())
+    }
+
+    async fn link_rich_menu_to_user(
+        &self,
+        auth_token: LineAuthToken,
+        line_id: LineId,
+        rich_menu_id: RichMenuId,
+    ) -> Result<(), GatewayError> {
+        self.link_rich_menu_to_users(auth_token, vec![line_id], rich_menu_id)
+            .await
+    }
+
+    async fn link_rich_menu_to_users(
+        &self,
+        _auth_token: LineAuthToken,
+        line_ids: Vec<LineId>,
+        rich_menu_id: RichMenuId,
+    ) -> Result<(), GatewayError> {
+        let mut state = self.line.lock()?;
+        check_rich_menu_is_ready(&state, &rich_menu_id)?;
+        for line_id in line_ids {
+            state
+                .user_rich_menu_ids
+                .insert(line_id.0, rich_menu_id.clone());
+        }
+        Ok(())
+    }
+
+    async fn unlink_rich_menu_from_user(
+        &self,
+        auth_token: LineAuthToken,
+        line_id: LineId,
+    ) -> Result<(), GatewayError> {
+        self.unlink_rich_menu_from_users(auth_token, vec![line_id])
+            .await
+    }
+
+    async fn unlink_rich_menu_from_users(
+        &self,
+        _auth_token: LineAuthToken,
+        line_ids: Vec<LineId>,
+    ) -> Result<(), GatewayError> {
+        let mut state = self.line.lock()?;
+        for line_id in line_ids {
+            state.user_rich_menu_ids.remove(&line_id.0);
+        }
+        Ok(())
+    }
+
+    async fn get_rich_menu_alias(
+        &self,
+        _auth_token: LineAuthToken,
+        alias_id: RichMenuAliasId,
+    ) -> Result<Option<RichMenuAlias>, GatewayError> {
+        Ok(self.line.lock()?.rich_menu_aliases.get(&alias_id).cloned())
+    }
+
+    async fn create_rich_menu_alias(
+        &self,
+        _auth_token: LineAuthToken,
+        source: RichMenuAlias,
+    ) -> Result<(), GatewayError> {
+        let mut state = self.line.lock()?;
+        if state.rich_menu_aliases.contains_key(&source.alias_id) {
+            return Err(line_error(400, "conflict richmenu alias id"));
+        }
+        check_rich_menu_is_ready(&state, &source.rich_menu_id)?;
+        state
+            .rich_menu_aliases
+            .insert(source.alias_id.clone(), source);
+        Ok(())
+    }
+
+    async fn update_rich_menu_alias(
+        &self,
+        _auth_token: LineAuthToken,
+        source: RichMenuAlias,
+    ) -> Result<(), GatewayError> {
+        let mut state = self.line.lock()?;
+        if !state.rich_menu_aliases.contains_key(&source.alias_id) {
+            return Err(line_error(404, "richmenu alias not found"));
+        }
+        check_rich_menu_is_ready(&state, &source.rich_menu_id)?;
+        state
+            .rich_menu_aliases
+            .insert(source.alias_id.clone(), source);
+        Ok(())
+    }
+
+    async fn delete_rich_menu_alias(
+        &self,
+        _auth_token: LineAuthToken,
+        alias_id: RichMenuAliasId,
+    ) -> Result<(), GatewayError> {
+        self.line
+            .lock()?
+            .rich_menu_aliases
+            .remove(&alias_id)
+            .ok_or(line_error(404, "richmenu alias not found"))?;
+        Ok(())
+    }
+}
+
+// ユーザーに表示するリッチメニューは、画像をアップロードしてある必要がある
+fn check_rich_menu_is_ready(
+    state: &InMemoryLineState,
+    rich_menu_id: &RichMenuId,
+) -> Result<(), GatewayError> {
+    if !state.rich_menu_exists(rich_menu_id) {
+        return Err(line_error(404, "Not found"));
+    }
+    if !state.rich_menu_images.contains_key(&rich_menu_id.0) {
+        return Err(line_error(
+            400,
+            "must upload richmenu image before applying it to user",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use async_trait::async_trait;
+
+use crate::{
+    gateway::{in_memory::InMemorySentMessages, GatewayError, InMemoryGatewayImpl},
+    model::message::send_message::request::{
+        CreateBotSendMessage, CreateManualSendMessage, CreateSendMessage, SendMessageRequest,
+        SentMessageResponse, SentMessagesResponse,
+    },
+};
+use domain::{
+    gateway::send_message::SendMessageGateway,
+    model::{
+        message::{
+            event::NewEvent,
+            send_message::{NewSendMessages, NewSendSender, SendMessage},
+        },
+        user_auth::{LineId, UserAuthData},
+    },
+};
+
+#[async_trait]
+impl SendMessageGateway for InMemoryGatewayImpl<SendMessage> {
+    async fn send_messages(
+        &self,
+        user_auth_data: UserAuthData,
+        sender: Option<NewSendSender>,
+        event: NewEvent,
+    ) -> Result<Vec<NewSendMessages>, GatewayError> {
+        let line_user_auth = match user_auth_data {
+            UserAuthData::Line(line_user_auth) => line_user_auth,
+            // メッセージを送れるのはMessaging APIのユーザーだけ
+            UserAuthData::Email(_) => {
+                return Err(GatewayError::UnsupportedUserAuth("email".to_string()))
+            }
+            UserAuthData::LineLogin(_) => {
+                return Err(GatewayError::UnsupportedUserAuth("line_login".to_string()))
+            }
+        };
+        let create_message = CreateSendMessage::from_event(event);
+        let requests = create_message.into_chunked_requests(line_user_auth.auth_id.0.clone());
+        self.send_line_messages(line_user_auth.auth_id, sender, requests)
+    }
+
+    async fn send_manual_messages(
+        &self,
+        user_auth_data: UserAuthData,
+        sender: NewSendSender,
+        texts: Vec<String>,
+    ) -> Result<Vec<NewSendMessages>, GatewayError> {
+        let UserAuthData::Line(line_user_auth) = user_auth_data else {
+            return Err(GatewayError::UnsupportedUserAuth(
+                "only LINE users can receive messages".to_string(),
+            ));
+        };
+        let to = line_user_auth.auth_id.0.clone();
+        let create_message = CreateManualSendMessage::from_texts(to.clone(), texts);
+        let requests = create_message.into_chunked_requests(to);
+        self.send_line_messages(line_user_auth.auth_id, Some(sender), requests)
+    }
+
+    async fn reply_bot_messages(
+        &self,
+        user_auth_data: UserAuthData,
+        reply_token: String,
+        texts: Vec<String>,
+    ) -> Result<Vec<NewSendMessages>, GatewayError> {
+        let UserAuthData::Line(line_user_auth) = user_auth_data else {
+            return Err(GatewayError::UnsupportedUserAuth(
+                "only LINE users can receive messages".to_string(),
+            ));
+        };
+        let create_message = CreateBotSendMessage::reply_texts(reply_token, texts);
+        let requests = create_message.into_chunked_requests(line_user_auth.auth_id.0.clone());
+        self.send_line_messages(line_user_auth.auth_id, None, requests)
+    }
+
+    async fn mark_as_read(&self, user_auth_data: UserAuthData) -> Result<(), GatewayError> {
+        let UserAuthData::Line(line_user_auth) = user_auth_data else {
+            return Err(GatewayError::UnsupportedUserAuth(
+                "only LINE users can be marked as read".to_string(),
+            ));
+        };
+        self.line.lock()?.read_line_ids.push(line_user_auth.auth_id);
+        Ok(())
+    }
+}
+
+impl InMemoryGatewayImpl<SendMessage> {
+    /// LINEのAPIの代わりに、送ったメッセージごとにメッセージIDを払い出して記録する
+    /// 送ったメッセージへの変換は、LINEのAPIを呼ぶときと同じものを使う
+    fn send_line_messages(
+        &self,
+        to: LineId,
+        sender: Option<NewSendSender>,
+        message_requests: Vec<SendMessageRequest>,
+    ) -> Result<Vec<NewSendMessages>, GatewayError> {
+        let mut state = self.line.lock()?;
+        let mut new_messages_vec = Vec::new();
+        for message_request in message_requests {
+            let message_count = match &message_request {
+                SendMessageRequest::Reply(r) => r.messages.len(),
+                SendMessageRequest::Push(r) => r.messages.len(),
+            };
+            let mut sent_messages = Vec::new();
+            for _ in 0..message_count {
+                state.last_message_id += 1;
+                sent_messages.push(SentMessageResponse {
+                    message_id: state.last_message_id.to_string(),
+                    quote_token: None,
+                });
+            }
+            let new_messages = message_request
+                .into_messages(sender.clone(), SentMessagesResponse { sent_messages });
+            state
+                .sent_messages
+                .push(InMemorySentMessages::new(to.clone(), new_messages.clone()));
+            new_messages_vec.push(new_messages);
+        }
+        Ok(new_messages_vec)
+    }
+}
@@ -0,0 +1,78 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::gateway::in_memory::line_error;
+use crate::gateway::{unexpected_error, GatewayError, InMemoryGatewayImpl};
+use domain::gateway::user_auth::UserAuthGateway;
+use domain::model::{
+    account_link::LineLinkToken,
+    email_user::{EmailUserProfile, PasswordHash},
+    line_login_user::{LineLoginId, LineLoginUserProfile},
+    line_user::LineUserProfile,
+    user::UserProfile,
+    user_auth::{LineLoginAuthData, LineUserAuthData, UserAuthData},
+};
+
+#[async_trait]
+impl UserAuthGateway for InMemoryGatewayImpl<UserAuthData> {
+    async fn get_user_profile(&self, source: UserAuthData) -> Result<UserProfile, GatewayError> {
+        let res = match source {
+            UserAuthData::Line(d) => UserProfile::Line(self.get_line_user_profile(d).await?),
+            // メールアドレスのユーザーは外部にプロフィールがないので、パスワードをハッシュ化するだけ
+            UserAuthData::Email(d) => UserProfile::Email(EmailUserProfile::new(
+                d.auth_id,
+                PasswordHash::from_plain(&d.password).map_err(unexpected_error)?,
+            )),
+            UserAuthData::LineLogin(d) => {
+                UserProfile::LineLogin(self.verify_line_login_id_token(d).await?)
+            }
+        };
+
+        Ok(res)
+    }
+
+    /// 登録したプロフィールを返す
+    /// 登録していないユーザーからもイベントを受け取れるように、LINEのユーザーIDを表示名にしたプロフィールを返す
+    async fn get_line_user_profile(
+        &self,
+        source: LineUserAuthData,
+    ) -> Result<LineUserProfile, GatewayError> {
+        let state = self.line.lock()?;
+        Ok(state
+            .line_user_profiles
+            .get(&source.auth_id.0)
+            .cloned()
+            .unwrap_or_else(|| {
+                LineUserProfile::new(
+                    source.auth_id.clone(),
+                    source.auth_id.0.clone(),
+                    String::new(),
+                    None,
+                    None,
+                )
+            }))
+    }
+
+    /// IDトークンは検証せず、そのままLINEログインのユーザーIDとして扱う
+    async fn verify_line_login_id_token(
+        &self,
+        source: LineLoginAuthData,
+    ) -> Result<LineLoginUserProfile, GatewayError> {
+        if source.id_token.is_empty() {
+            return Err(line_error(400, "Invalid IdToken."));
+        }
+        Ok(LineLoginUserProfile::new(
+            LineLoginId(source.id_token.clone()),
+            source.id_token,
+            String::new(),
+            None,
+        ))
+    }
+
+    async fn issue_line_link_token(
+        &self,
+        _source: LineUserAuthData,
+    ) -> Result<LineLinkToken, GatewayError> {
+        Ok(LineLinkToken::new(Uuid::new_v4().simple().to_string()))
+    }
+}
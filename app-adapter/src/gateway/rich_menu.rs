@@ -0,0 +1,301 @@
+use async_trait::async_trait;
+use reqwest::{header, RequestBuilder, StatusCode};
+
+use crate::{
+    gateway::{
+        error_response, request_error, GatewayError, HttpClientRepositoryImpl,
+        LINE_RICH_MENU_BULK_LIMIT,
+    },
+    model::rich_menu::{
+        CreateRichMenuAliasRequest, CreatedRichMenuResponse, RichMenuAliasResponse,
+        RichMenuBulkLinkRequest, RichMenuBulkUnlinkRequest, RichMenuListResponse, RichMenuRequest,
+        UpdateRichMenuAliasRequest,
+    },
+};
+use domain::{
+    gateway::rich_menu::RichMenuGateway,
+    model::{
+        rich_menu::{
+            NewRichMenu, RichMenu, RichMenuAlias, RichMenuAliasId, RichMenuId, RichMenuImage,
+        },
+        user_auth::{LineAuthToken, LineId},
+    },
+};
+
+const LINE_API_BASE_URL: &str = "https://api.line.me/v2/bot";
+// 画像のアップロードだけはapi-dataドメインを使う
+const LINE_API_DATA_BASE_URL: &str = "https://api-data.line.me/v2/bot";
+
+#[async_trait]
+impl RichMenuGateway for HttpClientRepositoryImpl<RichMenu> {
+    async fn create_rich_menu(
+        &self,
+        auth_token: LineAuthToken,
+        source: NewRichMenu,
+    ) -> Result<RichMenu, GatewayError> {
+        let request = RichMenuRequest::from(source.clone());
+        let body = self
+            .send_line_request(
+                self.client
+                    .post(format!("{}/richmenu", LINE_API_BASE_URL))
+                    .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0))
+                    .json(&request),
+            )
+            .await?;
+        let created: CreatedRichMenuResponse = serde_json::from_str(&body).map_err(|_| {
+            GatewayError::FailedConvertResponse(
+                body.to_string(),
+                "CreatedRichMenuResponse".to_string(),
+            )
+        })?;
+
+        Ok(source.into_rich_menu(RichMenuId::new(created.rich_menu_id)))
+    }
+
+    async fn upload_rich_menu_image(
+        &self,
+        auth_token: LineAuthToken,
+        rich_menu_id: RichMenuId,
+        image: RichMenuImage,
+    ) -> Result<(), GatewayError> {
+        self.send_line_request(
+            self.client
+                .post(format!(
+                    "{}/richmenu/{}/content",
+                    LINE_API_DATA_BASE_URL, rich_menu_id.0
+                ))
+                .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0))
+                .header(header::CONTENT_TYPE, image.content_type.mime_type())
+                .body(image.data),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn get_rich_menu_list(
+        &self,
+        auth_token: LineAuthToken,
+    ) -> Result<Vec<RichMenu>, GatewayError> {
+        let body = self
+            .send_line_request(
+                self.client
+                    .get(format!("{}/richmenu/list", LINE_API_BASE_URL))
+                    .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0)),
+            )
+            .await?;
+        let list: RichMenuListResponse = serde_json::from_str(&body).map_err(|_| {
+            GatewayError::FailedConvertResponse(
+                body.to_string(),
+                "RichMenuListResponse".to_string(),
+            )
+        })?;
+
+        Ok(list.richmenus.into_iter().map(|r| r.into()).collect())
+    }
+
+    async fn delete_rich_menu(
+        &self,
+        auth_token: LineAuthToken,
+        rich_menu_id: RichMenuId,
+    ) -> Result<(), GatewayError> {
+        self.send_line_request(
+            self.client
+                .delete(format!("{}/richmenu/{}", LINE_API_BASE_URL, rich_menu_id.0))
+                .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn set_default_rich_menu(
+        &self,
+        auth_token: LineAuthToken,
+        rich_menu_id: RichMenuId,
+    ) -> Result<(), GatewayError> {
+        self.send_line_request(
+            self.client
+                .post(format!(
+                    "{}/user/all/richmenu/{}",
+                    LINE_API_BASE_URL, rich_menu_id.0
+                ))
+                .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn link_rich_menu_to_user(
+        &self,
+        auth_token: LineAuthToken,
+        line_id: LineId,
+        rich_menu_id: RichMenuId,
+    ) -> Result<(), GatewayError> {
+        self.send_line_request(
+            self.client
+                .post(format!(
+                    "{}/user/{}/richmenu/{}",
+                    LINE_API_BASE_URL, line_id.0, rich_menu_id.0
+                ))
+                .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn link_rich_menu_to_users(
+        &self,
+        auth_token: LineAuthToken,
+        line_ids: Vec<LineId>,
+        rich_menu_id: RichMenuId,
+    ) -> Result<(), GatewayError> {
+        // 一度にリンクできるユーザー数に上限があるので、分割してリクエストする
+        for chunk in line_ids.chunks(LINE_RICH_MENU_BULK_LIMIT) {
+            let request = RichMenuBulkLinkRequest {
+                rich_menu_id: rich_menu_id.0.clone(),
+                user_ids: chunk.iter().map(|l| l.0.clone()).collect(),
+            };
+            self.send_line_request(
+                self.client
+                    .post(format!("{}/richmenu/bulk/link", LINE_API_BASE_URL))
+                    .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0))
+                    .json(&request),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn unlink_rich_menu_from_user(
+        &self,
+        auth_token: LineAuthToken,
+        line_id: LineId,
+    ) -> Result<(), GatewayError> {
+        self.send_line_request(
+            self.client
+                .delete(format!("{}/user/{}/richmenu", LINE_API_BASE_URL, line_id.0))
+                .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn unlink_rich_menu_from_users(
+        &self,
+        auth_token: LineAuthToken,
+        line_ids: Vec<LineId>,
+    ) -> Result<(), GatewayError> {
+        for chunk in line_ids.chunks(LINE_RICH_MENU_BULK_LIMIT) {
+            let request = RichMenuBulkUnlinkRequest {
+                user_ids: chunk.iter().map(|l| l.0.clone()).collect(),
+            };
+            self.send_line_request(
+                self.client
+                    .post(format!("{}/richmenu/bulk/unlink", LINE_API_BASE_URL))
+                    .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0))
+                    .json(&request),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn get_rich_menu_alias(
+        &self,
+        auth_token: LineAuthToken,
+        alias_id: RichMenuAliasId,
+    ) -> Result<Option<RichMenuAlias>, GatewayError> {
+        let res = self
+            .client
+            .get(format!(
+                "{}/richmenu/alias/{}",
+                LINE_API_BASE_URL, alias_id.0
+            ))
+            .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0))
+            .send()
+            .await
+            .map_err(request_error)?;
+        // エイリアスが存在しない場合は404が返ってくる
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !res.status().is_success() {
+            return Err(error_response(res).await);
+        }
+        let body = res.text().await.map_err(request_error)?;
+        let alias: RichMenuAliasResponse = serde_json::from_str(&body).map_err(|_| {
+            GatewayError::FailedConvertResponse(
+                body.to_string(),
+                "RichMenuAliasResponse".to_string(),
+            )
+        })?;
+
+        Ok(Some(alias.into()))
+    }
+
+    async fn create_rich_menu_alias(
+        &self,
+        auth_token: LineAuthToken,
+        source: RichMenuAlias,
+    ) -> Result<(), GatewayError> {
+        let request = CreateRichMenuAliasRequest::from(source);
+        self.send_line_request(
+            self.client
+                .post(format!("{}/richmenu/alias", LINE_API_BASE_URL))
+                .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0))
+                .json(&request),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn update_rich_menu_alias(
+        &self,
+        auth_token: LineAuthToken,
+        source: RichMenuAlias,
+    ) -> Result<(), GatewayError> {
+        let request = UpdateRichMenuAliasRequest {
+            rich_menu_id: source.rich_menu_id.0,
+        };
+        self.send_line_request(
+            self.client
+                .post(format!(
+                    "{}/richmenu/alias/{}",
+                    LINE_API_BASE_URL, source.alias_id.0
+                ))
+                .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0))
+                .json(&request),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_rich_menu_alias(
+        &self,
+        auth_token: LineAuthToken,
+        alias_id: RichMenuAliasId,
+    ) -> Result<(), GatewayError> {
+        self.send_line_request(
+            self.client
+                .delete(format!(
+                    "{}/richmenu/alias/{}",
+                    LINE_API_BASE_URL, alias_id.0
+                ))
+                .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0)),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+impl HttpClientRepositoryImpl<RichMenu> {
+    /// LINEのAPIにリクエストを送り、レスポンスボディを返す
+    /// リッチメニューAPIは成功時に空のJSONを返すものが多いので、ステータスコードで成否を判断する
+    async fn send_line_request(&self, request: RequestBuilder) -> Result<String, GatewayError> {
+        let res = request.send().await.map_err(request_error)?;
+        if !res.status().is_success() {
+            return Err(error_response(res).await);
+        }
+        let body = res.text().await.map_err(request_error)?;
+        Ok(body)
+    }
+}
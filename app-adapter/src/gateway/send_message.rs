@@ -1,12 +1,11 @@
-use anyhow::anyhow;
 use async_trait::async_trait;
 use reqwest::header;
 
 use crate::{
-    gateway::{GatewayError, HttpClientRepositoryImpl},
+    gateway::{error_response, request_error, GatewayError, HttpClientRepositoryImpl},
     model::message::send_message::request::{
-        CreateSendMessage, PushSendMessageRequest, ReplySendMessageRequest, SendMessageRequest,
-        SentMessagesResponse,
+        CreateBotSendMessage, CreateManualSendMessage, CreateSendMessage, MarkAsReadRequest,
+        PushSendMessageRequest, ReplySendMessageRequest, SendMessageRequest, SentMessagesResponse,
     },
 };
 use domain::{
@@ -27,7 +26,7 @@ impl SendMessageGateway for HttpClientRepositoryImpl<SendMessage> {
         user_auth_data: UserAuthData,
         sender: Option<NewSendSender>,
         event: NewEvent,
-    ) -> anyhow::Result<Vec<NewSendMessages>> {
+    ) -> Result<Vec<NewSendMessages>, GatewayError> {
         let messages = match user_auth_data {
             UserAuthData::Line(line_user_auth) => {
                 /*
@@ -39,9 +38,80 @@ impl SendMessageGateway for HttpClientRepositoryImpl<SendMessage> {
                 self.send_line_messages(line_user_auth.auth_token, sender, requests)
                     .await?
             }
+            // メッセージを送れるのはMessaging APIのユーザーだけ
+            UserAuthData::Email(_) => {
+                return Err(GatewayError::UnsupportedUserAuth("email".to_string()))
+            }
+            UserAuthData::LineLogin(_) => {
+                return Err(GatewayError::UnsupportedUserAuth("line_login".to_string()))
+            }
         };
         Ok(messages)
     }
+
+    /// スタッフが書いたテキストをpushで送る
+    /// 送ったメッセージはManualとして、送ったスタッフをsenderに持つ
+    async fn send_manual_messages(
+        &self,
+        user_auth_data: UserAuthData,
+        sender: NewSendSender,
+        texts: Vec<String>,
+    ) -> Result<Vec<NewSendMessages>, GatewayError> {
+        let UserAuthData::Line(line_user_auth) = user_auth_data else {
+            return Err(GatewayError::UnsupportedUserAuth(
+                "only LINE users can receive messages".to_string(),
+            ));
+        };
+        let to = line_user_auth.auth_id.0;
+        let create_message = CreateManualSendMessage::from_texts(to.clone(), texts);
+        let requests = create_message.into_chunked_requests(to);
+        self.send_line_messages(line_user_auth.auth_token, Some(sender), requests)
+            .await
+    }
+
+    /// 不在メッセージなど、botが決まった文面をreplyで送る
+    async fn reply_bot_messages(
+        &self,
+        user_auth_data: UserAuthData,
+        reply_token: String,
+        texts: Vec<String>,
+    ) -> Result<Vec<NewSendMessages>, GatewayError> {
+        let UserAuthData::Line(line_user_auth) = user_auth_data else {
+            return Err(GatewayError::UnsupportedUserAuth(
+                "only LINE users can receive messages".to_string(),
+            ));
+        };
+        let create_message = CreateBotSendMessage::reply_texts(reply_token, texts);
+        let requests = create_message.into_chunked_requests(line_user_auth.auth_id.0);
+        self.send_line_messages(line_user_auth.auth_token, None, requests)
+            .await
+    }
+
+    /// ユーザーのメッセージを既読にする
+    /// LINEのトーク画面に既読が付く。LINEとの契約でこのAPIが使えるチャネルでだけ呼ぶ
+    async fn mark_as_read(&self, user_auth_data: UserAuthData) -> Result<(), GatewayError> {
+        let UserAuthData::Line(line_user_auth) = user_auth_data else {
+            return Err(GatewayError::UnsupportedUserAuth(
+                "only LINE users can be marked as read".to_string(),
+            ));
+        };
+        let res = self
+            .client
+            .post("https://api.line.me/v2/bot/message/markAsRead")
+            .header("Content-Type", "application/json")
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", line_user_auth.auth_token.0),
+            )
+            .json(&MarkAsReadRequest::new(line_user_auth.auth_id.0))
+            .send()
+            .await
+            .map_err(request_error)?;
+        if !res.status().is_success() {
+            return Err(error_response(res).await);
+        }
+        Ok(())
+    }
 }
 
 impl HttpClientRepositoryImpl<SendMessage> {
@@ -50,7 +120,7 @@ impl HttpClientRepositoryImpl<SendMessage> {
         auth_token: LineAuthToken,
         sender: Option<NewSendSender>,
         message_requests: Vec<SendMessageRequest>,
-    ) -> anyhow::Result<Vec<NewSendMessages>> {
+    ) -> Result<Vec<NewSendMessages>, GatewayError> {
         let mut new_messages_vec = Vec::new();
         // メッセージのリクエストの順番を保つ必要があるので、同期処理にした
         for message_request in message_requests {
@@ -81,23 +151,26 @@ impl HttpClientRepositoryImpl<SendMessage> {
         auth_token: LineAuthToken,
         sender: Option<NewSendSender>,
         message_request: ReplySendMessageRequest,
-    ) -> anyhow::Result<NewSendMessages> {
-        let body = self
+    ) -> Result<NewSendMessages, GatewayError> {
+        let res = self
             .client
             .post("https://api.line.me/v2/bot/message/reply")
             .header("Content-Type", "application/json")
             .header(header::AUTHORIZATION, format!("Bearer {}", auth_token.0))
             .json(&message_request) // `.json()`を使ってリクエストボディを設定します。
             .send()
-            .await?
-            .text()
-            .await?;
+            .await
+            .map_err(request_error)?;
+        if !res.status().is_success() {
+            return Err(error_response(res).await);
+        }
+        let body = res.text().await.map_err(request_error)?;
 
         let sent_messages: SentMessagesResponse = serde_json::from_str(&body).map_err(|_| {
-            anyhow!(GatewayError::FailedConvertResponse(
+            GatewayError::FailedConvertResponse(
                 body.to_string(),
-                "SentMessagesResponse".to_string()
-            ))
+                "SentMessagesResponse".to_string(),
+            )
         })?;
 
         let new_messages = message_request.into_messages(sender, sent_messages);
@@ -108,8 +181,8 @@ impl HttpClientRepositoryImpl<SendMessage> {
         auth_token: LineAuthToken,
         sender: Option<NewSendSender>,
         message_request: PushSendMessageRequest,
-    ) -> anyhow::Result<NewSendMessages> {
-        let body = self
+    ) -> Result<NewSendMessages, GatewayError> {
+        let res = self
             .client
             .post("https://api.line.me/v2/bot/message/push")
             .header("Content-Type", "application/json")
@@ -117,15 +190,18 @@ impl HttpClientRepositoryImpl<SendMessage> {
             .header("X-Line-Retry-Key", message_request.retry_key.clone())
             .json(&message_request) // `.json()`を使ってリクエストボディを設定します。
             .send()
-            .await?
-            .text()
-            .await?;
+            .await
+            .map_err(request_error)?;
+        if !res.status().is_success() {
+            return Err(error_response(res).await);
+        }
+        let body = res.text().await.map_err(request_error)?;
 
         let sent_messages: SentMessagesResponse = serde_json::from_str(&body).map_err(|_| {
-            anyhow!(GatewayError::FailedConvertResponse(
+            GatewayError::FailedConvertResponse(
                 body.to_string(),
-                "SentMessagesResponse".to_string()
-            ))
+                "SentMessagesResponse".to_string(),
+            )
         })?;
 
         let new_messages = message_request.into_messages(sender, sent_messages);
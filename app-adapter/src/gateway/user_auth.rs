@@ -1,21 +1,33 @@
-use crate::gateway::HttpClientRepositoryImpl;
-use crate::model::line_user_auth::ResponseLineAuth;
-use anyhow::Ok;
+use crate::gateway::{
+    error_response, request_error, unexpected_error, GatewayError, HttpClientRepositoryImpl,
+};
+use crate::model::line_user_auth::{
+    ResponseLineAuth, ResponseLineLinkToken, ResponseLineLoginVerify,
+};
 use async_trait::async_trait;
 use domain::gateway::user_auth::UserAuthGateway;
 use domain::model::{
+    account_link::LineLinkToken,
+    email_user::{EmailUserProfile, PasswordHash},
+    line_login_user::LineLoginUserProfile,
     line_user::LineUserProfile,
     user::UserProfile,
-    user_auth::{LineUserAuthData, UserAuthData},
+    user_auth::{LineLoginAuthData, LineUserAuthData, UserAuthData},
 };
 use reqwest::header;
 
 #[async_trait]
 impl UserAuthGateway for HttpClientRepositoryImpl<UserAuthData> {
-    async fn get_user_profile(&self, source: UserAuthData) -> anyhow::Result<UserProfile> {
+    async fn get_user_profile(&self, source: UserAuthData) -> Result<UserProfile, GatewayError> {
         let res = match source {
-            UserAuthData::Line(d) => {
-                UserProfile::Line(self.get_line_user_profile(d).await.unwrap())
+            UserAuthData::Line(d) => UserProfile::Line(self.get_line_user_profile(d).await?),
+            // メールアドレスのユーザーは外部にプロフィールがないので、パスワードをハッシュ化するだけ
+            UserAuthData::Email(d) => UserProfile::Email(EmailUserProfile::new(
+                d.auth_id,
+                PasswordHash::from_plain(&d.password).map_err(unexpected_error)?,
+            )),
+            UserAuthData::LineLogin(d) => {
+                UserProfile::LineLogin(self.verify_line_login_id_token(d).await?)
             }
         };
 
@@ -25,8 +37,8 @@ impl UserAuthGateway for HttpClientRepositoryImpl<UserAuthData> {
     async fn get_line_user_profile(
         &self,
         source: LineUserAuthData,
-    ) -> anyhow::Result<LineUserProfile> {
-        let body = &self
+    ) -> Result<LineUserProfile, GatewayError> {
+        let res = self
             .client
             .get(format!(
                 "https://api.line.me/v2/bot/profile/{}",
@@ -37,13 +49,85 @@ impl UserAuthGateway for HttpClientRepositoryImpl<UserAuthData> {
                 format!("Bearer {}", source.auth_token.0),
             )
             .send()
-            .await?
-            .text()
-            .await?;
+            .await
+            .map_err(request_error)?;
+        // ブロックされているユーザーなどは404が返ってくる
+        if !res.status().is_success() {
+            return Err(error_response(res).await);
+        }
+        let body = res.text().await.map_err(request_error)?;
+
+        let res_line_auth: ResponseLineAuth = serde_json::from_str(&body).map_err(|_| {
+            GatewayError::FailedConvertResponse(body.to_string(), "ResponseLineAuth".to_string())
+        })?;
+
+        Ok(res_line_auth.into())
+    }
+
+    /// LINEログインのIDトークンをLINEで検証し、プロフィールを取り出す
+    /// 署名や有効期限、チャネルIDの検証はLINE側で行われる
+    async fn verify_line_login_id_token(
+        &self,
+        source: LineLoginAuthData,
+    ) -> Result<LineLoginUserProfile, GatewayError> {
+        let res = self
+            .client
+            .post("https://api.line.me/oauth2/v2.1/verify")
+            .form(&[
+                ("id_token", source.id_token.as_str()),
+                ("client_id", source.channel_id.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(request_error)?;
+        if !res.status().is_success() {
+            return Err(error_response(res).await);
+        }
+        let body = res.text().await.map_err(request_error)?;
+
+        let res_line_login_verify: ResponseLineLoginVerify =
+            serde_json::from_str(&body).map_err(|_| {
+                GatewayError::FailedConvertResponse(
+                    body.to_string(),
+                    "ResponseLineLoginVerify".to_string(),
+                )
+            })?;
+
+        Ok(res_line_login_verify.into())
+    }
+
+    /// アカウント連携のための連携トークンを発行する
+    /// 連携トークンの有効期限は10分で、一度しか使えない
+    async fn issue_line_link_token(
+        &self,
+        source: LineUserAuthData,
+    ) -> Result<LineLinkToken, GatewayError> {
+        let res = self
+            .client
+            .post(format!(
+                "https://api.line.me/v2/bot/user/{}/linkToken",
+                source.auth_id.0
+            ))
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", source.auth_token.0),
+            )
+            .send()
+            .await
+            .map_err(request_error)?;
+        if !res.status().is_success() {
+            return Err(error_response(res).await);
+        }
+        let body = res.text().await.map_err(request_error)?;
 
-        let res_line_auth: ResponseLineAuth = serde_json::from_str(body)
-            .unwrap_or_else(|_| panic!("Failed to convert body {} to ResponseLineAuth", body));
+        let res_line_link_token: ResponseLineLinkToken =
+            serde_json::from_str(&body).map_err(|_| {
+                GatewayError::FailedConvertResponse(
+                    body.to_string(),
+                    "ResponseLineLinkToken".to_string(),
+                )
+            })?;
 
-        Ok(res_line_auth.try_into()?)
+        Ok(res_line_link_token.into())
     }
 }
@@ -1,7 +1,16 @@
+pub mod business_hours;
+pub mod canned_response;
+pub mod email_user;
+pub mod line_login_user;
 pub mod line_user;
 pub mod line_user_auth;
 pub mod message;
+pub mod rich_menu;
+pub mod staff;
 pub mod talk_room;
+pub mod talk_room_change;
+pub mod talk_room_note;
+pub mod user_tag;
 
 #[macro_export]
 macro_rules! local_datetime {
@@ -0,0 +1,162 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow};
+
+use domain::model::business_hours::{
+    weekday_name, BusinessHoursCalendar, BusinessHoursException, BusinessHoursRange,
+    NewBusinessHoursCalendar, WeeklyBusinessHours,
+};
+
+const TIME_FORMAT: &str = "%H:%M";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// businessHoursCalendarsに、チャネルのIDをドキュメントのIDにして保存する
+/// 時刻はtimeZoneの現地時刻で、HH:MMの文字列で持つ
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessHoursCalendarTable {
+    pub time_zone: String,
+    pub weekly_hours: Vec<WeeklyBusinessHoursTable>,
+    pub exceptions: Vec<BusinessHoursExceptionTable>,
+    #[serde(with = "firestore::serialize_as_null")]
+    pub away_message: Option<String>,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub updated_at: DateTime<Local>,
+}
+
+/// TALK_ROOM_REPOSITORY=mysqlのときに、business_hours_calendarsから読み込む
+/// 曜日ごとの営業時間と例外の日は、firestoreと同じ形のJSONで持つ
+#[derive(FromRow, Debug)]
+pub struct BusinessHoursCalendarDbTable {
+    pub time_zone: String,
+    pub weekly_hours: Json<Vec<WeeklyBusinessHoursTable>>,
+    pub exceptions: Json<Vec<BusinessHoursExceptionTable>>,
+    pub away_message: Option<String>,
+    pub updated_at: DateTime<Local>,
+}
+
+impl From<BusinessHoursCalendarDbTable> for BusinessHoursCalendarTable {
+    fn from(c: BusinessHoursCalendarDbTable) -> Self {
+        BusinessHoursCalendarTable {
+            time_zone: c.time_zone,
+            weekly_hours: c.weekly_hours.0,
+            exceptions: c.exceptions.0,
+            away_message: c.away_message,
+            updated_at: c.updated_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklyBusinessHoursTable {
+    // mon、tueなど
+    pub weekday: String,
+    pub open: String,
+    pub close: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessHoursExceptionTable {
+    // YYYY-MM-DD
+    pub date: String,
+    // 空のときは休業日
+    pub hours: Vec<BusinessHoursRangeTable>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessHoursRangeTable {
+    pub open: String,
+    pub close: String,
+}
+
+impl From<(NewBusinessHoursCalendar, DateTime<Local>)> for BusinessHoursCalendarTable {
+    fn from((c, updated_at): (NewBusinessHoursCalendar, DateTime<Local>)) -> Self {
+        BusinessHoursCalendarTable {
+            time_zone: c.time_zone.name().to_string(),
+            weekly_hours: c
+                .weekly_hours
+                .into_iter()
+                .map(|w| WeeklyBusinessHoursTable {
+                    weekday: weekday_name(w.weekday).to_string(),
+                    open: w.hours.open.format(TIME_FORMAT).to_string(),
+                    close: w.hours.close.format(TIME_FORMAT).to_string(),
+                })
+                .collect(),
+            exceptions: c
+                .exceptions
+                .into_iter()
+                .map(|e| BusinessHoursExceptionTable {
+                    date: e.date.format(DATE_FORMAT).to_string(),
+                    hours: e.hours.into_iter().map(|h| h.into()).collect(),
+                })
+                .collect(),
+            away_message: c.away_message,
+            updated_at,
+        }
+    }
+}
+
+impl From<BusinessHoursRange> for BusinessHoursRangeTable {
+    fn from(h: BusinessHoursRange) -> Self {
+        BusinessHoursRangeTable {
+            open: h.open.format(TIME_FORMAT).to_string(),
+            close: h.close.format(TIME_FORMAT).to_string(),
+        }
+    }
+}
+
+impl TryFrom<BusinessHoursRangeTable> for BusinessHoursRange {
+    type Error = anyhow::Error;
+    fn try_from(h: BusinessHoursRangeTable) -> anyhow::Result<Self> {
+        BusinessHoursRange::new(
+            NaiveTime::parse_from_str(&h.open, TIME_FORMAT)?,
+            NaiveTime::parse_from_str(&h.close, TIME_FORMAT)?,
+        )
+    }
+}
+
+impl BusinessHoursCalendarTable {
+    pub fn into_calendar(self, channel_id: String) -> anyhow::Result<BusinessHoursCalendar> {
+        let weekly_hours = self
+            .weekly_hours
+            .into_iter()
+            .map(|w| {
+                Ok(WeeklyBusinessHours::new(
+                    w.weekday
+                        .parse::<Weekday>()
+                        .map_err(|_| anyhow::anyhow!("Unknown weekday: {}", w.weekday))?,
+                    BusinessHoursRangeTable {
+                        open: w.open,
+                        close: w.close,
+                    }
+                    .try_into()?,
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let exceptions = self
+            .exceptions
+            .into_iter()
+            .map(|e| {
+                Ok(BusinessHoursException::new(
+                    NaiveDate::parse_from_str(&e.date, DATE_FORMAT)?,
+                    e.hours
+                        .into_iter()
+                        .map(BusinessHoursRange::try_from)
+                        .collect::<anyhow::Result<Vec<_>>>()?,
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(BusinessHoursCalendar::new(
+            channel_id,
+            self.time_zone.parse()?,
+            weekly_hours,
+            exceptions,
+            self.away_message,
+            self.updated_at,
+        ))
+    }
+}
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Local};
+use domain::model::{
+    canned_response::{CannedResponse, CannedResponseId},
+    staff::StaffId,
+};
+use sqlx::FromRow;
+
+#[derive(FromRow, Debug)]
+pub struct CannedResponseTable {
+    pub id: i64,
+    pub title: String,
+    pub body: String,
+    pub created_by: i64,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+impl From<CannedResponseTable> for CannedResponse {
+    fn from(c: CannedResponseTable) -> Self {
+        CannedResponse::new(
+            CannedResponseId::new(c.id),
+            c.title,
+            c.body,
+            StaffId::new(c.created_by),
+            c.created_at,
+            c.updated_at,
+        )
+    }
+}
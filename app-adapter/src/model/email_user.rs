@@ -0,0 +1,32 @@
+use chrono::{DateTime, Local};
+use domain::model::{
+    account_link::ExternalMemberId,
+    email_user::{EmailAddress, EmailUserProfile, PasswordHash},
+    primary_user_id::PrimaryUserId,
+    user::{User, UserProfile},
+};
+use sqlx::FromRow;
+
+#[derive(FromRow, Debug)]
+pub struct EmailUserTable {
+    pub primary_user_id: String,
+    pub email: String,
+    pub password_hash: String,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+    pub external_member_id: Option<String>,
+}
+
+impl TryFrom<EmailUserTable> for User {
+    type Error = anyhow::Error;
+    fn try_from(e: EmailUserTable) -> Result<Self, Self::Error> {
+        Ok(User {
+            id: PrimaryUserId::new(e.primary_user_id),
+            user_profile: UserProfile::Email(EmailUserProfile {
+                auth_id: EmailAddress::new(e.email),
+                password_hash: PasswordHash::new(e.password_hash),
+            }),
+            external_member_id: e.external_member_id.map(ExternalMemberId::new),
+        })
+    }
+}
@@ -0,0 +1,36 @@
+use chrono::{DateTime, Local};
+use domain::model::{
+    account_link::ExternalMemberId,
+    line_login_user::{LineLoginId, LineLoginUserProfile},
+    primary_user_id::PrimaryUserId,
+    user::{User, UserProfile},
+};
+use sqlx::FromRow;
+
+#[derive(FromRow, Debug)]
+pub struct LineLoginUserTable {
+    pub primary_user_id: String,
+    pub line_login_id: String,
+    pub display_name: String,
+    pub picture_url: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+    pub external_member_id: Option<String>,
+}
+
+impl TryFrom<LineLoginUserTable> for User {
+    type Error = anyhow::Error;
+    fn try_from(l: LineLoginUserTable) -> Result<Self, Self::Error> {
+        Ok(User {
+            id: PrimaryUserId::new(l.primary_user_id),
+            user_profile: UserProfile::LineLogin(LineLoginUserProfile {
+                auth_id: LineLoginId::new(l.line_login_id),
+                display_name: l.display_name,
+                picture_url: l.picture_url,
+                email: l.email,
+            }),
+            external_member_id: l.external_member_id.map(ExternalMemberId::new),
+        })
+    }
+}
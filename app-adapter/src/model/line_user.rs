@@ -1,5 +1,6 @@
 use chrono::{DateTime, Local};
 use domain::model::{
+    account_link::ExternalMemberId,
     line_user::LineUserProfile,
     primary_user_id::PrimaryUserId,
     user::{User, UserProfile},
@@ -13,8 +14,12 @@ pub struct LineUserTable {
     pub line_id: String,
     pub display_name: String,
     pub picture_url: String,
+    pub status_message: Option<String>,
+    pub language: Option<String>,
     pub created_at: DateTime<Local>,
     pub updated_at: DateTime<Local>,
+    // line_account_linksをleft joinして取得する
+    pub external_member_id: Option<String>,
 }
 
 impl TryFrom<LineUserTable> for User {
@@ -26,7 +31,10 @@ impl TryFrom<LineUserTable> for User {
                 auth_id: LineId::new(l.line_id),
                 display_name: l.display_name,
                 picture_url: l.picture_url,
+                status_message: l.status_message,
+                language: l.language,
             }),
+            external_member_id: l.external_member_id.map(ExternalMemberId::new),
         })
     }
 }
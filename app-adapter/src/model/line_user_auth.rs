@@ -1,6 +1,11 @@
 use serde::Deserialize;
 
-use domain::model::{line_user::LineUserProfile, user_auth::LineId};
+use domain::model::{
+    account_link::LineLinkToken,
+    line_login_user::{LineLoginId, LineLoginUserProfile},
+    line_user::LineUserProfile,
+    user_auth::LineId,
+};
 
 #[derive(Deserialize)]
 pub struct ResponseLineAuth {
@@ -16,13 +21,49 @@ pub struct ResponseLineAuth {
 }
 
 // 保存するにはidを生成する必要があるので、NewMarketKindに変換する
-impl TryFrom<ResponseLineAuth> for LineUserProfile {
-    type Error = anyhow::Error;
-    fn try_from(s: ResponseLineAuth) -> anyhow::Result<Self> {
-        Ok(LineUserProfile {
+impl From<ResponseLineAuth> for LineUserProfile {
+    fn from(s: ResponseLineAuth) -> Self {
+        LineUserProfile {
             auth_id: LineId::new(s.user_id),
             display_name: s.display_name,
             picture_url: s.picture_url.unwrap_or("".to_string()),
-        })
+            status_message: s.status_message,
+            language: s.language,
+        }
+    }
+}
+
+// LINEログインのIDトークンを検証したときのレスポンス
+// https://developers.line.biz/ja/reference/line-login/#verify-id-token
+#[derive(Deserialize)]
+pub struct ResponseLineLoginVerify {
+    pub sub: String,
+    pub name: Option<String>,
+    pub picture: Option<String>,
+    pub email: Option<String>,
+}
+
+impl From<ResponseLineLoginVerify> for LineLoginUserProfile {
+    fn from(s: ResponseLineLoginVerify) -> Self {
+        LineLoginUserProfile {
+            auth_id: LineLoginId::new(s.sub),
+            display_name: s.name.unwrap_or("".to_string()),
+            picture_url: s.picture.unwrap_or("".to_string()),
+            email: s.email,
+        }
+    }
+}
+
+// 連携トークンを発行したときのレスポンス
+// https://developers.line.biz/ja/reference/messaging-api/#issue-link-token
+#[derive(Deserialize)]
+pub struct ResponseLineLinkToken {
+    #[serde(rename(deserialize = "linkToken"))]
+    pub link_token: String,
+}
+
+impl From<ResponseLineLinkToken> for LineLinkToken {
+    fn from(s: ResponseLineLinkToken) -> Self {
+        LineLinkToken::new(s.link_token)
     }
 }
@@ -1,25 +1,75 @@
-use domain::model::message::Messages;
+use anyhow::anyhow;
+use domain::model::message::{Messages, NewMessages};
 use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow};
 
-use crate::model::message::{event::EventTable, send_message::SendMessageTable};
+use crate::model::message::{
+    event::EventTable, send_message::SendMessageTable, system_event::SystemEventTable,
+};
 
 pub mod event;
 pub mod send_message;
+pub mod system_event;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(untagged)]
 pub enum MessagesTable {
     Event(EventTable),
     SendMessage(SendMessageTable),
+    SystemEvent(SystemEventTable),
 }
 
 impl MessagesTable {
-    pub fn into_messages(&self, document_id: &String) -> Messages {
-        match self {
+    /// 追加するメッセージを、ドキュメントのIDと保存する形に分ける
+    pub fn from_new_messages(source: NewMessages) -> (String, Self) {
+        match source {
+            NewMessages::Event(e) => (
+                e.id().value.to_string(),
+                MessagesTable::Event(EventTable::from(e)),
+            ),
+            NewMessages::SendMessages(m) => (
+                m.id.value.to_string(),
+                MessagesTable::SendMessage(SendMessageTable::from(m)),
+            ),
+        }
+    }
+
+    pub fn into_messages(&self, document_id: &String) -> anyhow::Result<Messages> {
+        Ok(match self {
             MessagesTable::Event(table) => Messages::Event(table.into_event(document_id)),
             MessagesTable::SendMessage(table) => {
                 Messages::SendMessages(table.into_messages(document_id))
             }
-        }
+            MessagesTable::SystemEvent(table) => {
+                Messages::SystemEvent(table.into_system_event(document_id)?)
+            }
+        })
+    }
+}
+
+/// TALK_ROOM_REPOSITORY=mysqlのときに、messagesテーブルから読み込む
+/// メッセージの種類ごとの列のどれか1つに、firestoreと同じ形のJSONが入っている
+#[derive(FromRow, Debug)]
+pub struct MessagesDbTable {
+    pub document_id: String,
+    pub event: Option<Json<EventTable>>,
+    pub send_message: Option<Json<SendMessageTable>>,
+    pub system_event: Option<Json<SystemEventTable>>,
+}
+
+impl MessagesDbTable {
+    pub fn into_messages(self) -> anyhow::Result<Messages> {
+        let messages_table = match (self.event, self.send_message, self.system_event) {
+            (Some(e), None, None) => MessagesTable::Event(e.0),
+            (None, Some(m), None) => MessagesTable::SendMessage(m.0),
+            (None, None, Some(s)) => MessagesTable::SystemEvent(s.0),
+            _ => {
+                return Err(anyhow!(
+                    "messages must have exactly one of event, send_message and system_event: {}",
+                    self.document_id
+                ))
+            }
+        };
+        messages_table.into_messages(&self.document_id)
     }
 }
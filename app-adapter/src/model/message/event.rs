@@ -8,19 +8,20 @@ use strum_macros::Display;
 
 use domain::model::{
     message::event::{
-        Event, EventContentProvider, EventContentProviderExternal, EventDeliveryContext,
-        EventEmoji, EventFollow, EventImageSet, EventMessage, EventMessageContent,
-        EventMessageContentAudio, EventMessageContentFile, EventMessageContentImage,
-        EventMessageContentLocation, EventMessageContentSticker, EventMessageContentText,
-        EventMessageContentVideo, EventPostback, EventPostbackContent, EventPostbackParams,
-        EventPostbackParamsDatetime, EventPostbackParamsRichMenu, EventStickerResourceType,
-        EventUnfollow, EventVideoPlayComplete, EventVideoPlayCompleteContent, NewEvent,
-        NewEventContentProvider, NewEventContentProviderExternal, NewEventDeliveryContext,
-        NewEventEmoji, NewEventFollow, NewEventImageSet, NewEventMessage, NewEventMessageContent,
-        NewEventMessageContentAudio, NewEventMessageContentFile, NewEventMessageContentImage,
-        NewEventMessageContentLocation, NewEventMessageContentSticker, NewEventMessageContentText,
-        NewEventMessageContentVideo, NewEventPostback, NewEventPostbackParams,
-        NewEventPostbackParamsDatetime, NewEventPostbackParamsRichMenu,
+        Event, EventAccountLink, EventAccountLinkContent, EventAccountLinkResult,
+        EventContentProvider, EventContentProviderExternal, EventDeliveryContext, EventEmoji,
+        EventFollow, EventImageSet, EventMessage, EventMessageContent, EventMessageContentAudio,
+        EventMessageContentFile, EventMessageContentImage, EventMessageContentLocation,
+        EventMessageContentSticker, EventMessageContentText, EventMessageContentVideo,
+        EventPostback, EventPostbackContent, EventPostbackParams, EventPostbackParamsDatetime,
+        EventPostbackParamsRichMenu, EventStickerResourceType, EventUnfollow,
+        EventVideoPlayComplete, EventVideoPlayCompleteContent, NewEvent, NewEventAccountLink,
+        NewEventAccountLinkResult, NewEventContentProvider, NewEventContentProviderExternal,
+        NewEventDeliveryContext, NewEventEmoji, NewEventFollow, NewEventImageSet, NewEventMessage,
+        NewEventMessageContent, NewEventMessageContentAudio, NewEventMessageContentFile,
+        NewEventMessageContentImage, NewEventMessageContentLocation, NewEventMessageContentSticker,
+        NewEventMessageContentText, NewEventMessageContentVideo, NewEventPostback,
+        NewEventPostbackParams, NewEventPostbackParamsDatetime, NewEventPostbackParamsRichMenu,
         NewEventStickerResourceType, NewEventUnfollow, NewEventVideoPlayComplete,
     },
     Id,
@@ -35,6 +36,7 @@ pub enum EventTable {
     Postback(EventPostbackTable),
     VideoPlayComplete(EventVideoPlayCompleteTable),
     Message(EventMessageTable),
+    AccountLink(EventAccountLinkTable),
 }
 
 impl EventTable {
@@ -45,6 +47,7 @@ impl EventTable {
             EventTable::Message(e) => e.created_at,
             EventTable::Postback(e) => e.created_at,
             EventTable::VideoPlayComplete(e) => e.created_at,
+            EventTable::AccountLink(e) => e.created_at,
         }
     }
     pub fn into_event(&self, document_id: &String) -> Event {
@@ -54,6 +57,7 @@ impl EventTable {
             EventTable::Message(m) => Event::Message(m.into_event(document_id)),
             EventTable::Postback(p) => Event::Postback(p.into_event(document_id)),
             EventTable::VideoPlayComplete(v) => Event::VideoPlayComplete(v.into_event(document_id)),
+            EventTable::AccountLink(a) => Event::AccountLink(a.into_event(document_id)),
         }
     }
 }
@@ -315,10 +319,10 @@ impl From<EventPostbackContentTable> for EventPostbackContent {
     fn from(p: EventPostbackContentTable) -> Self {
         Self {
             data: p.data,
-            params: match p.params {
+            params: p.params.map(|p| match p {
                 EventPostbackParamsTable::Datetime(p) => EventPostbackParams::Datetime(p.into()),
                 EventPostbackParamsTable::RichMenu(p) => EventPostbackParams::RichMenu(p.into()),
-            },
+            }),
         }
     }
 }
@@ -381,6 +385,60 @@ impl From<EventVideoPlayCompleteContentTable> for EventVideoPlayCompleteContent
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EventAccountLinkTable {
+    reply_token: Option<String>,
+    webhook_event_id: String,
+    delivery_context: EventDeliveryContextTable,
+    mode: String,
+    communication_type: EventCommunicationTypeTable,
+    sending_type: EventSendingTypeTable,
+    link: EventAccountLinkContentTable,
+    created_at: DateTime<Local>,
+    updated_at: DateTime<Local>,
+}
+
+impl EventAccountLinkTable {
+    pub fn into_event(&self, document_id: &String) -> EventAccountLink {
+        EventAccountLink {
+            id: Id::try_from(document_id.to_string())
+                .unwrap_or_else(|_| panic!("Failed to convert String {} to UUID", document_id)),
+            reply_token: self.reply_token.clone(),
+            delivery_context: EventDeliveryContext::from(self.delivery_context.clone()),
+            mode: self.mode.clone(),
+            webhook_event_id: self.webhook_event_id.clone(),
+            link: self.link.clone().into(),
+            created_at: self.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EventAccountLinkContentTable {
+    result: EventAccountLinkResultTable,
+    nonce: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum EventAccountLinkResultTable {
+    Ok,
+    Failed,
+}
+
+impl From<EventAccountLinkContentTable> for EventAccountLinkContent {
+    fn from(l: EventAccountLinkContentTable) -> Self {
+        Self {
+            result: match l.result {
+                EventAccountLinkResultTable::Ok => EventAccountLinkResult::Ok,
+                EventAccountLinkResultTable::Failed => EventAccountLinkResult::Failed,
+            },
+            nonce: l.nonce,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Display, Clone, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum EventCommunicationTypeTable {
@@ -397,7 +455,9 @@ pub enum EventSendingTypeTable {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct EventPostbackContentTable {
     pub data: String,
-    pub params: EventPostbackParamsTable,
+    // 日時を選ばないpostbackにはない
+    #[serde(default)]
+    pub params: Option<EventPostbackParamsTable>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -576,6 +636,7 @@ impl From<NewEvent> for EventTable {
             NewEvent::Message(m) => EventTable::Message(m.into()),
             NewEvent::Postback(p) => EventTable::Postback(p.into()),
             NewEvent::VideoPlayComplete(v) => EventTable::VideoPlayComplete(v.into()),
+            NewEvent::AccountLink(a) => EventTable::AccountLink(a.into()),
         }
     }
 }
@@ -636,14 +697,14 @@ impl From<NewEventPostback> for EventPostbackTable {
             sending_type: EventSendingTypeTable::Bot,
             postback: EventPostbackContentTable {
                 data: e.postback.data,
-                params: match e.postback.params {
+                params: e.postback.params.map(|p| match p {
                     NewEventPostbackParams::Datetime(p) => {
                         EventPostbackParamsTable::Datetime(p.into())
                     }
                     NewEventPostbackParams::RichMenu(p) => {
                         EventPostbackParamsTable::RichMenu(p.into())
                     }
-                },
+                }),
             },
             created_at: e.created_at,
             updated_at: e.created_at,
@@ -700,6 +761,28 @@ impl From<NewEventVideoPlayComplete> for EventVideoPlayCompleteTable {
     }
 }
 
+impl From<NewEventAccountLink> for EventAccountLinkTable {
+    fn from(e: NewEventAccountLink) -> Self {
+        EventAccountLinkTable {
+            reply_token: e.reply_token,
+            webhook_event_id: e.webhook_event_id,
+            delivery_context: e.delivery_context.into(),
+            mode: e.mode,
+            communication_type: EventCommunicationTypeTable::Receive,
+            sending_type: EventSendingTypeTable::Bot,
+            link: EventAccountLinkContentTable {
+                result: match e.link.result {
+                    NewEventAccountLinkResult::Ok => EventAccountLinkResultTable::Ok,
+                    NewEventAccountLinkResult::Failed => EventAccountLinkResultTable::Failed,
+                },
+                nonce: e.link.nonce,
+            },
+            created_at: e.created_at,
+            updated_at: e.created_at,
+        }
+    }
+}
+
 impl From<NewEventMessageContent> for EventMessageContentTable {
     fn from(m: NewEventMessageContent) -> Self {
         match m {
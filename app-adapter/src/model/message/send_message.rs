@@ -6,11 +6,12 @@ use self::table::{BotSendMessageTable, ManualSendMessageTable, SendMessageConten
 pub mod request;
 pub mod table;
 
+// Botはsenderを持たないので、senderを持つManualを先に試す
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(untagged)]
 pub enum SendMessageTable {
-    Bot(BotSendMessageTable),
     Manual(ManualSendMessageTable),
+    Bot(BotSendMessageTable),
 }
 
 impl From<NewSendMessages> for SendMessageTable {
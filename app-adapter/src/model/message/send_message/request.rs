@@ -92,6 +92,24 @@ pub struct CreateBotSendMessage {
 }
 
 impl CreateBotSendMessage {
+    pub fn reply_texts(reply_token: String, texts: Vec<String>) -> Self {
+        let messages = texts
+            .into_iter()
+            .map(|text| {
+                SendMessageContentRequest::Text(SendMessageContentTextRequest {
+                    text,
+                    emojis: None,
+                    quote_token: None,
+                })
+            })
+            .collect();
+        CreateBotSendMessage {
+            reply_token,
+            sending_method: SendSendingMethodRequest::Reply,
+            messages,
+        }
+    }
+
     pub fn into_chunked_requests(&self, to: String) -> Vec<SendMessageRequest> {
         let chunked_message_contents: Vec<Vec<SendMessageContentRequest>> =
             chunk_request(self.messages.clone());
@@ -150,6 +168,24 @@ pub struct CreateManualSendMessage {
 }
 
 impl CreateManualSendMessage {
+    pub fn from_texts(to: String, texts: Vec<String>) -> Self {
+        let messages = texts
+            .into_iter()
+            .map(|text| {
+                SendMessageContentRequest::Text(SendMessageContentTextRequest {
+                    text,
+                    emojis: None,
+                    quote_token: None,
+                })
+            })
+            .collect();
+        CreateManualSendMessage {
+            to,
+            sending_method: SendSendingMethodRequest::Push,
+            messages,
+        }
+    }
+
     pub fn into_chunked_requests(&self, to: String) -> Vec<SendMessageRequest> {
         let chunked_message_contents: Vec<Vec<SendMessageContentRequest>> =
             chunk_request(self.messages.clone());
@@ -158,7 +194,7 @@ impl CreateManualSendMessage {
             .map(|chunk| {
                 SendMessageRequest::Push(PushSendMessageRequest::new(
                     to.clone(),
-                    SendSendingTypeRequest::Bot,
+                    SendSendingTypeRequest::Manual,
                     chunk.to_vec(),
                 ))
             })
@@ -1102,3 +1138,24 @@ pub struct SentMessageResponse {
 pub struct SentMessagesResponse {
     pub sent_messages: Vec<SentMessageResponse>,
 }
+
+// 既読APIのリクエスト。既読にするユーザーのチャットを指定する
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkAsReadRequest {
+    pub chat: MarkAsReadChatRequest,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkAsReadChatRequest {
+    pub user_id: String,
+}
+
+impl MarkAsReadRequest {
+    pub fn new(user_id: String) -> Self {
+        Self {
+            chat: MarkAsReadChatRequest { user_id },
+        }
+    }
+}
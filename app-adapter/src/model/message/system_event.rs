@@ -0,0 +1,141 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::model::talk_room::{TalkRoomModeTable, TalkRoomStatusTable};
+use domain::model::{
+    message::system_event::{
+        NewSystemEvent, SystemEvent, SystemEventAssigned, SystemEventContent,
+        SystemEventModeChanged, SystemEventStatusChanged, SystemEventUnassigned,
+    },
+    staff::StaffId,
+    Id,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemEventTable {
+    // typeはsystemという値のみを取る
+    #[serde(rename = "type")]
+    #[serde(default = "system_event_type")]
+    event_type: String,
+    operator_staff_id: Option<i64>,
+    content: SystemEventContentTable,
+    created_at: DateTime<Local>,
+    updated_at: DateTime<Local>,
+}
+
+fn system_event_type() -> String {
+    "system".to_string()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "systemEventType")]
+#[serde(rename_all = "camelCase")]
+pub enum SystemEventContentTable {
+    Assigned(SystemEventAssignedTable),
+    Unassigned(SystemEventUnassignedTable),
+    StatusChanged(SystemEventStatusChangedTable),
+    ModeChanged(SystemEventModeChangedTable),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemEventAssignedTable {
+    assignee_staff_id: i64,
+    previous_assignee_staff_id: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemEventUnassignedTable {
+    previous_assignee_staff_id: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemEventStatusChangedTable {
+    from: TalkRoomStatusTable,
+    to: TalkRoomStatusTable,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemEventModeChangedTable {
+    from: TalkRoomModeTable,
+    to: TalkRoomModeTable,
+}
+
+impl From<NewSystemEvent> for SystemEventTable {
+    fn from(s: NewSystemEvent) -> Self {
+        SystemEventTable {
+            event_type: system_event_type(),
+            operator_staff_id: s.operator.map(|o| o.0),
+            content: s.content.into(),
+            created_at: s.created_at,
+            updated_at: s.created_at,
+        }
+    }
+}
+
+impl From<SystemEventContent> for SystemEventContentTable {
+    fn from(s: SystemEventContent) -> Self {
+        match s {
+            SystemEventContent::Assigned(a) => {
+                SystemEventContentTable::Assigned(SystemEventAssignedTable {
+                    assignee_staff_id: a.assignee.0,
+                    previous_assignee_staff_id: a.previous_assignee.map(|p| p.0),
+                })
+            }
+            SystemEventContent::Unassigned(u) => {
+                SystemEventContentTable::Unassigned(SystemEventUnassignedTable {
+                    previous_assignee_staff_id: u.previous_assignee.0,
+                })
+            }
+            SystemEventContent::StatusChanged(c) => {
+                SystemEventContentTable::StatusChanged(SystemEventStatusChangedTable {
+                    from: c.from.into(),
+                    to: c.to.into(),
+                })
+            }
+            SystemEventContent::ModeChanged(c) => {
+                SystemEventContentTable::ModeChanged(SystemEventModeChangedTable {
+                    from: c.from.into(),
+                    to: c.to.into(),
+                })
+            }
+        }
+    }
+}
+
+impl From<SystemEventContentTable> for SystemEventContent {
+    fn from(s: SystemEventContentTable) -> Self {
+        match s {
+            SystemEventContentTable::Assigned(a) => {
+                SystemEventContent::Assigned(SystemEventAssigned::new(
+                    StaffId::new(a.assignee_staff_id),
+                    a.previous_assignee_staff_id.map(StaffId::new),
+                ))
+            }
+            SystemEventContentTable::Unassigned(u) => SystemEventContent::Unassigned(
+                SystemEventUnassigned::new(StaffId::new(u.previous_assignee_staff_id)),
+            ),
+            SystemEventContentTable::StatusChanged(c) => SystemEventContent::StatusChanged(
+                SystemEventStatusChanged::new(c.from.into(), c.to.into()),
+            ),
+            SystemEventContentTable::ModeChanged(c) => SystemEventContent::ModeChanged(
+                SystemEventModeChanged::new(c.from.into(), c.to.into()),
+            ),
+        }
+    }
+}
+
+impl SystemEventTable {
+    pub fn into_system_event(&self, document_id: &String) -> anyhow::Result<SystemEvent> {
+        Ok(SystemEvent::new(
+            Id::try_from(document_id.to_string())?,
+            self.operator_staff_id.map(StaffId::new),
+            self.content.clone().into(),
+            self.created_at,
+        ))
+    }
+}
@@ -0,0 +1,362 @@
+use serde::{Deserialize, Serialize};
+
+use domain::model::rich_menu::{
+    NewRichMenu, RichMenu, RichMenuAction, RichMenuAlias, RichMenuAliasId, RichMenuArea,
+    RichMenuBounds, RichMenuDatetimepickerAction, RichMenuDatetimepickerMode, RichMenuId,
+    RichMenuMessageAction, RichMenuPostbackAction, RichMenuRichmenuswitchAction, RichMenuSize,
+    RichMenuUriAction,
+};
+
+/*
+ * Request
+ * リッチメニューAPIのリクエストをSerializeする用
+ */
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RichMenuRequest {
+    pub size: RichMenuSizeRequest,
+    pub selected: bool,
+    pub name: String,
+    pub chat_bar_text: String,
+    pub areas: Vec<RichMenuAreaRequest>,
+}
+
+impl From<NewRichMenu> for RichMenuRequest {
+    fn from(s: NewRichMenu) -> Self {
+        Self {
+            size: s.size.into(),
+            selected: s.selected,
+            name: s.name,
+            chat_bar_text: s.chat_bar_text,
+            areas: s.areas.into_iter().map(|a| a.into()).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RichMenuSizeRequest {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl From<RichMenuSize> for RichMenuSizeRequest {
+    fn from(s: RichMenuSize) -> Self {
+        Self {
+            width: s.width,
+            height: s.height,
+        }
+    }
+}
+
+impl From<RichMenuSizeRequest> for RichMenuSize {
+    fn from(s: RichMenuSizeRequest) -> Self {
+        Self {
+            width: s.width,
+            height: s.height,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RichMenuAreaRequest {
+    pub bounds: RichMenuBoundsRequest,
+    pub action: RichMenuActionRequest,
+}
+
+impl From<RichMenuArea> for RichMenuAreaRequest {
+    fn from(s: RichMenuArea) -> Self {
+        Self {
+            bounds: s.bounds.into(),
+            action: s.action.into(),
+        }
+    }
+}
+
+impl From<RichMenuAreaRequest> for RichMenuArea {
+    fn from(s: RichMenuAreaRequest) -> Self {
+        Self {
+            bounds: s.bounds.into(),
+            action: s.action.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RichMenuBoundsRequest {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl From<RichMenuBounds> for RichMenuBoundsRequest {
+    fn from(s: RichMenuBounds) -> Self {
+        Self {
+            x: s.x,
+            y: s.y,
+            width: s.width,
+            height: s.height,
+        }
+    }
+}
+
+impl From<RichMenuBoundsRequest> for RichMenuBounds {
+    fn from(s: RichMenuBoundsRequest) -> Self {
+        Self {
+            x: s.x,
+            y: s.y,
+            width: s.width,
+            height: s.height,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type")]
+#[serde(rename_all = "lowercase")]
+pub enum RichMenuActionRequest {
+    Postback(RichMenuPostbackActionRequest),
+    Message(RichMenuMessageActionRequest),
+    Uri(RichMenuUriActionRequest),
+    Datetimepicker(RichMenuDatetimepickerActionRequest),
+    Richmenuswitch(RichMenuRichmenuswitchActionRequest),
+}
+
+impl From<RichMenuAction> for RichMenuActionRequest {
+    fn from(s: RichMenuAction) -> Self {
+        match s {
+            RichMenuAction::Postback(a) => {
+                RichMenuActionRequest::Postback(RichMenuPostbackActionRequest {
+                    label: a.label,
+                    data: a.data,
+                    display_text: a.display_text,
+                })
+            }
+            RichMenuAction::Message(a) => {
+                RichMenuActionRequest::Message(RichMenuMessageActionRequest {
+                    label: a.label,
+                    text: a.text,
+                })
+            }
+            RichMenuAction::Uri(a) => RichMenuActionRequest::Uri(RichMenuUriActionRequest {
+                label: a.label,
+                uri: a.uri,
+            }),
+            RichMenuAction::Datetimepicker(a) => {
+                RichMenuActionRequest::Datetimepicker(RichMenuDatetimepickerActionRequest {
+                    label: a.label,
+                    data: a.data,
+                    mode: a.mode.into(),
+                    initial: a.initial,
+                    max: a.max,
+                    min: a.min,
+                })
+            }
+            RichMenuAction::Richmenuswitch(a) => {
+                RichMenuActionRequest::Richmenuswitch(RichMenuRichmenuswitchActionRequest {
+                    label: a.label,
+                    rich_menu_alias_id: a.rich_menu_alias_id.0,
+                    data: a.data,
+                })
+            }
+        }
+    }
+}
+
+impl From<RichMenuActionRequest> for RichMenuAction {
+    fn from(s: RichMenuActionRequest) -> Self {
+        match s {
+            RichMenuActionRequest::Postback(a) => RichMenuAction::Postback(
+                RichMenuPostbackAction::new(a.label, a.data, a.display_text),
+            ),
+            RichMenuActionRequest::Message(a) => {
+                RichMenuAction::Message(RichMenuMessageAction::new(a.label, a.text))
+            }
+            RichMenuActionRequest::Uri(a) => {
+                RichMenuAction::Uri(RichMenuUriAction::new(a.label, a.uri))
+            }
+            RichMenuActionRequest::Datetimepicker(a) => {
+                RichMenuAction::Datetimepicker(RichMenuDatetimepickerAction::new(
+                    a.label,
+                    a.data,
+                    a.mode.into(),
+                    a.initial,
+                    a.max,
+                    a.min,
+                ))
+            }
+            RichMenuActionRequest::Richmenuswitch(a) => {
+                RichMenuAction::Richmenuswitch(RichMenuRichmenuswitchAction::new(
+                    a.label,
+                    RichMenuAliasId::new(a.rich_menu_alias_id),
+                    a.data,
+                ))
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RichMenuPostbackActionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_text: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RichMenuMessageActionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RichMenuUriActionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub uri: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RichMenuDatetimepickerActionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub data: String,
+    pub mode: RichMenuDatetimepickerModeRequest,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum RichMenuDatetimepickerModeRequest {
+    Date,
+    Time,
+    Datetime,
+}
+
+impl From<RichMenuDatetimepickerMode> for RichMenuDatetimepickerModeRequest {
+    fn from(s: RichMenuDatetimepickerMode) -> Self {
+        match s {
+            RichMenuDatetimepickerMode::Date => Self::Date,
+            RichMenuDatetimepickerMode::Time => Self::Time,
+            RichMenuDatetimepickerMode::Datetime => Self::Datetime,
+        }
+    }
+}
+
+impl From<RichMenuDatetimepickerModeRequest> for RichMenuDatetimepickerMode {
+    fn from(s: RichMenuDatetimepickerModeRequest) -> Self {
+        match s {
+            RichMenuDatetimepickerModeRequest::Date => Self::Date,
+            RichMenuDatetimepickerModeRequest::Time => Self::Time,
+            RichMenuDatetimepickerModeRequest::Datetime => Self::Datetime,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RichMenuRichmenuswitchActionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub rich_menu_alias_id: String,
+    pub data: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RichMenuBulkLinkRequest {
+    pub rich_menu_id: String,
+    pub user_ids: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RichMenuBulkUnlinkRequest {
+    pub user_ids: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRichMenuAliasRequest {
+    pub rich_menu_alias_id: String,
+    pub rich_menu_id: String,
+}
+
+impl From<RichMenuAlias> for CreateRichMenuAliasRequest {
+    fn from(s: RichMenuAlias) -> Self {
+        Self {
+            rich_menu_alias_id: s.alias_id.0,
+            rich_menu_id: s.rich_menu_id.0,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRichMenuAliasRequest {
+    pub rich_menu_id: String,
+}
+
+/*
+ * Response
+ * リッチメニューAPIのレスポンスをDeserializeする用
+ */
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedRichMenuResponse {
+    pub rich_menu_id: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RichMenuResponse {
+    pub rich_menu_id: String,
+    pub size: RichMenuSizeRequest,
+    pub selected: bool,
+    pub name: String,
+    pub chat_bar_text: String,
+    pub areas: Vec<RichMenuAreaRequest>,
+}
+
+impl From<RichMenuResponse> for RichMenu {
+    fn from(s: RichMenuResponse) -> Self {
+        RichMenu::new(
+            RichMenuId::new(s.rich_menu_id),
+            s.size.into(),
+            s.selected,
+            s.name,
+            s.chat_bar_text,
+            s.areas.into_iter().map(|a| a.into()).collect(),
+        )
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RichMenuListResponse {
+    pub richmenus: Vec<RichMenuResponse>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RichMenuAliasResponse {
+    pub rich_menu_alias_id: String,
+    pub rich_menu_id: String,
+}
+
+impl From<RichMenuAliasResponse> for RichMenuAlias {
+    fn from(s: RichMenuAliasResponse) -> Self {
+        RichMenuAlias::new(
+            RichMenuAliasId::new(s.rich_menu_alias_id),
+            RichMenuId::new(s.rich_menu_id),
+        )
+    }
+}
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Local};
+use domain::model::{
+    email_user::{EmailAddress, PasswordHash},
+    staff::{Staff, StaffId, StaffRole},
+};
+use sqlx::FromRow;
+
+#[derive(FromRow, Debug)]
+pub struct StaffTable {
+    pub id: i64,
+    pub email: String,
+    pub name: String,
+    pub picture_url: String,
+    pub role: String,
+    pub password_hash: String,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+impl TryFrom<StaffTable> for Staff {
+    type Error = anyhow::Error;
+    fn try_from(s: StaffTable) -> Result<Self, Self::Error> {
+        Ok(Staff {
+            id: StaffId::new(s.id),
+            name: s.name,
+            email: EmailAddress::new(s.email),
+            picture_url: s.picture_url,
+            role: s.role.parse::<StaffRole>()?,
+            password_hash: PasswordHash::new(s.password_hash),
+        })
+    }
+}
@@ -1,15 +1,24 @@
+use anyhow::anyhow;
 use chrono::{DateTime, Local};
+use firestore::{paths_camel_case, FirestoreDocument};
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{types::Json, FromRow};
+use std::collections::HashMap;
 use strum_macros::Display;
 
 use domain::model::{
     message::{
-        event::{NewEvent, NewEventMessage, NewEventMessageContent},
-        send_message::NewSendMessage,
-        NewMessages,
+        event::{Event, EventMessageContent, NewEvent, NewEventMessage, NewEventMessageContent},
+        send_message::{NewSendMessage, SendMessage},
+        Messages, NewMessages,
     },
-    talk_room::NewTalkRoom,
+    primary_user_id::PrimaryUserId,
+    staff::StaffId,
+    talk_room::{
+        NewTalkRoom, NewTalkRoomWorkflow, TalkRoom, TalkRoomMode, TalkRoomStatus,
+        TalkRoomWorkflowFields,
+    },
+    talk_room_card::{LatestMessagePreview, TalkRoomCard},
 };
 
 #[derive(FromRow, Debug)]
@@ -19,6 +28,77 @@ pub struct TalkRoomDbTable {
     pub created_at: DateTime<Local>,
 }
 
+/// TALK_ROOM_REPOSITORY=mysqlのときに、talk_roomsからtalkRoomCardsと同じ項目を読み込む
+#[derive(FromRow, Debug)]
+pub struct TalkRoomCardDbTable {
+    pub document_id: String,
+    pub primary_user_id: String,
+    pub display_name: String,
+    pub rsvp: bool,
+    pub pinned: bool,
+    pub follow: bool,
+    // firestoreを使っていたときに作った行にはないので、NULLになる
+    pub latest_message: Option<Json<LatestMessageTable>>,
+    pub latest_messaged_at: Option<DateTime<Local>>,
+    pub sort_time: Option<DateTime<Local>>,
+    pub assignee: Option<i64>,
+    pub status: String,
+    pub mode: String,
+    pub unread: bool,
+    pub nickname: Option<String>,
+    pub user_message_count: i64,
+    pub read_message_counts: Option<Json<HashMap<String, i64>>>,
+    pub away_message_off_hours_since: Option<DateTime<Local>>,
+    pub created_at: DateTime<Local>,
+    pub updated_at: Option<DateTime<Local>>,
+}
+
+impl TalkRoomCardDbTable {
+    // firestoreを使っていたときに作った行は、talkRoomCardsの項目を持たない
+    pub fn has_card(&self) -> bool {
+        self.latest_message.is_some()
+            && self.latest_messaged_at.is_some()
+            && self.sort_time.is_some()
+            && self.updated_at.is_some()
+    }
+
+    /// firestoreのtalkRoomCardsと同じ形にして、ドメインモデルへの変換を共通にする
+    pub fn into_talk_room_card_table(self) -> anyhow::Result<TalkRoomCardTable> {
+        let (Some(latest_message), Some(latest_messaged_at), Some(sort_time), Some(updated_at)) = (
+            self.latest_message,
+            self.latest_messaged_at,
+            self.sort_time,
+            self.updated_at,
+        ) else {
+            return Err(anyhow!("talk room card is missing: {}", self.document_id));
+        };
+
+        Ok(TalkRoomCardTable {
+            display_name: self.display_name,
+            rsvp: self.rsvp,
+            pinned: self.pinned,
+            follow: self.follow,
+            latest_message: latest_message.0,
+            latest_messaged_at,
+            sort_time,
+            created_at: self.created_at,
+            updated_at,
+            assignee: self.assignee,
+            status: self.status.parse::<TalkRoomStatus>()?.into(),
+            mode: self.mode.parse::<TalkRoomMode>()?.into(),
+            unread: self.unread,
+            nickname: self.nickname,
+            user_message_count: self.user_message_count,
+            read_message_counts: self
+                .read_message_counts
+                .map(|counts| counts.0)
+                .unwrap_or_default(),
+            away_message_off_hours_since: self.away_message_off_hours_since,
+            document_id: Some(self.document_id),
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct TalkRoomTable {
@@ -43,6 +123,376 @@ pub struct TalkRoomCardTable {
     pub created_at: DateTime<Local>,
     #[serde(with = "firestore::serialize_as_timestamp")]
     pub updated_at: DateTime<Local>,
+    // 担当者とステータスを追加する前のドキュメントにはないので、デフォルトを使う
+    // 担当者なしで絞り込めるように、Noneのときはnullを書き込む
+    #[serde(default, with = "firestore::serialize_as_null")]
+    pub assignee: Option<i64>,
+    #[serde(default)]
+    pub status: TalkRoomStatusTable,
+    // モードを追加する前のドキュメントは、botが返信するtalkRoomとして扱う
+    #[serde(default)]
+    pub mode: TalkRoomModeTable,
+    #[serde(default)]
+    pub unread: bool,
+    // スタッフが付けた呼び名。外したときにnullにする
+    #[serde(default, with = "firestore::serialize_as_null")]
+    pub nickname: Option<String>,
+    // ユーザーから届いたメッセージの累計。incrementで加算する
+    #[serde(default)]
+    pub user_message_count: i64,
+    // スタッフのIDをキーにした、最後に既読にしたときのuserMessageCount
+    #[serde(default)]
+    pub read_message_counts: HashMap<String, i64>,
+    // 最後に不在メッセージを送った営業時間外の区切り。同じ営業時間外に二度送らないために使う
+    #[serde(default, with = "firestore::serialize_as_optional_timestamp")]
+    pub away_message_off_hours_since: Option<DateTime<Local>>,
+    // 一覧の取得時にfirestoreが入れるドキュメントのID。書き込みはしない
+    #[serde(default, rename = "_firestore_id", skip_serializing)]
+    pub document_id: Option<String>,
+}
+
+// talkRoomCardsの一覧の絞り込みに使う項目
+// 項目を追加する前に作ったドキュメントは絞り込みに含まれないので、デフォルトを書き込んで補う
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomCardFilterFieldsTable {
+    #[serde(default, with = "firestore::serialize_as_null")]
+    pub assignee: Option<i64>,
+    #[serde(default)]
+    pub status: TalkRoomStatusTable,
+    #[serde(default)]
+    pub unread: bool,
+}
+
+impl TalkRoomCardFilterFieldsTable {
+    /// ドキュメントにない絞り込みの項目を返す
+    pub fn missing_fields(document: &FirestoreDocument) -> Vec<String> {
+        paths_camel_case!(TalkRoomCardFilterFieldsTable::{ assignee, status, unread })
+            .into_iter()
+            .filter(|field| !document.fields.contains_key(field))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TalkRoomStatusTable {
+    #[default]
+    Open,
+    Pending,
+    Resolved,
+}
+
+impl From<TalkRoomStatus> for TalkRoomStatusTable {
+    fn from(s: TalkRoomStatus) -> Self {
+        match s {
+            TalkRoomStatus::Open => TalkRoomStatusTable::Open,
+            TalkRoomStatus::Pending => TalkRoomStatusTable::Pending,
+            TalkRoomStatus::Resolved => TalkRoomStatusTable::Resolved,
+        }
+    }
+}
+
+impl From<TalkRoomStatusTable> for TalkRoomStatus {
+    fn from(s: TalkRoomStatusTable) -> Self {
+        match s {
+            TalkRoomStatusTable::Open => TalkRoomStatus::Open,
+            TalkRoomStatusTable::Pending => TalkRoomStatus::Pending,
+            TalkRoomStatusTable::Resolved => TalkRoomStatus::Resolved,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum TalkRoomModeTable {
+    #[default]
+    Bot,
+    Human,
+    BotOutsideBusinessHours,
+}
+
+impl From<TalkRoomMode> for TalkRoomModeTable {
+    fn from(s: TalkRoomMode) -> Self {
+        match s {
+            TalkRoomMode::Bot => TalkRoomModeTable::Bot,
+            TalkRoomMode::Human => TalkRoomModeTable::Human,
+            TalkRoomMode::BotOutsideBusinessHours => TalkRoomModeTable::BotOutsideBusinessHours,
+        }
+    }
+}
+
+impl From<TalkRoomModeTable> for TalkRoomMode {
+    fn from(s: TalkRoomModeTable) -> Self {
+        match s {
+            TalkRoomModeTable::Bot => TalkRoomMode::Bot,
+            TalkRoomModeTable::Human => TalkRoomMode::Human,
+            TalkRoomModeTable::BotOutsideBusinessHours => TalkRoomMode::BotOutsideBusinessHours,
+        }
+    }
+}
+
+impl TalkRoomCardTable {
+    pub fn assignee(&self) -> Option<StaffId> {
+        self.assignee.map(StaffId::new)
+    }
+
+    /// メッセージを追加したときに、latestMessageと並び順、ユーザーのメッセージの累計を更新する
+    /// 同時に書き込んだ新しいメッセージがあるときは、latestMessageと並び順を古いメッセージで巻き戻さない
+    /// 担当者、ステータス、モード、ピン留め、rsvp、呼び名、表示名はスタッフが変更するので上書きしない
+    ///
+    /// # Arguments
+    /// * `source` - 追加したメッセージから作ったtalkRoomCards
+    /// * `is_user_message` - ユーザーから届いたメッセージかどうか
+    ///
+    pub fn add_messages(&mut self, source: &TalkRoomCardTable, is_user_message: bool) {
+        if self.latest_messaged_at <= source.latest_messaged_at {
+            self.follow = source.follow;
+            self.latest_message = source.latest_message.clone();
+            self.latest_messaged_at = source.latest_messaged_at;
+            self.sort_time = source.sort_time;
+            self.unread = source.unread;
+            self.created_at = source.created_at;
+        }
+        self.updated_at = source.updated_at;
+        if is_user_message {
+            self.user_message_count += 1;
+        }
+    }
+
+    /// talkRoomCardsがなくなったときに、talk_roomsの作成日時と保存済みのメッセージから作り直す
+    /// 担当者、ステータス、モード、ピン留め、rsvp、呼び名、既読の位置は残っていないので、作成したときと同じ初期値にする
+    /// latestMessageにできるメッセージがなければNoneを返す
+    ///
+    /// # Arguments
+    /// * `display_name` - line_usersに保存したLINEのプロフィールの表示名
+    /// * `created_at` - talk_roomsの作成日時
+    /// * `messages` - talkRoomのすべてのメッセージ。新しい順に並べる
+    ///
+    pub fn rebuild(
+        display_name: String,
+        created_at: DateTime<Local>,
+        messages: &[Messages],
+    ) -> Option<Self> {
+        let (latest_messages, latest_message) = messages
+            .iter()
+            .find_map(|m| LatestMessageTable::from_messages(m).map(|l| (m, l)))?;
+        let latest_messaged_at = *latest_messages.created_at();
+        // 最後に届いたフォローかブロックで決める。どちらもなければフォローしているものとする
+        let follow = messages
+            .iter()
+            .find_map(|m| match m {
+                Messages::Event(Event::Follow(_)) => Some(true),
+                Messages::Event(Event::Unfollow(_)) => Some(false),
+                _ => None,
+            })
+            .unwrap_or(true);
+        let user_message_count = messages
+            .iter()
+            .filter(|m| matches!(m, Messages::Event(Event::Message(_))))
+            .count() as i64;
+
+        Some(TalkRoomCardTable {
+            display_name,
+            rsvp: false,
+            pinned: false,
+            follow,
+            latest_message,
+            latest_messaged_at,
+            sort_time: latest_messaged_at,
+            created_at,
+            updated_at: Local::now(),
+            assignee: None,
+            status: TalkRoomStatusTable::default(),
+            mode: TalkRoomModeTable::default(),
+            unread: matches!(latest_messages, Messages::Event(Event::Message(_))),
+            nickname: None,
+            user_message_count,
+            read_message_counts: HashMap::new(),
+            away_message_off_hours_since: None,
+            document_id: None,
+        })
+    }
+
+    /// latestMessageが指すメッセージと合わせて、talkRoomにする
+    pub fn into_talk_room(
+        self,
+        document_id: String,
+        primary_user_id: PrimaryUserId,
+        latest_messages: Messages,
+    ) -> anyhow::Result<TalkRoom> {
+        Ok(TalkRoom {
+            assignee: self.assignee(),
+            status: self.status.into(),
+            unread: self.unread,
+            nickname: self.nickname,
+            mode: self.mode.into(),
+            ..TalkRoom::new(
+                document_id.try_into()?,
+                primary_user_id,
+                self.display_name,
+                self.rsvp,
+                self.pinned,
+                self.follow,
+                latest_messages,
+                self.latest_messaged_at,
+                self.sort_time,
+                self.created_at,
+                self.updated_at,
+            )
+        })
+    }
+
+    pub fn into_talk_room_card(
+        self,
+        document_id: String,
+        primary_user_id: PrimaryUserId,
+    ) -> anyhow::Result<TalkRoomCard> {
+        Ok(TalkRoomCard {
+            id: document_id.try_into()?,
+            primary_user_id,
+            assignee: self.assignee(),
+            status: self.status.into(),
+            mode: self.mode.into(),
+            latest_message: self.latest_message.preview(),
+            read_message_counts: self
+                .read_message_counts
+                .iter()
+                .filter_map(|(k, v)| k.parse().ok().map(|id| (StaffId::new(id), *v)))
+                .collect(),
+            user_message_count: self.user_message_count,
+            display_name: self.display_name,
+            nickname: self.nickname,
+            rsvp: self.rsvp,
+            pinned: self.pinned,
+            follow: self.follow,
+            unread: self.unread,
+            latest_messaged_at: self.latest_messaged_at,
+            sort_time: self.sort_time,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+// talkRoomCardsの担当者、ステータス、モードだけを更新するときに使う
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomCardWorkflowTable {
+    #[serde(with = "firestore::serialize_as_null")]
+    pub assignee: Option<i64>,
+    pub status: TalkRoomStatusTable,
+    pub mode: TalkRoomModeTable,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub updated_at: DateTime<Local>,
+}
+
+impl TalkRoomCardWorkflowTable {
+    /// 変更した項目とupdatedAtだけを書き込むためのフィールドマスク
+    pub fn update_fields(changed: TalkRoomWorkflowFields) -> Vec<String> {
+        let mut fields = paths_camel_case!(TalkRoomCardWorkflowTable::{ updated_at });
+        if changed.assignee {
+            fields.extend(paths_camel_case!(TalkRoomCardWorkflowTable::{ assignee }));
+        }
+        if changed.status {
+            fields.extend(paths_camel_case!(TalkRoomCardWorkflowTable::{ status }));
+        }
+        if changed.mode {
+            fields.extend(paths_camel_case!(TalkRoomCardWorkflowTable::{ mode }));
+        }
+        fields
+    }
+
+    /// 変更した項目とupdated_atだけを、talkRoomCardsに反映する
+    pub fn apply_to(self, card: &mut TalkRoomCardTable, changed: TalkRoomWorkflowFields) {
+        if changed.assignee {
+            card.assignee = self.assignee;
+        }
+        if changed.status {
+            card.status = self.status;
+        }
+        if changed.mode {
+            card.mode = self.mode;
+        }
+        card.updated_at = self.updated_at;
+    }
+}
+
+impl From<NewTalkRoomWorkflow> for TalkRoomCardWorkflowTable {
+    fn from(s: NewTalkRoomWorkflow) -> Self {
+        TalkRoomCardWorkflowTable {
+            assignee: s.assignee.map(|a| a.0),
+            status: s.status.into(),
+            mode: s.mode.into(),
+            updated_at: s.updated_at,
+        }
+    }
+}
+
+// talkRoomCardsのpinnedだけを更新するときに使う
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomCardPinnedTable {
+    pub pinned: bool,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub updated_at: DateTime<Local>,
+}
+
+// talkRoomCardsのrsvpだけを更新するときに使う
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomCardRsvpTable {
+    pub rsvp: bool,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub updated_at: DateTime<Local>,
+}
+
+// talkRoomCardsのnicknameだけを更新するときに使う
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomCardNicknameTable {
+    #[serde(with = "firestore::serialize_as_null")]
+    pub nickname: Option<String>,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub updated_at: DateTime<Local>,
+}
+
+// talkRoomCardsのawayMessageOffHoursSinceだけを更新するときに使う
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomCardAwayMessageTable {
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub away_message_off_hours_since: DateTime<Local>,
+}
+
+// talkRoomCardsのreadMessageCountsのうち、1人のスタッフの分だけを更新するときに使う
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomCardReadMarkerTable {
+    pub read_message_counts: HashMap<String, i64>,
+}
+
+impl TalkRoomCardReadMarkerTable {
+    // マップのキーは数字から始まるので、バッククォートで囲んだフィールドパスにする
+    pub fn field_path(staff_id: StaffId) -> String {
+        format!("readMessageCounts.`{}`", staff_id.0)
+    }
+}
+
+// talkRoomsのprimaryUserIdだけを更新するときに使う
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomPrimaryUserIdTable {
+    pub primary_user_id: String,
+}
+
+// talkRoomCardsのdisplayNameだけを更新するときに使う
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomCardDisplayNameTable {
+    pub display_name: String,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub updated_at: DateTime<Local>,
 }
 
 #[derive(Serialize, Deserialize, Display, Clone, Debug)]
@@ -54,6 +504,7 @@ pub enum LatestMessageTable {
     Postback(TalkRoomPostbackTable),
     VideoPlayComplete(TalkRoomVideoPlayCompleteTable),
     Message(TalkRoomMessageTable),
+    AccountLink(TalkRoomAccountLinkTable),
 }
 
 impl LatestMessageTable {
@@ -64,8 +515,30 @@ impl LatestMessageTable {
             LatestMessageTable::Postback(e) => &e.document_id,
             LatestMessageTable::VideoPlayComplete(e) => &e.document_id,
             LatestMessageTable::Message(e) => e.document_id(),
+            LatestMessageTable::AccountLink(e) => &e.document_id,
         }
     }
+
+    // talkRoomの一覧に表示する最新メッセージのプレビュー
+    pub fn preview(&self) -> LatestMessagePreview {
+        let message_type = match self {
+            LatestMessageTable::Follow(_) => "follow",
+            LatestMessageTable::Unfollow(_) => "unfollow",
+            LatestMessageTable::Postback(_) => "postback",
+            LatestMessageTable::VideoPlayComplete(_) => "videoplaycomplete",
+            LatestMessageTable::Message(m) => m.message_type(),
+            LatestMessageTable::AccountLink(_) => "accountlink",
+        };
+        let text = match self {
+            LatestMessageTable::Message(TalkRoomMessageTable::Text(m)) => Some(m.text.clone()),
+            _ => None,
+        };
+        LatestMessagePreview::new(
+            self.document_id().to_string(),
+            message_type.to_string(),
+            text,
+        )
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -92,6 +565,12 @@ pub struct TalkRoomVideoPlayCompleteTable {
     document_id: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomAccountLinkTable {
+    document_id: String,
+}
+
 #[derive(Serialize, Deserialize, Display, Clone, Debug)]
 #[serde(tag = "messageType")] // JSONにmessageTypeというフィールドでタグ名を含む
 #[serde(rename_all = "lowercase")]
@@ -121,6 +600,20 @@ impl TalkRoomMessageTable {
             TalkRoomMessageTable::Template(e) => &e.document_id,
         }
     }
+
+    pub fn message_type(&self) -> &'static str {
+        match self {
+            TalkRoomMessageTable::Text(_) => "text",
+            TalkRoomMessageTable::Image(_) => "image",
+            TalkRoomMessageTable::Video(_) => "video",
+            TalkRoomMessageTable::Audio(_) => "audio",
+            TalkRoomMessageTable::File(_) => "file",
+            TalkRoomMessageTable::Location(_) => "location",
+            TalkRoomMessageTable::Sticker(_) => "sticker",
+            TalkRoomMessageTable::Imagemap(_) => "imagemap",
+            TalkRoomMessageTable::Template(_) => "template",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -199,6 +692,15 @@ impl From<NewTalkRoom> for TalkRoomCardTable {
             sort_time: s.sort_time,
             created_at: s.created_at,
             updated_at: s.created_at,
+            assignee: s.assignee.map(|a| a.0),
+            status: s.status.into(),
+            mode: s.mode.into(),
+            unread: s.unread,
+            nickname: s.nickname,
+            user_message_count: 0,
+            read_message_counts: HashMap::new(),
+            away_message_off_hours_since: None,
+            document_id: None,
         }
     }
 }
@@ -232,6 +734,9 @@ impl From<NewEvent> for LatestMessageTable {
                 })
             }
             NewEvent::Message(e) => LatestMessageTable::Message(e.into()),
+            NewEvent::AccountLink(e) => LatestMessageTable::AccountLink(TalkRoomAccountLinkTable {
+                document_id: e.id.value.to_string(),
+            }),
         }
     }
 }
@@ -306,3 +811,177 @@ impl TalkRoomMessageTable {
         }
     }
 }
+
+impl LatestMessageTable {
+    /// 保存済みのメッセージから、talkRoomCardsのlatestMessageを作り直す
+    /// system_eventはlatestMessageにしないのでNoneを返す
+    pub fn from_messages(messages: &Messages) -> Option<Self> {
+        let document_id = messages.id();
+        match messages {
+            Messages::Event(e) => Some(match e {
+                Event::Follow(_) => LatestMessageTable::Follow(TalkRoomFollowTable { document_id }),
+                Event::Unfollow(_) => {
+                    LatestMessageTable::Unfollow(TalkRoomUnfollowTable { document_id })
+                }
+                Event::Postback(_) => {
+                    LatestMessageTable::Postback(TalkRoomPostbackTable { document_id })
+                }
+                Event::VideoPlayComplete(_) => {
+                    LatestMessageTable::VideoPlayComplete(TalkRoomVideoPlayCompleteTable {
+                        document_id,
+                    })
+                }
+                Event::Message(m) => LatestMessageTable::Message(match &m.message {
+                    EventMessageContent::Text(t) => {
+                        TalkRoomMessageTable::Text(TalkRoomMessageTextTable {
+                            document_id,
+                            text: t.text.clone(),
+                        })
+                    }
+                    EventMessageContent::Image(_) => {
+                        TalkRoomMessageTable::Image(TalkRoomImageMessageTable { document_id })
+                    }
+                    EventMessageContent::Video(_) => {
+                        TalkRoomMessageTable::Video(TalkRoomVideoMessageTable { document_id })
+                    }
+                    EventMessageContent::Audio(_) => {
+                        TalkRoomMessageTable::Audio(TalkRoomAudioMessageTable { document_id })
+                    }
+                    EventMessageContent::File(_) => {
+                        TalkRoomMessageTable::File(TalkRoomFileMessageTable { document_id })
+                    }
+                    EventMessageContent::Location(_) => {
+                        TalkRoomMessageTable::Location(TalkRoomLocationMessageTable { document_id })
+                    }
+                    EventMessageContent::Sticker(_) => {
+                        TalkRoomMessageTable::Sticker(TalkRoomStickerMessageTable { document_id })
+                    }
+                }),
+                Event::AccountLink(_) => {
+                    LatestMessageTable::AccountLink(TalkRoomAccountLinkTable { document_id })
+                }
+            }),
+            // 複数のメッセージを送ったときは、最後のメッセージをlatestMessageにする
+            Messages::SendMessages(m) => m.messages.last().map(|message| {
+                LatestMessageTable::Message(match message {
+                    SendMessage::Text(t) => TalkRoomMessageTable::Text(TalkRoomMessageTextTable {
+                        document_id,
+                        text: t.text.clone(),
+                    }),
+                    SendMessage::Sticker(_) => {
+                        TalkRoomMessageTable::Sticker(TalkRoomStickerMessageTable { document_id })
+                    }
+                    SendMessage::Image(_) => {
+                        TalkRoomMessageTable::Image(TalkRoomImageMessageTable { document_id })
+                    }
+                    SendMessage::Video(_) => {
+                        TalkRoomMessageTable::Video(TalkRoomVideoMessageTable { document_id })
+                    }
+                    SendMessage::Audio(_) => {
+                        TalkRoomMessageTable::Audio(TalkRoomAudioMessageTable { document_id })
+                    }
+                    SendMessage::Location(_) => {
+                        TalkRoomMessageTable::Location(TalkRoomLocationMessageTable { document_id })
+                    }
+                    SendMessage::Imagemap(_) => {
+                        TalkRoomMessageTable::Imagemap(TalkRoomImagemapMessageTable { document_id })
+                    }
+                    SendMessage::Template(_) => {
+                        TalkRoomMessageTable::Template(TalkRoomTemplateMessageTable { document_id })
+                    }
+                })
+            }),
+            Messages::SystemEvent(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Local};
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::model::message::MessagesTable;
+    use domain::model::{
+        message::event::{
+            NewEventDeliveryContext, NewEventFollow, NewEventMessageContentText, NewEventUnfollow,
+        },
+        Id,
+    };
+
+    fn delivery_context() -> NewEventDeliveryContext {
+        NewEventDeliveryContext {
+            is_redelivery: false,
+        }
+    }
+
+    fn stored(event: NewEvent) -> Messages {
+        let (document_id, messages_table) =
+            MessagesTable::from_new_messages(NewMessages::Event(event));
+        messages_table.into_messages(&document_id).unwrap()
+    }
+
+    fn text_message(text: &str, created_at: DateTime<Local>) -> Messages {
+        stored(NewEvent::Message(NewEventMessage {
+            id: Id::gen(),
+            reply_token: "reply_token".to_string(),
+            delivery_context: delivery_context(),
+            message: NewEventMessageContent::Text(NewEventMessageContentText {
+                id: Uuid::new_v4().to_string(),
+                text: text.to_string(),
+                emojis: vec![],
+            }),
+            mode: "active".to_string(),
+            webhook_event_id: Uuid::new_v4().to_string(),
+            created_at,
+        }))
+    }
+
+    #[test]
+    fn test_rebuild_talk_room_card_from_messages() {
+        let now = Local::now();
+        let created_at = now - Duration::days(1);
+        let follow = stored(NewEvent::Follow(NewEventFollow {
+            id: Id::gen(),
+            reply_token: "reply_token".to_string(),
+            delivery_context: delivery_context(),
+            mode: "active".to_string(),
+            webhook_event_id: Uuid::new_v4().to_string(),
+            created_at,
+        }));
+        let unfollow = stored(NewEvent::Unfollow(NewEventUnfollow {
+            id: Id::gen(),
+            delivery_context: delivery_context(),
+            mode: "active".to_string(),
+            webhook_event_id: Uuid::new_v4().to_string(),
+            created_at: now,
+        }));
+        let first = text_message("こんにちは", created_at + Duration::hours(1));
+        let latest = text_message("お薬について", created_at + Duration::hours(2));
+
+        let card = TalkRoomCardTable::rebuild(
+            "テストユーザー".to_string(),
+            created_at,
+            &[latest.clone(), first, follow.clone()],
+        )
+        .unwrap();
+        assert_eq!(card.display_name, "テストユーザー");
+        assert_eq!(card.latest_message.document_id(), &latest.id());
+        assert_eq!(card.latest_messaged_at, *latest.created_at());
+        assert_eq!(card.sort_time, *latest.created_at());
+        assert_eq!(card.created_at, created_at);
+        assert_eq!(card.user_message_count, 2);
+        assert!(card.follow);
+        assert!(card.unread);
+        assert!(card.assignee.is_none());
+
+        // ブロックされた後はフォローしていないものとして作り直す
+        let card =
+            TalkRoomCardTable::rebuild("".to_string(), created_at, &[unfollow, follow]).unwrap();
+        assert!(!card.follow);
+        assert!(!card.unread);
+
+        assert!(TalkRoomCardTable::rebuild("".to_string(), created_at, &[]).is_none());
+    }
+}
@@ -0,0 +1,102 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use domain::model::talk_room_change::{TalkRoomChange, TalkRoomChangeResumeToken};
+use domain::repository::RepositoryError;
+use firestore::{
+    FirestoreListenerTarget, FirestoreListenerTargetResumeType, FirestoreListenerToken,
+    FirestoreResumeStateStorage, ValueStruct,
+};
+use sqlx::FromRow;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc::Sender, Mutex};
+
+type AnyBoxedErrResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// talkRoomの変更の監視を再開する位置を、ターゲットごとにメモリに持つ
+/// 再開する位置が進むたびに、全ターゲットの位置をまとめてCheckpointとして送る
+#[derive(Clone)]
+pub struct TalkRoomChangeResumeTokens {
+    // ターゲットのIDと、再開する位置を16進数にした文字列
+    tokens: Arc<Mutex<HashMap<u32, String>>>,
+    sender: Sender<Result<TalkRoomChange, RepositoryError>>,
+}
+
+impl TalkRoomChangeResumeTokens {
+    pub fn new(
+        resume_token: Option<TalkRoomChangeResumeToken>,
+        sender: Sender<Result<TalkRoomChange, RepositoryError>>,
+    ) -> anyhow::Result<Self> {
+        let tokens = match resume_token {
+            Some(t) => serde_json::from_str(&t.0)?,
+            None => HashMap::new(),
+        };
+        Ok(Self {
+            tokens: Arc::new(Mutex::new(tokens)),
+            sender,
+        })
+    }
+}
+
+#[async_trait]
+impl FirestoreResumeStateStorage for TalkRoomChangeResumeTokens {
+    async fn read_resume_state(
+        &self,
+        target: &FirestoreListenerTarget,
+    ) -> AnyBoxedErrResult<Option<FirestoreListenerTargetResumeType>> {
+        match self.tokens.lock().await.get(target.value()) {
+            Some(token) => Ok(Some(FirestoreListenerTargetResumeType::Token(
+                FirestoreListenerToken::new(decode_hex(token)?),
+            ))),
+            // 再開する位置がないときは、すでにあるドキュメントを受け取らないように今から後の変更だけを受け取る
+            None => Ok(Some(FirestoreListenerTargetResumeType::ReadTime(
+                Local::now(),
+            ))),
+        }
+    }
+
+    async fn update_resume_token(
+        &self,
+        target: &FirestoreListenerTarget,
+        token: FirestoreListenerToken,
+    ) -> AnyBoxedErrResult<()> {
+        let resume_token = {
+            let mut tokens = self.tokens.lock().await;
+            tokens.insert(*target.value(), encode_hex(token.value()));
+            serde_json::to_string(&*tokens)?
+        };
+        // 受け取る側がいなくなったときは監視を止めるので、送れなくてもエラーにしない
+        self.sender
+            .send(Ok(TalkRoomChange::Checkpoint(
+                TalkRoomChangeResumeToken::new(resume_token),
+            )))
+            .await
+            .ok();
+        Ok(())
+    }
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(s: &str) -> anyhow::Result<Vec<u8>> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .ok_or(anyhow::anyhow!("Invalid resume token: {}", s))
+                .and_then(|b| Ok(u8::from_str_radix(b, 16)?))
+        })
+        .collect()
+}
+
+/// TALK_ROOM_REPOSITORY=mysqlのときに、talk_room_changesから読み込む
+/// message_document_idがないときはtalk_roomsの変更、あるときはメッセージの追加
+#[derive(FromRow, Debug)]
+pub struct TalkRoomChangeDbTable {
+    pub id: i64,
+    pub talk_room_document_id: String,
+    pub message_document_id: Option<String>,
+    pub created_at: DateTime<Local>,
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use domain::model::{
+    staff::StaffId,
+    talk_room::TalkRoom,
+    talk_room_note::{NewTalkRoomNote, TalkRoomNote, TalkRoomNoteAuthor},
+    Id,
+};
+
+/// talkRoomsのサブコレクションnotesに保存するメモ
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomNoteTable {
+    pub author: TalkRoomNoteAuthorTable,
+    pub body: String,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomNoteAuthorTable {
+    pub staff_id: i64,
+    pub name: String,
+}
+
+impl From<NewTalkRoomNote> for TalkRoomNoteTable {
+    fn from(n: NewTalkRoomNote) -> Self {
+        TalkRoomNoteTable {
+            author: TalkRoomNoteAuthorTable {
+                staff_id: n.author.id.0,
+                name: n.author.name,
+            },
+            body: n.body,
+            created_at: n.created_at,
+            updated_at: n.created_at,
+        }
+    }
+}
+
+impl TalkRoomNoteTable {
+    pub fn into_talk_room_note(
+        self,
+        talk_room_id: Id<TalkRoom>,
+        document_id: String,
+    ) -> anyhow::Result<TalkRoomNote> {
+        Ok(TalkRoomNote::new(
+            document_id.try_into()?,
+            talk_room_id,
+            TalkRoomNoteAuthor::new(StaffId::new(self.author.staff_id), self.author.name),
+            self.body,
+            self.created_at,
+            self.updated_at,
+        ))
+    }
+}
+
+/// TALK_ROOM_REPOSITORY=mysqlのときに、talk_room_notesから読み込む
+#[derive(FromRow, Debug)]
+pub struct TalkRoomNoteDbTable {
+    pub document_id: String,
+    pub author_staff_id: i64,
+    pub author_name: String,
+    pub body: String,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+impl TalkRoomNoteDbTable {
+    pub fn into_talk_room_note(self, talk_room_id: Id<TalkRoom>) -> anyhow::Result<TalkRoomNote> {
+        TalkRoomNoteTable {
+            author: TalkRoomNoteAuthorTable {
+                staff_id: self.author_staff_id,
+                name: self.author_name,
+            },
+            body: self.body,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+        .into_talk_room_note(talk_room_id, self.document_id)
+    }
+}
+
+// メモの本文だけを更新するときに使う
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomNoteBodyTable {
+    pub body: String,
+    pub updated_at: DateTime<Local>,
+}
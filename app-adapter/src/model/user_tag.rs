@@ -0,0 +1,38 @@
+use domain::model::{
+    primary_user_id::PrimaryUserId,
+    user_auth::LineId,
+    user_tag::{LineUserTags, UserTag},
+};
+use sqlx::FromRow;
+
+// line_usersとuser_tagsをleft joinした行。タグがないユーザーはtagがNULLになる
+#[derive(FromRow, Debug)]
+pub struct LineUserTagRow {
+    pub primary_user_id: String,
+    pub line_id: String,
+    pub tag: Option<String>,
+}
+
+/// primary_user_id順に並んだ行を、ユーザーごとのLineUserTagsにまとめる
+///
+/// # Arguments
+/// * `rows` - primary_user_idでソート済みの行
+///
+pub fn into_line_user_tags_vec(rows: Vec<LineUserTagRow>) -> anyhow::Result<Vec<LineUserTags>> {
+    let mut line_user_tags_vec: Vec<LineUserTags> = Vec::new();
+    for row in rows {
+        let tag = row.tag.map(|t| t.parse::<UserTag>()).transpose()?;
+        match line_user_tags_vec.last_mut() {
+            Some(last) if last.primary_user_id.value() == &row.primary_user_id => {
+                last.tags.extend(tag);
+            }
+            _ => line_user_tags_vec.push(LineUserTags::new(
+                PrimaryUserId::new(row.primary_user_id),
+                LineId::new(row.line_id),
+                tag.into_iter().collect(),
+            )),
+        }
+    }
+
+    Ok(line_user_tags_vec)
+}
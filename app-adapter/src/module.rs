@@ -1,35 +1,104 @@
-use crate::gateway::HttpClientRepositoryImpl;
-use crate::persistance::{firestore::Firestore, mysql::Db};
-use crate::repository::{DatabaseRepositoryImpl, DbFirestoreRepositoryImpl};
-use domain::gateway::{send_message::SendMessageGateway, user_auth::UserAuthGateway};
+use crate::gateway::{in_memory::InMemoryLine, HttpClientRepositoryImpl, InMemoryGatewayImpl};
+use crate::persistance::{db::Db, firestore::Firestore, in_memory::InMemoryDb};
+use crate::repository::{
+    DatabaseRepositoryImpl, DbFirestoreRepositoryImpl, FirestoreRepositoryImpl,
+    InMemoryRepositoryImpl,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use domain::gateway::{
+    rich_menu::RichMenuGateway, send_message::SendMessageGateway, user_auth::UserAuthGateway,
+};
 use domain::model::message::send_message::SendMessage;
-use domain::model::{talk_room::TalkRoom, user::User, user_auth::UserAuthData};
-use domain::repository::{talk_room::TalkRoomRepository, user::UserRepository};
+use domain::model::{
+    business_hours::BusinessHoursCalendar, canned_response::CannedResponse, rich_menu::RichMenu,
+    staff::Staff, talk_room::TalkRoom, talk_room_note::TalkRoomNote, user::User,
+    user_auth::UserAuthData, user_tag::UserTag,
+};
+use domain::repository::{
+    business_hours::BusinessHoursRepository, canned_response::CannedResponseRepository,
+    staff::StaffRepository, talk_room::TalkRoomRepository, talk_room_note::TalkRoomNoteRepository,
+    user::UserRepository, user_tag::UserTagRepository,
+};
 use reqwest::Client;
 
-pub trait AdaptersModuleExt {
-    type UserAuthGate: UserAuthGateway;
-    type UserRepo: UserRepository;
-    type TalkRoomRepo: TalkRoomRepository;
-    type SendMessageGate: SendMessageGateway;
+// サーバーのスレッドをまたいで共有するので、Send + Syncにする
+#[async_trait]
+pub trait AdaptersModuleExt: Send + Sync + 'static {
+    type UserAuthGate: UserAuthGateway + Send + Sync;
+    type UserRepo: UserRepository + Send + Sync;
+    type TalkRoomRepo: TalkRoomRepository + Send + Sync;
+    type SendMessageGate: SendMessageGateway + Send + Sync;
+    type RichMenuGate: RichMenuGateway + Send + Sync;
+    type UserTagRepo: UserTagRepository + Send + Sync;
+    type StaffRepo: StaffRepository + Send + Sync;
+    type TalkRoomNoteRepo: TalkRoomNoteRepository + Send + Sync;
+    type CannedResponseRepo: CannedResponseRepository + Send + Sync;
+    type BusinessHoursRepo: BusinessHoursRepository + Send + Sync;
     fn user_auth_gateway(&self) -> &Self::UserAuthGate;
     fn user_repository(&self) -> &Self::UserRepo;
     fn talk_room_repository(&self) -> &Self::TalkRoomRepo;
     fn send_message_gateway(&self) -> &Self::SendMessageGate;
+    fn rich_menu_gateway(&self) -> &Self::RichMenuGate;
+    fn user_tag_repository(&self) -> &Self::UserTagRepo;
+    fn staff_repository(&self) -> &Self::StaffRepo;
+    fn talk_room_note_repository(&self) -> &Self::TalkRoomNoteRepo;
+    fn canned_response_repository(&self) -> &Self::CannedResponseRepo;
+    fn business_hours_repository(&self) -> &Self::BusinessHoursRepo;
+
+    /// 外部のサービスに接続でき、リクエストを処理できる状態か確かめる
+    /// 外部のサービスを使わないアダプターは、いつでも処理できる
+    async fn check_readiness(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
 
-pub struct AdaptersModule {
+/// T、N、Bはtalk room、メモ、営業時間のリポジトリ
+/// firestoreを使わない環境では、MySqlAdaptersModuleのとおりDatabaseRepositoryImplを使う
+pub struct AdaptersModule<
+    T = DbFirestoreRepositoryImpl<TalkRoom>,
+    N = FirestoreRepositoryImpl<TalkRoomNote>,
+    B = FirestoreRepositoryImpl<BusinessHoursCalendar>,
+> {
+    db: Db,
+    // firestoreを使わない環境ではNone
+    firestore: Option<Firestore>,
     user_auth_gateway: HttpClientRepositoryImpl<UserAuthData>,
     user_repository: DatabaseRepositoryImpl<User>,
-    talk_room_repository: DbFirestoreRepositoryImpl<TalkRoom>,
+    talk_room_repository: T,
     send_message_gateway: HttpClientRepositoryImpl<SendMessage>,
+    rich_menu_gateway: HttpClientRepositoryImpl<RichMenu>,
+    user_tag_repository: DatabaseRepositoryImpl<UserTag>,
+    staff_repository: DatabaseRepositoryImpl<Staff>,
+    talk_room_note_repository: N,
+    canned_response_repository: DatabaseRepositoryImpl<CannedResponse>,
+    business_hours_repository: B,
 }
 
-impl AdaptersModuleExt for AdaptersModule {
+/// talk room、メモ、営業時間もMySQLに持ち、firestoreを使わない
+pub type MySqlAdaptersModule = AdaptersModule<
+    DatabaseRepositoryImpl<TalkRoom>,
+    DatabaseRepositoryImpl<TalkRoomNote>,
+    DatabaseRepositoryImpl<BusinessHoursCalendar>,
+>;
+
+#[async_trait]
+impl<T, N, B> AdaptersModuleExt for AdaptersModule<T, N, B>
+where
+    T: TalkRoomRepository + Send + Sync + 'static,
+    N: TalkRoomNoteRepository + Send + Sync + 'static,
+    B: BusinessHoursRepository + Send + Sync + 'static,
+{
     type UserAuthGate = HttpClientRepositoryImpl<UserAuthData>;
     type UserRepo = DatabaseRepositoryImpl<User>;
-    type TalkRoomRepo = DbFirestoreRepositoryImpl<TalkRoom>;
+    type TalkRoomRepo = T;
     type SendMessageGate = HttpClientRepositoryImpl<SendMessage>;
+    type RichMenuGate = HttpClientRepositoryImpl<RichMenu>;
+    type UserTagRepo = DatabaseRepositoryImpl<UserTag>;
+    type StaffRepo = DatabaseRepositoryImpl<Staff>;
+    type TalkRoomNoteRepo = N;
+    type CannedResponseRepo = DatabaseRepositoryImpl<CannedResponse>;
+    type BusinessHoursRepo = B;
 
     fn user_auth_gateway(&self) -> &Self::UserAuthGate {
         &self.user_auth_gateway
@@ -43,41 +112,238 @@ impl AdaptersModuleExt for AdaptersModule {
     fn send_message_gateway(&self) -> &Self::SendMessageGate {
         &self.send_message_gateway
     }
+    fn rich_menu_gateway(&self) -> &Self::RichMenuGate {
+        &self.rich_menu_gateway
+    }
+    fn user_tag_repository(&self) -> &Self::UserTagRepo {
+        &self.user_tag_repository
+    }
+    fn staff_repository(&self) -> &Self::StaffRepo {
+        &self.staff_repository
+    }
+    fn talk_room_note_repository(&self) -> &Self::TalkRoomNoteRepo {
+        &self.talk_room_note_repository
+    }
+    fn canned_response_repository(&self) -> &Self::CannedResponseRepo {
+        &self.canned_response_repository
+    }
+    fn business_hours_repository(&self) -> &Self::BusinessHoursRepo {
+        &self.business_hours_repository
+    }
+
+    async fn check_readiness(&self) -> anyhow::Result<()> {
+        self.db.ping().await.context("Database is not ready")?;
+        if let Some(firestore) = &self.firestore {
+            firestore.ping().await.context("Firestore is not ready")?;
+        }
+
+        Ok(())
+    }
 }
 
 impl AdaptersModule {
     pub fn new(client: Client, db: Db, firestore: Firestore) -> Self {
+        let talk_room_repository = DbFirestoreRepositoryImpl::new(db.clone(), firestore.clone());
+        let talk_room_note_repository = FirestoreRepositoryImpl::new(firestore.clone());
+        let business_hours_repository = FirestoreRepositoryImpl::new(firestore.clone());
+        AdaptersModule::with_repositories(
+            client,
+            db,
+            Some(firestore),
+            talk_room_repository,
+            talk_room_note_repository,
+            business_hours_repository,
+        )
+    }
+}
+
+impl MySqlAdaptersModule {
+    /// talkRoomCardsとメッセージ、メモ、営業時間もMySQLに持つ。firestoreには接続しない
+    pub fn with_mysql_talk_rooms(client: Client, db: Db) -> Self {
+        let talk_room_repository = DatabaseRepositoryImpl::new(db.clone());
+        let talk_room_note_repository = DatabaseRepositoryImpl::new(db.clone());
+        let business_hours_repository = DatabaseRepositoryImpl::new(db.clone());
+        AdaptersModule::with_repositories(
+            client,
+            db,
+            None,
+            talk_room_repository,
+            talk_room_note_repository,
+            business_hours_repository,
+        )
+    }
+}
+
+impl<T, N, B> AdaptersModule<T, N, B> {
+    fn with_repositories(
+        client: Client,
+        db: Db,
+        firestore: Option<Firestore>,
+        talk_room_repository: T,
+        talk_room_note_repository: N,
+        business_hours_repository: B,
+    ) -> Self {
         let user_auth_gateway = HttpClientRepositoryImpl::new(client.clone());
         let user_repository = DatabaseRepositoryImpl::new(db.clone());
-        let talk_room_repository = DbFirestoreRepositoryImpl::new(db, firestore.clone());
-        let send_message_gateway = HttpClientRepositoryImpl::new(client);
+        let send_message_gateway = HttpClientRepositoryImpl::new(client.clone());
+        let rich_menu_gateway = HttpClientRepositoryImpl::new(client);
+        let user_tag_repository = DatabaseRepositoryImpl::new(db.clone());
+        let staff_repository = DatabaseRepositoryImpl::new(db.clone());
+        let canned_response_repository = DatabaseRepositoryImpl::new(db.clone());
 
         Self {
+            db,
+            firestore,
             user_auth_gateway,
             user_repository,
             talk_room_repository,
             send_message_gateway,
+            rich_menu_gateway,
+            user_tag_repository,
+            staff_repository,
+            talk_room_note_repository,
+            canned_response_repository,
+            business_hours_repository,
+        }
+    }
+}
+
+/// MySQLとfirestore、LINEのAPIの代わりにメモリを使う
+/// 外部のサービスなしで、ローカルでの動作確認や結合テストに使う
+pub struct InMemoryAdaptersModule {
+    db: InMemoryDb,
+    line: InMemoryLine,
+    user_auth_gateway: InMemoryGatewayImpl<UserAuthData>,
+    user_repository: InMemoryRepositoryImpl<User>,
+    talk_room_repository: InMemoryRepositoryImpl<TalkRoom>,
+    send_message_gateway: InMemoryGatewayImpl<SendMessage>,
+    rich_menu_gateway: InMemoryGatewayImpl<RichMenu>,
+    user_tag_repository: InMemoryRepositoryImpl<UserTag>,
+    staff_repository: InMemoryRepositoryImpl<Staff>,
+    talk_room_note_repository: InMemoryRepositoryImpl<TalkRoomNote>,
+    canned_response_repository: InMemoryRepositoryImpl<CannedResponse>,
+    business_hours_repository: InMemoryRepositoryImpl<BusinessHoursCalendar>,
+}
+
+#[async_trait]
+impl AdaptersModuleExt for InMemoryAdaptersModule {
+    type UserAuthGate = InMemoryGatewayImpl<UserAuthData>;
+    type UserRepo = InMemoryRepositoryImpl<User>;
+    type TalkRoomRepo = InMemoryRepositoryImpl<TalkRoom>;
+    type SendMessageGate = InMemoryGatewayImpl<SendMessage>;
+    type RichMenuGate = InMemoryGatewayImpl<RichMenu>;
+    type UserTagRepo = InMemoryRepositoryImpl<UserTag>;
+    type StaffRepo = InMemoryRepositoryImpl<Staff>;
+    type TalkRoomNoteRepo = InMemoryRepositoryImpl<TalkRoomNote>;
+    type CannedResponseRepo = InMemoryRepositoryImpl<CannedResponse>;
+    type BusinessHoursRepo = InMemoryRepositoryImpl<BusinessHoursCalendar>;
+
+    fn user_auth_gateway(&self) -> &Self::UserAuthGate {
+        &self.user_auth_gateway
+    }
+    fn user_repository(&self) -> &Self::UserRepo {
+        &self.user_repository
+    }
+    fn talk_room_repository(&self) -> &Self::TalkRoomRepo {
+        &self.talk_room_repository
+    }
+    fn send_message_gateway(&self) -> &Self::SendMessageGate {
+        &self.send_message_gateway
+    }
+    fn rich_menu_gateway(&self) -> &Self::RichMenuGate {
+        &self.rich_menu_gateway
+    }
+    fn user_tag_repository(&self) -> &Self::UserTagRepo {
+        &self.user_tag_repository
+    }
+    fn staff_repository(&self) -> &Self::StaffRepo {
+        &self.staff_repository
+    }
+    fn talk_room_note_repository(&self) -> &Self::TalkRoomNoteRepo {
+        &self.talk_room_note_repository
+    }
+    fn canned_response_repository(&self) -> &Self::CannedResponseRepo {
+        &self.canned_response_repository
+    }
+    fn business_hours_repository(&self) -> &Self::BusinessHoursRepo {
+        &self.business_hours_repository
+    }
+}
+
+impl InMemoryAdaptersModule {
+    pub fn new(db: InMemoryDb, line: InMemoryLine) -> Self {
+        Self {
+            user_auth_gateway: InMemoryGatewayImpl::new(line.clone()),
+            user_repository: InMemoryRepositoryImpl::new(db.clone()),
+            talk_room_repository: InMemoryRepositoryImpl::new(db.clone()),
+            send_message_gateway: InMemoryGatewayImpl::new(line.clone()),
+            rich_menu_gateway: InMemoryGatewayImpl::new(line.clone()),
+            user_tag_repository: InMemoryRepositoryImpl::new(db.clone()),
+            staff_repository: InMemoryRepositoryImpl::new(db.clone()),
+            talk_room_note_repository: InMemoryRepositoryImpl::new(db.clone()),
+            canned_response_repository: InMemoryRepositoryImpl::new(db.clone()),
+            business_hours_repository: InMemoryRepositoryImpl::new(db.clone()),
+            db,
+            line,
         }
     }
+
+    /// テストでデータを用意したり、書き込まれた内容を確かめたりするときに使う
+    pub fn db(&self) -> &InMemoryDb {
+        &self.db
+    }
+
+    /// テストでLINEのプロフィールを登録したり、送ったメッセージを確かめたりするときに使う
+    pub fn line(&self) -> &InMemoryLine {
+        &self.line
+    }
+}
+
+impl Default for InMemoryAdaptersModule {
+    fn default() -> Self {
+        Self::new(InMemoryDb::new(), InMemoryLine::new())
+    }
 }
 
 pub mod test {
     use super::AdaptersModuleExt;
-    use domain::gateway::{send_message::MockSendMessageGateway, user_auth::MockUserAuthGateway};
-    use domain::repository::{talk_room::MockTalkRoomRepository, user::MockUserRepository};
+    use async_trait::async_trait;
+    use domain::gateway::{
+        rich_menu::MockRichMenuGateway, send_message::MockSendMessageGateway,
+        user_auth::MockUserAuthGateway,
+    };
+    use domain::repository::{
+        business_hours::MockBusinessHoursRepository, canned_response::MockCannedResponseRepository,
+        staff::MockStaffRepository, talk_room::MockTalkRoomRepository,
+        talk_room_note::MockTalkRoomNoteRepository, user::MockUserRepository,
+        user_tag::MockUserTagRepository,
+    };
 
     pub struct TestAdaptersModule {
         user_auth_gateway: MockUserAuthGateway,
         user_repository: MockUserRepository,
         talk_room_repository: MockTalkRoomRepository,
         send_message_gateway: MockSendMessageGateway,
+        rich_menu_gateway: MockRichMenuGateway,
+        user_tag_repository: MockUserTagRepository,
+        staff_repository: MockStaffRepository,
+        talk_room_note_repository: MockTalkRoomNoteRepository,
+        canned_response_repository: MockCannedResponseRepository,
+        business_hours_repository: MockBusinessHoursRepository,
     }
 
+    #[async_trait]
     impl AdaptersModuleExt for TestAdaptersModule {
         type UserAuthGate = MockUserAuthGateway;
         type UserRepo = MockUserRepository;
         type TalkRoomRepo = MockTalkRoomRepository;
         type SendMessageGate = MockSendMessageGateway;
+        type RichMenuGate = MockRichMenuGateway;
+        type UserTagRepo = MockUserTagRepository;
+        type StaffRepo = MockStaffRepository;
+        type TalkRoomNoteRepo = MockTalkRoomNoteRepository;
+        type CannedResponseRepo = MockCannedResponseRepository;
+        type BusinessHoursRepo = MockBusinessHoursRepository;
 
         fn user_auth_gateway(&self) -> &Self::UserAuthGate {
             &self.user_auth_gateway
@@ -91,20 +357,52 @@ pub mod test {
         fn send_message_gateway(&self) -> &Self::SendMessageGate {
             &self.send_message_gateway
         }
+        fn rich_menu_gateway(&self) -> &Self::RichMenuGate {
+            &self.rich_menu_gateway
+        }
+        fn user_tag_repository(&self) -> &Self::UserTagRepo {
+            &self.user_tag_repository
+        }
+        fn staff_repository(&self) -> &Self::StaffRepo {
+            &self.staff_repository
+        }
+        fn talk_room_note_repository(&self) -> &Self::TalkRoomNoteRepo {
+            &self.talk_room_note_repository
+        }
+        fn canned_response_repository(&self) -> &Self::CannedResponseRepo {
+            &self.canned_response_repository
+        }
+        fn business_hours_repository(&self) -> &Self::BusinessHoursRepo {
+            &self.business_hours_repository
+        }
     }
 
     impl TestAdaptersModule {
+        // テストではアダプターごとにモックを渡す
+        #[allow(clippy::too_many_arguments)]
         pub fn new(
             user_auth_gateway: MockUserAuthGateway,
             user_repository: MockUserRepository,
             talk_room_repository: MockTalkRoomRepository,
             send_message_gateway: MockSendMessageGateway,
+            rich_menu_gateway: MockRichMenuGateway,
+            user_tag_repository: MockUserTagRepository,
+            staff_repository: MockStaffRepository,
+            talk_room_note_repository: MockTalkRoomNoteRepository,
+            canned_response_repository: MockCannedResponseRepository,
+            business_hours_repository: MockBusinessHoursRepository,
         ) -> Self {
             Self {
                 user_auth_gateway,
                 user_repository,
                 talk_room_repository,
                 send_message_gateway,
+                rich_menu_gateway,
+                user_tag_repository,
+                staff_repository,
+                talk_room_note_repository,
+                canned_response_repository,
+                business_hours_repository,
             }
         }
     }
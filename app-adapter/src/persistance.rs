@@ -1,2 +1,3 @@
+pub mod db;
 pub mod firestore;
-pub mod mysql;
+pub mod in_memory;
@@ -0,0 +1,301 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveDateTime};
+use sqlx::{
+    database::HasArguments,
+    migrate::{Migrate, Migrator},
+    mysql::MySqlPoolOptions,
+    postgres::PgPoolOptions,
+    query::Query,
+    sqlite::SqlitePoolOptions,
+    Database, MySql, Pool, Postgres, Row, Sqlite,
+};
+
+use crate::repository::RepositoryError;
+
+/// DATABASE_URLのスキームで選んだデータベースへの接続
+/// リポジトリはwith_pool!で接続先ごとのプールを取り出し、同じ処理を実行する
+#[derive(Clone)]
+pub enum Db {
+    MySql(Arc<Pool<MySql>>),
+    Postgres(Arc<Pool<Postgres>>),
+    Sqlite(Arc<Pool<Sqlite>>),
+}
+
+impl Db {
+    /// mysql://、postgres://、sqlite:のどれかで始まるURLに接続する
+    pub async fn connect(database_url: &str) -> anyhow::Result<Db> {
+        let db = match database_url.split(':').next() {
+            Some("mysql") | Some("mariadb") => Db::MySql(Arc::new(
+                MySqlPoolOptions::new()
+                    .max_connections(8)
+                    .connect(database_url)
+                    .await?,
+            )),
+            Some("postgres") | Some("postgresql") => Db::Postgres(Arc::new(
+                PgPoolOptions::new()
+                    .max_connections(8)
+                    .connect(database_url)
+                    .await?,
+            )),
+            Some("sqlite") => Db::Sqlite(Arc::new(
+                SqlitePoolOptions::new()
+                    // インメモリのデータベースはコネクションごとに別になるので、1つだけにする
+                    .max_connections(if database_url.contains(":memory:") {
+                        1
+                    } else {
+                        8
+                    })
+                    .connect(database_url)
+                    .await?,
+            )),
+            _ => {
+                return Err(anyhow!(
+                    "DATABASE_URL must start with mysql://, postgres:// or sqlite:"
+                ))
+            }
+        };
+
+        Ok(db)
+    }
+
+    /// データベースに接続して、クエリを実行できるか確かめる
+    pub async fn ping(&self) -> anyhow::Result<()> {
+        with_pool!(self, pool => sqlx::query("select 1").execute(&**pool).await.map(|_| ()))?;
+
+        Ok(())
+    }
+
+    /// migrations/の接続先のデータベースのディレクトリにあるマイグレーションのうち、まだのものを適用する
+    /// マイグレーションはビルド時にバイナリに埋め込む
+    pub async fn run_migrations(&self) -> anyhow::Result<()> {
+        let migrator = self.migrator();
+        with_pool!(self, pool => migrator.run(&**pool).await)?;
+
+        Ok(())
+    }
+
+    /// 最後に適用したマイグレーションを1つ戻し、戻したバージョンを返す
+    /// 適用済みのマイグレーションがなければ何もしない
+    pub async fn revert_migration(&self) -> anyhow::Result<Option<i64>> {
+        let migrator = self.migrator();
+        with_pool!(self, pool => {
+            let mut versions = {
+                let mut conn = pool.acquire().await?;
+                conn.ensure_migrations_table().await?;
+                conn.list_applied_migrations()
+                    .await?
+                    .into_iter()
+                    .map(|migration| migration.version)
+                    .collect::<Vec<_>>()
+            };
+            versions.sort();
+            let Some(version) = versions.pop() else {
+                return Ok(None);
+            };
+            // undoは指定したバージョンより後のマイグレーションをすべて戻す
+            migrator
+                .undo(&**pool, versions.last().copied().unwrap_or(0))
+                .await?;
+
+            Ok(Some(version))
+        })
+    }
+
+    fn migrator(&self) -> Migrator {
+        match self {
+            Db::MySql(_) => sqlx::migrate!("../migrations/mysql"),
+            Db::Postgres(_) => sqlx::migrate!("../migrations/postgres"),
+            Db::Sqlite(_) => sqlx::migrate!("../migrations/sqlite"),
+        }
+    }
+
+    /// MySQLにしかない型や構文を使うリポジトリのためのプール
+    /// ほかのデータベースに接続しているときはエラーにする
+    pub fn mysql_pool(&self) -> Result<&Arc<Pool<MySql>>, RepositoryError> {
+        match self {
+            Db::MySql(pool) => Ok(pool),
+            _ => Err(RepositoryError::Unexpected(
+                "this repository requires MySQL".to_string(),
+            )),
+        }
+    }
+}
+
+/// 接続先のデータベースのプールを$poolに束縛して、$bodyを実行する
+/// $bodyはデータベースごとにコンパイルされるので、SQLはsql()で接続先に合わせて書き換える
+macro_rules! with_pool {
+    ($db:expr, $pool:ident => $body:expr) => {
+        match $db {
+            $crate::persistance::db::Db::MySql($pool) => $body,
+            $crate::persistance::db::Db::Postgres($pool) => $body,
+            $crate::persistance::db::Db::Sqlite($pool) => $body,
+        }
+    };
+}
+pub(crate) use with_pool;
+
+/// リポジトリのSQLはMySQLの書き方で書き、接続先に合わせて書き換える
+#[async_trait]
+pub trait Backend: Database {
+    /// 日時をバインドするときの型
+    type Timestamp;
+
+    /// * Postgres - プレースホルダーの?を$1、$2...にする
+    /// * SQLite - 書き込みでデータベース全体をロックするので、末尾のfor updateを取り除く。
+    ///   MySQLと同じくローカルタイムで書き込むように、CURRENT_TIMESTAMPを書き換える
+    fn sql(query: &str) -> Cow<'_, str>;
+
+    /// MySQLとSQLiteはタイムゾーンを持たずローカルタイムで、Postgresはタイムゾーン付きで持つ
+    fn timestamp(value: DateTime<Local>) -> Self::Timestamp;
+
+    /// 自動採番のidを取得できるように、insert文の末尾に付ける句
+    fn returning_id() -> &'static str;
+
+    /// returning_id()を付けたinsert文を実行して、自動採番されたidを返す
+    async fn insert_returning_id<'q>(
+        pool: &Pool<Self>,
+        query: Query<'q, Self, <Self as HasArguments<'q>>::Arguments>,
+    ) -> Result<i64, sqlx::Error>;
+}
+
+#[async_trait]
+impl Backend for MySql {
+    type Timestamp = NaiveDateTime;
+
+    fn sql(query: &str) -> Cow<'_, str> {
+        Cow::Borrowed(query)
+    }
+
+    fn timestamp(value: DateTime<Local>) -> Self::Timestamp {
+        value.naive_local()
+    }
+
+    // MySQLはreturningを使えないので、実行結果のlast_insert_idから取得する
+    fn returning_id() -> &'static str {
+        ""
+    }
+
+    async fn insert_returning_id<'q>(
+        pool: &Pool<Self>,
+        query: Query<'q, Self, <Self as HasArguments<'q>>::Arguments>,
+    ) -> Result<i64, sqlx::Error> {
+        let result = query.execute(pool).await?;
+        i64::try_from(result.last_insert_id()).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+    }
+}
+
+#[async_trait]
+impl Backend for Postgres {
+    type Timestamp = DateTime<Local>;
+
+    fn sql(query: &str) -> Cow<'_, str> {
+        let mut sql = String::with_capacity(query.len());
+        let mut index = 0;
+        for c in query.chars() {
+            if c == '?' {
+                index += 1;
+                sql.push_str(&format!("${}", index));
+            } else {
+                sql.push(c);
+            }
+        }
+        Cow::Owned(sql)
+    }
+
+    fn timestamp(value: DateTime<Local>) -> Self::Timestamp {
+        value
+    }
+
+    fn returning_id() -> &'static str {
+        " returning id"
+    }
+
+    async fn insert_returning_id<'q>(
+        pool: &Pool<Self>,
+        query: Query<'q, Self, <Self as HasArguments<'q>>::Arguments>,
+    ) -> Result<i64, sqlx::Error> {
+        query.fetch_one(pool).await?.try_get(0)
+    }
+}
+
+#[async_trait]
+impl Backend for Sqlite {
+    type Timestamp = NaiveDateTime;
+
+    fn sql(query: &str) -> Cow<'_, str> {
+        let query = query.trim_end();
+        let query = query.strip_suffix("for update").unwrap_or(query);
+        Cow::Owned(query.replace("CURRENT_TIMESTAMP", "datetime('now', 'localtime')"))
+    }
+
+    fn timestamp(value: DateTime<Local>) -> Self::Timestamp {
+        value.naive_local()
+    }
+
+    fn returning_id() -> &'static str {
+        " returning id"
+    }
+
+    async fn insert_returning_id<'q>(
+        pool: &Pool<Self>,
+        query: Query<'q, Self, <Self as HasArguments<'q>>::Arguments>,
+    ) -> Result<i64, sqlx::Error> {
+        query.fetch_one(pool).await?.try_get(0)
+    }
+}
+
+/// プールの型から接続先を判断して、SQLを書き換える
+pub fn sql<'a, DB: Backend>(_pool: &Pool<DB>, query: &'a str) -> Cow<'a, str> {
+    DB::sql(query)
+}
+
+pub fn timestamp<DB: Backend>(_pool: &Pool<DB>, value: DateTime<Local>) -> DB::Timestamp {
+    DB::timestamp(value)
+}
+
+pub fn returning_id<DB: Backend>(_pool: &Pool<DB>) -> &'static str {
+    DB::returning_id()
+}
+
+pub async fn insert_returning_id<'q, DB: Backend>(
+    pool: &Pool<DB>,
+    query: Query<'q, DB, <DB as HasArguments<'q>>::Arguments>,
+) -> Result<i64, sqlx::Error> {
+    DB::insert_returning_id(pool, query).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::Db;
+
+    #[tokio::test]
+    async fn test_run_and_revert_migrations() {
+        let db = Db::connect("sqlite::memory:").await.unwrap();
+        // 適用済みのマイグレーションがなければ、何も戻さない
+        assert_eq!(db.revert_migration().await.unwrap(), None);
+
+        db.run_migrations().await.unwrap();
+        // 適用済みなら何もしない
+        db.run_migrations().await.unwrap();
+        let Db::Sqlite(pool) = &db else {
+            unreachable!()
+        };
+        let count: i64 = sqlx::query_scalar("select count(*) from primary_users")
+            .fetch_one(&**pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+
+        assert_eq!(db.revert_migration().await.unwrap(), Some(20231126000000));
+        assert_eq!(db.revert_migration().await.unwrap(), Some(20231119000000));
+        assert!(sqlx::query("select count(*) from primary_users")
+            .fetch_one(&**pool)
+            .await
+            .is_err());
+        db.run_migrations().await.unwrap();
+    }
+}
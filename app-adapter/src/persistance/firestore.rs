@@ -1,21 +1,20 @@
 use firestore::*;
-use std::env;
 use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct Firestore(pub(crate) Arc<FirestoreDb>);
 
 impl Firestore {
-    pub async fn new() -> Self {
-        let firestore = FirestoreDb::new(
-            &env::var("FIRESTORE_PROJECT_ID")
-                .unwrap_or_else(|_| panic!("FIRESTORE_PROJECT_ID must be set!")),
-        )
-        .await
-        .unwrap_or_else(|_| {
-            panic!("Cannot connect to the Firestore. Please check your configuration.")
-        });
+    pub async fn connect(project_id: &str) -> anyhow::Result<Self> {
+        let firestore = FirestoreDb::new(project_id).await?;
 
-        Self(Arc::new(firestore))
+        Ok(Self(Arc::new(firestore)))
+    }
+
+    /// firestoreに接続できるか確かめる
+    pub async fn ping(&self) -> anyhow::Result<()> {
+        self.0.ping().await?;
+
+        Ok(())
     }
 }
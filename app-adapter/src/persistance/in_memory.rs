@@ -0,0 +1,116 @@
+use chrono::{DateTime, Local};
+use derive_new::new;
+use domain::model::{
+    account_link::{ExternalMemberId, NewAccountLinkNonce},
+    business_hours::BusinessHoursCalendar,
+    canned_response::CannedResponse,
+    staff::Staff,
+    talk_room_change::TalkRoomChange,
+    talk_room_note::TalkRoomNote,
+    user::UserProfile,
+    user_tag::UserTag,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::broadcast;
+
+use crate::model::message::MessagesTable;
+use crate::model::talk_room::TalkRoomCardTable;
+use crate::repository::RepositoryError;
+
+// 受け取る側が追いつかないときに溜めておく変更の数
+const TALK_ROOM_CHANGE_BUFFER_SIZE: usize = 100;
+
+/// MySQLとfirestoreの代わりに、すべてのテーブルをメモリに持つ
+/// ローカルでの動作確認とテストで使うので、プロセスを止めると消える
+#[derive(Clone)]
+pub struct InMemoryDb(Arc<Mutex<InMemoryTables>>);
+
+impl InMemoryDb {
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(InMemoryTables::default())))
+    }
+
+    /// 読み込みと書き込みは、ロックを取っている間にまとめて行う
+    /// awaitをまたいでロックを持たないようにする
+    pub(crate) fn lock(&self) -> Result<MutexGuard<'_, InMemoryTables>, RepositoryError> {
+        self.0
+            .lock()
+            .map_err(|e| RepositoryError::Unexpected(e.to_string()))
+    }
+}
+
+impl Default for InMemoryDb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) struct InMemoryTables {
+    pub primary_users: BTreeSet<String>,
+    pub users: Vec<InMemoryUserRow>,
+    // primary_user_idごとに連携している会員システムのアカウント
+    pub account_links: HashMap<String, ExternalMemberId>,
+    pub account_link_nonces: HashMap<String, NewAccountLinkNonce>,
+    pub user_tags: HashMap<String, Vec<UserTag>>,
+    pub staffs: Vec<Staff>,
+    pub canned_responses: Vec<CannedResponse>,
+    // talkRoomのドキュメントのIDがキー
+    pub talk_rooms: BTreeMap<String, InMemoryTalkRoom>,
+    pub talk_room_notes: HashMap<String, Vec<TalkRoomNote>>,
+    // チャネルIDがキー
+    pub business_hours_calendars: HashMap<String, BusinessHoursCalendar>,
+    // 変更の監視を再開できるように、これまでの変更を順番に持つ
+    pub talk_room_changes: Vec<TalkRoomChange>,
+    pub talk_room_change_sender: broadcast::Sender<(u64, TalkRoomChange)>,
+}
+
+impl Default for InMemoryTables {
+    fn default() -> Self {
+        let (talk_room_change_sender, _) = broadcast::channel(TALK_ROOM_CHANGE_BUFFER_SIZE);
+        Self {
+            primary_users: BTreeSet::new(),
+            users: vec![],
+            account_links: HashMap::new(),
+            account_link_nonces: HashMap::new(),
+            user_tags: HashMap::new(),
+            staffs: vec![],
+            canned_responses: vec![],
+            talk_rooms: BTreeMap::new(),
+            talk_room_notes: HashMap::new(),
+            business_hours_calendars: HashMap::new(),
+            talk_room_changes: vec![],
+            talk_room_change_sender,
+        }
+    }
+}
+
+impl InMemoryTables {
+    /// 変更を記録し、監視しているストリームに送る
+    /// 変更の位置は1から数えるので、0は何も受け取っていない位置になる
+    pub fn publish_talk_room_change(&mut self, change: TalkRoomChange) {
+        self.talk_room_changes.push(change.clone());
+        let position = self.talk_room_changes.len() as u64;
+        // 監視しているストリームがないときは送れないが、記録には残っている
+        self.talk_room_change_sender.send((position, change)).ok();
+    }
+}
+
+/// 認証プロバイダーごとのユーザー
+/// 同じプロバイダーのユーザーは、1つのprimary_user_idに1つまでしか紐づけられない
+#[derive(new)]
+pub(crate) struct InMemoryUserRow {
+    pub primary_user_id: String,
+    pub user_profile: UserProfile,
+    pub updated_at: DateTime<Local>,
+}
+
+/// MySQLのtalk_roomsと、firestoreのtalkRoomCards、messagesを1つにまとめたもの
+/// firestoreと同じ形で持ち、読み書きの変換もfirestoreと同じものを使う
+#[derive(new)]
+pub(crate) struct InMemoryTalkRoom {
+    pub primary_user_id: String,
+    pub card: TalkRoomCardTable,
+    // messagesのドキュメントのIDがキー
+    pub messages: HashMap<String, MessagesTable>,
+}
@@ -1,14 +1,27 @@
-use crate::persistance::{firestore::Firestore, mysql::Db};
+use crate::persistance::{db::Db, firestore::Firestore, in_memory::InMemoryDb};
 use derive_new::new;
+use firestore::errors::FirestoreError;
 use std::marker::PhantomData;
-use thiserror::Error;
 
+pub mod business_hours;
+pub mod canned_response;
+pub mod in_memory;
+pub mod mysql;
+pub mod staff;
 pub mod talk_room;
+pub mod talk_room_note;
+#[cfg(test)]
+mod talk_room_suite;
 pub mod user;
+pub mod user_tag;
+
+pub use domain::repository::RepositoryError;
 
 const TALK_ROOM_COLLECTION_NAME: &str = "talkRooms";
 const TALK_ROOM_CARD_COLLECTION_NAME: &str = "talkRoomCards";
 const MESSAGE_COLLECTION_NAME: &str = "messages";
+const NOTE_COLLECTION_NAME: &str = "notes";
+const BUSINESS_HOURS_CALENDAR_COLLECTION_NAME: &str = "businessHoursCalendars";
 
 #[derive(new)]
 pub struct DatabaseRepositoryImpl<T> {
@@ -29,16 +42,70 @@ pub struct DbFirestoreRepositoryImpl<T> {
     _marker: PhantomData<T>,
 }
 
-#[derive(Debug, Error)]
-pub enum RepositoryError {
-    #[error("Unexpected Error: {0}")]
-    Unexpected(String),
-    #[error("NotAuthFound, auth_id is {0}")]
-    NotAuthFound(String),
-    #[error("NotFound, table is {0}, id is {1}")]
-    NotFound(String, String),
-    #[error("CouldNotInsert, table is {0}, column {1} is {2}")]
-    CouldNotInsert(String, String, String),
-    #[error(transparent)]
-    Other(#[from] anyhow::Error),
+// MySQLとfirestoreの代わりにメモリを使う。ローカルでの動作確認とテスト用
+#[derive(new)]
+pub struct InMemoryRepositoryImpl<T> {
+    pub db: InMemoryDb,
+    _marker: PhantomData<T>,
+}
+
+/// sqlxのエラーを種類ごとにRepositoryErrorへ振り分ける
+/// 見つからないときは、テーブルとIDが分かる呼び出し側でNotFoundにする
+pub(crate) fn db_error(e: sqlx::Error) -> RepositoryError {
+    match e {
+        sqlx::Error::PoolTimedOut
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::WorkerCrashed => RepositoryError::Unavailable(e.to_string()),
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            RepositoryError::Conflict(
+                db_err.table().unwrap_or_default().to_string(),
+                db_err.constraint().unwrap_or_default().to_string(),
+                db_err.message().to_string(),
+            )
+        }
+        _ => RepositoryError::Unexpected(e.to_string()),
+    }
+}
+
+/// 書き込めなかったときのエラー
+/// 一意キーの競合のときは、何と競合したかが分かるconflictにして、それ以外はdb_errorの振り分けのまま返す
+pub(crate) fn insert_error(e: sqlx::Error, conflict: RepositoryError) -> RepositoryError {
+    conflict_as(db_error(e), conflict)
+}
+
+/// Conflictのときだけconflictに置き換え、それ以外のエラーはそのまま返す
+pub(crate) fn conflict_as(e: RepositoryError, conflict: RepositoryError) -> RepositoryError {
+    match e {
+        RepositoryError::Conflict(..) => conflict,
+        e => e,
+    }
+}
+
+/// firestoreのエラーを種類ごとにRepositoryErrorへ振り分ける
+pub(crate) fn firestore_error(e: FirestoreError) -> RepositoryError {
+    match e {
+        FirestoreError::DataNotFoundError(ref not_found) => RepositoryError::NotFound(
+            "firestore".to_string(),
+            not_found.data_detail_message.clone(),
+        ),
+        FirestoreError::DataConflictError(ref conflict) => RepositoryError::Conflict(
+            "firestore".to_string(),
+            "document".to_string(),
+            conflict.details.clone(),
+        ),
+        FirestoreError::InvalidParametersError(_) => RepositoryError::InvalidInput(e.to_string()),
+        FirestoreError::NetworkError(_) => RepositoryError::Unavailable(e.to_string()),
+        FirestoreError::DatabaseError(ref db_err) if db_err.retry_possible => {
+            RepositoryError::Unavailable(e.to_string())
+        }
+        _ => RepositoryError::Unexpected(e.to_string()),
+    }
+}
+
+/// 保存したデータを読み込めなかったなど、呼び出し側で扱いようのないエラー
+/// モデルの変換はanyhowで返すので、リポジトリの境界でUnexpectedにする
+pub(crate) fn unexpected_error(e: anyhow::Error) -> RepositoryError {
+    RepositoryError::Unexpected(format!("{:#}", e))
 }
@@ -0,0 +1,59 @@
+use async_trait::async_trait;
+use chrono::Local;
+use std::sync::Arc;
+
+use crate::model::business_hours::BusinessHoursCalendarTable;
+use crate::repository::{
+    firestore_error, unexpected_error, FirestoreRepositoryImpl, RepositoryError,
+    BUSINESS_HOURS_CALENDAR_COLLECTION_NAME,
+};
+use domain::{
+    model::business_hours::{BusinessHoursCalendar, NewBusinessHoursCalendar},
+    repository::business_hours::BusinessHoursRepository,
+};
+
+#[async_trait]
+impl BusinessHoursRepository for FirestoreRepositoryImpl<BusinessHoursCalendar> {
+    async fn get_calendar(
+        &self,
+        channel_id: String,
+    ) -> Result<Option<BusinessHoursCalendar>, RepositoryError> {
+        let firestore = Arc::clone(&self.pool.0);
+        let calendar_table: Option<BusinessHoursCalendarTable> = firestore
+            .fluent()
+            .select()
+            .by_id_in(BUSINESS_HOURS_CALENDAR_COLLECTION_NAME)
+            .obj()
+            .one(&channel_id)
+            .await
+            .map_err(firestore_error)?;
+
+        calendar_table
+            .map(|c| c.into_calendar(channel_id))
+            .transpose()
+            .map_err(unexpected_error)
+    }
+
+    /// チャネルの営業時間を丸ごと置き換える
+    async fn save_calendar(
+        &self,
+        channel_id: String,
+        source: NewBusinessHoursCalendar,
+    ) -> Result<BusinessHoursCalendar, RepositoryError> {
+        let firestore = Arc::clone(&self.pool.0);
+        let calendar_table = BusinessHoursCalendarTable::from((source, Local::now()));
+        firestore
+            .fluent()
+            .update()
+            .in_col(BUSINESS_HOURS_CALENDAR_COLLECTION_NAME)
+            .document_id(&channel_id)
+            .object(&calendar_table)
+            .execute::<()>()
+            .await
+            .map_err(firestore_error)?;
+
+        calendar_table
+            .into_calendar(channel_id)
+            .map_err(unexpected_error)
+    }
+}
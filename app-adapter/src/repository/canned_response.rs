@@ -0,0 +1,148 @@
+use crate::model::canned_response::CannedResponseTable;
+use crate::persistance::db::{insert_returning_id, returning_id, sql, with_pool};
+use crate::repository::DatabaseRepositoryImpl;
+use async_trait::async_trait;
+use domain::model::canned_response::{CannedResponse, CannedResponseId, NewCannedResponse};
+use domain::repository::canned_response::CannedResponseRepository;
+
+use super::{db_error, insert_error, RepositoryError};
+
+#[async_trait]
+impl CannedResponseRepository for DatabaseRepositoryImpl<CannedResponse> {
+    async fn get_canned_responses(&self) -> Result<Vec<CannedResponse>, RepositoryError> {
+        let canned_response_rows = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, CannedResponseTable>(
+                r#"
+                select id, title, body, created_by, created_at, updated_at from canned_responses
+                order by id
+                "#,
+            )
+            .fetch_all(&**pool)
+            .await
+        })
+        .map_err(db_error)?;
+
+        Ok(canned_response_rows
+            .into_iter()
+            .map(CannedResponse::from)
+            .collect())
+    }
+
+    async fn get_canned_response(
+        &self,
+        canned_response_id: CannedResponseId,
+    ) -> Result<CannedResponse, RepositoryError> {
+        let canned_response_row = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, CannedResponseTable>(&sql(
+                pool,
+                r#"
+                select id, title, body, created_by, created_at, updated_at from canned_responses
+                where id = ?
+                "#,
+            ))
+            .bind(canned_response_id.0)
+            .fetch_one(&**pool)
+            .await
+        })
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => RepositoryError::NotFound(
+                "canned_responses".to_string(),
+                canned_response_id.0.to_string(),
+            ),
+            _ => db_error(e),
+        })?;
+
+        Ok(canned_response_row.into())
+    }
+
+    async fn create_canned_response(
+        &self,
+        source: NewCannedResponse,
+    ) -> Result<CannedResponse, RepositoryError> {
+        let canned_response_id = with_pool!(&self.pool, pool => {
+            let query = format!(
+                r#"
+                insert into canned_responses (title, body, created_by)
+                values (?, ?, ?){}
+                "#,
+                returning_id(pool)
+            );
+            let query = sql(pool, &query);
+            let query = sqlx::query(&query)
+                .bind(source.title.clone())
+                .bind(source.body)
+                .bind(source.created_by.0);
+            insert_returning_id(pool, query).await
+        })
+        .map_err(|e| {
+            insert_error(
+                e,
+                RepositoryError::Conflict(
+                    "canned_responses".to_string(),
+                    "title".to_string(),
+                    source.title,
+                ),
+            )
+        })?;
+
+        self.get_canned_response(CannedResponseId::new(canned_response_id))
+            .await
+    }
+
+    async fn update_canned_response(
+        &self,
+        canned_response_id: CannedResponseId,
+        title: String,
+        body: String,
+    ) -> Result<CannedResponse, RepositoryError> {
+        // 同じ内容への更新では影響を受けた行が0になるので、先に存在を確かめる
+        self.get_canned_response(canned_response_id).await?;
+        with_pool!(&self.pool, pool => {
+            sqlx::query(&sql(
+                pool,
+                r#"
+                update canned_responses
+                set title = ?, body = ?, updated_at = CURRENT_TIMESTAMP
+                where id = ?
+                "#,
+            ))
+            .bind(title)
+            .bind(body)
+            .bind(canned_response_id.0)
+            .execute(&**pool)
+            .await
+            .map(|_| ())
+        })
+        .map_err(db_error)?;
+
+        self.get_canned_response(canned_response_id).await
+    }
+
+    async fn delete_canned_response(
+        &self,
+        canned_response_id: CannedResponseId,
+    ) -> Result<(), RepositoryError> {
+        let rows_affected = with_pool!(&self.pool, pool => {
+            sqlx::query(&sql(
+                pool,
+                r#"
+                delete from canned_responses
+                where id = ?
+                "#,
+            ))
+            .bind(canned_response_id.0)
+            .execute(&**pool)
+            .await
+            .map(|result| result.rows_affected())
+        })
+        .map_err(db_error)?;
+        if rows_affected == 0 {
+            return Err(RepositoryError::NotFound(
+                "canned_responses".to_string(),
+                canned_response_id.0.to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
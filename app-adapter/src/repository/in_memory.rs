@@ -0,0 +1,7 @@
+pub mod business_hours;
+pub mod canned_response;
+pub mod staff;
+pub mod talk_room;
+pub mod talk_room_note;
+pub mod user;
+pub mod user_tag;
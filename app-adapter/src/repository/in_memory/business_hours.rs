@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use chrono::Local;
+
+use crate::model::business_hours::BusinessHoursCalendarTable;
+use crate::repository::{unexpected_error, InMemoryRepositoryImpl, RepositoryError};
+use domain::{
+    model::business_hours::{BusinessHoursCalendar, NewBusinessHoursCalendar},
+    repository::business_hours::BusinessHoursRepository,
+};
+
+#[async_trait]
+impl BusinessHoursRepository for InMemoryRepositoryImpl<BusinessHoursCalendar> {
+    async fn get_calendar(
+        &self,
+        channel_id: String,
+    ) -> Result<Option<BusinessHoursCalendar>, RepositoryError> {
+        let tables = self.db.lock()?;
+        Ok(tables.business_hours_calendars.get(&channel_id).cloned())
+    }
+
+    /// チャネルの営業時間を丸ごと置き換える
+    /// firestoreと同じく、保存するときの変換を通して不正な営業時間を弾く
+    async fn save_calendar(
+        &self,
+        channel_id: String,
+        source: NewBusinessHoursCalendar,
+    ) -> Result<BusinessHoursCalendar, RepositoryError> {
+        let calendar = BusinessHoursCalendarTable::from((source, Local::now()))
+            .into_calendar(channel_id.clone())
+            .map_err(unexpected_error)?;
+        let mut tables = self.db.lock()?;
+        tables
+            .business_hours_calendars
+            .insert(channel_id, calendar.clone());
+
+        Ok(calendar)
+    }
+}
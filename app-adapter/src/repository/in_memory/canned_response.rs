@@ -0,0 +1,95 @@
+use async_trait::async_trait;
+use chrono::Local;
+
+use crate::repository::{InMemoryRepositoryImpl, RepositoryError};
+use domain::model::canned_response::{CannedResponse, CannedResponseId, NewCannedResponse};
+use domain::repository::canned_response::CannedResponseRepository;
+
+#[async_trait]
+impl CannedResponseRepository for InMemoryRepositoryImpl<CannedResponse> {
+    async fn get_canned_responses(&self) -> Result<Vec<CannedResponse>, RepositoryError> {
+        let tables = self.db.lock()?;
+        Ok(tables.canned_responses.clone())
+    }
+
+    async fn get_canned_response(
+        &self,
+        canned_response_id: CannedResponseId,
+    ) -> Result<CannedResponse, RepositoryError> {
+        let tables = self.db.lock()?;
+        tables
+            .canned_responses
+            .iter()
+            .find(|canned_response| canned_response.id == canned_response_id)
+            .cloned()
+            .ok_or(RepositoryError::NotFound(
+                "canned_responses".to_string(),
+                canned_response_id.0.to_string(),
+            ))
+    }
+
+    /// MySQLのauto_incrementと同じく、IDは1から順に払い出す
+    async fn create_canned_response(
+        &self,
+        source: NewCannedResponse,
+    ) -> Result<CannedResponse, RepositoryError> {
+        let mut tables = self.db.lock()?;
+        let canned_response_id = tables
+            .canned_responses
+            .iter()
+            .map(|canned_response| canned_response.id.0)
+            .max();
+        let now = Local::now();
+        let canned_response = CannedResponse::new(
+            CannedResponseId::new(canned_response_id.unwrap_or_default() + 1),
+            source.title,
+            source.body,
+            source.created_by,
+            now,
+            now,
+        );
+        tables.canned_responses.push(canned_response.clone());
+
+        Ok(canned_response)
+    }
+
+    async fn update_canned_response(
+        &self,
+        canned_response_id: CannedResponseId,
+        title: String,
+        body: String,
+    ) -> Result<CannedResponse, RepositoryError> {
+        let mut tables = self.db.lock()?;
+        let canned_response = tables
+            .canned_responses
+            .iter_mut()
+            .find(|canned_response| canned_response.id == canned_response_id)
+            .ok_or(RepositoryError::NotFound(
+                "canned_responses".to_string(),
+                canned_response_id.0.to_string(),
+            ))?;
+        canned_response.title = title;
+        canned_response.body = body;
+        canned_response.updated_at = Local::now();
+
+        Ok(canned_response.clone())
+    }
+
+    async fn delete_canned_response(
+        &self,
+        canned_response_id: CannedResponseId,
+    ) -> Result<(), RepositoryError> {
+        let mut tables = self.db.lock()?;
+        let position = tables
+            .canned_responses
+            .iter()
+            .position(|canned_response| canned_response.id == canned_response_id)
+            .ok_or(RepositoryError::NotFound(
+                "canned_responses".to_string(),
+                canned_response_id.0.to_string(),
+            ))?;
+        tables.canned_responses.remove(position);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use async_trait::async_trait;
+
+use crate::repository::{InMemoryRepositoryImpl, RepositoryError};
+use domain::model::email_user::EmailAddress;
+use domain::model::staff::{NewStaff, Staff, StaffId, StaffRole};
+use domain::repository::staff::StaffRepository;
+
+#[async_trait]
+impl StaffRepository for InMemoryRepositoryImpl<Staff> {
+    async fn get_staff(&self, staff_id: StaffId) -> Result<Staff, RepositoryError> {
+        let tables = self.db.lock()?;
+        tables
+            .staffs
+            .iter()
+            .find(|staff| staff.id == staff_id)
+            .cloned()
+            .ok_or(RepositoryError::NotFound(
+                "staffs".to_string(),
+                staff_id.0.to_string(),
+            ))
+    }
+
+    async fn get_staff_by_email(&self, email: EmailAddress) -> Result<Staff, RepositoryError> {
+        let tables = self.db.lock()?;
+        tables
+            .staffs
+            .iter()
+            .find(|staff| staff.email == email)
+            .cloned()
+            .ok_or(RepositoryError::NotFound("staffs".to_string(), email.0))
+    }
+
+    async fn get_staffs(&self) -> Result<Vec<Staff>, RepositoryError> {
+        let tables = self.db.lock()?;
+        Ok(tables.staffs.clone())
+    }
+
+    /// MySQLのauto_incrementと同じく、IDは1から順に払い出す
+    async fn create_staff(&self, source: NewStaff) -> Result<Staff, RepositoryError> {
+        let mut tables = self.db.lock()?;
+        if tables
+            .staffs
+            .iter()
+            .any(|staff| staff.email == source.email)
+        {
+            return Err(RepositoryError::Conflict(
+                "staffs".to_string(),
+                "email".to_string(),
+                source.email.0,
+            ));
+        }
+        let staff_id = tables.staffs.iter().map(|staff| staff.id.0).max();
+        let staff = Staff::new(
+            StaffId::new(staff_id.unwrap_or_default() + 1),
+            source.name,
+            source.email,
+            source.picture_url,
+            source.role,
+            source.password_hash,
+        );
+        tables.staffs.push(staff.clone());
+
+        Ok(staff)
+    }
+
+    async fn update_staff_role(
+        &self,
+        staff_id: StaffId,
+        role: StaffRole,
+    ) -> Result<Staff, RepositoryError> {
+        let mut tables = self.db.lock()?;
+        let staff = tables
+            .staffs
+            .iter_mut()
+            .find(|staff| staff.id == staff_id)
+            .ok_or(RepositoryError::NotFound(
+                "staffs".to_string(),
+                staff_id.0.to_string(),
+            ))?;
+        staff.role = role;
+
+        Ok(staff.clone())
+    }
+}
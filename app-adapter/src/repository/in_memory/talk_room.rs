@@ -0,0 +1,568 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use futures::StreamExt;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::model::message::MessagesTable;
+use crate::model::talk_room::{TalkRoomCardTable, TalkRoomCardWorkflowTable};
+use crate::persistance::in_memory::{InMemoryTables, InMemoryTalkRoom};
+use crate::repository::in_memory::user::merge_primary_users;
+use crate::repository::{
+    unexpected_error, InMemoryRepositoryImpl, RepositoryError, MESSAGE_COLLECTION_NAME,
+    TALK_ROOM_CARD_COLLECTION_NAME, TALK_ROOM_COLLECTION_NAME,
+};
+use domain::{
+    model::{
+        message::{MessageCursor, Messages, MessagesPage},
+        primary_user_id::PrimaryUserId,
+        staff::StaffId,
+        talk_room::{NewTalkRoom, NewTalkRoomWorkflow, TalkRoom},
+        talk_room_card::{
+            TalkRoomAssigneeFilter, TalkRoomCard, TalkRoomCardPage, TalkRoomCursor, TalkRoomFilter,
+            TalkRoomReadMarker,
+        },
+        talk_room_change::{
+            TalkRoomChange, TalkRoomChangeResumeToken, TalkRoomChangeStream, TalkRoomMessage,
+        },
+        talk_room_consistency::{TalkRoomConsistencyScan, TalkRoomInconsistency},
+        Id,
+    },
+    repository::talk_room::TalkRoomRepository,
+};
+
+#[async_trait]
+impl TalkRoomRepository for InMemoryRepositoryImpl<TalkRoom> {
+    async fn get_talk_room(
+        &self,
+        primary_user_id: PrimaryUserId,
+    ) -> Result<TalkRoom, RepositoryError> {
+        let tables = self.db.lock()?;
+        let document_id = document_id_of_primary_user_id(&tables, &primary_user_id)?;
+        Ok(into_talk_room(&tables, &document_id)?)
+    }
+
+    async fn get_talk_room_by_id(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+    ) -> Result<TalkRoom, RepositoryError> {
+        let tables = self.db.lock()?;
+        Ok(into_talk_room(&tables, &talk_room_id.value.to_string())?)
+    }
+
+    /// firestoreと同じく、ピン留めしたものを先頭にsort_timeの新しい順で並べる
+    async fn get_talk_room_cards(
+        &self,
+        filter: TalkRoomFilter,
+        cursor: Option<TalkRoomCursor>,
+        limit: u32,
+    ) -> Result<TalkRoomCardPage, RepositoryError> {
+        let tables = self.db.lock()?;
+        let mut talk_room_cards = tables
+            .talk_rooms
+            .keys()
+            .map(|document_id| into_talk_room_card(&tables, document_id))
+            .collect::<Result<Vec<TalkRoomCard>, _>>()?
+            .into_iter()
+            .filter(|c| matches_filter(c, &filter))
+            .collect::<Vec<_>>();
+        talk_room_cards.sort_by_key(|card| Reverse(card_sort_key(card)));
+        let mut talk_room_cards = talk_room_cards
+            .into_iter()
+            .filter(|c| {
+                cursor.as_ref().is_none_or(|cursor| {
+                    card_sort_key(c)
+                        < (cursor.pinned, cursor.sort_time, cursor.id.value.to_string())
+                })
+            })
+            // 続きがあるかを判定するために1件多く取得する
+            .take(limit as usize + 1)
+            .collect::<Vec<_>>();
+        let has_next = talk_room_cards.len() > limit as usize;
+        talk_room_cards.truncate(limit as usize);
+        let next_cursor = if has_next {
+            talk_room_cards.last().map(TalkRoomCursor::from)
+        } else {
+            None
+        };
+
+        Ok(TalkRoomCardPage::new(talk_room_cards, next_cursor))
+    }
+
+    /// firestoreと同じく、作成日時とドキュメントのIDの新しい順で並べる
+    async fn get_messages(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        cursor: Option<MessageCursor>,
+        limit: u32,
+    ) -> Result<MessagesPage, RepositoryError> {
+        let tables = self.db.lock()?;
+        let talk_room = get_in_memory_talk_room(&tables, &talk_room_id.value.to_string())?;
+        let mut messages = talk_room
+            .messages
+            .iter()
+            .map(|(document_id, messages_table)| messages_table.into_messages(document_id))
+            .collect::<anyhow::Result<Vec<Messages>>>()
+            .map_err(unexpected_error)?;
+        messages.sort_by_key(|messages| Reverse(message_sort_key(messages)));
+        let mut messages = messages
+            .into_iter()
+            .filter(|m| {
+                cursor.as_ref().is_none_or(|cursor| {
+                    message_sort_key(m) < (cursor.created_at, cursor.id.clone())
+                })
+            })
+            // 続きがあるかを判定するために1件多く取得する
+            .take(limit as usize + 1)
+            .collect::<Vec<_>>();
+        let has_next = messages.len() > limit as usize;
+        messages.truncate(limit as usize);
+        let next_cursor = if has_next {
+            messages.last().map(MessageCursor::from)
+        } else {
+            None
+        };
+
+        Ok(MessagesPage::new(messages, next_cursor))
+    }
+
+    /// これまでの変更の位置を再開する位置として使う
+    /// ロックを取っている間に監視を始めるので、記録から送り直す変更と、これから届く変更は重ならない
+    ///
+    /// # Arguments
+    /// * `resume_token` - 前の接続で最後に受け取ったCheckpoint。Noneのときは今から後の変更だけを受け取る
+    ///
+    async fn listen_changes(
+        &self,
+        resume_token: Option<TalkRoomChangeResumeToken>,
+    ) -> Result<TalkRoomChangeStream, RepositoryError> {
+        let position = resume_token
+            .map(|t| {
+                t.0.parse::<usize>().map_err(|e| {
+                    RepositoryError::Unexpected(format!("invalid resume token {}: {}", t.0, e))
+                })
+            })
+            .transpose()?;
+        let (missed_changes, receiver) = {
+            let tables = self.db.lock()?;
+            let missed_changes = match position {
+                Some(position) => tables
+                    .talk_room_changes
+                    .iter()
+                    .enumerate()
+                    .skip(position)
+                    .map(|(i, change)| (i as u64 + 1, change.clone()))
+                    .collect(),
+                None => vec![],
+            };
+            (missed_changes, tables.talk_room_change_sender.subscribe())
+        };
+
+        let changes = futures::stream::unfold(receiver, |mut receiver| async move {
+            match receiver.recv().await {
+                Ok(change) => Some((Ok(change), receiver)),
+                Err(RecvError::Closed) => None,
+                // 受け取る側が追いつかず変更を取りこぼしたときは、再接続してもらう
+                Err(e) => Some((Err(RepositoryError::Unexpected(e.to_string())), receiver)),
+            }
+        });
+        Ok(futures::stream::iter(missed_changes.into_iter().map(Ok))
+            .chain(changes)
+            .flat_map(|result| {
+                futures::stream::iter(match result {
+                    Ok((position, change)) => vec![
+                        Ok(change),
+                        Ok(TalkRoomChange::Checkpoint(TalkRoomChangeResumeToken::new(
+                            position.to_string(),
+                        ))),
+                    ],
+                    Err(e) => vec![Err(e)],
+                })
+            })
+            .boxed())
+    }
+
+    /// talk_roomsとtalkRoomCards、最初のメッセージをまとめて作成する
+    async fn create_talk_room(&self, source: NewTalkRoom) -> Result<TalkRoom, RepositoryError> {
+        let document_id = source.id.value.to_string();
+        let mut tables = self.db.lock()?;
+        // MySQLのユニーク制約と同じく、1人のユーザーには1つのtalkRoomしか作れない
+        if tables
+            .talk_rooms
+            .values()
+            .any(|t| &t.primary_user_id == source.primary_user_id.value())
+        {
+            return Err(RepositoryError::Conflict(
+                "talk_rooms".to_string(),
+                "primary_user_id".to_string(),
+                source.primary_user_id.value().to_string(),
+            ));
+        }
+        if tables.talk_rooms.contains_key(&document_id) {
+            return Err(RepositoryError::Conflict(
+                TALK_ROOM_COLLECTION_NAME.to_string(),
+                "document_id".to_string(),
+                document_id,
+            ));
+        }
+        tables.talk_rooms.insert(
+            document_id,
+            InMemoryTalkRoom::new(
+                source.primary_user_id.value().to_string(),
+                TalkRoomCardTable::from(source.clone()),
+                HashMap::new(),
+            ),
+        );
+
+        Ok(insert_messages(&mut tables, source)?)
+    }
+
+    /// firestoreと同じく、新しいメッセージがすでにあるときはlatestMessageと並び順を巻き戻さない
+    async fn create_messages(&self, source: NewTalkRoom) -> Result<TalkRoom, RepositoryError> {
+        let mut tables = self.db.lock()?;
+        Ok(insert_messages(&mut tables, source)?)
+    }
+
+    async fn update_display_name(
+        &self,
+        primary_user_id: PrimaryUserId,
+        display_name: String,
+    ) -> Result<(), RepositoryError> {
+        let mut tables = self.db.lock()?;
+        let document_id = document_id_of_primary_user_id(&tables, &primary_user_id)?;
+        update_card(&mut tables, &document_id, |card| {
+            card.display_name = display_name;
+            card.updated_at = Local::now();
+        })?;
+
+        Ok(())
+    }
+
+    /// ユーザーの統合とtalkRoomの付け替えを、同じロックの中で行う
+    async fn merge_users(
+        &self,
+        into: PrimaryUserId,
+        from: PrimaryUserId,
+    ) -> Result<(), RepositoryError> {
+        let mut tables = self.db.lock()?;
+        merge_primary_users(&mut tables, &into, &from)?;
+
+        Ok(())
+    }
+
+    async fn update_pinned(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        pinned: bool,
+    ) -> Result<(), RepositoryError> {
+        let mut tables = self.db.lock()?;
+        update_card(&mut tables, &talk_room_id.value.to_string(), |card| {
+            card.pinned = pinned;
+            card.updated_at = Local::now();
+        })?;
+
+        Ok(())
+    }
+
+    async fn update_rsvp(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        rsvp: bool,
+    ) -> Result<(), RepositoryError> {
+        let mut tables = self.db.lock()?;
+        update_card(&mut tables, &talk_room_id.value.to_string(), |card| {
+            card.rsvp = rsvp;
+            card.updated_at = Local::now();
+        })?;
+
+        Ok(())
+    }
+
+    async fn update_nickname(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        nickname: Option<String>,
+    ) -> Result<(), RepositoryError> {
+        let mut tables = self.db.lock()?;
+        update_card(&mut tables, &talk_room_id.value.to_string(), |card| {
+            card.nickname = nickname;
+            card.updated_at = Local::now();
+        })?;
+
+        Ok(())
+    }
+
+    async fn mark_as_read(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        staff_id: StaffId,
+    ) -> Result<TalkRoomReadMarker, RepositoryError> {
+        let mut tables = self.db.lock()?;
+        let document_id = talk_room_id.value.to_string();
+        let read_message_count = get_in_memory_talk_room(&tables, &document_id)?
+            .card
+            .user_message_count;
+        // firestoreと同じく、既読の位置だけを書き換え、updatedAtは変えない
+        let talk_room = get_in_memory_talk_room_mut(&mut tables, &document_id)?;
+        talk_room
+            .card
+            .read_message_counts
+            .insert(staff_id.0.to_string(), read_message_count);
+        publish_card_updated(&mut tables, &document_id)?;
+
+        Ok(TalkRoomReadMarker::new(
+            talk_room_id,
+            staff_id,
+            read_message_count,
+            Local::now(),
+        ))
+    }
+
+    /// 担当者とステータス、モードを更新し、変更の履歴をメッセージに追加する
+    /// latestMessageは変えないので、talkRoomの一覧の並び順は変わらない
+    async fn update_workflow(&self, source: NewTalkRoomWorkflow) -> Result<(), RepositoryError> {
+        let document_id = source.id.value.to_string();
+        let mut tables = self.db.lock()?;
+        let talk_room = get_in_memory_talk_room(&tables, &document_id)?;
+        if let Some(system_event) = source
+            .system_events
+            .iter()
+            .find(|e| talk_room.messages.contains_key(&e.id.value.to_string()))
+        {
+            return Err(RepositoryError::Conflict(
+                MESSAGE_COLLECTION_NAME.to_string(),
+                "document_id".to_string(),
+                system_event.id.value.to_string(),
+            ));
+        }
+
+        let workflow_table = TalkRoomCardWorkflowTable::from(source.clone());
+        update_card(&mut tables, &document_id, |card| {
+            workflow_table.apply_to(card, source.changed_fields())
+        })?;
+        for system_event in source.system_events {
+            let message_document_id = system_event.id.value.to_string();
+            let messages_table = MessagesTable::SystemEvent(system_event.into());
+            let messages = messages_table
+                .into_messages(&message_document_id)
+                .map_err(unexpected_error)?;
+            get_in_memory_talk_room_mut(&mut tables, &document_id)?
+                .messages
+                .insert(message_document_id, messages_table);
+            tables.publish_talk_room_change(TalkRoomChange::MessageAdded(TalkRoomMessage::new(
+                source.id.clone(),
+                messages,
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn claim_away_message(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        off_hours_since: DateTime<Local>,
+    ) -> Result<bool, RepositoryError> {
+        let mut tables = self.db.lock()?;
+        let talk_room = get_in_memory_talk_room_mut(&mut tables, &talk_room_id.value.to_string())?;
+        let already_sent = talk_room
+            .card
+            .away_message_off_hours_since
+            .is_some_and(|sent| sent >= off_hours_since);
+        if already_sent {
+            return Ok(false);
+        }
+        talk_room.card.away_message_off_hours_since = Some(off_hours_since);
+
+        Ok(true)
+    }
+
+    /// talk_roomsとfirestoreのドキュメントを1つにまとめて持っているので、食い違いは起きない
+    async fn scan_consistency(&self) -> Result<TalkRoomConsistencyScan, RepositoryError> {
+        let tables = self.db.lock()?;
+        Ok(TalkRoomConsistencyScan::new(
+            tables.talk_rooms.len(),
+            vec![],
+        ))
+    }
+
+    async fn repair_inconsistency(
+        &self,
+        inconsistency: TalkRoomInconsistency,
+    ) -> Result<(), RepositoryError> {
+        Err(RepositoryError::Unexpected(format!(
+            "in-memory talk rooms have no inconsistency to repair: {:?}",
+            inconsistency
+        )))
+    }
+}
+
+/// talkRoomCardsを更新し、メッセージを追加する
+/// ロックを取っている間に行うので、latestMessageが存在しないメッセージを指すことはない
+fn insert_messages(
+    tables: &mut InMemoryTables,
+    source: NewTalkRoom,
+) -> Result<TalkRoom, RepositoryError> {
+    let talk_room_document_id = source.id.value.to_string();
+    let talk_room_card_table = TalkRoomCardTable::from(source.clone());
+    let is_user_message = source.latest_messages.is_user_message();
+    let (message_document_id, messages_table) =
+        MessagesTable::from_new_messages(source.latest_messages.clone());
+    let last_messages = messages_table
+        .into_messages(&message_document_id)
+        .map_err(unexpected_error)?;
+
+    let talk_room =
+        tables
+            .talk_rooms
+            .get_mut(&talk_room_document_id)
+            .ok_or(RepositoryError::NotFound(
+                TALK_ROOM_CARD_COLLECTION_NAME.to_string(),
+                talk_room_document_id.clone(),
+            ))?;
+    if talk_room.messages.contains_key(&message_document_id) {
+        return Err(RepositoryError::Conflict(
+            MESSAGE_COLLECTION_NAME.to_string(),
+            "document_id".to_string(),
+            message_document_id,
+        ));
+    }
+    talk_room
+        .card
+        .add_messages(&talk_room_card_table, is_user_message);
+    talk_room
+        .messages
+        .insert(message_document_id, messages_table);
+    publish_card_updated(tables, &talk_room_document_id)?;
+    tables.publish_talk_room_change(TalkRoomChange::MessageAdded(TalkRoomMessage::new(
+        source.id.clone(),
+        last_messages.clone(),
+    )));
+
+    talk_room_card_table
+        .into_talk_room(talk_room_document_id, source.primary_user_id, last_messages)
+        .map_err(unexpected_error)
+}
+
+/// talkRoomCardsをスタッフの操作などで更新し、監視しているストリームに送る
+fn update_card(
+    tables: &mut InMemoryTables,
+    document_id: &String,
+    update: impl FnOnce(&mut TalkRoomCardTable),
+) -> Result<(), RepositoryError> {
+    let card = &mut get_in_memory_talk_room_mut(tables, document_id)?.card;
+    update(card);
+    publish_card_updated(tables, document_id)
+}
+
+fn publish_card_updated(
+    tables: &mut InMemoryTables,
+    document_id: &String,
+) -> Result<(), RepositoryError> {
+    let talk_room_card = into_talk_room_card(tables, document_id)?;
+    tables.publish_talk_room_change(TalkRoomChange::CardUpdated(talk_room_card));
+    Ok(())
+}
+
+fn into_talk_room(
+    tables: &InMemoryTables,
+    document_id: &String,
+) -> Result<TalkRoom, RepositoryError> {
+    let talk_room = get_in_memory_talk_room(tables, document_id)?;
+    let card = talk_room.card.clone();
+    let message_document_id = card.latest_message.document_id();
+    let latest_messages = talk_room
+        .messages
+        .get(message_document_id)
+        .ok_or(RepositoryError::NotFound(
+            MESSAGE_COLLECTION_NAME.to_string(),
+            message_document_id.to_string(),
+        ))?
+        .into_messages(message_document_id)
+        .map_err(unexpected_error)?;
+
+    card.into_talk_room(
+        document_id.clone(),
+        PrimaryUserId::new(talk_room.primary_user_id.clone()),
+        latest_messages,
+    )
+    .map_err(unexpected_error)
+}
+
+fn into_talk_room_card(
+    tables: &InMemoryTables,
+    document_id: &String,
+) -> Result<TalkRoomCard, RepositoryError> {
+    let talk_room = get_in_memory_talk_room(tables, document_id)?;
+    talk_room
+        .card
+        .clone()
+        .into_talk_room_card(
+            document_id.clone(),
+            PrimaryUserId::new(talk_room.primary_user_id.clone()),
+        )
+        .map_err(unexpected_error)
+}
+
+fn get_in_memory_talk_room<'a>(
+    tables: &'a InMemoryTables,
+    document_id: &String,
+) -> Result<&'a InMemoryTalkRoom, RepositoryError> {
+    tables
+        .talk_rooms
+        .get(document_id)
+        .ok_or(RepositoryError::NotFound(
+            "talk_rooms".to_string(),
+            document_id.clone(),
+        ))
+}
+
+fn get_in_memory_talk_room_mut<'a>(
+    tables: &'a mut InMemoryTables,
+    document_id: &String,
+) -> Result<&'a mut InMemoryTalkRoom, RepositoryError> {
+    tables
+        .talk_rooms
+        .get_mut(document_id)
+        .ok_or(RepositoryError::NotFound(
+            "talk_rooms".to_string(),
+            document_id.clone(),
+        ))
+}
+
+fn document_id_of_primary_user_id(
+    tables: &InMemoryTables,
+    primary_user_id: &PrimaryUserId,
+) -> Result<String, RepositoryError> {
+    tables
+        .talk_rooms
+        .iter()
+        .find(|(_, t)| &t.primary_user_id == primary_user_id.value())
+        .map(|(document_id, _)| document_id.clone())
+        .ok_or(RepositoryError::NotFound(
+            "talk_rooms".to_string(),
+            primary_user_id.value().to_string(),
+        ))
+}
+
+fn matches_filter(card: &TalkRoomCard, filter: &TalkRoomFilter) -> bool {
+    filter.pinned.is_none_or(|v| card.pinned == v)
+        && filter.follow.is_none_or(|v| card.follow == v)
+        && filter.rsvp.is_none_or(|v| card.rsvp == v)
+        && filter.unread.is_none_or(|v| card.unread == v)
+        && filter.status.is_none_or(|v| card.status == v)
+        && filter.assignee.is_none_or(|v| match v {
+            TalkRoomAssigneeFilter::Staff(staff_id) => card.assignee == Some(staff_id),
+            TalkRoomAssigneeFilter::Unassigned => card.assignee.is_none(),
+        })
+}
+
+// firestoreの__name__と同じく、sort_timeが同じときはドキュメントのIDで並べる
+fn card_sort_key(card: &TalkRoomCard) -> (bool, DateTime<Local>, String) {
+    (card.pinned, card.sort_time, card.id.value.to_string())
+}
+
+fn message_sort_key(messages: &Messages) -> (DateTime<Local>, String) {
+    (*messages.created_at(), messages.id())
+}
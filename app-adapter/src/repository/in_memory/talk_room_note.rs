@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+use chrono::Local;
+use std::cmp::Reverse;
+
+use crate::repository::{InMemoryRepositoryImpl, RepositoryError, NOTE_COLLECTION_NAME};
+use domain::{
+    model::{
+        talk_room::TalkRoom,
+        talk_room_note::{NewTalkRoomNote, TalkRoomNote},
+        Id,
+    },
+    repository::talk_room_note::TalkRoomNoteRepository,
+};
+
+#[async_trait]
+impl TalkRoomNoteRepository for InMemoryRepositoryImpl<TalkRoomNote> {
+    /// talkRoomのメモを新しい順に取得する
+    async fn get_notes(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+    ) -> Result<Vec<TalkRoomNote>, RepositoryError> {
+        let tables = self.db.lock()?;
+        let mut notes = tables
+            .talk_room_notes
+            .get(&talk_room_id.value.to_string())
+            .cloned()
+            .unwrap_or_default();
+        notes.sort_by_key(|note| Reverse(note.created_at));
+
+        Ok(notes)
+    }
+
+    async fn get_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+    ) -> Result<TalkRoomNote, RepositoryError> {
+        let tables = self.db.lock()?;
+        tables
+            .talk_room_notes
+            .get(&talk_room_id.value.to_string())
+            .and_then(|notes| notes.iter().find(|note| note.id == note_id))
+            .cloned()
+            .ok_or(RepositoryError::NotFound(
+                NOTE_COLLECTION_NAME.to_string(),
+                note_id.value.to_string(),
+            ))
+    }
+
+    async fn create_note(&self, source: NewTalkRoomNote) -> Result<TalkRoomNote, RepositoryError> {
+        let mut tables = self.db.lock()?;
+        let notes = tables
+            .talk_room_notes
+            .entry(source.talk_room_id.value.to_string())
+            .or_default();
+        if notes.iter().any(|note| note.id == source.id) {
+            return Err(RepositoryError::Conflict(
+                NOTE_COLLECTION_NAME.to_string(),
+                "id".to_string(),
+                source.id.value.to_string(),
+            ));
+        }
+        let note = TalkRoomNote::new(
+            source.id,
+            source.talk_room_id,
+            source.author,
+            source.body,
+            source.created_at,
+            source.created_at,
+        );
+        notes.push(note.clone());
+
+        Ok(note)
+    }
+
+    /// メモの本文を書き換える。書いたスタッフと作成日時は変えない
+    async fn update_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+        body: String,
+    ) -> Result<(), RepositoryError> {
+        let mut tables = self.db.lock()?;
+        let note = tables
+            .talk_room_notes
+            .get_mut(&talk_room_id.value.to_string())
+            .and_then(|notes| notes.iter_mut().find(|note| note.id == note_id))
+            .ok_or(RepositoryError::NotFound(
+                NOTE_COLLECTION_NAME.to_string(),
+                note_id.value.to_string(),
+            ))?;
+        note.body = body;
+        note.updated_at = Local::now();
+
+        Ok(())
+    }
+
+    async fn delete_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+    ) -> Result<(), RepositoryError> {
+        let mut tables = self.db.lock()?;
+        let notes = tables
+            .talk_room_notes
+            .get_mut(&talk_room_id.value.to_string())
+            .ok_or(RepositoryError::NotFound(
+                NOTE_COLLECTION_NAME.to_string(),
+                note_id.value.to_string(),
+            ))?;
+        let position =
+            notes
+                .iter()
+                .position(|note| note.id == note_id)
+                .ok_or(RepositoryError::NotFound(
+                    NOTE_COLLECTION_NAME.to_string(),
+                    note_id.value.to_string(),
+                ))?;
+        notes.remove(position);
+
+        Ok(())
+    }
+}
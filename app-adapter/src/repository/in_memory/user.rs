@@ -0,0 +1,379 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use std::mem::discriminant;
+
+use crate::persistance::in_memory::{InMemoryTables, InMemoryUserRow};
+use crate::repository::{InMemoryRepositoryImpl, RepositoryError};
+use domain::model::account_link::{ExternalMemberId, NewAccountLink, NewAccountLinkNonce};
+use domain::model::line_user::LineUserProfile;
+use domain::model::primary_user_id::PrimaryUserId;
+use domain::model::user::{User, UserProfile};
+use domain::model::user_auth::{AuthUserId, LineId};
+use domain::model::Id;
+use domain::repository::user::UserRepository;
+
+#[async_trait]
+impl UserRepository for InMemoryRepositoryImpl<User> {
+    async fn get_user(&self, source: AuthUserId) -> Result<User, RepositoryError> {
+        let tables = self.db.lock()?;
+        tables
+            .users
+            .iter()
+            .find(|row| row.user_profile.auth_id().as_ref() == Some(&source))
+            .map(|row| into_user(&tables, row))
+            .ok_or(RepositoryError::NotFound(
+                identity_table_of_auth_id(&source).to_string(),
+                source.value().to_string(),
+            ))
+    }
+
+    async fn get_line_user(&self, source: LineId) -> Result<User, RepositoryError> {
+        self.get_user(AuthUserId::Line(source)).await
+    }
+
+    async fn get_line_user_by_primary_user_id(
+        &self,
+        primary_user_id: PrimaryUserId,
+    ) -> Result<User, RepositoryError> {
+        let tables = self.db.lock()?;
+        tables
+            .users
+            .iter()
+            .find(|row| {
+                &row.primary_user_id == primary_user_id.value()
+                    && matches!(row.user_profile, UserProfile::Line(_))
+            })
+            .map(|row| into_user(&tables, row))
+            .ok_or(RepositoryError::NotFound(
+                "line_users".to_string(),
+                primary_user_id.value().to_string(),
+            ))
+    }
+
+    /// 新しいprimary_user_idを払い出し、認証プロバイダーのユーザーを作成する
+    async fn create_user(&self, source: UserProfile) -> Result<User, RepositoryError> {
+        let auth_id = source.auth_id().ok_or(RepositoryError::InvalidInput(
+            "auth_id is required to create a user".to_string(),
+        ))?;
+        let primary_user_id = Id::<User>::gen().value.to_string();
+        {
+            let mut tables = self.db.lock()?;
+            check_user_profile(&tables, &primary_user_id, &source)?;
+            tables.primary_users.insert(primary_user_id.clone());
+            tables
+                .users
+                .push(InMemoryUserRow::new(primary_user_id, source, Local::now()));
+        }
+
+        self.get_user(auth_id).await
+    }
+
+    /// 認証プロバイダーのユーザーがあればそれを返し、なければ作成する
+    /// 確認と作成を同じロックの中で行うので、同じユーザーを重複して作成しない
+    async fn get_or_create_user(&self, source: UserProfile) -> Result<User, RepositoryError> {
+        let auth_id = source.auth_id().ok_or(RepositoryError::InvalidInput(
+            "auth_id is required to create a user".to_string(),
+        ))?;
+        {
+            let mut tables = self.db.lock()?;
+            if let Some(row) = tables
+                .users
+                .iter()
+                .find(|row| row.user_profile.auth_id().as_ref() == Some(&auth_id))
+            {
+                return Ok(into_user(&tables, row));
+            }
+            let primary_user_id = Id::<User>::gen().value.to_string();
+            check_user_profile(&tables, &primary_user_id, &source)?;
+            tables.primary_users.insert(primary_user_id.clone());
+            tables
+                .users
+                .push(InMemoryUserRow::new(primary_user_id, source, Local::now()));
+        }
+
+        self.get_user(auth_id).await
+    }
+
+    async fn create_line_user(&self, source: LineUserProfile) -> Result<User, RepositoryError> {
+        self.create_user(UserProfile::Line(source)).await
+    }
+
+    /// LINEから取得し直したプロフィールで更新する
+    /// 変更がなくてもupdated_atは更新し、定期的なプロフィールの取得対象から外す
+    async fn update_line_user(&self, source: LineUserProfile) -> Result<User, RepositoryError> {
+        let line_id = source.auth_id.clone();
+        {
+            let mut tables = self.db.lock()?;
+            let row = tables
+                .users
+                .iter_mut()
+                .find(
+                    |row| matches!(&row.user_profile, UserProfile::Line(p) if p.auth_id == line_id),
+                )
+                .ok_or(RepositoryError::NotFound(
+                    "line_users".to_string(),
+                    line_id.0.clone(),
+                ))?;
+            row.user_profile = UserProfile::Line(source);
+            row.updated_at = Local::now();
+        }
+
+        self.get_line_user(line_id).await
+    }
+
+    async fn get_line_users_updated_before(
+        &self,
+        updated_before: DateTime<Local>,
+        limit: i64,
+    ) -> Result<Vec<User>, RepositoryError> {
+        let tables = self.db.lock()?;
+        let mut rows = tables
+            .users
+            .iter()
+            .filter(|row| {
+                matches!(row.user_profile, UserProfile::Line(_)) && row.updated_at < updated_before
+            })
+            .collect::<Vec<_>>();
+        rows.sort_by_key(|row| row.updated_at);
+
+        Ok(rows
+            .into_iter()
+            .take(usize::try_from(limit).unwrap_or_default())
+            .map(|row| into_user(&tables, row))
+            .collect())
+    }
+
+    /// 既存のprimary_user_idに、別の認証プロバイダーのユーザーを紐づける
+    /// 同じプロバイダーのユーザーは1つのprimary_user_idに1つまでしか紐づけられない
+    async fn link_user(
+        &self,
+        primary_user_id: PrimaryUserId,
+        source: UserProfile,
+    ) -> Result<User, RepositoryError> {
+        let auth_id = source.auth_id().ok_or(RepositoryError::InvalidInput(
+            "auth_id is required to link a user".to_string(),
+        ))?;
+        {
+            let mut tables = self.db.lock()?;
+            check_primary_user(&tables, &primary_user_id)?;
+            check_user_profile(&tables, primary_user_id.value(), &source)?;
+            tables.users.push(InMemoryUserRow::new(
+                primary_user_id.value().to_string(),
+                source,
+                Local::now(),
+            ));
+        }
+
+        self.get_user(auth_id).await
+    }
+
+    async fn create_account_link_nonce(
+        &self,
+        source: NewAccountLinkNonce,
+    ) -> Result<(), RepositoryError> {
+        let mut tables = self.db.lock()?;
+        if tables.account_link_nonces.contains_key(&source.nonce.0) {
+            return Err(RepositoryError::Conflict(
+                "line_account_link_nonces".to_string(),
+                "nonce".to_string(),
+                source.nonce.0,
+            ));
+        }
+        tables
+            .account_link_nonces
+            .insert(source.nonce.0.clone(), source);
+
+        Ok(())
+    }
+
+    /// accountLinkイベントのnonceを照合し、会員システムのアカウントと連携する
+    /// nonceは一度しか使えないので、照合したら削除する
+    async fn link_account(
+        &self,
+        source: NewAccountLink,
+    ) -> Result<ExternalMemberId, RepositoryError> {
+        let nonce = source.nonce.0;
+        let mut tables = self.db.lock()?;
+        let account_link_nonce = tables
+            .account_link_nonces
+            .get(&nonce)
+            .filter(|n| n.expires_at > Local::now())
+            .cloned()
+            .ok_or(RepositoryError::NotFound(
+                "line_account_link_nonces".to_string(),
+                nonce.clone(),
+            ))?;
+        tables.account_link_nonces.remove(&nonce);
+        // 連携し直したときは、新しい会員システムのアカウントで上書きする
+        tables.account_links.insert(
+            source.primary_user_id.value().to_string(),
+            account_link_nonce.external_member_id.clone(),
+        );
+
+        Ok(account_link_nonce.external_member_id)
+    }
+
+    async fn unlink_account(&self, primary_user_id: PrimaryUserId) -> Result<(), RepositoryError> {
+        let mut tables = self.db.lock()?;
+        tables
+            .account_links
+            .remove(primary_user_id.value())
+            .ok_or(RepositoryError::NotFound(
+                "line_account_links".to_string(),
+                primary_user_id.value().to_string(),
+            ))?;
+
+        Ok(())
+    }
+}
+
+/// fromに紐づく認証プロバイダーのユーザーとタグ、talkRoomをintoに付け替え、fromを削除する
+/// 同じプロバイダーのユーザーやtalkRoomが両方にある場合は、どちらを残すか決められないので失敗させる
+/// talkRoomの付け替えと同じロックの中で行えるように、テーブルを受け取る
+pub(crate) fn merge_primary_users(
+    tables: &mut InMemoryTables,
+    into: &PrimaryUserId,
+    from: &PrimaryUserId,
+) -> Result<(), RepositoryError> {
+    if into == from {
+        return Err(RepositoryError::InvalidInput(
+            "Cannot merge a user into itself".to_string(),
+        ));
+    }
+    check_primary_user(tables, into)?;
+    check_primary_user(tables, from)?;
+    // 途中で失敗して半分だけ付け替えた状態にならないように、先にすべて確かめる
+    let conflicted_profile = tables
+        .users
+        .iter()
+        .filter(|row| &row.primary_user_id == from.value())
+        .find(|from_row| {
+            tables.users.iter().any(|row| {
+                &row.primary_user_id == into.value()
+                    && discriminant(&row.user_profile) == discriminant(&from_row.user_profile)
+            })
+        });
+    if let Some(from_row) = conflicted_profile {
+        return Err(RepositoryError::Conflict(
+            identity_table_of_profile(&from_row.user_profile).to_string(),
+            "primary_user_id".to_string(),
+            into.value().to_string(),
+        ));
+    }
+    let has_talk_room = |primary_user_id: &PrimaryUserId| {
+        tables
+            .talk_rooms
+            .values()
+            .any(|t| &t.primary_user_id == primary_user_id.value())
+    };
+    for (table, conflicted) in [
+        (
+            "line_account_links",
+            tables.account_links.contains_key(into.value())
+                && tables.account_links.contains_key(from.value()),
+        ),
+        ("talk_rooms", has_talk_room(into) && has_talk_room(from)),
+    ] {
+        if conflicted {
+            return Err(RepositoryError::Conflict(
+                table.to_string(),
+                "primary_user_id".to_string(),
+                into.value().to_string(),
+            ));
+        }
+    }
+
+    for row in tables
+        .users
+        .iter_mut()
+        .filter(|row| &row.primary_user_id == from.value())
+    {
+        row.primary_user_id = into.value().to_string();
+    }
+    if let Some(external_member_id) = tables.account_links.remove(from.value()) {
+        tables
+            .account_links
+            .insert(into.value().to_string(), external_member_id);
+    }
+    for talk_room in tables
+        .talk_rooms
+        .values_mut()
+        .filter(|t| &t.primary_user_id == from.value())
+    {
+        talk_room.primary_user_id = into.value().to_string();
+    }
+    // タグは両方に付いていれば1つにまとめる
+    let from_tags = tables.user_tags.remove(from.value()).unwrap_or_default();
+    let into_tags = tables
+        .user_tags
+        .entry(into.value().to_string())
+        .or_default();
+    for tag in from_tags {
+        if !into_tags.contains(&tag) {
+            into_tags.push(tag);
+        }
+    }
+    tables.primary_users.remove(from.value());
+
+    Ok(())
+}
+
+fn into_user(tables: &InMemoryTables, row: &InMemoryUserRow) -> User {
+    User {
+        external_member_id: tables.account_links.get(&row.primary_user_id).cloned(),
+        ..User::new(
+            PrimaryUserId::new(row.primary_user_id.clone()),
+            row.user_profile.clone(),
+        )
+    }
+}
+
+fn check_primary_user(
+    tables: &InMemoryTables,
+    primary_user_id: &PrimaryUserId,
+) -> Result<(), RepositoryError> {
+    if !tables.primary_users.contains(primary_user_id.value()) {
+        return Err(RepositoryError::NotFound(
+            "primary_users".to_string(),
+            primary_user_id.value().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// MySQLのユニーク制約と同じく、認証プロバイダーのユーザーが重複しないかを確かめる
+fn check_user_profile(
+    tables: &InMemoryTables,
+    primary_user_id: &str,
+    source: &UserProfile,
+) -> Result<(), RepositoryError> {
+    let duplicated = tables.users.iter().any(|row| {
+        discriminant(&row.user_profile) == discriminant(source)
+            && (row.primary_user_id == primary_user_id
+                || row.user_profile.auth_id() == source.auth_id())
+    });
+    if duplicated {
+        return Err(RepositoryError::Conflict(
+            identity_table_of_profile(source).to_string(),
+            "primary_user_id".to_string(),
+            primary_user_id.to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn identity_table_of_profile(source: &UserProfile) -> &'static str {
+    match source {
+        UserProfile::Line(_) => "line_users",
+        UserProfile::Email(_) => "email_users",
+        UserProfile::LineLogin(_) => "line_login_users",
+    }
+}
+
+fn identity_table_of_auth_id(source: &AuthUserId) -> &'static str {
+    match source {
+        AuthUserId::Line(_) => "line_users",
+        AuthUserId::Email(_) => "email_users",
+        AuthUserId::LineLogin(_) => "line_login_users",
+    }
+}
@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use domain::model::user::UserProfile;
+
+use crate::persistance::in_memory::InMemoryTables;
+use crate::repository::{InMemoryRepositoryImpl, RepositoryError};
+use domain::model::primary_user_id::PrimaryUserId;
+use domain::model::user_tag::{LineUserTags, UserTag};
+use domain::repository::user_tag::UserTagRepository;
+
+#[async_trait]
+impl UserTagRepository for InMemoryRepositoryImpl<UserTag> {
+    async fn get_line_user_tags(
+        &self,
+        source: PrimaryUserId,
+    ) -> Result<LineUserTags, RepositoryError> {
+        let tables = self.db.lock()?;
+        line_user_tags(&tables)
+            .into_iter()
+            .find(|line_user_tags| line_user_tags.primary_user_id == source)
+            .ok_or(RepositoryError::NotFound(
+                "line_users".to_string(),
+                source.value().to_string(),
+            ))
+    }
+
+    async fn get_all_line_user_tags(&self) -> Result<Vec<LineUserTags>, RepositoryError> {
+        let tables = self.db.lock()?;
+        let mut line_user_tags = line_user_tags(&tables);
+        line_user_tags.sort_by(|a, b| a.primary_user_id.value().cmp(b.primary_user_id.value()));
+
+        Ok(line_user_tags)
+    }
+
+    async fn add_user_tags(
+        &self,
+        primary_user_id: PrimaryUserId,
+        tags: Vec<UserTag>,
+    ) -> Result<(), RepositoryError> {
+        let mut tables = self.db.lock()?;
+        // MySQLの外部キーと同じく、存在しないユーザーにはタグを付けられない
+        if !tables.primary_users.contains(primary_user_id.value()) {
+            return Err(RepositoryError::Conflict(
+                "user_tags".to_string(),
+                "primary_user_id".to_string(),
+                primary_user_id.value().to_string(),
+            ));
+        }
+        let user_tags = tables
+            .user_tags
+            .entry(primary_user_id.value().to_string())
+            .or_default();
+        // 既に付いているタグは無視する
+        for tag in tags {
+            if !user_tags.contains(&tag) {
+                user_tags.push(tag);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn remove_user_tags(
+        &self,
+        primary_user_id: PrimaryUserId,
+        tags: Vec<UserTag>,
+    ) -> Result<(), RepositoryError> {
+        let mut tables = self.db.lock()?;
+        if let Some(user_tags) = tables.user_tags.get_mut(primary_user_id.value()) {
+            user_tags.retain(|tag| !tags.contains(tag));
+        }
+
+        Ok(())
+    }
+}
+
+fn line_user_tags(tables: &InMemoryTables) -> Vec<LineUserTags> {
+    tables
+        .users
+        .iter()
+        .filter_map(|row| match &row.user_profile {
+            UserProfile::Line(profile) => Some(LineUserTags::new(
+                PrimaryUserId::new(row.primary_user_id.clone()),
+                profile.auth_id.clone(),
+                tables
+                    .user_tags
+                    .get(&row.primary_user_id)
+                    .cloned()
+                    .unwrap_or_default(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,3 @@
+pub mod business_hours;
+pub mod talk_room;
+pub mod talk_room_note;
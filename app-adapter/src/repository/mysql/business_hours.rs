@@ -0,0 +1,167 @@
+use async_trait::async_trait;
+use chrono::Local;
+use sqlx::types::Json;
+
+use crate::model::business_hours::{BusinessHoursCalendarDbTable, BusinessHoursCalendarTable};
+use crate::persistance::db::{sql, with_pool};
+use crate::repository::{db_error, unexpected_error, DatabaseRepositoryImpl, RepositoryError};
+use domain::{
+    model::business_hours::{BusinessHoursCalendar, NewBusinessHoursCalendar},
+    repository::business_hours::BusinessHoursRepository,
+};
+
+/// firestoreを使えない環境のために、営業時間もMySQLに持つ
+#[async_trait]
+impl BusinessHoursRepository for DatabaseRepositoryImpl<BusinessHoursCalendar> {
+    async fn get_calendar(
+        &self,
+        channel_id: String,
+    ) -> Result<Option<BusinessHoursCalendar>, RepositoryError> {
+        let calendar_row = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, BusinessHoursCalendarDbTable>(&sql(
+                pool,
+                r#"
+                select time_zone, weekly_hours, exceptions, away_message, updated_at
+                from business_hours_calendars
+                where channel_id = ?
+                "#,
+            ))
+            .bind(channel_id.clone())
+            .fetch_optional(&**pool)
+            .await
+        })
+        .map_err(db_error)?;
+
+        calendar_row
+            .map(|c| BusinessHoursCalendarTable::from(c).into_calendar(channel_id))
+            .transpose()
+            .map_err(unexpected_error)
+    }
+
+    /// チャネルの営業時間を丸ごと置き換える
+    async fn save_calendar(
+        &self,
+        channel_id: String,
+        source: NewBusinessHoursCalendar,
+    ) -> Result<BusinessHoursCalendar, RepositoryError> {
+        let calendar_table = BusinessHoursCalendarTable::from((source, Local::now()));
+        with_pool!(&self.pool, pool => {
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(db_error)?;
+            // データベースごとにupsertの書き方が違うので、消してから入れ直す
+            sqlx::query(&sql(
+                pool,
+                "delete from business_hours_calendars where channel_id = ?",
+            ))
+            .bind(channel_id.clone())
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            sqlx::query(&sql(
+                pool,
+                r#"
+                insert into business_hours_calendars
+                (channel_id, time_zone, weekly_hours, exceptions, away_message, updated_at)
+                values (?, ?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(channel_id.clone())
+            .bind(calendar_table.time_zone.clone())
+            .bind(Json(&calendar_table.weekly_hours))
+            .bind(Json(&calendar_table.exceptions))
+            .bind(calendar_table.away_message.clone())
+            .bind(calendar_table.updated_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            tx.commit()
+                .await
+                .map_err(db_error)?;
+        });
+
+        calendar_table
+            .into_calendar(channel_id)
+            .map_err(unexpected_error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, NaiveTime, Weekday};
+    use domain::model::business_hours::{
+        BusinessHoursCalendar, BusinessHoursException, BusinessHoursRange,
+        NewBusinessHoursCalendar, WeeklyBusinessHours,
+    };
+    use domain::repository::business_hours::BusinessHoursRepository;
+
+    use crate::persistance::db::Db;
+    use crate::repository::DatabaseRepositoryImpl;
+
+    async fn sqlite() -> Db {
+        let db = Db::connect("sqlite::memory:")
+            .await
+            .expect("failed to connect to sqlite");
+        db.run_migrations().await.expect("failed to migrate sqlite");
+        db
+    }
+
+    fn hours(open: u32, close: u32) -> BusinessHoursRange {
+        BusinessHoursRange::new(
+            NaiveTime::from_hms_opt(open, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(close, 0, 0).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_save_and_replace_calendar() {
+        let repository = DatabaseRepositoryImpl::<BusinessHoursCalendar>::new(sqlite().await);
+        let channel_id = "1234567890".to_string();
+        assert_eq!(
+            repository.get_calendar(channel_id.clone()).await.unwrap(),
+            None
+        );
+
+        let saved = repository
+            .save_calendar(
+                channel_id.clone(),
+                NewBusinessHoursCalendar::new(
+                    "Asia/Tokyo".parse().unwrap(),
+                    vec![WeeklyBusinessHours::new(Weekday::Mon, hours(9, 18))],
+                    vec![BusinessHoursException::new(
+                        NaiveDate::from_ymd_opt(2023, 12, 31).unwrap(),
+                        vec![],
+                    )],
+                    Some("営業時間外です".to_string()),
+                ),
+            )
+            .await
+            .unwrap();
+        let calendar = repository
+            .get_calendar(channel_id.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(calendar, saved);
+
+        // 丸ごと置き換えるので、前の例外の日は残らない
+        let saved = repository
+            .save_calendar(
+                channel_id.clone(),
+                NewBusinessHoursCalendar::new(
+                    "Asia/Tokyo".parse().unwrap(),
+                    vec![WeeklyBusinessHours::new(Weekday::Tue, hours(10, 17))],
+                    vec![],
+                    None,
+                ),
+            )
+            .await
+            .unwrap();
+        let calendar = repository.get_calendar(channel_id).await.unwrap().unwrap();
+        assert_eq!(calendar, saved);
+        assert!(calendar.exceptions.is_empty());
+        assert_eq!(calendar.away_message, None);
+    }
+}
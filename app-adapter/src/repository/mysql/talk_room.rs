@@ -0,0 +1,945 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use futures::StreamExt;
+use sqlx::{pool::PoolConnection, types::Json, MySql, MySqlConnection, QueryBuilder};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::model::message::{MessagesDbTable, MessagesTable};
+use crate::model::talk_room::{
+    LatestMessageTable, TalkRoomCardDbTable, TalkRoomCardTable, TalkRoomCardWorkflowTable,
+};
+use crate::model::talk_room_change::TalkRoomChangeDbTable;
+use crate::repository::user::merge_primary_users;
+use crate::repository::{
+    db_error, insert_error, unexpected_error, DatabaseRepositoryImpl, RepositoryError,
+};
+use domain::{
+    model::{
+        message::{MessageCursor, Messages, MessagesPage},
+        primary_user_id::PrimaryUserId,
+        staff::StaffId,
+        talk_room::{NewTalkRoom, NewTalkRoomWorkflow, TalkRoom, TalkRoomMode, TalkRoomStatus},
+        talk_room_card::{
+            TalkRoomAssigneeFilter, TalkRoomCardPage, TalkRoomCursor, TalkRoomFilter,
+            TalkRoomReadMarker,
+        },
+        talk_room_change::{
+            TalkRoomChange, TalkRoomChangeResumeToken, TalkRoomChangeStream, TalkRoomMessage,
+        },
+        talk_room_consistency::{TalkRoomConsistencyScan, TalkRoomInconsistency},
+        Id,
+    },
+    repository::talk_room::TalkRoomRepository,
+};
+
+// talk_roomsから、talkRoomCardsと同じ項目を読み込むときの列
+const TALK_ROOM_CARD_COLUMNS: &str = "document_id, primary_user_id, display_name, rsvp, pinned, \
+    follow, latest_message, latest_messaged_at, sort_time, assignee, status, mode, unread, \
+    nickname, user_message_count, read_message_counts, away_message_off_hours_since, \
+    created_at, updated_at";
+const MESSAGE_COLUMNS: &str = "document_id, event, send_message, system_event";
+// 新しい変更がないときに、talk_room_changesを読み直すまでの間隔
+const TALK_ROOM_CHANGE_POLL_INTERVAL_MILLIS: u64 = 1000;
+// talk_room_changesを一度に読み込む数
+const TALK_ROOM_CHANGE_BATCH_SIZE: i64 = 100;
+// 抜けているidを、ロールバックされた欠番とみなすまでの秒数。書き込みのトランザクションより長くする
+const TALK_ROOM_CHANGE_COMMIT_LAG_SECS: i64 = 10;
+// talk_room_changesを読めなかったときに、読み直すまでの間隔の上限
+const TALK_ROOM_CHANGE_MAX_BACKOFF_MILLIS: u64 = 30_000;
+// latestMessageを作り直すときに遡るメッセージの数。system_eventが続いたときのために余裕をもたせる
+const LATEST_MESSAGE_LOOKBACK: i64 = 20;
+
+/// firestoreを使えない環境のために、talkRoomCardsとmessagesもMySQLに持つ
+/// 書き込みはtalk_roomsの行をロックしたトランザクションで行い、firestoreと同じ結果になるようにする
+#[async_trait]
+impl TalkRoomRepository for DatabaseRepositoryImpl<TalkRoom> {
+    async fn get_talk_room(
+        &self,
+        primary_user_id: PrimaryUserId,
+    ) -> Result<TalkRoom, RepositoryError> {
+        let mut conn = self.acquire().await?;
+        let row = fetch_card_row_by_primary_user_id(&mut conn, &primary_user_id).await?;
+        Ok(get_talk_room_of_row(&mut conn, row).await?)
+    }
+
+    async fn get_talk_room_by_id(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+    ) -> Result<TalkRoom, RepositoryError> {
+        let mut conn = self.acquire().await?;
+        let row = fetch_card_row(&mut conn, &talk_room_id.value.to_string(), false).await?;
+        Ok(get_talk_room_of_row(&mut conn, row).await?)
+    }
+
+    /// firestoreと同じく、ピン留めしたものを先頭にsort_timeの新しい順で並べる
+    /// sort_timeが同じときは、firestoreの__name__と同じくdocument_idで並べる
+    async fn get_talk_room_cards(
+        &self,
+        filter: TalkRoomFilter,
+        cursor: Option<TalkRoomCursor>,
+        limit: u32,
+    ) -> Result<TalkRoomCardPage, RepositoryError> {
+        let mut query = QueryBuilder::<MySql>::new(format!(
+            "select {} from talk_rooms where latest_message is not null",
+            TALK_ROOM_CARD_COLUMNS
+        ));
+        if let Some(pinned) = filter.pinned {
+            query.push(" and pinned = ").push_bind(pinned);
+        }
+        if let Some(follow) = filter.follow {
+            query.push(" and follow = ").push_bind(follow);
+        }
+        if let Some(rsvp) = filter.rsvp {
+            query.push(" and rsvp = ").push_bind(rsvp);
+        }
+        if let Some(unread) = filter.unread {
+            query.push(" and unread = ").push_bind(unread);
+        }
+        if let Some(status) = filter.status {
+            query.push(" and status = ").push_bind(status.as_str());
+        }
+        match filter.assignee {
+            Some(TalkRoomAssigneeFilter::Staff(staff_id)) => {
+                query.push(" and assignee = ").push_bind(staff_id.0);
+            }
+            Some(TalkRoomAssigneeFilter::Unassigned) => {
+                query.push(" and assignee is null");
+            }
+            None => {}
+        }
+        if let Some(c) = cursor {
+            query
+                .push(" and (pinned, sort_time, document_id) < (")
+                .push_bind(c.pinned)
+                .push(", ")
+                .push_bind(c.sort_time)
+                .push(", ")
+                .push_bind(c.id.value.to_string())
+                .push(")");
+        }
+        // 続きがあるかを判定するために1件多く取得する
+        query
+            .push(" order by pinned desc, sort_time desc, document_id desc limit ")
+            .push_bind(i64::from(limit) + 1);
+
+        let mut conn = self.acquire().await?;
+        let mut rows = query
+            .build_query_as::<TalkRoomCardDbTable>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(db_error)?;
+        let has_next = rows.len() > limit as usize;
+        rows.truncate(limit as usize);
+
+        let talk_room_cards = rows
+            .into_iter()
+            .map(|row| {
+                let document_id = row.document_id.clone();
+                let (primary_user_id, talk_room_card_table) = card_table_of(row)?;
+                talk_room_card_table.into_talk_room_card(document_id, primary_user_id)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(unexpected_error)?;
+        let next_cursor = if has_next {
+            talk_room_cards.last().map(TalkRoomCursor::from)
+        } else {
+            None
+        };
+
+        Ok(TalkRoomCardPage::new(talk_room_cards, next_cursor))
+    }
+
+    /// firestoreと同じく、作成日時とドキュメントのIDの新しい順で並べる
+    async fn get_messages(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        cursor: Option<MessageCursor>,
+        limit: u32,
+    ) -> Result<MessagesPage, RepositoryError> {
+        let document_id = talk_room_id.value.to_string();
+        let mut conn = self.acquire().await?;
+        // 存在しないtalkRoomのときはNotFoundを返す
+        fetch_card_row(&mut conn, &document_id, false).await?;
+
+        let mut query = QueryBuilder::<MySql>::new(format!(
+            "select {} from messages where talk_room_document_id = ",
+            MESSAGE_COLUMNS
+        ));
+        query.push_bind(document_id);
+        if let Some(c) = cursor {
+            query
+                .push(" and (created_at, document_id) < (")
+                .push_bind(c.created_at)
+                .push(", ")
+                .push_bind(c.id)
+                .push(")");
+        }
+        // 続きがあるかを判定するために1件多く取得する
+        query
+            .push(" order by created_at desc, document_id desc limit ")
+            .push_bind(i64::from(limit) + 1);
+        let mut rows = query
+            .build_query_as::<MessagesDbTable>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(db_error)?;
+        let has_next = rows.len() > limit as usize;
+        rows.truncate(limit as usize);
+
+        let messages = rows
+            .into_iter()
+            .map(MessagesDbTable::into_messages)
+            .collect::<anyhow::Result<Vec<Messages>>>()
+            .map_err(unexpected_error)?;
+        let next_cursor = if has_next {
+            messages.last().map(MessageCursor::from)
+        } else {
+            None
+        };
+
+        Ok(MessagesPage::new(messages, next_cursor))
+    }
+
+    /// talk_room_changesを定期的に読み、記録した順に変更を送る
+    /// talk_room_changesのIDを再開する位置として使う
+    ///
+    /// # Arguments
+    /// * `resume_token` - 前の接続で最後に受け取ったCheckpoint。Noneのときは今から後の変更だけを受け取る
+    ///
+    async fn listen_changes(
+        &self,
+        resume_token: Option<TalkRoomChangeResumeToken>,
+    ) -> Result<TalkRoomChangeStream, RepositoryError> {
+        let position = match resume_token {
+            Some(t) => t.0.parse::<i64>().map_err(|e| {
+                RepositoryError::Unexpected(format!("invalid resume token {}: {}", t.0, e))
+            })?,
+            None => {
+                let mut conn = self.acquire().await?;
+                sqlx::query_scalar::<_, Option<i64>>("select max(id) from talk_room_changes")
+                    .fetch_one(&mut *conn)
+                    .await
+                    .map_err(db_error)?
+                    .unwrap_or_default()
+            }
+        };
+
+        let repository = DatabaseRepositoryImpl::<TalkRoom>::new(self.pool.clone());
+        let changes = futures::stream::unfold(
+            (repository, position, 0u32),
+            |(repository, position, failures)| async move {
+                loop {
+                    match repository.get_changes_after(position).await {
+                        Ok(changes) => match changes.last() {
+                            Some((last_position, _)) => {
+                                let last_position = *last_position;
+                                return Some((Ok(changes), (repository, last_position, 0)));
+                            }
+                            None => {
+                                tokio::time::sleep(Duration::from_millis(
+                                    TALK_ROOM_CHANGE_POLL_INTERVAL_MILLIS,
+                                ))
+                                .await
+                            }
+                        },
+                        Err(e) => {
+                            // MySQLにつながらない間に読み直し続けないように、失敗が続くほど間隔を空ける
+                            let backoff = TALK_ROOM_CHANGE_POLL_INTERVAL_MILLIS
+                                .saturating_mul(1 << failures.min(5))
+                                .min(TALK_ROOM_CHANGE_MAX_BACKOFF_MILLIS);
+                            tokio::time::sleep(Duration::from_millis(backoff)).await;
+                            return Some((Err(e), (repository, position, failures + 1)));
+                        }
+                    }
+                }
+            },
+        );
+        Ok(changes
+            .flat_map(|result| {
+                futures::stream::iter(match result {
+                    // 送る変更がなくなっていても、再開する位置は進める
+                    Ok(changes) => changes
+                        .into_iter()
+                        .flat_map(|(position, change)| {
+                            change.into_iter().chain([TalkRoomChange::Checkpoint(
+                                TalkRoomChangeResumeToken::new(position.to_string()),
+                            )])
+                        })
+                        .map(Ok)
+                        .collect::<Vec<_>>(),
+                    Err(e) => vec![Err(e)],
+                })
+            })
+            .boxed())
+    }
+
+    /// talk_roomsと最初のメッセージを、一つのトランザクションで作成する
+    async fn create_talk_room(&self, source: NewTalkRoom) -> Result<TalkRoom, RepositoryError> {
+        let pool = Arc::clone(self.pool.mysql_pool()?);
+        let document_id = source.id.value.to_string();
+        let mut tx = pool.begin().await.map_err(db_error)?;
+        sqlx::query(
+            r#"
+            insert into talk_rooms(document_id, primary_user_id, created_at)
+            values (?, ?, ?)
+            "#,
+        )
+        .bind(document_id.clone())
+        .bind(source.primary_user_id.value())
+        .bind(source.created_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            insert_error(
+                e,
+                RepositoryError::Conflict(
+                    "talk_rooms".to_string(),
+                    "primary_user_id".to_string(),
+                    source.primary_user_id.value().to_string(),
+                ),
+            )
+        })?;
+        save_card(
+            &mut tx,
+            &document_id,
+            &TalkRoomCardTable::from(source.clone()),
+        )
+        .await?;
+        let talk_room = insert_messages(&mut tx, source).await?;
+        tx.commit().await.map_err(db_error)?;
+
+        Ok(talk_room)
+    }
+
+    /// talk_roomsの行をロックしてから更新するので、同時に届いたメッセージで数え漏れたり、
+    /// latestMessageを古いメッセージで巻き戻したりしない
+    async fn create_messages(&self, source: NewTalkRoom) -> Result<TalkRoom, RepositoryError> {
+        let pool = Arc::clone(self.pool.mysql_pool()?);
+        let mut tx = pool.begin().await.map_err(db_error)?;
+        let talk_room = insert_messages(&mut tx, source).await?;
+        tx.commit().await.map_err(db_error)?;
+
+        Ok(talk_room)
+    }
+
+    async fn update_display_name(
+        &self,
+        primary_user_id: PrimaryUserId,
+        display_name: String,
+    ) -> Result<(), RepositoryError> {
+        let document_id = {
+            let mut conn = self.acquire().await?;
+            fetch_card_row_by_primary_user_id(&mut conn, &primary_user_id)
+                .await?
+                .document_id
+        };
+        self.update_card(&document_id, |card| {
+            card.display_name = display_name;
+            card.updated_at = Local::now();
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// ユーザーの統合で、fromのtalkRoomをintoに付け替える
+    /// intoがすでにtalkRoomを持っているときは、ユニーク制約で失敗してユーザーの統合ごと取り消す
+    async fn merge_users(
+        &self,
+        into: PrimaryUserId,
+        from: PrimaryUserId,
+    ) -> Result<(), RepositoryError> {
+        let pool = Arc::clone(self.pool.mysql_pool()?);
+        let mut tx = pool.begin().await.map_err(db_error)?;
+        merge_primary_users(&mut tx, &into, &from).await?;
+        tx.commit().await.map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn update_pinned(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        pinned: bool,
+    ) -> Result<(), RepositoryError> {
+        self.update_card(&talk_room_id.value.to_string(), |card| {
+            card.pinned = pinned;
+            card.updated_at = Local::now();
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_rsvp(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        rsvp: bool,
+    ) -> Result<(), RepositoryError> {
+        self.update_card(&talk_room_id.value.to_string(), |card| {
+            card.rsvp = rsvp;
+            card.updated_at = Local::now();
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_nickname(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        nickname: Option<String>,
+    ) -> Result<(), RepositoryError> {
+        self.update_card(&talk_room_id.value.to_string(), |card| {
+            card.nickname = nickname;
+            card.updated_at = Local::now();
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// firestoreと同じく、既読の位置だけを書き換え、updated_atは変えない
+    async fn mark_as_read(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        staff_id: StaffId,
+    ) -> Result<TalkRoomReadMarker, RepositoryError> {
+        let read_message_count = self
+            .update_card(&talk_room_id.value.to_string(), |card| {
+                let read_message_count = card.user_message_count;
+                card.read_message_counts
+                    .insert(staff_id.0.to_string(), read_message_count);
+                read_message_count
+            })
+            .await?;
+
+        Ok(TalkRoomReadMarker::new(
+            talk_room_id,
+            staff_id,
+            read_message_count,
+            Local::now(),
+        ))
+    }
+
+    /// 担当者とステータス、モードを更新し、変更の履歴をメッセージに追加する
+    /// latestMessageは変えないので、talkRoomの一覧の並び順は変わらない
+    async fn update_workflow(&self, source: NewTalkRoomWorkflow) -> Result<(), RepositoryError> {
+        let document_id = source.id.value.to_string();
+        let pool = Arc::clone(self.pool.mysql_pool()?);
+        let mut tx = pool.begin().await.map_err(db_error)?;
+        let (_, mut talk_room_card_table) =
+            card_table_of(fetch_card_row(&mut tx, &document_id, true).await?)?;
+        // ロックした行に、変更した項目だけを反映する
+        TalkRoomCardWorkflowTable::from(source.clone())
+            .apply_to(&mut talk_room_card_table, source.changed_fields());
+        save_card(&mut tx, &document_id, &talk_room_card_table).await?;
+        record_change(&mut tx, &document_id, None).await?;
+        for system_event in source.system_events {
+            let message_document_id = system_event.id.value.to_string();
+            let messages_table = MessagesTable::SystemEvent(system_event.into());
+            insert_message(&mut tx, &document_id, &message_document_id, &messages_table).await?;
+            record_change(&mut tx, &document_id, Some(&message_document_id)).await?;
+        }
+        tx.commit().await.map_err(db_error)?;
+
+        Ok(())
+    }
+
+    /// 同時に届いたメッセージで二重に送らないように、talk_roomsの行をロックして確かめる
+    async fn claim_away_message(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        off_hours_since: DateTime<Local>,
+    ) -> Result<bool, RepositoryError> {
+        let document_id = talk_room_id.value.to_string();
+        let pool = Arc::clone(self.pool.mysql_pool()?);
+        let mut tx = pool.begin().await.map_err(db_error)?;
+        let (_, mut talk_room_card_table) =
+            card_table_of(fetch_card_row(&mut tx, &document_id, true).await?)?;
+        let already_sent = talk_room_card_table
+            .away_message_off_hours_since
+            .is_some_and(|sent| sent >= off_hours_since);
+        if already_sent {
+            return Ok(false);
+        }
+        talk_room_card_table.away_message_off_hours_since = Some(off_hours_since);
+        save_card(&mut tx, &document_id, &talk_room_card_table).await?;
+        tx.commit().await.map_err(db_error)?;
+
+        Ok(true)
+    }
+
+    /// firestoreを使わないので、talk_roomsとメッセージの食い違いだけを探す
+    /// firestoreを使っていたときに作った行は、talkRoomCardsの項目がないものとして扱う
+    async fn scan_consistency(&self) -> Result<TalkRoomConsistencyScan, RepositoryError> {
+        let mut conn = self.acquire().await?;
+        let rows = sqlx::query_as::<_, TalkRoomCardDbTable>(&format!(
+            "select {} from talk_rooms",
+            TALK_ROOM_CARD_COLUMNS
+        ))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(db_error)?;
+        let scanned_count = rows.len();
+
+        let mut inconsistencies = vec![];
+        for row in rows {
+            let document_id = row.document_id.clone();
+            if !row.has_card() {
+                inconsistencies.push(TalkRoomInconsistency::MissingCard(document_id));
+                continue;
+            }
+            let (_, talk_room_card_table) = card_table_of(row)?;
+            let message_document_id = talk_room_card_table.latest_message.document_id();
+            if fetch_message(&mut conn, &document_id, message_document_id)
+                .await?
+                .is_none()
+            {
+                inconsistencies.push(TalkRoomInconsistency::MissingLatestMessage {
+                    talk_room_document_id: document_id,
+                    message_document_id: message_document_id.clone(),
+                });
+            }
+        }
+
+        Ok(TalkRoomConsistencyScan::new(scanned_count, inconsistencies))
+    }
+
+    /// talkRoomCardsの項目がない行は、メッセージから作り直す
+    /// latestMessageが指すメッセージがなければ、残っているメッセージのうち最新のものにする
+    /// それ以外の食い違いはfirestoreとの間でしか起きない
+    async fn repair_inconsistency(
+        &self,
+        inconsistency: TalkRoomInconsistency,
+    ) -> Result<(), RepositoryError> {
+        let talk_room_document_id = match inconsistency {
+            TalkRoomInconsistency::MissingCard(document_id) => {
+                self.rebuild_card(&document_id).await?;
+                return Ok(());
+            }
+            TalkRoomInconsistency::MissingLatestMessage {
+                talk_room_document_id,
+                ..
+            } => talk_room_document_id,
+            _ => {
+                return Err(RepositoryError::Unexpected(format!(
+                    "talk rooms in MySQL can not be repaired automatically: {:?}",
+                    inconsistency
+                )));
+            }
+        };
+        let latest_message = {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, MessagesDbTable>(&format!(
+                r#"
+                select {} from messages
+                where talk_room_document_id = ?
+                order by created_at desc, document_id desc
+                limit ?
+                "#,
+                MESSAGE_COLUMNS
+            ))
+            .bind(talk_room_document_id.clone())
+            .bind(LATEST_MESSAGE_LOOKBACK)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(MessagesDbTable::into_messages)
+            .collect::<anyhow::Result<Vec<Messages>>>()
+            .map_err(unexpected_error)?
+            .iter()
+            .find_map(LatestMessageTable::from_messages)
+            .ok_or(RepositoryError::NotFound(
+                "messages".to_string(),
+                talk_room_document_id.clone(),
+            ))?
+        };
+        self.update_card(&talk_room_document_id, |card| {
+            card.latest_message = latest_message;
+            card.updated_at = Local::now();
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl DatabaseRepositoryImpl<TalkRoom> {
+    async fn acquire(&self) -> Result<PoolConnection<MySql>, RepositoryError> {
+        self.pool.mysql_pool()?.acquire().await.map_err(db_error)
+    }
+
+    /// talk_roomsの行をロックしてtalkRoomCardsの項目を更新し、変更を記録する
+    async fn update_card<R>(
+        &self,
+        document_id: &String,
+        update: impl FnOnce(&mut TalkRoomCardTable) -> R,
+    ) -> Result<R, RepositoryError> {
+        let pool = Arc::clone(self.pool.mysql_pool()?);
+        let mut tx = pool.begin().await.map_err(db_error)?;
+        let (_, mut talk_room_card_table) =
+            card_table_of(fetch_card_row(&mut tx, document_id, true).await?)?;
+        let result = update(&mut talk_room_card_table);
+        save_card(&mut tx, document_id, &talk_room_card_table).await?;
+        record_change(&mut tx, document_id, None).await?;
+        tx.commit().await.map_err(db_error)?;
+
+        Ok(result)
+    }
+
+    /// firestoreを使っていたときに作った行に、talkRoomCardsの項目を書き込む
+    /// 担当者などの列は書き込まれていないので、作成したときと同じ初期値にする
+    /// 表示名も空のままなので、line_usersに保存したLINEのプロフィールを使う
+    async fn rebuild_card(&self, document_id: &String) -> Result<(), RepositoryError> {
+        let pool = Arc::clone(self.pool.mysql_pool()?);
+        let mut tx = pool.begin().await.map_err(db_error)?;
+        let row = fetch_card_row(&mut tx, document_id, true).await?;
+        // 突き合わせた後にメッセージが届いて書き込まれていたら、そのままにする
+        if row.has_card() {
+            return Ok(());
+        }
+        let display_name = sqlx::query_scalar::<_, String>(
+            "select display_name from line_users where primary_user_id = ?",
+        )
+        .bind(&row.primary_user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .unwrap_or(row.display_name);
+        let messages = sqlx::query_as::<_, MessagesDbTable>(&format!(
+            r#"
+            select {} from messages
+            where talk_room_document_id = ?
+            order by created_at desc, document_id desc
+            "#,
+            MESSAGE_COLUMNS
+        ))
+        .bind(document_id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(MessagesDbTable::into_messages)
+        .collect::<anyhow::Result<Vec<Messages>>>()
+        .map_err(unexpected_error)?;
+        let talk_room_card_table =
+            TalkRoomCardTable::rebuild(display_name, row.created_at, &messages).ok_or(
+                RepositoryError::NotFound("messages".to_string(), document_id.clone()),
+            )?;
+        save_card(&mut tx, document_id, &talk_room_card_table).await?;
+        record_change(&mut tx, document_id, None).await?;
+        tx.commit().await.map_err(db_error)?;
+
+        Ok(())
+    }
+
+    /// positionより後に記録した変更を、記録した順に読み込む
+    /// 変更したtalkRoomやメッセージがもうないときは、変更をNoneにする
+    ///
+    /// idは採番した順に決まり、コミットした順とは限らない。先に進んでまだコミットしていない変更を
+    /// 飛ばさないように、抜けているidがあればそこで止める。抜けた後の変更が
+    /// TALK_ROOM_CHANGE_COMMIT_LAG_SECSより古くなったら、ロールバックされた欠番とみなして先に進む
+    async fn get_changes_after(
+        &self,
+        position: i64,
+    ) -> Result<Vec<(i64, Option<TalkRoomChange>)>, RepositoryError> {
+        let mut conn = self.acquire().await?;
+        let rows = sqlx::query_as::<_, TalkRoomChangeDbTable>(
+            r#"
+            select id, talk_room_document_id, message_document_id, created_at from talk_room_changes
+            where id > ?
+            order by id
+            limit ?
+            "#,
+        )
+        .bind(position)
+        .bind(TALK_ROOM_CHANGE_BATCH_SIZE)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(db_error)?;
+        let settled_before =
+            Local::now() - chrono::Duration::seconds(TALK_ROOM_CHANGE_COMMIT_LAG_SECS);
+        let mut next_id = position + 1;
+        let rows = rows.into_iter().take_while(|row| {
+            let settled = row.id == next_id || row.created_at < settled_before;
+            next_id = row.id + 1;
+            settled
+        });
+
+        let mut changes = vec![];
+        for row in rows {
+            let change = match &row.message_document_id {
+                Some(message_document_id) => {
+                    match fetch_message(&mut conn, &row.talk_room_document_id, message_document_id)
+                        .await?
+                    {
+                        Some(messages) => Some(TalkRoomChange::MessageAdded(TalkRoomMessage::new(
+                            row.talk_room_document_id
+                                .clone()
+                                .try_into()
+                                .map_err(unexpected_error)?,
+                            messages,
+                        ))),
+                        None => None,
+                    }
+                }
+                None => match fetch_card_row(&mut conn, &row.talk_room_document_id, false).await {
+                    Ok(card_row) => {
+                        let (primary_user_id, talk_room_card_table) = card_table_of(card_row)?;
+                        Some(TalkRoomChange::CardUpdated(
+                            talk_room_card_table
+                                .into_talk_room_card(
+                                    row.talk_room_document_id.clone(),
+                                    primary_user_id,
+                                )
+                                .map_err(unexpected_error)?,
+                        ))
+                    }
+                    Err(RepositoryError::NotFound(_, _)) => None,
+                    Err(e) => return Err(e),
+                },
+            };
+            changes.push((row.id, change));
+        }
+
+        Ok(changes)
+    }
+}
+
+/// talkRoomCardsを更新し、メッセージを追加する
+/// talk_roomsの行をロックしてから読み込むので、latestMessageが存在しないメッセージを指すことはない
+async fn insert_messages(
+    conn: &mut MySqlConnection,
+    source: NewTalkRoom,
+) -> Result<TalkRoom, RepositoryError> {
+    let talk_room_document_id = source.id.value.to_string();
+    let talk_room_card_table = TalkRoomCardTable::from(source.clone());
+    let is_user_message = source.latest_messages.is_user_message();
+    let (message_document_id, messages_table) =
+        MessagesTable::from_new_messages(source.latest_messages.clone());
+    let last_messages = messages_table
+        .into_messages(&message_document_id)
+        .map_err(unexpected_error)?;
+
+    let (_, mut current_talk_room_card_table) =
+        card_table_of(fetch_card_row(conn, &talk_room_document_id, true).await?)?;
+    current_talk_room_card_table.add_messages(&talk_room_card_table, is_user_message);
+    save_card(conn, &talk_room_document_id, &current_talk_room_card_table).await?;
+    insert_message(
+        conn,
+        &talk_room_document_id,
+        &message_document_id,
+        &messages_table,
+    )
+    .await?;
+    record_change(conn, &talk_room_document_id, None).await?;
+    record_change(conn, &talk_room_document_id, Some(&message_document_id)).await?;
+
+    talk_room_card_table
+        .into_talk_room(talk_room_document_id, source.primary_user_id, last_messages)
+        .map_err(unexpected_error)
+}
+
+/// talkRoomCardsの項目をまとめて書き込む
+async fn save_card(
+    conn: &mut MySqlConnection,
+    document_id: &String,
+    talk_room_card_table: &TalkRoomCardTable,
+) -> Result<(), RepositoryError> {
+    sqlx::query(
+        r#"
+        update talk_rooms
+        set display_name = ?, rsvp = ?, pinned = ?, follow = ?, latest_message = ?,
+            latest_messaged_at = ?, sort_time = ?, assignee = ?, status = ?, mode = ?,
+            unread = ?, nickname = ?, user_message_count = ?, read_message_counts = ?,
+            away_message_off_hours_since = ?, created_at = ?, updated_at = ?
+        where document_id = ?
+        "#,
+    )
+    .bind(talk_room_card_table.display_name.clone())
+    .bind(talk_room_card_table.rsvp)
+    .bind(talk_room_card_table.pinned)
+    .bind(talk_room_card_table.follow)
+    .bind(Json(&talk_room_card_table.latest_message))
+    .bind(talk_room_card_table.latest_messaged_at)
+    .bind(talk_room_card_table.sort_time)
+    .bind(talk_room_card_table.assignee)
+    .bind(TalkRoomStatus::from(talk_room_card_table.status).as_str())
+    .bind(TalkRoomMode::from(talk_room_card_table.mode).as_str())
+    .bind(talk_room_card_table.unread)
+    .bind(talk_room_card_table.nickname.clone())
+    .bind(talk_room_card_table.user_message_count)
+    .bind(Json(&talk_room_card_table.read_message_counts))
+    .bind(talk_room_card_table.away_message_off_hours_since)
+    .bind(talk_room_card_table.created_at)
+    .bind(talk_room_card_table.updated_at)
+    .bind(document_id)
+    .execute(conn)
+    .await
+    .map_err(db_error)?;
+
+    Ok(())
+}
+
+/// firestoreと同じく、同じtalkRoomに同じIDのメッセージがあるときは失敗させる
+async fn insert_message(
+    conn: &mut MySqlConnection,
+    talk_room_document_id: &String,
+    message_document_id: &String,
+    messages_table: &MessagesTable,
+) -> Result<(), RepositoryError> {
+    let created_at = *messages_table
+        .into_messages(message_document_id)
+        .map_err(unexpected_error)?
+        .created_at();
+    let (event, send_message, system_event) = match messages_table {
+        MessagesTable::Event(e) => (Some(Json(e)), None, None),
+        MessagesTable::SendMessage(m) => (None, Some(Json(m)), None),
+        MessagesTable::SystemEvent(s) => (None, None, Some(Json(s))),
+    };
+    sqlx::query(
+        r#"
+        insert into messages(talk_room_document_id, document_id, event, send_message, system_event, created_at)
+        values (?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(talk_room_document_id)
+    .bind(message_document_id)
+    .bind(event)
+    .bind(send_message)
+    .bind(system_event)
+    .bind(created_at)
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        insert_error(e, RepositoryError::Conflict(
+            "messages".to_string(),
+            "document_id".to_string(),
+            message_document_id.to_string(),
+        ))
+    })?;
+
+    Ok(())
+}
+
+/// listen_changesで送れるように、変更を記録する
+async fn record_change(
+    conn: &mut MySqlConnection,
+    talk_room_document_id: &String,
+    message_document_id: Option<&String>,
+) -> Result<(), RepositoryError> {
+    sqlx::query(
+        r#"
+        insert into talk_room_changes(talk_room_document_id, message_document_id, created_at)
+        values (?, ?, ?)
+        "#,
+    )
+    .bind(talk_room_document_id)
+    .bind(message_document_id)
+    .bind(Local::now())
+    .execute(conn)
+    .await
+    .map_err(db_error)?;
+
+    Ok(())
+}
+
+/// 更新するときはfor_updateをtrueにして、トランザクションが終わるまで行をロックする
+async fn fetch_card_row(
+    conn: &mut MySqlConnection,
+    document_id: &String,
+    for_update: bool,
+) -> Result<TalkRoomCardDbTable, RepositoryError> {
+    let sql = format!(
+        "select {} from talk_rooms where document_id = ?{}",
+        TALK_ROOM_CARD_COLUMNS,
+        if for_update { " for update" } else { "" }
+    );
+    sqlx::query_as::<_, TalkRoomCardDbTable>(&sql)
+        .bind(document_id)
+        .fetch_one(conn)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => {
+                RepositoryError::NotFound("talk_rooms".to_string(), document_id.to_string())
+            }
+            _ => db_error(e),
+        })
+}
+
+async fn fetch_card_row_by_primary_user_id(
+    conn: &mut MySqlConnection,
+    primary_user_id: &PrimaryUserId,
+) -> Result<TalkRoomCardDbTable, RepositoryError> {
+    sqlx::query_as::<_, TalkRoomCardDbTable>(&format!(
+        "select {} from talk_rooms where primary_user_id = ?",
+        TALK_ROOM_CARD_COLUMNS
+    ))
+    .bind(primary_user_id.value())
+    .fetch_one(conn)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => RepositoryError::NotFound(
+            "talk_rooms".to_string(),
+            primary_user_id.value().to_string(),
+        ),
+        _ => db_error(e),
+    })
+}
+
+async fn fetch_message(
+    conn: &mut MySqlConnection,
+    talk_room_document_id: &String,
+    message_document_id: &String,
+) -> Result<Option<Messages>, RepositoryError> {
+    sqlx::query_as::<_, MessagesDbTable>(&format!(
+        "select {} from messages where talk_room_document_id = ? and document_id = ?",
+        MESSAGE_COLUMNS
+    ))
+    .bind(talk_room_document_id)
+    .bind(message_document_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(db_error)?
+    .map(MessagesDbTable::into_messages)
+    .transpose()
+    .map_err(unexpected_error)
+}
+
+async fn get_talk_room_of_row(
+    conn: &mut MySqlConnection,
+    row: TalkRoomCardDbTable,
+) -> Result<TalkRoom, RepositoryError> {
+    let document_id = row.document_id.clone();
+    let (primary_user_id, talk_room_card_table) = card_table_of(row)?;
+    let message_document_id = talk_room_card_table.latest_message.document_id();
+    let latest_messages = fetch_message(conn, &document_id, message_document_id)
+        .await?
+        .ok_or(RepositoryError::NotFound(
+            "messages".to_string(),
+            message_document_id.to_string(),
+        ))?;
+    talk_room_card_table
+        .into_talk_room(document_id, primary_user_id, latest_messages)
+        .map_err(unexpected_error)
+}
+
+/// firestoreを使っていたときに作った行は、talkRoomCardsがないときと同じくNotFoundにする
+fn card_table_of(
+    row: TalkRoomCardDbTable,
+) -> Result<(PrimaryUserId, TalkRoomCardTable), RepositoryError> {
+    if !row.has_card() {
+        return Err(RepositoryError::NotFound(
+            "talk_rooms".to_string(),
+            row.document_id,
+        ));
+    }
+    let primary_user_id = PrimaryUserId::new(row.primary_user_id.clone());
+    Ok((
+        primary_user_id,
+        row.into_talk_room_card_table().map_err(unexpected_error)?,
+    ))
+}
@@ -0,0 +1,289 @@
+use async_trait::async_trait;
+use chrono::Local;
+
+use crate::model::talk_room_note::{TalkRoomNoteDbTable, TalkRoomNoteTable};
+use crate::persistance::db::{sql, with_pool};
+use crate::repository::{
+    db_error, insert_error, unexpected_error, DatabaseRepositoryImpl, RepositoryError,
+};
+use domain::{
+    model::{
+        talk_room::TalkRoom,
+        talk_room_note::{NewTalkRoomNote, TalkRoomNote},
+        Id,
+    },
+    repository::talk_room_note::TalkRoomNoteRepository,
+};
+
+const TALK_ROOM_NOTE_COLUMNS: &str =
+    "document_id, author_staff_id, author_name, body, created_at, updated_at";
+
+/// firestoreを使えない環境のために、talkRoomのメモもMySQLに持つ
+#[async_trait]
+impl TalkRoomNoteRepository for DatabaseRepositoryImpl<TalkRoomNote> {
+    /// talkRoomのメモを新しい順に取得する
+    async fn get_notes(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+    ) -> Result<Vec<TalkRoomNote>, RepositoryError> {
+        let query = format!(
+            r#"
+            select {} from talk_room_notes
+            where talk_room_document_id = ?
+            order by created_at desc, document_id desc
+            "#,
+            TALK_ROOM_NOTE_COLUMNS
+        );
+        let note_rows = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, TalkRoomNoteDbTable>(&sql(pool, &query))
+                .bind(talk_room_id.value.to_string())
+                .fetch_all(&**pool)
+                .await
+        })
+        .map_err(db_error)?;
+
+        note_rows
+            .into_iter()
+            .map(|row| row.into_talk_room_note(talk_room_id.clone()))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(unexpected_error)
+    }
+
+    async fn get_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+    ) -> Result<TalkRoomNote, RepositoryError> {
+        let document_id = note_id.value.to_string();
+        let query = format!(
+            r#"
+            select {} from talk_room_notes
+            where talk_room_document_id = ? and document_id = ?
+            "#,
+            TALK_ROOM_NOTE_COLUMNS
+        );
+        let note_row = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, TalkRoomNoteDbTable>(&sql(pool, &query))
+                .bind(talk_room_id.value.to_string())
+                .bind(document_id.clone())
+                .fetch_one(&**pool)
+                .await
+        })
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => {
+                RepositoryError::NotFound("talk_room_notes".to_string(), document_id.clone())
+            }
+            _ => db_error(e),
+        })?;
+
+        note_row
+            .into_talk_room_note(talk_room_id)
+            .map_err(unexpected_error)
+    }
+
+    async fn create_note(&self, source: NewTalkRoomNote) -> Result<TalkRoomNote, RepositoryError> {
+        let talk_room_id = source.talk_room_id.clone();
+        let document_id = source.id.value.to_string();
+        let note_table = TalkRoomNoteTable::from(source);
+        with_pool!(&self.pool, pool => {
+            sqlx::query(&sql(
+                pool,
+                r#"
+                insert into talk_room_notes
+                (talk_room_document_id, document_id, author_staff_id, author_name, body, created_at, updated_at)
+                values (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            ))
+            .bind(talk_room_id.value.to_string())
+            .bind(document_id.clone())
+            .bind(note_table.author.staff_id)
+            .bind(note_table.author.name.clone())
+            .bind(note_table.body.clone())
+            .bind(note_table.created_at)
+            .bind(note_table.updated_at)
+            .execute(&**pool)
+            .await
+            .map(|_| ())
+        })
+        .map_err(|e| {
+            insert_error(
+                e,
+                RepositoryError::Conflict(
+                    "talk_room_notes".to_string(),
+                    "document_id".to_string(),
+                    document_id.clone(),
+                ),
+            )
+        })?;
+
+        note_table
+            .into_talk_room_note(talk_room_id, document_id)
+            .map_err(unexpected_error)
+    }
+
+    /// メモの本文を書き換える。書いたスタッフと作成日時は変えない
+    async fn update_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+        body: String,
+    ) -> Result<(), RepositoryError> {
+        // 同じ内容への更新では影響を受けた行が0になるので、先に存在を確かめる
+        self.get_note(talk_room_id.clone(), note_id.clone()).await?;
+        with_pool!(&self.pool, pool => {
+            sqlx::query(&sql(
+                pool,
+                r#"
+                update talk_room_notes
+                set body = ?, updated_at = ?
+                where talk_room_document_id = ? and document_id = ?
+                "#,
+            ))
+            .bind(body)
+            .bind(Local::now())
+            .bind(talk_room_id.value.to_string())
+            .bind(note_id.value.to_string())
+            .execute(&**pool)
+            .await
+            .map(|_| ())
+        })
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+    ) -> Result<(), RepositoryError> {
+        let rows_affected = with_pool!(&self.pool, pool => {
+            sqlx::query(&sql(
+                pool,
+                r#"
+                delete from talk_room_notes
+                where talk_room_document_id = ? and document_id = ?
+                "#,
+            ))
+            .bind(talk_room_id.value.to_string())
+            .bind(note_id.value.to_string())
+            .execute(&**pool)
+            .await
+            .map(|result| result.rows_affected())
+        })
+        .map_err(db_error)?;
+        if rows_affected == 0 {
+            return Err(RepositoryError::NotFound(
+                "talk_room_notes".to_string(),
+                note_id.value.to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Local;
+    use domain::model::{
+        staff::StaffId,
+        talk_room_note::{NewTalkRoomNote, TalkRoomNote, TalkRoomNoteAuthor},
+        Id,
+    };
+    use domain::repository::talk_room_note::TalkRoomNoteRepository;
+
+    use crate::persistance::db::Db;
+    use crate::repository::{DatabaseRepositoryImpl, RepositoryError};
+
+    async fn sqlite() -> Db {
+        let db = Db::connect("sqlite::memory:")
+            .await
+            .expect("failed to connect to sqlite");
+        db.run_migrations().await.expect("failed to migrate sqlite");
+        db
+    }
+
+    #[tokio::test]
+    async fn test_create_update_and_delete_notes() {
+        let repository = DatabaseRepositoryImpl::<TalkRoomNote>::new(sqlite().await);
+        let talk_room_id = Id::gen();
+        let author = TalkRoomNoteAuthor::new(StaffId::new(1), "staff1".to_string());
+
+        let first = repository
+            .create_note(NewTalkRoomNote::new(
+                Id::gen(),
+                talk_room_id.clone(),
+                author.clone(),
+                "最初のメモ".to_string(),
+                Local::now(),
+            ))
+            .await
+            .unwrap();
+        let second = repository
+            .create_note(NewTalkRoomNote::new(
+                Id::gen(),
+                talk_room_id.clone(),
+                author.clone(),
+                "次のメモ".to_string(),
+                Local::now(),
+            ))
+            .await
+            .unwrap();
+        // ほかのtalkRoomのメモは含めない
+        repository
+            .create_note(NewTalkRoomNote::new(
+                Id::gen(),
+                Id::gen(),
+                author,
+                "ほかのtalkRoomのメモ".to_string(),
+                Local::now(),
+            ))
+            .await
+            .unwrap();
+
+        let notes = repository.get_notes(talk_room_id.clone()).await.unwrap();
+        assert_eq!(notes, vec![second.clone(), first.clone()]);
+
+        repository
+            .update_note(
+                talk_room_id.clone(),
+                first.id.clone(),
+                "書き換えたメモ".to_string(),
+            )
+            .await
+            .unwrap();
+        let note = repository
+            .get_note(talk_room_id.clone(), first.id.clone())
+            .await
+            .unwrap();
+        assert_eq!(note.body, "書き換えたメモ");
+        assert_eq!(note.author, first.author);
+        assert_eq!(note.created_at, first.created_at);
+
+        repository
+            .delete_note(talk_room_id.clone(), first.id.clone())
+            .await
+            .unwrap();
+        assert!(matches!(
+            repository
+                .get_note(talk_room_id.clone(), first.id.clone())
+                .await,
+            Err(RepositoryError::NotFound(..))
+        ));
+        assert!(matches!(
+            repository
+                .update_note(talk_room_id.clone(), first.id.clone(), "".to_string())
+                .await,
+            Err(RepositoryError::NotFound(..))
+        ));
+        assert!(matches!(
+            repository.delete_note(talk_room_id.clone(), first.id).await,
+            Err(RepositoryError::NotFound(..))
+        ));
+        assert_eq!(
+            repository.get_notes(talk_room_id).await.unwrap(),
+            vec![second]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use crate::model::staff::StaffTable;
+use crate::persistance::db::{insert_returning_id, returning_id, sql, with_pool};
+use crate::repository::DatabaseRepositoryImpl;
+use async_trait::async_trait;
+use domain::model::email_user::EmailAddress;
+use domain::model::staff::{NewStaff, Staff, StaffId, StaffRole};
+use domain::repository::staff::StaffRepository;
+
+use super::{db_error, insert_error, unexpected_error, RepositoryError};
+
+#[async_trait]
+impl StaffRepository for DatabaseRepositoryImpl<Staff> {
+    async fn get_staff(&self, staff_id: StaffId) -> Result<Staff, RepositoryError> {
+        let staff_row = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, StaffTable>(&sql(
+                pool,
+                r#"
+                select id, email, name, picture_url, role, password_hash, created_at, updated_at from staffs
+                where id = ?
+                "#,
+            ))
+            .bind(staff_id.0)
+            .fetch_one(&**pool)
+            .await
+        })
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => RepositoryError::NotFound(
+                "staffs".to_string(),
+                staff_id.0.to_string()
+            ),
+            _ => db_error(e),
+        })?;
+
+        staff_row.try_into().map_err(unexpected_error)
+    }
+
+    async fn get_staff_by_email(&self, email: EmailAddress) -> Result<Staff, RepositoryError> {
+        let email = email.0;
+        let staff_row = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, StaffTable>(&sql(
+                pool,
+                r#"
+                select id, email, name, picture_url, role, password_hash, created_at, updated_at from staffs
+                where email = ?
+                "#,
+            ))
+            .bind(email.clone())
+            .fetch_one(&**pool)
+            .await
+        })
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => {
+                RepositoryError::NotFound("staffs".to_string(), email)
+            }
+            _ => db_error(e),
+        })?;
+
+        staff_row.try_into().map_err(unexpected_error)
+    }
+
+    async fn get_staffs(&self) -> Result<Vec<Staff>, RepositoryError> {
+        let staff_rows = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, StaffTable>(
+                r#"
+                select id, email, name, picture_url, role, password_hash, created_at, updated_at from staffs
+                order by id
+                "#,
+            )
+            .fetch_all(&**pool)
+            .await
+        })
+        .map_err(db_error)?;
+
+        staff_rows
+            .into_iter()
+            .map(|row| row.try_into().map_err(unexpected_error))
+            .collect()
+    }
+
+    async fn create_staff(&self, source: NewStaff) -> Result<Staff, RepositoryError> {
+        let email = source.email.0;
+        let staff_id = with_pool!(&self.pool, pool => {
+            let query = format!(
+                r#"
+                insert into staffs (email, name, picture_url, role, password_hash)
+                values (?, ?, ?, ?, ?){}
+                "#,
+                returning_id(pool)
+            );
+            let query = sql(pool, &query);
+            let query = sqlx::query(&query)
+                .bind(email.clone())
+                .bind(source.name)
+                .bind(source.picture_url)
+                .bind(source.role.as_str())
+                .bind(source.password_hash.0);
+            insert_returning_id(pool, query).await
+        })
+        .map_err(|e| {
+            insert_error(
+                e,
+                RepositoryError::Conflict("staffs".to_string(), "email".to_string(), email),
+            )
+        })?;
+
+        self.get_staff(StaffId::new(staff_id)).await
+    }
+
+    async fn update_staff_role(
+        &self,
+        staff_id: StaffId,
+        role: StaffRole,
+    ) -> Result<Staff, RepositoryError> {
+        // 同じロールへの更新では影響を受けた行が0になるので、先に存在を確かめる
+        self.get_staff(staff_id).await?;
+        with_pool!(&self.pool, pool => {
+            sqlx::query(&sql(
+                pool,
+                r#"
+                update staffs
+                set role = ?, updated_at = CURRENT_TIMESTAMP
+                where id = ?
+                "#,
+            ))
+            .bind(role.as_str())
+            .bind(staff_id.0)
+            .execute(&**pool)
+            .await
+            .map(|_| ())
+        })
+        .map_err(db_error)?;
+
+        self.get_staff(staff_id).await
+    }
+}
@@ -1,169 +1,423 @@
-use anyhow::anyhow;
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Local};
+use firestore::{
+    path_camel_case, paths_camel_case, FirestoreDb, FirestoreDocument, FirestoreListenEvent,
+    FirestoreListenerTarget, FirestoreQueryCursor, FirestoreQueryDirection, FirestoreReference,
+    FirestoreTimestamp, FirestoreWritePrecondition,
+};
+use futures::{stream::BoxStream, FutureExt, StreamExt, TryStreamExt};
+use std::collections::BTreeSet;
 use std::sync::Arc;
 
 use crate::model::message::event::EventTable;
 use crate::model::message::send_message::SendMessageTable;
 use crate::model::message::MessagesTable;
-use crate::model::talk_room::{TalkRoomCardTable, TalkRoomDbTable, TalkRoomTable};
+use crate::model::talk_room::{
+    LatestMessageTable, TalkRoomCardAwayMessageTable, TalkRoomCardDisplayNameTable,
+    TalkRoomCardFilterFieldsTable, TalkRoomCardNicknameTable, TalkRoomCardPinnedTable,
+    TalkRoomCardReadMarkerTable, TalkRoomCardRsvpTable, TalkRoomCardTable,
+    TalkRoomCardWorkflowTable, TalkRoomDbTable, TalkRoomPrimaryUserIdTable, TalkRoomStatusTable,
+    TalkRoomTable,
+};
+use crate::model::talk_room_change::TalkRoomChangeResumeTokens;
+use crate::persistance::db::{sql, with_pool};
+use crate::repository::user::merge_primary_users;
 use crate::repository::{
+    conflict_as, db_error, firestore_error, insert_error, unexpected_error,
     DbFirestoreRepositoryImpl, RepositoryError, MESSAGE_COLLECTION_NAME,
     TALK_ROOM_CARD_COLLECTION_NAME, TALK_ROOM_COLLECTION_NAME,
 };
 use domain::{
     model::{
-        message::{Messages, NewMessages},
+        message::{MessageCursor, Messages, MessagesPage, NewMessages},
         primary_user_id::PrimaryUserId,
-        talk_room::{NewTalkRoom, TalkRoom},
+        staff::StaffId,
+        talk_room::{NewTalkRoom, NewTalkRoomWorkflow, TalkRoom},
+        talk_room_card::{
+            TalkRoomAssigneeFilter, TalkRoomCardPage, TalkRoomCursor, TalkRoomFilter,
+            TalkRoomReadMarker,
+        },
+        talk_room_change::{
+            TalkRoomChange, TalkRoomChangeResumeToken, TalkRoomChangeStream, TalkRoomMessage,
+        },
+        talk_room_consistency::{TalkRoomConsistencyScan, TalkRoomInconsistency},
+        Id,
     },
     repository::talk_room::TalkRoomRepository,
 };
 
+// talkRoomの変更を監視するときのターゲット。再開する位置はターゲットごとに持つ
+const TALK_ROOM_CARD_LISTENER_TARGET: u32 = 1;
+const MESSAGE_LISTENER_TARGET: u32 = 2;
+// 受け取る側が追いつかないときに溜めておく変更の数
+const TALK_ROOM_CHANGE_BUFFER_SIZE: usize = 100;
+// create_talk_roomはMySQLのコミット前にfirestoreへ書き込むので、作成直後のドキュメントは孤立とみなさない
+const ORPHANED_DOCUMENT_GRACE_MINUTES: i64 = 10;
+// 突き合わせのときに、firestoreのドキュメントを一度に取得する数
+const CONSISTENCY_LIST_PAGE_SIZE: usize = 500;
+// 突き合わせのときに、latestMessageが指すメッセージを並行して確かめる数
+const CONSISTENCY_CHECK_CONCURRENCY: usize = 20;
+// latestMessageを作り直すときに遡るメッセージの数。system_eventが続いたときのために余裕をもたせる
+const LATEST_MESSAGE_LOOKBACK: u32 = 20;
+
 #[async_trait]
 impl TalkRoomRepository for DbFirestoreRepositoryImpl<TalkRoom> {
-    async fn get_talk_room(&self, primary_user_id: PrimaryUserId) -> anyhow::Result<TalkRoom> {
+    async fn get_talk_room(
+        &self,
+        primary_user_id: PrimaryUserId,
+    ) -> Result<TalkRoom, RepositoryError> {
         /*
          * DBのtalk_roomsテーブルからprimary_user_idを元にtalk_roomを取得する
          */
-        let pool = Arc::clone(self.db.pool());
-        let primary_user_id_str = primary_user_id.value().to_string();
-        let talk_room_db_table = sqlx::query_as::<_, TalkRoomDbTable>(
-            r#"
-            select * from talk_rooms
-            where primary_user_id = ?
-            "#,
-        )
-        .bind(primary_user_id_str.clone())
-        .fetch_one(&*pool)
-        .await
-        .map_err(|e| match e {
-            sqlx::Error::RowNotFound => {
-                anyhow!(RepositoryError::NotFound(
-                    "talk_rooms".to_string(),
-                    primary_user_id_str.clone()
+        let document_id = self.get_document_id(&primary_user_id).await?;
+        Ok(self
+            .get_talk_room_in_firestore(document_id, primary_user_id)
+            .await?)
+    }
+
+    async fn get_talk_room_by_id(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+    ) -> Result<TalkRoom, RepositoryError> {
+        let document_id = talk_room_id.value.to_string();
+        let primary_user_id = self.get_primary_user_id(&document_id).await?;
+        Ok(self
+            .get_talk_room_in_firestore(document_id, primary_user_id)
+            .await?)
+    }
+
+    /// talkRoomの一覧を、ピン留めしたものを先頭にsort_timeの新しい順で取得する
+    /// 絞り込み条件の組み合わせごとにfirestoreの複合インデックスが必要。firestore.indexes.jsonを参照
+    /// 担当者やステータス、未読を追加する前のドキュメントはその項目で絞り込めないので、
+    /// 整合性チェックのMissingCardFieldsを直すまで絞り込んだ結果に含まれない
+    ///
+    /// # Arguments
+    /// * `filter` - 絞り込み条件
+    /// * `cursor` - 前のページの最後のtalkRoom。Noneのときは先頭から取得する
+    /// * `limit` - 1ページの件数
+    ///
+    async fn get_talk_room_cards(
+        &self,
+        filter: TalkRoomFilter,
+        cursor: Option<TalkRoomCursor>,
+        limit: u32,
+    ) -> Result<TalkRoomCardPage, RepositoryError> {
+        let firestore = Arc::clone(&self.firestore.0);
+        let query = firestore
+            .fluent()
+            .select()
+            .from(TALK_ROOM_CARD_COLLECTION_NAME)
+            .filter(|q| {
+                q.for_all([
+                    filter
+                        .pinned
+                        .and_then(|v| q.field(path_camel_case!(TalkRoomCardTable::pinned)).eq(v)),
+                    filter
+                        .follow
+                        .and_then(|v| q.field(path_camel_case!(TalkRoomCardTable::follow)).eq(v)),
+                    filter
+                        .rsvp
+                        .and_then(|v| q.field(path_camel_case!(TalkRoomCardTable::rsvp)).eq(v)),
+                    filter
+                        .unread
+                        .and_then(|v| q.field(path_camel_case!(TalkRoomCardTable::unread)).eq(v)),
+                    filter.status.and_then(|v| {
+                        q.field(path_camel_case!(TalkRoomCardTable::status))
+                            .eq(TalkRoomStatusTable::from(v))
+                    }),
+                    filter.assignee.and_then(|v| {
+                        let field = q.field(path_camel_case!(TalkRoomCardTable::assignee));
+                        match v {
+                            TalkRoomAssigneeFilter::Staff(staff_id) => field.eq(staff_id.0),
+                            TalkRoomAssigneeFilter::Unassigned => field.is_null(),
+                        }
+                    }),
+                ])
+            })
+            .order_by([
+                (
+                    path_camel_case!(TalkRoomCardTable::pinned),
+                    FirestoreQueryDirection::Descending,
+                ),
+                (
+                    path_camel_case!(TalkRoomCardTable::sort_time),
+                    FirestoreQueryDirection::Descending,
+                ),
+                // sort_timeが同じtalkRoomの順番を決めるために、ドキュメントのIDでも並べる
+                ("__name__".to_string(), FirestoreQueryDirection::Descending),
+            ])
+            // 続きがあるかを判定するために1件多く取得する
+            .limit(limit + 1);
+        let query = match cursor {
+            Some(c) => query.start_at(FirestoreQueryCursor::AfterValue(vec![
+                c.pinned.into(),
+                FirestoreTimestamp(c.sort_time).into(),
+                FirestoreReference(format!(
+                    "{}/{}/{}",
+                    firestore.get_documents_path(),
+                    TALK_ROOM_CARD_COLLECTION_NAME,
+                    c.id.value
                 ))
-            }
-            _ => anyhow!(RepositoryError::Unexpected(e.to_string())),
-        })?;
-        println!("talk_room_db_table: {:?}", talk_room_db_table);
+                .into(),
+            ])),
+            None => query,
+        };
+        let mut talk_room_card_tables: Vec<TalkRoomCardTable> =
+            query.obj().query().await.map_err(firestore_error)?;
+        let has_next = talk_room_card_tables.len() > limit as usize;
+        talk_room_card_tables.truncate(limit as usize);
+
         /*
-         * FirestoreのtalkRoomsとtalkRoomCardsコレクションからdocument_idを元にtalk_roomとtalk_room_cardを取得する
+         * primary_user_idはDBのtalk_roomsテーブルにしかないので、まとめて取得する
          */
+        let document_ids = talk_room_card_tables
+            .iter()
+            .map(|t| {
+                t.document_id.clone().ok_or(RepositoryError::Unexpected(
+                    "talkRoomCards document id is missing".to_string(),
+                ))
+            })
+            .collect::<Result<Vec<String>, _>>()?;
+        let primary_user_ids = self.get_primary_user_ids(&document_ids).await?;
+        let talk_room_cards = talk_room_card_tables
+            .into_iter()
+            .zip(document_ids)
+            .map(|(t, document_id)| {
+                let primary_user_id = primary_user_ids
+                    .iter()
+                    .find(|r| r.document_id == document_id)
+                    .map(|r| PrimaryUserId::new(r.primary_user_id.clone()))
+                    .ok_or(RepositoryError::NotFound(
+                        "talk_rooms".to_string(),
+                        document_id.clone(),
+                    ))?;
+                t.into_talk_room_card(document_id, primary_user_id)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(unexpected_error)?;
+        let next_cursor = if has_next {
+            talk_room_cards.last().map(TalkRoomCursor::from)
+        } else {
+            None
+        };
+
+        Ok(TalkRoomCardPage::new(talk_room_cards, next_cursor))
+    }
+
+    /// talkRoomのメッセージ履歴を新しい順に取得する
+    /// イベント、送信メッセージ、システムイベントをまとめて返す
+    ///
+    /// # Arguments
+    /// * `talk_room_id` - 履歴を取得するtalkRoom
+    /// * `cursor` - 前のページの最後のメッセージ。Noneのときは最新から取得する
+    /// * `limit` - 1ページの件数
+    ///
+    async fn get_messages(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        cursor: Option<MessageCursor>,
+        limit: u32,
+    ) -> Result<MessagesPage, RepositoryError> {
+        let document_id = talk_room_id.value.to_string();
+        // 存在しないtalkRoomのときはNotFoundを返す
+        self.get_primary_user_id(&document_id).await?;
+
         let firestore = Arc::clone(&self.firestore.0);
-        let document_id = talk_room_db_table.document_id;
-        let talk_room_table: TalkRoomTable = firestore
+        let parent_path = firestore
+            .parent_path(TALK_ROOM_COLLECTION_NAME, &document_id)
+            .map_err(firestore_error)?;
+        let query = firestore
             .fluent()
             .select()
-            .by_id_in(TALK_ROOM_COLLECTION_NAME)
-            .obj()
-            .one(&document_id)
-            .await?
-            .ok_or(RepositoryError::NotFound(
-                TALK_ROOM_COLLECTION_NAME.to_string(),
-                document_id.clone(),
-            ))?;
-        println!("talk_room_table: {:?}", talk_room_table);
+            .from(MESSAGE_COLLECTION_NAME)
+            .parent(&parent_path)
+            .order_by([
+                ("createdAt".to_string(), FirestoreQueryDirection::Descending),
+                // 作成日時が同じメッセージの順番を決めるために、ドキュメントのIDでも並べる
+                ("__name__".to_string(), FirestoreQueryDirection::Descending),
+            ])
+            // 続きがあるかを判定するために1件多く取得する
+            .limit(limit + 1);
+        let query = match cursor {
+            // createdAtは文字列で保存しているので、同じ形式でシリアライズする
+            Some(c) => query.start_at(FirestoreQueryCursor::AfterValue(vec![
+                c.created_at.into(),
+                FirestoreReference(format!(
+                    "{}/{}/{}",
+                    parent_path, MESSAGE_COLLECTION_NAME, c.id
+                ))
+                .into(),
+            ])),
+            None => query,
+        };
+        let mut documents = query.query().await.map_err(firestore_error)?;
+        let has_next = documents.len() > limit as usize;
+        documents.truncate(limit as usize);
 
-        let talk_room_card_table: TalkRoomCardTable = firestore
-            .fluent()
-            .select()
-            .by_id_in(TALK_ROOM_CARD_COLLECTION_NAME)
-            .obj()
-            .one(&document_id)
-            .await?
-            .ok_or(RepositoryError::NotFound(
-                TALK_ROOM_CARD_COLLECTION_NAME.to_string(),
-                document_id.clone(),
-            ))?;
-        println!("talk_room_card_table: {:?}", talk_room_card_table);
+        let messages = documents
+            .iter()
+            .map(|doc| {
+                let message_document_id = doc
+                    .name
+                    .split('/')
+                    .next_back()
+                    .unwrap_or_default()
+                    .to_string();
+                let messages_table: MessagesTable = FirestoreDb::deserialize_doc_to(doc)?;
+                messages_table.into_messages(&message_document_id)
+            })
+            .collect::<anyhow::Result<Vec<Messages>>>()
+            .map_err(unexpected_error)?;
+        let next_cursor = if has_next {
+            messages.last().map(MessageCursor::from)
+        } else {
+            None
+        };
 
-        let message_document_id = talk_room_card_table.latest_message.document_id();
-        let messages_table: MessagesTable = firestore
-            .fluent()
-            .select()
-            .by_id_in(MESSAGE_COLLECTION_NAME)
-            .parent(&firestore.parent_path(TALK_ROOM_COLLECTION_NAME, &document_id)?)
-            .obj()
-            .one(&message_document_id)
-            .await?
-            .ok_or(RepositoryError::NotFound(
-                MESSAGE_COLLECTION_NAME.to_string(),
-                message_document_id.to_string(),
-            ))?;
-        println!("messages_table: {:?}", messages_table.clone());
-        let latest_messages = messages_table.into_messages(message_document_id);
-
-        Ok(TalkRoom::new(
-            document_id.try_into()?,
-            primary_user_id,
-            talk_room_card_table.display_name,
-            talk_room_card_table.rsvp,
-            talk_room_card_table.pinned,
-            talk_room_card_table.follow,
-            latest_messages,
-            talk_room_card_table.latest_messaged_at,
-            talk_room_card_table.sort_time,
-            talk_room_card_table.created_at,
-            talk_room_card_table.updated_at,
-        ))
+        Ok(MessagesPage::new(messages, next_cursor))
     }
 
-    async fn create_talk_room(&self, source: NewTalkRoom) -> anyhow::Result<TalkRoom> {
-        let db = Arc::clone(self.db.pool());
-        let document_id = source.id.value.to_string();
-        // firestoreの書き込みが失敗したときにもDBへの書き込みも失敗するようにする
-        let mut tx = db.begin().await.expect("Unable to begin transaction");
-        sqlx::query(
-            r#"
-            insert into talk_rooms(document_id, primary_user_id, created_at)
-            values (?, ?, default)
-            "#,
-        )
-        .bind(source.id.value.to_string())
-        .bind(source.primary_user_id.value())
-        .execute(&mut *tx)
-        .await
-        .expect("Unable to insert a talk rooms");
-
-        let talk_room_table = TalkRoomTable::from(source.clone());
-        println!("talk_room_table: {:?}", talk_room_table);
-        let talk_room_card_table = TalkRoomCardTable::from(source.clone());
-        println!("talk_room_card_table: {:?}", talk_room_card_table);
+    /// talkRoomCardsの更新と、全talkRoomのメッセージの追加を監視する
+    /// 接続ごとに監視を始め、返したストリームが破棄されたら監視を止める
+    ///
+    /// # Arguments
+    /// * `resume_token` - 前の接続で最後に受け取ったCheckpoint。Noneのときは今から後の変更だけを受け取る
+    ///
+    async fn listen_changes(
+        &self,
+        resume_token: Option<TalkRoomChangeResumeToken>,
+    ) -> Result<TalkRoomChangeStream, RepositoryError> {
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(TALK_ROOM_CHANGE_BUFFER_SIZE);
+        let storage = TalkRoomChangeResumeTokens::new(resume_token, sender.clone())
+            .map_err(unexpected_error)?;
         let firestore = Arc::clone(&self.firestore.0);
+        let mut listener = firestore
+            .create_listener(storage)
+            .await
+            .map_err(firestore_error)?;
         firestore
             .fluent()
-            .insert()
-            .into(TALK_ROOM_COLLECTION_NAME)
-            .document_id(&document_id)
-            .object(&talk_room_table)
-            .execute()
-            .await
-            .map_err(|e| {
-                println!("firestore insert error: {}", e);
-                anyhow!(RepositoryError::CouldNotInsert(
-                    TALK_ROOM_COLLECTION_NAME.to_string(),
-                    "document_id".to_string(),
-                    document_id.clone(),
-                ))
-            })?;
+            .select()
+            .from(TALK_ROOM_CARD_COLLECTION_NAME)
+            .listen()
+            .add_target(
+                FirestoreListenerTarget::new(TALK_ROOM_CARD_LISTENER_TARGET),
+                &mut listener,
+            )
+            .map_err(firestore_error)?;
+        // メッセージはtalkRoomごとのサブコレクションにあるので、コレクショングループで監視する
         firestore
             .fluent()
-            .insert()
-            .into(TALK_ROOM_CARD_COLLECTION_NAME)
-            .document_id(&document_id)
-            .object(&talk_room_card_table)
-            .execute()
+            .select()
+            .from(MESSAGE_COLLECTION_NAME)
+            .all_descendants()
+            .listen()
+            .add_target(
+                FirestoreListenerTarget::new(MESSAGE_LISTENER_TARGET),
+                &mut listener,
+            )
+            .map_err(firestore_error)?;
+
+        let repository = Arc::new(DbFirestoreRepositoryImpl::<TalkRoom>::new(
+            self.db.clone(),
+            self.firestore.clone(),
+        ));
+        let change_sender = sender.clone();
+        listener
+            .start(move |event| {
+                let repository = Arc::clone(&repository);
+                let sender = change_sender.clone();
+                async move {
+                    let FirestoreListenEvent::DocumentChange(change) = event else {
+                        return Ok(());
+                    };
+                    let Some(document) = change.document else {
+                        return Ok(());
+                    };
+                    if let Some(talk_room_change) = repository
+                        .talk_room_change_from_document(&document)
+                        .await
+                        .transpose()
+                    {
+                        sender.send(talk_room_change).await.ok();
+                    }
+                    Ok(())
+                }
+            })
+            .await
+            .map_err(firestore_error)?;
+        tokio::spawn(async move {
+            sender.closed().await;
+            listener.shutdown().await.ok();
+        });
+
+        Ok(futures::stream::poll_fn(move |cx| receiver.poll_recv(cx)).boxed())
+    }
+
+    async fn create_talk_room(&self, source: NewTalkRoom) -> Result<TalkRoom, RepositoryError> {
+        let document_id = source.id.value.to_string();
+        with_pool!(&self.db, db => {
+            // firestoreの書き込みが失敗したときにもDBへの書き込みも失敗するようにする
+            let mut tx = db.begin().await.map_err(db_error)?;
+            sqlx::query(&sql(
+                db,
+                r#"
+                insert into talk_rooms(document_id, primary_user_id)
+                values (?, ?)
+                "#,
+            ))
+            .bind(source.id.value.to_string())
+            .bind(source.primary_user_id.value())
+            .execute(&mut *tx)
             .await
             .map_err(|e| {
-                println!("firestore insert error: {}", e);
-                anyhow!(RepositoryError::CouldNotInsert(
-                    TALK_ROOM_CARD_COLLECTION_NAME.to_string(),
+                insert_error(e, RepositoryError::Conflict(
+                    "talk_rooms".to_string(),
                     "document_id".to_string(),
                     document_id.clone(),
                 ))
             })?;
-        // トランザクションはスコープ外になると自動的にロールバックしてくれるので、firestoreでエラーが起きた場合もDBへの書き込みも削除される
-        tx.commit().await.expect("Unable to commit the transaction");
+
+            let talk_room_table = TalkRoomTable::from(source.clone());
+            println!("talk_room_table: {:?}", talk_room_table);
+            let talk_room_card_table = TalkRoomCardTable::from(source.clone());
+            println!("talk_room_card_table: {:?}", talk_room_card_table);
+            let firestore = Arc::clone(&self.firestore.0);
+            firestore
+                .fluent()
+                .insert()
+                .into(TALK_ROOM_COLLECTION_NAME)
+                .document_id(&document_id)
+                .object(&talk_room_table)
+                .execute::<TalkRoomTable>()
+                .await
+                .map_err(|e| {
+                    println!("firestore insert error: {}", e);
+                    conflict_as(firestore_error(e), RepositoryError::Conflict(
+                        TALK_ROOM_COLLECTION_NAME.to_string(),
+                        "document_id".to_string(),
+                        document_id.clone(),
+                    ))
+                })?;
+            firestore
+                .fluent()
+                .insert()
+                .into(TALK_ROOM_CARD_COLLECTION_NAME)
+                .document_id(&document_id)
+                .object(&talk_room_card_table)
+                .execute::<TalkRoomCardTable>()
+                .await
+                .map_err(|e| {
+                    println!("firestore insert error: {}", e);
+                    conflict_as(firestore_error(e), RepositoryError::Conflict(
+                        TALK_ROOM_CARD_COLLECTION_NAME.to_string(),
+                        "document_id".to_string(),
+                        document_id.clone(),
+                    ))
+                })?;
+            // トランザクションはスコープ外になると自動的にロールバックしてくれるので、firestoreでエラーが起きた場合もDBへの書き込みも削除される
+            tx.commit().await.map_err(db_error)?;
+        });
 
         /*
          * イベントを作成する
@@ -174,87 +428,1060 @@ impl TalkRoomRepository for DbFirestoreRepositoryImpl<TalkRoom> {
     }
 
     /// talkRoomをupdateし、イベントを作成する
+    /// talkRoomCardsの更新とmessagesへの追加は一つのトランザクションで書き込むので、
+    /// latestMessageが存在しないメッセージを指すことはない
     ///
     /// # Arguments
     /// * `source` - 更新するtalkRoom。latest_messageには最新のイベントを入れる
     ///
-    async fn create_messages(&self, source: NewTalkRoom) -> anyhow::Result<TalkRoom> {
+    async fn create_messages(&self, source: NewTalkRoom) -> Result<TalkRoom, RepositoryError> {
         let firestore = Arc::clone(&self.firestore.0);
         let talk_room_document_id = source.id.value.to_string();
         let talk_room_card_table = TalkRoomCardTable::from(source.clone());
+        // ユーザーのメッセージの累計は、同時に届いたメッセージで数え漏れないようにincrementで加算する
+        let is_user_message = source.latest_messages.is_user_message();
+        let new_latest_messages = source.latest_messages;
+        let (message_document_id, messages_table, last_messages) = match &new_latest_messages {
+            NewMessages::Event(e) => {
+                let document_id = e.id().value.to_string();
+                let event_table = EventTable::from(e.clone());
+                let last_messages = Messages::Event(event_table.clone().into_event(&document_id));
+                (
+                    document_id,
+                    MessagesTable::Event(event_table),
+                    last_messages,
+                )
+            }
+            NewMessages::SendMessages(m) => {
+                let document_id = m.id.value.to_string();
+                let send_message_table = SendMessageTable::from(m.clone());
+                let last_messages =
+                    Messages::SendMessages(send_message_table.clone().into_messages(&document_id));
+                (
+                    document_id,
+                    MessagesTable::SendMessage(send_message_table),
+                    last_messages,
+                )
+            }
+        };
+        let parent_path = firestore
+            .parent_path(TALK_ROOM_COLLECTION_NAME, &talk_room_document_id)
+            .map_err(firestore_error)?;
+        firestore
+            .run_transaction(|db, transaction| {
+                let talk_room_document_id = talk_room_document_id.clone();
+                let talk_room_card_table = talk_room_card_table.clone();
+                let message_document_id = message_document_id.clone();
+                let messages_table = messages_table.clone();
+                let parent_path = parent_path.clone();
+                async move {
+                    let current_talk_room_card_table: Option<TalkRoomCardTable> = db
+                        .fluent()
+                        .select()
+                        .by_id_in(TALK_ROOM_CARD_COLLECTION_NAME)
+                        .obj()
+                        .one(&talk_room_document_id)
+                        .await?;
+                    // 同時に書き込んだ新しいメッセージがあるときは、latestMessageと並び順を古いメッセージで巻き戻さない
+                    let is_latest = current_talk_room_card_table.is_none_or(|c| {
+                        c.latest_messaged_at <= talk_room_card_table.latest_messaged_at
+                    });
+                    // 担当者、ステータス、モード、ピン留め、rsvp、呼び名はスタッフが変更するので、ここでは上書きしない
+                    // 表示名はupdate_display_nameでLINEのプロフィールから更新する
+                    let fields = if is_latest {
+                        paths_camel_case!(TalkRoomCardTable::{
+                            follow,
+                            latest_message,
+                            latest_messaged_at,
+                            sort_time,
+                            unread,
+                            created_at,
+                            updated_at
+                        })
+                    } else {
+                        paths_camel_case!(TalkRoomCardTable::{ updated_at })
+                    };
+                    db.fluent()
+                        .update()
+                        .fields(fields)
+                        .in_col(TALK_ROOM_CARD_COLLECTION_NAME)
+                        .document_id(&talk_room_document_id)
+                        .object(&talk_room_card_table)
+                        .transforms(|t| {
+                            t.fields([is_user_message
+                                .then(|| {
+                                    t.field(path_camel_case!(TalkRoomCardTable::user_message_count))
+                                        .increment(1)
+                                })
+                                .flatten()])
+                        })
+                        .add_to_transaction(transaction)?;
+                    // insertはトランザクションに入れられないので、存在しないことを条件にupdateで作成する
+                    db.fluent()
+                        .update()
+                        .in_col(MESSAGE_COLLECTION_NAME)
+                        .precondition(FirestoreWritePrecondition::Exists(false))
+                        .document_id(&message_document_id)
+                        .parent(&parent_path)
+                        .object(&messages_table)
+                        .add_to_transaction(transaction)?;
+
+                    Ok(())
+                }
+                .boxed()
+            })
+            .await
+            .map_err(firestore_error)?;
+
+        Ok(TalkRoom {
+            assignee: source.assignee,
+            status: source.status,
+            unread: source.unread,
+            nickname: source.nickname,
+            mode: source.mode,
+            ..TalkRoom::new(
+                talk_room_document_id.try_into().map_err(unexpected_error)?,
+                source.primary_user_id,
+                talk_room_card_table.display_name,
+                talk_room_card_table.rsvp,
+                talk_room_card_table.pinned,
+                talk_room_card_table.follow,
+                last_messages,
+                talk_room_card_table.latest_messaged_at,
+                talk_room_card_table.sort_time,
+                talk_room_card_table.created_at,
+                talk_room_card_table.updated_at,
+            )
+        })
+    }
+
+    /// talkRoomCardsのdisplayNameだけを更新する
+    /// 他のフィールドはスタッフの操作やイベントで更新されるので、上書きしないようにする
+    ///
+    /// # Arguments
+    /// * `primary_user_id` - talkRoomのユーザー
+    /// * `display_name` - LINEのプロフィールから取得した表示名
+    ///
+    async fn update_display_name(
+        &self,
+        primary_user_id: PrimaryUserId,
+        display_name: String,
+    ) -> Result<(), RepositoryError> {
+        let document_id = self.get_document_id(&primary_user_id).await?;
+        let display_name_table = TalkRoomCardDisplayNameTable {
+            display_name,
+            updated_at: Local::now(),
+        };
+        let firestore = Arc::clone(&self.firestore.0);
         firestore
             .fluent()
             .update()
+            .fields(paths_camel_case!(TalkRoomCardDisplayNameTable::{
+                display_name,
+                updated_at
+            }))
             .in_col(TALK_ROOM_CARD_COLLECTION_NAME)
-            .document_id(&talk_room_document_id)
-            .object(&talk_room_card_table)
-            .execute()
-            .await?;
-        let new_latest_messages = source.latest_messages;
-        /*
-         * イベントを作成する
-         */
-        let last_messages = self
-            .insert_messages(&talk_room_document_id, &new_latest_messages)
+            .document_id(&document_id)
+            .object(&display_name_table)
+            .execute::<TalkRoomCardDisplayNameTable>()
+            .await
+            .map_err(firestore_error)?;
+
+        Ok(())
+    }
+
+    /// talkRoomCardsのpinnedだけを更新する
+    async fn update_pinned(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        pinned: bool,
+    ) -> Result<(), RepositoryError> {
+        let firestore = Arc::clone(&self.firestore.0);
+        firestore
+            .fluent()
+            .update()
+            .fields(paths_camel_case!(TalkRoomCardPinnedTable::{ pinned, updated_at }))
+            .in_col(TALK_ROOM_CARD_COLLECTION_NAME)
+            .document_id(talk_room_id.value.to_string())
+            .object(&TalkRoomCardPinnedTable {
+                pinned,
+                updated_at: Local::now(),
+            })
+            .execute::<TalkRoomCardPinnedTable>()
+            .await
+            .map_err(firestore_error)?;
+
+        Ok(())
+    }
+
+    /// talkRoomCardsのrsvpだけを更新する
+    async fn update_rsvp(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        rsvp: bool,
+    ) -> Result<(), RepositoryError> {
+        let firestore = Arc::clone(&self.firestore.0);
+        firestore
+            .fluent()
+            .update()
+            .fields(paths_camel_case!(TalkRoomCardRsvpTable::{ rsvp, updated_at }))
+            .in_col(TALK_ROOM_CARD_COLLECTION_NAME)
+            .document_id(talk_room_id.value.to_string())
+            .object(&TalkRoomCardRsvpTable {
+                rsvp,
+                updated_at: Local::now(),
+            })
+            .execute::<TalkRoomCardRsvpTable>()
+            .await
+            .map_err(firestore_error)?;
+
+        Ok(())
+    }
+
+    /// talkRoomCardsのnicknameだけを更新する
+    /// LINEの表示名はdisplayNameに残るので、プロフィールの更新で呼び名は消えない
+    ///
+    /// # Arguments
+    /// * `talk_room_id` - 呼び名を付けるtalkRoom
+    /// * `nickname` - スタッフが付けた呼び名。Noneのときは呼び名を外す
+    ///
+    async fn update_nickname(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        nickname: Option<String>,
+    ) -> Result<(), RepositoryError> {
+        let firestore = Arc::clone(&self.firestore.0);
+        firestore
+            .fluent()
+            .update()
+            .fields(paths_camel_case!(TalkRoomCardNicknameTable::{ nickname, updated_at }))
+            .in_col(TALK_ROOM_CARD_COLLECTION_NAME)
+            .document_id(talk_room_id.value.to_string())
+            .object(&TalkRoomCardNicknameTable {
+                nickname,
+                updated_at: Local::now(),
+            })
+            .execute::<TalkRoomCardNicknameTable>()
+            .await
+            .map_err(firestore_error)?;
+
+        Ok(())
+    }
+
+    /// スタッフの既読の位置を、今のユーザーのメッセージの累計に進める
+    /// 既読の位置はスタッフごとにreadMessageCountsに持ち、他のスタッフの分は上書きしない
+    ///
+    /// # Arguments
+    /// * `talk_room_id` - 既読にするtalkRoom
+    /// * `staff_id` - 既読にしたスタッフ
+    ///
+    async fn mark_as_read(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        staff_id: StaffId,
+    ) -> Result<TalkRoomReadMarker, RepositoryError> {
+        let document_id = talk_room_id.value.to_string();
+        let firestore = Arc::clone(&self.firestore.0);
+        let talk_room_card_table: TalkRoomCardTable = firestore
+            .fluent()
+            .select()
+            .by_id_in(TALK_ROOM_CARD_COLLECTION_NAME)
+            .obj()
+            .one(&document_id)
+            .await
+            .map_err(firestore_error)?
+            .ok_or(RepositoryError::NotFound(
+                TALK_ROOM_CARD_COLLECTION_NAME.to_string(),
+                document_id.clone(),
+            ))?;
+        let read_message_count = talk_room_card_table.user_message_count;
+        firestore
+            .fluent()
+            .update()
+            .fields([TalkRoomCardReadMarkerTable::field_path(staff_id)])
+            .in_col(TALK_ROOM_CARD_COLLECTION_NAME)
+            .document_id(&document_id)
+            .object(&TalkRoomCardReadMarkerTable {
+                read_message_counts: [(staff_id.0.to_string(), read_message_count)].into(),
+            })
+            .execute::<TalkRoomCardReadMarkerTable>()
+            .await
+            .map_err(firestore_error)?;
+
+        Ok(TalkRoomReadMarker::new(
+            talk_room_id,
+            staff_id,
+            read_message_count,
+            Local::now(),
+        ))
+    }
+
+    /// 営業時間外の区切りごとに一度だけ、不在メッセージを送る権利を取る
+    /// 同時に届いたメッセージで二重に送らないように、読み込みと書き込みをトランザクションで行う
+    ///
+    /// # Arguments
+    /// * `talk_room_id` - 不在メッセージを送るtalkRoom
+    /// * `off_hours_since` - 今の営業時間外が始まった時刻
+    ///
+    async fn claim_away_message(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        off_hours_since: DateTime<Local>,
+    ) -> Result<bool, RepositoryError> {
+        let document_id = talk_room_id.value.to_string();
+        let firestore = Arc::clone(&self.firestore.0);
+        let claimed = firestore
+            .run_transaction(|db, transaction| {
+                let document_id = document_id.clone();
+                async move {
+                    let talk_room_card_table: Option<TalkRoomCardTable> = db
+                        .fluent()
+                        .select()
+                        .by_id_in(TALK_ROOM_CARD_COLLECTION_NAME)
+                        .obj()
+                        .one(&document_id)
+                        .await?;
+                    let already_sent = talk_room_card_table
+                        .and_then(|t| t.away_message_off_hours_since)
+                        .is_some_and(|sent| sent >= off_hours_since);
+                    if already_sent {
+                        return Ok(false);
+                    }
+                    db.fluent()
+                        .update()
+                        .fields(paths_camel_case!(TalkRoomCardAwayMessageTable::{
+                            away_message_off_hours_since
+                        }))
+                        .in_col(TALK_ROOM_CARD_COLLECTION_NAME)
+                        .document_id(&document_id)
+                        .object(&TalkRoomCardAwayMessageTable {
+                            away_message_off_hours_since: off_hours_since,
+                        })
+                        .add_to_transaction(transaction)?;
+
+                    Ok(true)
+                }
+                .boxed()
+            })
+            .await
+            .map_err(firestore_error)?;
+
+        Ok(claimed)
+    }
+
+    /// MySQLのtalk_roomsを基準に、firestoreのtalkRooms、talkRoomCards、messagesを突き合わせる
+    /// firestoreのドキュメントはページごとに読み、突き合わせのためにIDだけを残す
+    /// 全件を読むので、定期ジョブや管理用のエンドポイントからだけ呼ぶ
+    async fn scan_consistency(&self) -> Result<TalkRoomConsistencyScan, RepositoryError> {
+        let document_ids: BTreeSet<String> = with_pool!(&self.db, pool => {
+            sqlx::query_as::<_, TalkRoomDbTable>("select * from talk_rooms")
+                .fetch_all(&**pool)
+                .await
+        })
+        .map_err(db_error)?
+        .into_iter()
+        .map(|t| t.document_id)
+        .collect();
+        let orphaned_before = Local::now() - Duration::minutes(ORPHANED_DOCUMENT_GRACE_MINUTES);
+
+        let mut inconsistencies = vec![];
+        let mut orphaned_document_ids = BTreeSet::new();
+
+        let mut talk_room_document_ids = BTreeSet::new();
+        let mut pages = self.list_document_pages(TALK_ROOM_COLLECTION_NAME).await?;
+        while let Some(documents) = pages.try_next().await? {
+            for document in documents {
+                let document_id = document_id_of(&document);
+                match FirestoreDb::deserialize_doc_to::<TalkRoomTable>(&document) {
+                    Ok(talk_room_table) => {
+                        if !document_ids.contains(&document_id)
+                            && talk_room_table.created_at < orphaned_before
+                        {
+                            orphaned_document_ids.insert(document_id.clone());
+                        }
+                    }
+                    Err(err) => {
+                        inconsistencies.push(TalkRoomInconsistency::UndeserializableDocument {
+                            talk_room_document_id: document_id.clone(),
+                            collection_id: TALK_ROOM_COLLECTION_NAME.to_string(),
+                            error: err.to_string(),
+                        })
+                    }
+                }
+                talk_room_document_ids.insert(document_id);
+            }
+        }
+
+        let mut talk_room_card_document_ids = BTreeSet::new();
+        let mut pages = self
+            .list_document_pages(TALK_ROOM_CARD_COLLECTION_NAME)
             .await?;
+        while let Some(documents) = pages.try_next().await? {
+            let mut latest_messages = vec![];
+            for document in documents {
+                let document_id = document_id_of(&document);
+                match FirestoreDb::deserialize_doc_to::<TalkRoomCardTable>(&document) {
+                    Ok(talk_room_card_table) if document_ids.contains(&document_id) => {
+                        let fields = TalkRoomCardFilterFieldsTable::missing_fields(&document);
+                        if !fields.is_empty() {
+                            inconsistencies.push(TalkRoomInconsistency::MissingCardFields {
+                                talk_room_document_id: document_id.clone(),
+                                fields,
+                            });
+                        }
+                        latest_messages.push((
+                            document_id.clone(),
+                            talk_room_card_table.latest_message.document_id().clone(),
+                        ));
+                    }
+                    Ok(talk_room_card_table) => {
+                        if talk_room_card_table.created_at < orphaned_before {
+                            orphaned_document_ids.insert(document_id.clone());
+                        }
+                    }
+                    Err(err) => {
+                        inconsistencies.push(TalkRoomInconsistency::UndeserializableDocument {
+                            talk_room_document_id: document_id.clone(),
+                            collection_id: TALK_ROOM_CARD_COLLECTION_NAME.to_string(),
+                            error: err.to_string(),
+                        })
+                    }
+                }
+                talk_room_card_document_ids.insert(document_id);
+            }
+            // latestMessageが指すメッセージは、ページの分をまとめて並行して確かめる
+            let missing_latest_messages: Vec<Option<TalkRoomInconsistency>> =
+                futures::stream::iter(latest_messages)
+                    .map(|(talk_room_document_id, message_document_id)| async move {
+                        let exists = self
+                            .message_exists(&talk_room_document_id, &message_document_id)
+                            .await?;
+                        Ok::<_, RepositoryError>((!exists).then_some(
+                            TalkRoomInconsistency::MissingLatestMessage {
+                                talk_room_document_id,
+                                message_document_id,
+                            },
+                        ))
+                    })
+                    .buffered(CONSISTENCY_CHECK_CONCURRENCY)
+                    .try_collect()
+                    .await?;
+            inconsistencies.extend(missing_latest_messages.into_iter().flatten());
+        }
+
+        for document_id in &document_ids {
+            if !talk_room_document_ids.contains(document_id) {
+                inconsistencies.push(TalkRoomInconsistency::MissingTalkRoomDocument(
+                    document_id.clone(),
+                ));
+            }
+            if !talk_room_card_document_ids.contains(document_id) {
+                inconsistencies.push(TalkRoomInconsistency::MissingCard(document_id.clone()));
+            }
+        }
+
+        for document_id in &orphaned_document_ids {
+            inconsistencies.push(TalkRoomInconsistency::OrphanedDocuments {
+                talk_room_document_id: document_id.to_string(),
+                has_messages: self.has_messages(document_id).await?,
+            });
+        }
 
-        Ok(TalkRoom::new(
-            talk_room_document_id.try_into()?,
-            source.primary_user_id,
-            talk_room_card_table.display_name,
-            talk_room_card_table.rsvp,
-            talk_room_card_table.pinned,
-            talk_room_card_table.follow,
-            last_messages,
-            talk_room_card_table.latest_messaged_at,
-            talk_room_card_table.sort_time,
-            talk_room_card_table.created_at,
-            talk_room_card_table.updated_at,
+        Ok(TalkRoomConsistencyScan::new(
+            document_ids.len() + orphaned_document_ids.len(),
+            inconsistencies,
         ))
     }
+
+    /// 食い違いを1件直す
+    /// 突き合わせてから時間が経っているかもしれないので、直す前に状態を確かめ直す
+    async fn repair_inconsistency(
+        &self,
+        inconsistency: TalkRoomInconsistency,
+    ) -> Result<(), RepositoryError> {
+        let firestore = Arc::clone(&self.firestore.0);
+        match inconsistency {
+            // MySQLの行からtalkRoomsのドキュメントを作り直す
+            TalkRoomInconsistency::MissingTalkRoomDocument(document_id) => {
+                let talk_room_db_table = self
+                    .get_primary_user_ids(std::slice::from_ref(&document_id))
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or(RepositoryError::NotFound(
+                        "talk_rooms".to_string(),
+                        document_id.clone(),
+                    ))?;
+                firestore
+                    .fluent()
+                    .update()
+                    .in_col(TALK_ROOM_COLLECTION_NAME)
+                    .document_id(&document_id)
+                    .object(&TalkRoomTable {
+                        primary_user_id: talk_room_db_table.primary_user_id,
+                        created_at: talk_room_db_table.created_at,
+                    })
+                    .execute::<TalkRoomTable>()
+                    .await
+                    .map_err(firestore_error)?;
+            }
+            // 残っているメッセージのうち最新のものを、latestMessageにする
+            TalkRoomInconsistency::MissingLatestMessage {
+                talk_room_document_id,
+                ..
+            } => {
+                let parent_path = firestore
+                    .parent_path(TALK_ROOM_COLLECTION_NAME, &talk_room_document_id)
+                    .map_err(firestore_error)?;
+                let documents = firestore
+                    .fluent()
+                    .select()
+                    .from(MESSAGE_COLLECTION_NAME)
+                    .parent(&parent_path)
+                    .order_by([("createdAt".to_string(), FirestoreQueryDirection::Descending)])
+                    .limit(LATEST_MESSAGE_LOOKBACK)
+                    .query()
+                    .await
+                    .map_err(firestore_error)?;
+                let latest_message = documents
+                    .iter()
+                    .map(|doc| {
+                        let messages_table: MessagesTable = FirestoreDb::deserialize_doc_to(doc)?;
+                        messages_table.into_messages(&document_id_of(doc))
+                    })
+                    .collect::<anyhow::Result<Vec<Messages>>>()
+                    .map_err(unexpected_error)?
+                    .iter()
+                    .find_map(LatestMessageTable::from_messages)
+                    .ok_or(RepositoryError::NotFound(
+                        MESSAGE_COLLECTION_NAME.to_string(),
+                        talk_room_document_id.clone(),
+                    ))?;
+                let mut talk_room_card_table: TalkRoomCardTable = firestore
+                    .fluent()
+                    .select()
+                    .by_id_in(TALK_ROOM_CARD_COLLECTION_NAME)
+                    .obj()
+                    .one(&talk_room_document_id)
+                    .await
+                    .map_err(firestore_error)?
+                    .ok_or(RepositoryError::NotFound(
+                        TALK_ROOM_CARD_COLLECTION_NAME.to_string(),
+                        talk_room_document_id.clone(),
+                    ))?;
+                talk_room_card_table.latest_message = latest_message;
+                talk_room_card_table.updated_at = Local::now();
+                firestore
+                    .fluent()
+                    .update()
+                    .fields(paths_camel_case!(TalkRoomCardTable::{
+                        latest_message,
+                        updated_at
+                    }))
+                    .in_col(TALK_ROOM_CARD_COLLECTION_NAME)
+                    .document_id(&talk_room_document_id)
+                    .object(&talk_room_card_table)
+                    .execute::<TalkRoomCardTable>()
+                    .await
+                    .map_err(firestore_error)?;
+            }
+            // 絞り込みの項目がないときだけデフォルトを書き込み、その間にスタッフが変更した値は上書きしない
+            TalkRoomInconsistency::MissingCardFields {
+                talk_room_document_id,
+                ..
+            } => {
+                firestore
+                    .run_transaction(|db, transaction| {
+                        let talk_room_document_id = talk_room_document_id.clone();
+                        async move {
+                            let document = db
+                                .fluent()
+                                .select()
+                                .by_id_in(TALK_ROOM_CARD_COLLECTION_NAME)
+                                .one(&talk_room_document_id)
+                                .await?;
+                            let Some(document) = document else {
+                                return Ok(());
+                            };
+                            let fields = TalkRoomCardFilterFieldsTable::missing_fields(&document);
+                            if fields.is_empty() {
+                                return Ok(());
+                            }
+                            db.fluent()
+                                .update()
+                                .fields(fields)
+                                .in_col(TALK_ROOM_CARD_COLLECTION_NAME)
+                                .document_id(&talk_room_document_id)
+                                .object(&TalkRoomCardFilterFieldsTable::default())
+                                .add_to_transaction(transaction)?;
+
+                            Ok(())
+                        }
+                        .boxed()
+                    })
+                    .await
+                    .map_err(firestore_error)?;
+            }
+            // メッセージのない孤立したtalkRoomsとtalkRoomCardsを消す
+            TalkRoomInconsistency::OrphanedDocuments {
+                talk_room_document_id,
+                ..
+            } => {
+                let exists_in_db = !self
+                    .get_primary_user_ids(std::slice::from_ref(&talk_room_document_id))
+                    .await?
+                    .is_empty();
+                if exists_in_db || self.has_messages(&talk_room_document_id).await? {
+                    return Err(RepositoryError::Unexpected(format!(
+                        "talk room is no longer orphaned: {}",
+                        talk_room_document_id
+                    )));
+                }
+                for collection_id in [TALK_ROOM_CARD_COLLECTION_NAME, TALK_ROOM_COLLECTION_NAME] {
+                    firestore
+                        .fluent()
+                        .delete()
+                        .from(collection_id)
+                        .document_id(&talk_room_document_id)
+                        .execute()
+                        .await
+                        .map_err(firestore_error)?;
+                }
+            }
+            // talk_roomsの行と残っているメッセージから、talkRoomCardsを作り直す
+            // スタッフが変更した担当者などは残っていないので、作成したときと同じ初期値にする
+            TalkRoomInconsistency::MissingCard(document_id) => {
+                let talk_room_db_table = self
+                    .get_primary_user_ids(std::slice::from_ref(&document_id))
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or(RepositoryError::NotFound(
+                        "talk_rooms".to_string(),
+                        document_id.clone(),
+                    ))?;
+                let display_name = self
+                    .get_line_display_name(&talk_room_db_table.primary_user_id)
+                    .await?;
+                let parent_path = firestore
+                    .parent_path(TALK_ROOM_COLLECTION_NAME, &document_id)
+                    .map_err(firestore_error)?;
+                let messages = firestore
+                    .fluent()
+                    .select()
+                    .from(MESSAGE_COLLECTION_NAME)
+                    .parent(&parent_path)
+                    .order_by([("createdAt".to_string(), FirestoreQueryDirection::Descending)])
+                    .query()
+                    .await
+                    .map_err(firestore_error)?
+                    .iter()
+                    .map(|doc| {
+                        let messages_table: MessagesTable = FirestoreDb::deserialize_doc_to(doc)?;
+                        messages_table.into_messages(&document_id_of(doc))
+                    })
+                    .collect::<anyhow::Result<Vec<Messages>>>()
+                    .map_err(unexpected_error)?;
+                let talk_room_card_table = TalkRoomCardTable::rebuild(
+                    display_name,
+                    talk_room_db_table.created_at,
+                    &messages,
+                )
+                .ok_or(RepositoryError::NotFound(
+                    MESSAGE_COLLECTION_NAME.to_string(),
+                    document_id.clone(),
+                ))?;
+                // 突き合わせた後にメッセージが届いて作られていたら、そちらを上書きしないようにinsertで作る
+                firestore
+                    .fluent()
+                    .insert()
+                    .into(TALK_ROOM_CARD_COLLECTION_NAME)
+                    .document_id(&document_id)
+                    .object(&talk_room_card_table)
+                    .execute::<TalkRoomCardTable>()
+                    .await
+                    .map_err(firestore_error)?;
+            }
+            // 読み込めないドキュメントは、どう直すかを人が決める
+            TalkRoomInconsistency::UndeserializableDocument {
+                talk_room_document_id,
+                collection_id,
+                ..
+            } => {
+                return Err(RepositoryError::Unexpected(format!(
+                    "{} can not be repaired automatically: {}",
+                    collection_id, talk_room_document_id
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// ユーザーの統合で、fromのtalkRoomをintoに付け替える
+    /// firestoreのprimaryUserIdもコミットの前に書き込み、失敗したときはユーザーの統合ごと取り消す
+    ///
+    /// # Arguments
+    /// * `into` - 残すユーザー
+    /// * `from` - intoに統合して削除するユーザー
+    ///
+    async fn merge_users(
+        &self,
+        into: PrimaryUserId,
+        from: PrimaryUserId,
+    ) -> Result<(), RepositoryError> {
+        with_pool!(&self.db, db => {
+            let mut tx = db
+                .begin()
+                .await
+                .map_err(db_error)?;
+            let document_id = merge_primary_users(&mut tx, &into, &from).await?;
+            if let Some(document_id) = document_id {
+                let firestore = Arc::clone(&self.firestore.0);
+                firestore
+                    .fluent()
+                    .update()
+                    .fields(paths_camel_case!(TalkRoomPrimaryUserIdTable::{
+                        primary_user_id
+                    }))
+                    .in_col(TALK_ROOM_COLLECTION_NAME)
+                    .document_id(&document_id)
+                    .object(&TalkRoomPrimaryUserIdTable {
+                        primary_user_id: into.value().to_string(),
+                    })
+                    .execute::<TalkRoomPrimaryUserIdTable>()
+                    .await.map_err(firestore_error)?;
+            }
+            tx.commit()
+                .await
+                .map_err(db_error)?;
+        });
+
+        Ok(())
+    }
+
+    /// talkRoomCardsの担当者とステータスを更新し、変更の履歴をmessagesに追加する
+    /// latestMessageは変えないので、talkRoomの一覧の並び順は変わらない
+    /// 同時にほかの項目を変更しても上書きしないように、変更した項目だけを書き込む
+    ///
+    /// # Arguments
+    /// * `source` - 変更後の担当者とステータス、変更の履歴
+    ///
+    async fn update_workflow(&self, source: NewTalkRoomWorkflow) -> Result<(), RepositoryError> {
+        let document_id = source.id.value.to_string();
+        let firestore = Arc::clone(&self.firestore.0);
+        firestore
+            .fluent()
+            .update()
+            .fields(TalkRoomCardWorkflowTable::update_fields(
+                source.changed_fields(),
+            ))
+            .in_col(TALK_ROOM_CARD_COLLECTION_NAME)
+            .document_id(&document_id)
+            .object(&TalkRoomCardWorkflowTable::from(source.clone()))
+            .execute::<TalkRoomCardWorkflowTable>()
+            .await
+            .map_err(firestore_error)?;
+        for system_event in source.system_events {
+            self.insert_messages_table_to_firestore(
+                &document_id,
+                &system_event.id.value.to_string(),
+                &MessagesTable::SystemEvent(system_event.into()),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
 }
 
 impl DbFirestoreRepositoryImpl<TalkRoom> {
-    async fn insert_messages(
+    /// コレクションのドキュメントを、ページごとに取得する
+    async fn list_document_pages(
+        &self,
+        collection_id: &str,
+    ) -> Result<BoxStream<'_, Result<Vec<FirestoreDocument>, RepositoryError>>, RepositoryError>
+    {
+        Ok(self
+            .firestore
+            .0
+            .fluent()
+            .list()
+            .from(collection_id)
+            .page_size(CONSISTENCY_LIST_PAGE_SIZE)
+            .stream_all_with_errors()
+            .await
+            .map_err(firestore_error)?
+            .try_chunks(CONSISTENCY_LIST_PAGE_SIZE)
+            .map_err(|err| firestore_error(err.1))
+            .boxed())
+    }
+
+    async fn message_exists(
         &self,
         talk_room_document_id: &String,
-        new_latest_messages: &NewMessages,
-    ) -> anyhow::Result<Messages> {
-        let last_messages = match new_latest_messages {
-            NewMessages::Event(e) => {
-                let document_id = e.id().value.to_string();
-                let event_table = EventTable::from(e.clone());
-                self.insert_messages_table_to_firestore(
-                    talk_room_document_id,
-                    &document_id,
-                    &MessagesTable::Event(event_table.clone()),
-                )
-                .await?;
-                Messages::Event(event_table.into_event(&document_id))
+        message_document_id: &String,
+    ) -> Result<bool, RepositoryError> {
+        let firestore = Arc::clone(&self.firestore.0);
+        let document = firestore
+            .fluent()
+            .select()
+            .by_id_in(MESSAGE_COLLECTION_NAME)
+            .parent(
+                &firestore
+                    .parent_path(TALK_ROOM_COLLECTION_NAME, talk_room_document_id)
+                    .map_err(firestore_error)?,
+            )
+            .one(message_document_id)
+            .await
+            .map_err(firestore_error)?;
+        Ok(document.is_some())
+    }
+
+    async fn has_messages(&self, talk_room_document_id: &String) -> Result<bool, RepositoryError> {
+        let firestore = Arc::clone(&self.firestore.0);
+        let documents = firestore
+            .fluent()
+            .select()
+            .from(MESSAGE_COLLECTION_NAME)
+            .parent(
+                &firestore
+                    .parent_path(TALK_ROOM_COLLECTION_NAME, talk_room_document_id)
+                    .map_err(firestore_error)?,
+            )
+            .limit(1)
+            .query()
+            .await
+            .map_err(firestore_error)?;
+        Ok(!documents.is_empty())
+    }
+
+    /// 変更されたドキュメントを、talkRoomCardsかメッセージかをパスで見分けて変換する
+    /// どちらでもないドキュメントのときはNoneを返す
+    async fn talk_room_change_from_document(
+        &self,
+        document: &FirestoreDocument,
+    ) -> Result<Option<TalkRoomChange>, RepositoryError> {
+        let path = document.name.split('/').collect::<Vec<_>>();
+        match path.as_slice() {
+            [.., TALK_ROOM_CARD_COLLECTION_NAME, document_id] => {
+                let document_id = document_id.to_string();
+                let talk_room_card_table: TalkRoomCardTable =
+                    FirestoreDb::deserialize_doc_to(document).map_err(firestore_error)?;
+                let primary_user_id = self.get_primary_user_id(&document_id).await?;
+                let talk_room_card = talk_room_card_table
+                    .into_talk_room_card(document_id, primary_user_id)
+                    .map_err(unexpected_error)?;
+                Ok(Some(TalkRoomChange::CardUpdated(talk_room_card)))
             }
-            NewMessages::SendMessages(m) => {
-                let document_id = m.id.value.to_string();
-                let send_message_table = SendMessageTable::from(m.clone());
-                self.insert_messages_table_to_firestore(
-                    talk_room_document_id,
-                    &document_id,
-                    &MessagesTable::SendMessage(send_message_table.clone()),
-                )
-                .await?;
-                Messages::SendMessages(send_message_table.into_messages(&document_id))
+            [.., TALK_ROOM_COLLECTION_NAME, talk_room_id, MESSAGE_COLLECTION_NAME, message_document_id] =>
+            {
+                let messages_table: MessagesTable =
+                    FirestoreDb::deserialize_doc_to(document).map_err(firestore_error)?;
+                Ok(Some(TalkRoomChange::MessageAdded(TalkRoomMessage::new(
+                    talk_room_id
+                        .to_string()
+                        .try_into()
+                        .map_err(unexpected_error)?,
+                    messages_table
+                        .into_messages(&message_document_id.to_string())
+                        .map_err(unexpected_error)?,
+                ))))
             }
-        };
-        Ok(last_messages)
+            _ => Ok(None),
+        }
     }
+
+    async fn get_talk_room_in_firestore(
+        &self,
+        document_id: String,
+        primary_user_id: PrimaryUserId,
+    ) -> Result<TalkRoom, RepositoryError> {
+        /*
+         * FirestoreのtalkRoomsとtalkRoomCardsコレクションからdocument_idを元にtalk_roomとtalk_room_cardを取得する
+         */
+        let firestore = Arc::clone(&self.firestore.0);
+        let talk_room_table: TalkRoomTable = firestore
+            .fluent()
+            .select()
+            .by_id_in(TALK_ROOM_COLLECTION_NAME)
+            .obj()
+            .one(&document_id)
+            .await
+            .map_err(firestore_error)?
+            .ok_or(RepositoryError::NotFound(
+                TALK_ROOM_COLLECTION_NAME.to_string(),
+                document_id.clone(),
+            ))?;
+        println!("talk_room_table: {:?}", talk_room_table);
+
+        let talk_room_card_table: TalkRoomCardTable = firestore
+            .fluent()
+            .select()
+            .by_id_in(TALK_ROOM_CARD_COLLECTION_NAME)
+            .obj()
+            .one(&document_id)
+            .await
+            .map_err(firestore_error)?
+            .ok_or(RepositoryError::NotFound(
+                TALK_ROOM_CARD_COLLECTION_NAME.to_string(),
+                document_id.clone(),
+            ))?;
+        println!("talk_room_card_table: {:?}", talk_room_card_table);
+
+        let message_document_id = talk_room_card_table.latest_message.document_id();
+        let messages_table: MessagesTable = firestore
+            .fluent()
+            .select()
+            .by_id_in(MESSAGE_COLLECTION_NAME)
+            .parent(
+                &firestore
+                    .parent_path(TALK_ROOM_COLLECTION_NAME, &document_id)
+                    .map_err(firestore_error)?,
+            )
+            .obj()
+            .one(&message_document_id)
+            .await
+            .map_err(firestore_error)?
+            .ok_or(RepositoryError::NotFound(
+                MESSAGE_COLLECTION_NAME.to_string(),
+                message_document_id.to_string(),
+            ))?;
+        println!("messages_table: {:?}", messages_table.clone());
+        let latest_messages = messages_table
+            .into_messages(message_document_id)
+            .map_err(unexpected_error)?;
+
+        Ok(TalkRoom {
+            assignee: talk_room_card_table.assignee(),
+            status: talk_room_card_table.status.into(),
+            unread: talk_room_card_table.unread,
+            nickname: talk_room_card_table.nickname,
+            mode: talk_room_card_table.mode.into(),
+            ..TalkRoom::new(
+                document_id.try_into().map_err(unexpected_error)?,
+                primary_user_id,
+                talk_room_card_table.display_name,
+                talk_room_card_table.rsvp,
+                talk_room_card_table.pinned,
+                talk_room_card_table.follow,
+                latest_messages,
+                talk_room_card_table.latest_messaged_at,
+                talk_room_card_table.sort_time,
+                talk_room_card_table.created_at,
+                talk_room_card_table.updated_at,
+            )
+        })
+    }
+
+    async fn get_document_id(
+        &self,
+        primary_user_id: &PrimaryUserId,
+    ) -> Result<String, RepositoryError> {
+        let primary_user_id_str = primary_user_id.value().to_string();
+        let talk_room_db_table = with_pool!(&self.db, pool => {
+            sqlx::query_as::<_, TalkRoomDbTable>(&sql(
+                pool,
+                r#"
+                select * from talk_rooms
+                where primary_user_id = ?
+                "#,
+            ))
+            .bind(primary_user_id_str.clone())
+            .fetch_one(&**pool)
+            .await
+        })
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => {
+                RepositoryError::NotFound("talk_rooms".to_string(), primary_user_id_str)
+            }
+            _ => db_error(e),
+        })?;
+
+        Ok(talk_room_db_table.document_id)
+    }
+
+    async fn get_primary_user_id(
+        &self,
+        document_id: &String,
+    ) -> Result<PrimaryUserId, RepositoryError> {
+        let talk_room_db_table = with_pool!(&self.db, pool => {
+            sqlx::query_as::<_, TalkRoomDbTable>(&sql(
+                pool,
+                r#"
+                select * from talk_rooms
+                where document_id = ?
+                "#,
+            ))
+            .bind(document_id)
+            .fetch_one(&**pool)
+            .await
+        })
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => {
+                RepositoryError::NotFound("talk_rooms".to_string(), document_id.to_string())
+            }
+            _ => db_error(e),
+        })?;
+
+        Ok(PrimaryUserId::new(talk_room_db_table.primary_user_id))
+    }
+
+    async fn get_primary_user_ids(
+        &self,
+        document_ids: &[String],
+    ) -> Result<Vec<TalkRoomDbTable>, RepositoryError> {
+        if document_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let placeholders = vec!["?"; document_ids.len()].join(", ");
+        let query = format!(
+            "select * from talk_rooms where document_id in ({})",
+            placeholders
+        );
+        with_pool!(&self.db, pool => {
+            let query = sql(pool, &query);
+            document_ids
+                .iter()
+                .fold(sqlx::query_as::<_, TalkRoomDbTable>(&query), |q, id| {
+                    q.bind(id)
+                })
+                .fetch_all(&**pool)
+                .await
+        })
+        .map_err(db_error)
+    }
+
+    /// talkRoomCardsの表示名にする、line_usersに保存したLINEのプロフィールの表示名
+    /// LINE以外で登録したユーザーはline_usersにいないので、空にしてupdate_display_nameで更新する
+    async fn get_line_display_name(
+        &self,
+        primary_user_id: &str,
+    ) -> Result<String, RepositoryError> {
+        let display_name = with_pool!(&self.db, pool => {
+            sqlx::query_scalar::<_, String>(&sql(
+                pool,
+                "select display_name from line_users where primary_user_id = ?",
+            ))
+            .bind(primary_user_id)
+            .fetch_optional(&**pool)
+            .await
+        })
+        .map_err(db_error)?;
+
+        Ok(display_name.unwrap_or_default())
+    }
+
     async fn insert_messages_table_to_firestore(
         &self,
         talk_room_document_id: &String,
         document_id: &String,
         messges_table: &MessagesTable,
-    ) -> anyhow::Result<()> {
+    ) -> Result<(), RepositoryError> {
         let firestore = Arc::clone(&self.firestore.0);
-        let parent_path =
-            firestore.parent_path(TALK_ROOM_COLLECTION_NAME, talk_room_document_id)?;
+        let parent_path = firestore
+            .parent_path(TALK_ROOM_COLLECTION_NAME, talk_room_document_id)
+            .map_err(firestore_error)?;
         firestore
             .fluent()
             .insert()
@@ -262,8 +1489,19 @@ impl DbFirestoreRepositoryImpl<TalkRoom> {
             .document_id(document_id)
             .parent(&parent_path)
             .object(messges_table)
-            .execute()
-            .await?;
+            .execute::<MessagesTable>()
+            .await
+            .map_err(firestore_error)?;
         Ok(())
     }
 }
+
+// ドキュメントのパスの最後の部分がIDになる
+fn document_id_of(document: &FirestoreDocument) -> String {
+    document
+        .name
+        .split('/')
+        .next_back()
+        .unwrap_or_default()
+        .to_string()
+}
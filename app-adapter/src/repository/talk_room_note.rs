@@ -0,0 +1,166 @@
+use async_trait::async_trait;
+use chrono::Local;
+use firestore::{
+    paths_camel_case, FirestoreDb, FirestoreQueryDirection, FirestoreWritePrecondition,
+};
+use std::sync::Arc;
+
+use crate::model::talk_room_note::{TalkRoomNoteBodyTable, TalkRoomNoteTable};
+use crate::repository::{
+    firestore_error, unexpected_error, FirestoreRepositoryImpl, RepositoryError,
+    NOTE_COLLECTION_NAME, TALK_ROOM_COLLECTION_NAME,
+};
+use domain::{
+    model::{
+        talk_room::TalkRoom,
+        talk_room_note::{NewTalkRoomNote, TalkRoomNote},
+        Id,
+    },
+    repository::talk_room_note::TalkRoomNoteRepository,
+};
+
+#[async_trait]
+impl TalkRoomNoteRepository for FirestoreRepositoryImpl<TalkRoomNote> {
+    /// talkRoomのメモを新しい順に取得する
+    async fn get_notes(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+    ) -> Result<Vec<TalkRoomNote>, RepositoryError> {
+        let firestore = Arc::clone(&self.pool.0);
+        let parent_path = firestore
+            .parent_path(TALK_ROOM_COLLECTION_NAME, talk_room_id.value.to_string())
+            .map_err(firestore_error)?;
+        let documents = firestore
+            .fluent()
+            .select()
+            .from(NOTE_COLLECTION_NAME)
+            .parent(&parent_path)
+            .order_by([("createdAt".to_string(), FirestoreQueryDirection::Descending)])
+            .query()
+            .await
+            .map_err(firestore_error)?;
+
+        documents
+            .iter()
+            .map(|doc| {
+                let document_id = doc
+                    .name
+                    .split('/')
+                    .next_back()
+                    .unwrap_or_default()
+                    .to_string();
+                let note_table: TalkRoomNoteTable =
+                    FirestoreDb::deserialize_doc_to(doc).map_err(firestore_error)?;
+                note_table
+                    .into_talk_room_note(talk_room_id.clone(), document_id)
+                    .map_err(unexpected_error)
+            })
+            .collect()
+    }
+
+    async fn get_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+    ) -> Result<TalkRoomNote, RepositoryError> {
+        let document_id = note_id.value.to_string();
+        let firestore = Arc::clone(&self.pool.0);
+        let parent_path = firestore
+            .parent_path(TALK_ROOM_COLLECTION_NAME, talk_room_id.value.to_string())
+            .map_err(firestore_error)?;
+        let note_table: TalkRoomNoteTable = firestore
+            .fluent()
+            .select()
+            .by_id_in(NOTE_COLLECTION_NAME)
+            .parent(&parent_path)
+            .obj()
+            .one(&document_id)
+            .await
+            .map_err(firestore_error)?
+            .ok_or(RepositoryError::NotFound(
+                NOTE_COLLECTION_NAME.to_string(),
+                document_id.clone(),
+            ))?;
+
+        note_table
+            .into_talk_room_note(talk_room_id, document_id)
+            .map_err(unexpected_error)
+    }
+
+    async fn create_note(&self, source: NewTalkRoomNote) -> Result<TalkRoomNote, RepositoryError> {
+        let talk_room_id = source.talk_room_id.clone();
+        let document_id = source.id.value.to_string();
+        let note_table = TalkRoomNoteTable::from(source);
+        let firestore = Arc::clone(&self.pool.0);
+        let parent_path = firestore
+            .parent_path(TALK_ROOM_COLLECTION_NAME, talk_room_id.value.to_string())
+            .map_err(firestore_error)?;
+        firestore
+            .fluent()
+            .insert()
+            .into(NOTE_COLLECTION_NAME)
+            .document_id(&document_id)
+            .parent(&parent_path)
+            .object(&note_table)
+            .execute::<TalkRoomNoteTable>()
+            .await
+            .map_err(firestore_error)?;
+
+        note_table
+            .into_talk_room_note(talk_room_id, document_id)
+            .map_err(unexpected_error)
+    }
+
+    /// メモの本文を書き換える。書いたスタッフと作成日時は変えない
+    async fn update_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+        body: String,
+    ) -> Result<(), RepositoryError> {
+        let firestore = Arc::clone(&self.pool.0);
+        let parent_path = firestore
+            .parent_path(TALK_ROOM_COLLECTION_NAME, talk_room_id.value.to_string())
+            .map_err(firestore_error)?;
+        firestore
+            .fluent()
+            .update()
+            .fields(paths_camel_case!(TalkRoomNoteBodyTable::{body, updated_at}))
+            .in_col(NOTE_COLLECTION_NAME)
+            // 削除されたメモを作り直さないように、あるときだけ更新する
+            .precondition(FirestoreWritePrecondition::Exists(true))
+            .document_id(note_id.value.to_string())
+            .parent(&parent_path)
+            .object(&TalkRoomNoteBodyTable {
+                body,
+                updated_at: Local::now(),
+            })
+            .execute::<TalkRoomNoteBodyTable>()
+            .await
+            .map_err(firestore_error)?;
+        Ok(())
+    }
+
+    async fn delete_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+    ) -> Result<(), RepositoryError> {
+        let firestore = Arc::clone(&self.pool.0);
+        let parent_path = firestore
+            .parent_path(TALK_ROOM_COLLECTION_NAME, talk_room_id.value.to_string())
+            .map_err(firestore_error)?;
+        firestore
+            .fluent()
+            .delete()
+            .from(NOTE_COLLECTION_NAME)
+            .parent(&parent_path)
+            // ないメモを削除したときは、NotFoundにできるようにエラーにする
+            .precondition(FirestoreWritePrecondition::Exists(true))
+            .document_id(note_id.value.to_string())
+            .execute()
+            .await
+            .map_err(firestore_error)?;
+        Ok(())
+    }
+}
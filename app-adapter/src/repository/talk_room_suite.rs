@@ -0,0 +1,510 @@
+//! TalkRoomRepositoryの実装が同じ振る舞いをすることを確かめるテスト
+//! 実装ごとに同じテストを流す。MySQLとfirestoreを使う実装は、--features database-interaction-testで実行する
+//! DATABASE_URLにマイグレーション済みのDBを、FIRESTORE_PROJECT_IDとFIRESTORE_EMULATOR_HOSTにエミュレーターを指定する
+//! 他のテストのデータが残っていても通るように、毎回新しいユーザーとtalkRoomを作る
+use chrono::{Duration, DurationRound, Local};
+use std::env;
+use uuid::Uuid;
+
+use crate::persistance::db::Db;
+use crate::persistance::firestore::Firestore;
+use crate::persistance::in_memory::InMemoryDb;
+use crate::repository::{
+    DatabaseRepositoryImpl, DbFirestoreRepositoryImpl, InMemoryRepositoryImpl, RepositoryError,
+    TALK_ROOM_CARD_COLLECTION_NAME,
+};
+use domain::{
+    model::{
+        line_user::LineUserProfile,
+        message::event::{
+            NewEvent, NewEventDeliveryContext, NewEventFollow, NewEventMessage,
+            NewEventMessageContent, NewEventMessageContentText,
+        },
+        primary_user_id::PrimaryUserId,
+        staff::StaffId,
+        talk_room::{NewTalkRoom, TalkRoom, TalkRoomMode, TalkRoomStatus},
+        talk_room_card::{TalkRoomAssigneeFilter, TalkRoomFilter},
+        talk_room_consistency::TalkRoomInconsistency,
+        user::{User, UserProfile},
+        user_auth::LineId,
+        Id,
+    },
+    repository::talk_room::TalkRoomRepository,
+};
+
+fn new_user() -> User {
+    let primary_user_id = PrimaryUserId::new(Uuid::new_v4().to_string());
+    let line_id = LineId::new(format!("U{}", Uuid::new_v4().simple()));
+    User::new(
+        primary_user_id,
+        UserProfile::Line(LineUserProfile::new(
+            line_id,
+            "テストユーザー".to_string(),
+            "".to_string(),
+            None,
+            None,
+        )),
+    )
+}
+
+// MySQLはマイクロ秒までしか持たないので、どの実装でも同じ値になるように切り捨てる
+fn base_time() -> chrono::DateTime<Local> {
+    Local::now()
+        .duration_trunc(Duration::seconds(1))
+        .expect("failed to truncate time")
+}
+
+fn follow_event(created_at: chrono::DateTime<Local>) -> NewEvent {
+    NewEvent::Follow(NewEventFollow {
+        id: Id::gen(),
+        reply_token: "reply_token".to_string(),
+        delivery_context: NewEventDeliveryContext {
+            is_redelivery: false,
+        },
+        mode: "active".to_string(),
+        webhook_event_id: Uuid::new_v4().to_string(),
+        created_at,
+    })
+}
+
+fn text_event(text: &str, created_at: chrono::DateTime<Local>) -> NewEvent {
+    NewEvent::Message(NewEventMessage {
+        id: Id::gen(),
+        reply_token: "reply_token".to_string(),
+        delivery_context: NewEventDeliveryContext {
+            is_redelivery: false,
+        },
+        message: NewEventMessageContent::Text(NewEventMessageContentText {
+            id: Uuid::new_v4().to_string(),
+            text: text.to_string(),
+            emojis: vec![],
+        }),
+        mode: "active".to_string(),
+        webhook_event_id: Uuid::new_v4().to_string(),
+        created_at,
+    })
+}
+
+/// 既存のtalkRoomにメッセージを追加するときと同じく、IDをtalkRoomのものにそろえる
+fn new_messages(talk_room: &TalkRoom, user: &User, event: NewEvent) -> NewTalkRoom {
+    NewTalkRoom {
+        id: talk_room.id.clone(),
+        ..NewTalkRoom::from((user.clone(), event))
+    }
+}
+
+fn is_not_found(e: &RepositoryError) -> bool {
+    matches!(e, RepositoryError::NotFound(_, _))
+}
+
+async fn create_and_get<R: TalkRoomRepository>(repository: &R) {
+    let user = new_user();
+    let created = repository
+        .create_talk_room(NewTalkRoom::from((user.clone(), follow_event(base_time()))))
+        .await
+        .unwrap();
+
+    let by_user = repository.get_talk_room(user.id.clone()).await.unwrap();
+    let by_id = repository
+        .get_talk_room_by_id(created.id.clone())
+        .await
+        .unwrap();
+    for talk_room in [&by_user, &by_id] {
+        assert_eq!(talk_room.id, created.id);
+        assert_eq!(talk_room.primary_user_id, user.id);
+        assert_eq!(talk_room.display_name, "テストユーザー");
+        assert!(talk_room.follow);
+        assert!(!talk_room.pinned);
+        assert_eq!(talk_room.status, TalkRoomStatus::default());
+        assert_eq!(talk_room.mode, TalkRoomMode::default());
+        assert_eq!(talk_room.latest_messages.id(), created.latest_messages.id());
+    }
+}
+
+async fn create_duplicated_user<R: TalkRoomRepository>(repository: &R) {
+    let user = new_user();
+    repository
+        .create_talk_room(NewTalkRoom::from((user.clone(), follow_event(base_time()))))
+        .await
+        .unwrap();
+
+    let result = repository
+        .create_talk_room(NewTalkRoom::from((user.clone(), follow_event(base_time()))))
+        .await;
+    assert!(matches!(
+        result.unwrap_err(),
+        RepositoryError::Conflict(_, _, _)
+    ));
+}
+
+async fn not_found<R: TalkRoomRepository>(repository: &R) {
+    let result = repository.get_talk_room_by_id(Id::gen()).await;
+    assert!(is_not_found(&result.unwrap_err()));
+    let result = repository
+        .get_talk_room(PrimaryUserId::new(Uuid::new_v4().to_string()))
+        .await;
+    assert!(is_not_found(&result.unwrap_err()));
+    let result = repository.get_messages(Id::gen(), None, 10).await;
+    assert!(is_not_found(&result.unwrap_err()));
+}
+
+async fn create_messages<R: TalkRoomRepository>(repository: &R) {
+    let user = new_user();
+    let now = base_time();
+    let talk_room = repository
+        .create_talk_room(NewTalkRoom::from((
+            user.clone(),
+            follow_event(now - Duration::seconds(10)),
+        )))
+        .await
+        .unwrap();
+    let latest = repository
+        .create_messages(new_messages(
+            &talk_room,
+            &user,
+            text_event("こんにちは", now),
+        ))
+        .await
+        .unwrap();
+    // 遅れて届いた古いメッセージでは、latestMessageを巻き戻さない
+    repository
+        .create_messages(new_messages(
+            &talk_room,
+            &user,
+            text_event("遅れて届いた", now - Duration::seconds(5)),
+        ))
+        .await
+        .unwrap();
+
+    let stored = repository
+        .get_talk_room_by_id(talk_room.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(stored.latest_messages.id(), latest.latest_messages.id());
+    assert!(stored.unread);
+
+    // 既読の位置はユーザーから届いたメッセージの数
+    let staff_id = StaffId::new(1);
+    let marker = repository
+        .mark_as_read(talk_room.id.clone(), staff_id)
+        .await
+        .unwrap();
+    assert_eq!(marker.read_message_count, 2);
+
+    // メッセージは新しい順に、カーソルで続きを取得できる
+    let first = repository
+        .get_messages(talk_room.id.clone(), None, 2)
+        .await
+        .unwrap();
+    assert_eq!(first.messages.len(), 2);
+    assert_eq!(first.messages[0].id(), latest.latest_messages.id());
+    assert!(first.messages[0].created_at() > first.messages[1].created_at());
+    let second = repository
+        .get_messages(talk_room.id.clone(), first.next_cursor, 2)
+        .await
+        .unwrap();
+    assert_eq!(second.messages.len(), 1);
+    assert_eq!(second.messages[0].id(), talk_room.latest_messages.id());
+    assert!(second.next_cursor.is_none());
+}
+
+async fn update_card<R: TalkRoomRepository>(repository: &R) {
+    let user = new_user();
+    let talk_room = repository
+        .create_talk_room(NewTalkRoom::from((user.clone(), follow_event(base_time()))))
+        .await
+        .unwrap();
+
+    repository
+        .update_pinned(talk_room.id.clone(), true)
+        .await
+        .unwrap();
+    repository
+        .update_rsvp(talk_room.id.clone(), true)
+        .await
+        .unwrap();
+    repository
+        .update_nickname(talk_room.id.clone(), Some("常連さん".to_string()))
+        .await
+        .unwrap();
+    repository
+        .update_display_name(user.id.clone(), "新しい名前".to_string())
+        .await
+        .unwrap();
+
+    let stored = repository
+        .get_talk_room_by_id(talk_room.id.clone())
+        .await
+        .unwrap();
+    assert!(stored.pinned);
+    assert!(stored.rsvp);
+    assert_eq!(stored.nickname, Some("常連さん".to_string()));
+    assert_eq!(stored.display_name, "新しい名前");
+    // 呼び名などの変更は、メッセージの追加で上書きしない
+    let stored = repository
+        .create_messages(new_messages(
+            &talk_room,
+            &user,
+            text_event("こんにちは", base_time() + Duration::seconds(1)),
+        ))
+        .await
+        .unwrap();
+    let stored = repository.get_talk_room_by_id(stored.id).await.unwrap();
+    assert!(stored.pinned);
+    assert_eq!(stored.nickname, Some("常連さん".to_string()));
+}
+
+async fn talk_room_cards<R: TalkRoomRepository>(repository: &R) {
+    // 他のテストのtalkRoomを含めないように、このテストだけの担当者で絞り込む
+    let staff_id = StaffId::new(Uuid::new_v4().as_u64_pair().0 as i64 & i64::MAX);
+    let now = base_time();
+    let mut ids = vec![];
+    for i in 0..3 {
+        let user = new_user();
+        let talk_room = repository
+            .create_talk_room(NewTalkRoom::from((
+                user,
+                follow_event(now + Duration::seconds(i)),
+            )))
+            .await
+            .unwrap();
+        // 同じ読み込み結果から別々に変更しても、ほかの変更を上書きしない
+        for workflow in [
+            talk_room.assign(staff_id, staff_id),
+            talk_room.change_status(Some(staff_id), TalkRoomStatus::Pending),
+            talk_room.change_mode(Some(staff_id), TalkRoomMode::Human),
+        ] {
+            repository.update_workflow(workflow.unwrap()).await.unwrap();
+        }
+        ids.push(talk_room.id);
+    }
+    // ピン留めしたものは、古くても先頭に並べる
+    repository
+        .update_pinned(ids[0].clone(), true)
+        .await
+        .unwrap();
+
+    let filter = TalkRoomFilter {
+        assignee: Some(TalkRoomAssigneeFilter::Staff(staff_id)),
+        ..TalkRoomFilter::default()
+    };
+    let first = repository
+        .get_talk_room_cards(filter.clone(), None, 2)
+        .await
+        .unwrap();
+    let second = repository
+        .get_talk_room_cards(filter.clone(), first.next_cursor.clone(), 2)
+        .await
+        .unwrap();
+    assert!(second.next_cursor.is_none());
+    let cards = first
+        .talk_room_cards
+        .iter()
+        .chain(second.talk_room_cards.iter())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        cards.iter().map(|c| c.id.clone()).collect::<Vec<_>>(),
+        vec![ids[0].clone(), ids[2].clone(), ids[1].clone()]
+    );
+    assert!(cards
+        .iter()
+        .all(|c| c.status == TalkRoomStatus::Pending && c.mode == TalkRoomMode::Human));
+
+    let pinned = repository
+        .get_talk_room_cards(
+            TalkRoomFilter {
+                pinned: Some(false),
+                ..filter
+            },
+            None,
+            10,
+        )
+        .await
+        .unwrap();
+    assert_eq!(pinned.talk_room_cards.len(), 2);
+}
+
+async fn claim_away_message<R: TalkRoomRepository>(repository: &R) {
+    let user = new_user();
+    let talk_room = repository
+        .create_talk_room(NewTalkRoom::from((user, follow_event(base_time()))))
+        .await
+        .unwrap();
+    let off_hours_since = base_time();
+
+    // 同じ営業時間外には一度だけ送る
+    assert!(repository
+        .claim_away_message(talk_room.id.clone(), off_hours_since)
+        .await
+        .unwrap());
+    assert!(!repository
+        .claim_away_message(talk_room.id.clone(), off_hours_since)
+        .await
+        .unwrap());
+    assert!(repository
+        .claim_away_message(talk_room.id.clone(), off_hours_since + Duration::hours(12))
+        .await
+        .unwrap());
+}
+
+/// firestoreを使っていたときに作った行のように、talkRoomCardsの項目を消してから直す
+async fn repair_missing_card_in_mysql(repository: &DatabaseRepositoryImpl<TalkRoom>) {
+    let user = new_user();
+    let now = base_time();
+    let talk_room = repository
+        .create_talk_room(NewTalkRoom::from((
+            user.clone(),
+            follow_event(now - Duration::seconds(10)),
+        )))
+        .await
+        .unwrap();
+    let latest = repository
+        .create_messages(new_messages(
+            &talk_room,
+            &user,
+            text_event("こんにちは", now),
+        ))
+        .await
+        .unwrap();
+    let document_id = talk_room.id.value.to_string();
+    sqlx::query(
+        r#"
+        update talk_rooms
+        set latest_message = null, latest_messaged_at = null, sort_time = null,
+            updated_at = null, user_message_count = 0
+        where document_id = ?
+        "#,
+    )
+    .bind(&document_id)
+    .execute(&**repository.pool.mysql_pool().unwrap())
+    .await
+    .unwrap();
+
+    let missing_card = TalkRoomInconsistency::MissingCard(document_id);
+    let scan = repository.scan_consistency().await.unwrap();
+    assert!(scan.inconsistencies.contains(&missing_card));
+    assert!(missing_card.repairable());
+    repository
+        .repair_inconsistency(missing_card.clone())
+        .await
+        .unwrap();
+
+    let scan = repository.scan_consistency().await.unwrap();
+    assert!(!scan.inconsistencies.contains(&missing_card));
+    let stored = repository
+        .get_talk_room_by_id(talk_room.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(stored.display_name, "テストユーザー");
+    assert_eq!(stored.latest_messages.id(), latest.latest_messages.id());
+    assert_eq!(stored.latest_messaged_at, now);
+    assert!(stored.follow);
+    assert!(stored.unread);
+    let marker = repository
+        .mark_as_read(talk_room.id.clone(), StaffId::new(1))
+        .await
+        .unwrap();
+    assert_eq!(marker.read_message_count, 1);
+}
+
+/// talkRoomCardsのドキュメントを消してから直す
+async fn repair_missing_card_in_firestore(repository: &DbFirestoreRepositoryImpl<TalkRoom>) {
+    let user = new_user();
+    let now = base_time();
+    let talk_room = repository
+        .create_talk_room(NewTalkRoom::from((
+            user.clone(),
+            follow_event(now - Duration::seconds(10)),
+        )))
+        .await
+        .unwrap();
+    let latest = repository
+        .create_messages(new_messages(
+            &talk_room,
+            &user,
+            text_event("こんにちは", now),
+        ))
+        .await
+        .unwrap();
+    let document_id = talk_room.id.value.to_string();
+    repository
+        .firestore
+        .0
+        .fluent()
+        .delete()
+        .from(TALK_ROOM_CARD_COLLECTION_NAME)
+        .document_id(&document_id)
+        .execute()
+        .await
+        .unwrap();
+
+    let missing_card = TalkRoomInconsistency::MissingCard(document_id);
+    let scan = repository.scan_consistency().await.unwrap();
+    assert!(scan.inconsistencies.contains(&missing_card));
+    assert!(missing_card.repairable());
+    repository
+        .repair_inconsistency(missing_card.clone())
+        .await
+        .unwrap();
+
+    let scan = repository.scan_consistency().await.unwrap();
+    assert!(!scan.inconsistencies.contains(&missing_card));
+    // line_usersに行がないユーザーなので、表示名は空で作り直す
+    let stored = repository
+        .get_talk_room_by_id(talk_room.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(stored.display_name, "");
+    assert_eq!(stored.latest_messages.id(), latest.latest_messages.id());
+    assert_eq!(stored.latest_messaged_at, now);
+    assert!(stored.follow);
+    assert!(stored.unread);
+    let marker = repository
+        .mark_as_read(talk_room.id.clone(), StaffId::new(1))
+        .await
+        .unwrap();
+    assert_eq!(marker.read_message_count, 1);
+}
+
+async fn run_all<R: TalkRoomRepository>(repository: &R) {
+    create_and_get(repository).await;
+    create_duplicated_user(repository).await;
+    not_found(repository).await;
+    create_messages(repository).await;
+    update_card(repository).await;
+    talk_room_cards(repository).await;
+    claim_away_message(repository).await;
+}
+
+#[tokio::test]
+async fn test_in_memory_talk_room_repository() {
+    let repository = InMemoryRepositoryImpl::<TalkRoom>::new(InMemoryDb::new());
+    run_all(&repository).await;
+}
+
+#[tokio::test]
+#[cfg_attr(not(feature = "database-interaction-test"), ignore)]
+async fn test_mysql_talk_room_repository() {
+    let repository = DatabaseRepositoryImpl::<TalkRoom>::new(
+        Db::connect(&env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap(),
+    );
+    run_all(&repository).await;
+    repair_missing_card_in_mysql(&repository).await;
+}
+
+#[tokio::test]
+#[cfg_attr(not(feature = "database-interaction-test"), ignore)]
+async fn test_firestore_talk_room_repository() {
+    let repository = DbFirestoreRepositoryImpl::<TalkRoom>::new(
+        Db::connect(&env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap(),
+        Firestore::connect(&env::var("FIRESTORE_PROJECT_ID").unwrap())
+            .await
+            .unwrap(),
+    );
+    run_all(&repository).await;
+    repair_missing_card_in_firestore(&repository).await;
+}
@@ -1,113 +1,904 @@
-use std::sync::Arc;
-
+use crate::model::email_user::EmailUserTable;
+use crate::model::line_login_user::LineLoginUserTable;
 use crate::model::line_user::LineUserTable;
+use crate::persistance::db::{sql, timestamp, with_pool, Backend};
 use crate::repository::DatabaseRepositoryImpl;
-use anyhow::{anyhow, Ok};
 use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use domain::model::account_link::{ExternalMemberId, NewAccountLink, NewAccountLinkNonce};
+use domain::model::email_user::EmailAddress;
+use domain::model::line_login_user::LineLoginId;
 use domain::model::line_user::LineUserProfile;
+use domain::model::primary_user_id::PrimaryUserId;
 use domain::model::user::{User, UserProfile};
 use domain::model::user_auth::{AuthUserId, LineId};
 use domain::model::Id;
 use domain::repository::user::UserRepository;
+use sqlx::database::HasArguments;
+use sqlx::{Encode, Executor, IntoArguments, Transaction, Type};
+
+use super::{db_error, insert_error, unexpected_error, RepositoryError};
 
-use super::RepositoryError;
+// primary_user_idに紐づく認証プロバイダーごとのテーブルと、会員システムとの連携のテーブル
+const USER_IDENTITY_TABLES: [&str; 4] = [
+    "line_users",
+    "email_users",
+    "line_login_users",
+    "line_account_links",
+];
 
 #[async_trait]
 impl UserRepository for DatabaseRepositoryImpl<User> {
-    async fn get_user(&self, source: AuthUserId) -> anyhow::Result<User> {
+    async fn get_user(&self, source: AuthUserId) -> Result<User, RepositoryError> {
         let res = match source {
             AuthUserId::Line(line_id) => self.get_line_user(line_id).await?,
+            AuthUserId::Email(email) => self.get_email_user(email).await?,
+            AuthUserId::LineLogin(line_login_id) => self.get_line_login_user(line_login_id).await?,
         };
 
         Ok(res)
     }
 
-    async fn get_line_user(&self, source: LineId) -> anyhow::Result<User> {
-        let pool = Arc::clone(self.pool.pool());
+    async fn get_line_user(&self, source: LineId) -> Result<User, RepositoryError> {
         let line_id = source.0;
-        let line_user_row = sqlx::query_as::<_, LineUserTable>(
-            r#"
-                select primary_user_id, line_id, display_name, picture_url, created_at, updated_at from line_users
-                where line_id = ?
-                "#,
-            )
-        .bind(line_id.clone())
-        .fetch_one(&*pool)
-        .await
+        let line_user_row = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, LineUserTable>(&sql(pool, r#"
+                select lu.primary_user_id, lu.line_id, lu.display_name, lu.picture_url, lu.status_message, lu.language, lu.created_at, lu.updated_at, al.external_member_id from line_users lu
+                left join line_account_links al on al.primary_user_id = lu.primary_user_id
+                where lu.line_id = ?
+                "#))
+            .bind(line_id.clone())
+            .fetch_one(&**pool)
+            .await
+        })
         .map_err(|e| match e {
-            sqlx::Error::RowNotFound => anyhow!(RepositoryError::NotFound("line_users".to_string(), line_id)),
-            _ => anyhow!(RepositoryError::Unexpected(e.to_string())),
+            sqlx::Error::RowNotFound => RepositoryError::NotFound("line_users".to_string(), line_id),
+            _ => db_error(e),
         })?;
 
-        Ok(line_user_row.try_into()?)
+        Ok(line_user_row.try_into().map_err(unexpected_error)?)
     }
 
-    async fn create_user(&self, source: UserProfile) -> anyhow::Result<User> {
-        let res = match source {
-            UserProfile::Line(line_user) => self.create_line_user(line_user).await?,
-        };
+    /// primary_user_idに紐づくLINEのユーザーを取得する
+    /// talkRoomからLINEのAPIを呼ぶときに、LINEのユーザーIDを引くために使う
+    async fn get_line_user_by_primary_user_id(
+        &self,
+        primary_user_id: PrimaryUserId,
+    ) -> Result<User, RepositoryError> {
+        let primary_user_id = primary_user_id.value().to_string();
+        let line_user_row = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, LineUserTable>(&sql(pool, r#"
+                select lu.primary_user_id, lu.line_id, lu.display_name, lu.picture_url, lu.status_message, lu.language, lu.created_at, lu.updated_at, al.external_member_id from line_users lu
+                left join line_account_links al on al.primary_user_id = lu.primary_user_id
+                where lu.primary_user_id = ?
+                "#))
+            .bind(primary_user_id.clone())
+            .fetch_one(&**pool)
+            .await
+        })
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => RepositoryError::NotFound("line_users".to_string(), primary_user_id),
+            _ => db_error(e),
+        })?;
 
-        Ok(res)
+        Ok(line_user_row.try_into().map_err(unexpected_error)?)
     }
 
-    async fn create_line_user(&self, source: LineUserProfile) -> anyhow::Result<User> {
-        let pool = Arc::clone(self.pool.pool());
-        let mut tx = pool.begin().await.expect("Unable to begin transaction");
+    /// 新しいprimary_user_idを払い出し、認証プロバイダーのユーザーを作成する
+    async fn create_user(&self, source: UserProfile) -> Result<User, RepositoryError> {
+        let auth_id = source.auth_id().ok_or(RepositoryError::InvalidInput(
+            "auth_id is required to create a user".to_string(),
+        ))?;
         let primary_user_id = Id::<User>::gen().value.to_string();
-        sqlx::query(
-            r#"
-            insert into primary_users (id, created_at)
-            values (?, default);
-            "#,
-        )
-        .bind(primary_user_id.clone())
-        .execute(&mut *tx)
-        .await
-        .map_err(|_| {
-            anyhow!(RepositoryError::CouldNotInsert(
-                "primary_users".to_string(),
-                "id".to_string(),
-                primary_user_id.clone(),
-            ))
+        with_pool!(&self.pool, pool => {
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(db_error)?;
+            sqlx::query(&sql(pool, "insert into primary_users (id) values (?)"))
+                .bind(primary_user_id.clone())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    insert_error(e, RepositoryError::Conflict(
+                        "primary_users".to_string(),
+                        "id".to_string(),
+                        primary_user_id.clone(),
+                    ))
+                })?;
+            insert_user_profile(&mut tx, &primary_user_id, source).await?;
+            tx.commit()
+                .await
+                .map_err(db_error)?;
+        });
+
+        self.get_user(auth_id).await
+    }
+
+    /// 認証プロバイダーのユーザーがあればそれを返し、なければ作成する
+    /// 同じユーザーを同時に作成したときは一意キーで片方が失敗するので、先に作成されたユーザーを返す
+    async fn get_or_create_user(&self, source: UserProfile) -> Result<User, RepositoryError> {
+        let auth_id = source.auth_id().ok_or(RepositoryError::InvalidInput(
+            "auth_id is required to create a user".to_string(),
+        ))?;
+        match self.get_user(auth_id.clone()).await {
+            Err(RepositoryError::NotFound(..)) => {}
+            res => return res,
+        }
+        match self.create_user(source).await {
+            Err(err) => self.get_user(auth_id).await.map_err(|_| err),
+            res => res,
+        }
+    }
+
+    async fn create_line_user(&self, source: LineUserProfile) -> Result<User, RepositoryError> {
+        self.create_user(UserProfile::Line(source)).await
+    }
+
+    /// LINEから取得し直したプロフィールで更新する
+    /// 変更がなくてもupdated_atは更新し、定期的なプロフィールの取得対象から外す
+    async fn update_line_user(&self, source: LineUserProfile) -> Result<User, RepositoryError> {
+        let line_id = source.auth_id.0.clone();
+        let rows_affected = with_pool!(&self.pool, pool => {
+            sqlx::query(&sql(pool, r#"
+                update line_users
+                set display_name = ?, picture_url = ?, status_message = ?, language = ?, updated_at = CURRENT_TIMESTAMP
+                where line_id = ?
+                "#))
+            .bind(source.display_name)
+            .bind(source.picture_url)
+            .bind(source.status_message)
+            .bind(source.language)
+            .bind(line_id.clone())
+            .execute(&**pool)
+            .await
+            .map(|result| result.rows_affected())
+        })
+        .map_err(db_error)?;
+        if rows_affected == 0 {
+            return Err(RepositoryError::NotFound("line_users".to_string(), line_id));
+        }
+
+        self.get_line_user(LineId::new(line_id)).await
+    }
+
+    async fn get_line_users_updated_before(
+        &self,
+        updated_before: DateTime<Local>,
+        limit: i64,
+    ) -> Result<Vec<User>, RepositoryError> {
+        let line_user_rows = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, LineUserTable>(&sql(pool, r#"
+                select lu.primary_user_id, lu.line_id, lu.display_name, lu.picture_url, lu.status_message, lu.language, lu.created_at, lu.updated_at, al.external_member_id from line_users lu
+                left join line_account_links al on al.primary_user_id = lu.primary_user_id
+                where lu.updated_at < ?
+                order by lu.updated_at
+                limit ?
+                "#))
+            .bind(timestamp(pool, updated_before))
+            .bind(limit)
+            .fetch_all(&**pool)
+            .await
+        })
+        .map_err(db_error)?;
+
+        line_user_rows
+            .into_iter()
+            .map(|row| row.try_into().map_err(unexpected_error))
+            .collect()
+    }
+
+    /// 既存のprimary_user_idに、別の認証プロバイダーのユーザーを紐づける
+    /// 同じプロバイダーのユーザーは1つのprimary_user_idに1つまでしか紐づけられない
+    ///
+    /// # Arguments
+    /// * `primary_user_id` - 紐づけ先のユーザー
+    /// * `source` - 紐づける認証プロバイダーのユーザー
+    ///
+    async fn link_user(
+        &self,
+        primary_user_id: PrimaryUserId,
+        source: UserProfile,
+    ) -> Result<User, RepositoryError> {
+        let auth_id = source.auth_id().ok_or(RepositoryError::InvalidInput(
+            "auth_id is required to link a user".to_string(),
+        ))?;
+        with_pool!(&self.pool, pool => {
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(db_error)?;
+            lock_primary_user(&mut tx, primary_user_id.value()).await?;
+            insert_user_profile(&mut tx, primary_user_id.value(), source).await?;
+            tx.commit()
+                .await
+                .map_err(db_error)?;
+        });
+
+        self.get_user(auth_id).await
+    }
+
+    async fn create_account_link_nonce(
+        &self,
+        source: NewAccountLinkNonce,
+    ) -> Result<(), RepositoryError> {
+        with_pool!(&self.pool, pool => {
+            sqlx::query(&sql(pool, r#"
+                insert into line_account_link_nonces (nonce, external_member_id, expires_at)
+                values (?, ?, ?)
+                "#))
+            .bind(source.nonce.0.clone())
+            .bind(source.external_member_id.0)
+            .bind(timestamp(pool, source.expires_at))
+            .execute(&**pool)
+            .await
+            .map(|_| ())
+        })
+        .map_err(|e| {
+            insert_error(
+                e,
+                RepositoryError::Conflict(
+                    "line_account_link_nonces".to_string(),
+                    "nonce".to_string(),
+                    source.nonce.0,
+                ),
+            )
         })?;
-        sqlx::query(
-            r#"
-            insert into line_users(primary_user_id, line_id, display_name, picture_url, created_at, updated_at)
-            values (?, ?, ?, ?, default, default)
-            "#,
-        )
-        .bind(primary_user_id.clone())
-        .bind(source.auth_id.0)
-        .bind(source.display_name)
-        .bind(source.picture_url)
-        .execute(&mut *tx)
-        .await
-        .map_err(|_| {
-            anyhow!(RepositoryError::CouldNotInsert(
-                "line_users".to_string(),
-                "primary_user_id".to_string(),
-                primary_user_id,
+
+        Ok(())
+    }
+
+    /// accountLinkイベントのnonceを照合し、会員システムのアカウントと連携する
+    /// nonceは一度しか使えないので、照合したら削除する
+    ///
+    /// # Arguments
+    /// * `source` - 連携するユーザーと、accountLinkイベントで受け取ったnonce
+    ///
+    async fn link_account(
+        &self,
+        source: NewAccountLink,
+    ) -> Result<ExternalMemberId, RepositoryError> {
+        let nonce = source.nonce.0;
+        let external_member_id = with_pool!(&self.pool, pool => {
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(db_error)?;
+            // 有効期限はDBの時刻ではなく、保存したときと同じくアプリケーションの時刻で比べる
+            let external_member_id: String = sqlx::query_scalar(&sql(pool, r#"
+                select external_member_id from line_account_link_nonces
+                where nonce = ? and expires_at > ?
+                for update
+                "#))
+            .bind(nonce.clone())
+            .bind(timestamp(pool, Local::now()))
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => RepositoryError::NotFound(
+                    "line_account_link_nonces".to_string(),
+                    nonce.clone()
+                ),
+                _ => db_error(e),
+            })?;
+            sqlx::query(&sql(pool, "delete from line_account_link_nonces where nonce = ?"))
+                .bind(nonce.clone())
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+            // 連携し直したときは、新しい会員システムのアカウントで上書きする
+            let linked = sqlx::query_scalar::<_, String>(&sql(
+                pool,
+                "select primary_user_id from line_account_links where primary_user_id = ? for update",
             ))
-        })?;
-        tx.commit().await.expect("Unable to commit transaction");
+            .bind(source.primary_user_id.value())
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .is_some();
+            let query = if linked {
+                "update line_account_links set external_member_id = ?, updated_at = CURRENT_TIMESTAMP where primary_user_id = ?"
+            } else {
+                "insert into line_account_links (external_member_id, primary_user_id) values (?, ?)"
+            };
+            sqlx::query(&sql(pool, query))
+                .bind(external_member_id.clone())
+                .bind(source.primary_user_id.value())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    insert_error(e, RepositoryError::Conflict(
+                        "line_account_links".to_string(),
+                        "external_member_id".to_string(),
+                        external_member_id.clone(),
+                    ))
+                })?;
+            tx.commit()
+                .await
+                .map_err(db_error)?;
 
-        let line_user_row = sqlx::query_as::<_, LineUserTable>(
-            r#"
-            select * from line_users where line_id = LAST_INSERT_ID()
-            "#,
-        )
-        .fetch_one(&*pool)
-        .await
+            external_member_id
+        });
+
+        Ok(ExternalMemberId::new(external_member_id))
+    }
+
+    async fn unlink_account(&self, primary_user_id: PrimaryUserId) -> Result<(), RepositoryError> {
+        let rows_affected = with_pool!(&self.pool, pool => {
+            sqlx::query(&sql(pool, "delete from line_account_links where primary_user_id = ?"))
+                .bind(primary_user_id.value())
+                .execute(&**pool)
+                .await
+                .map(|result| result.rows_affected())
+        })
+        .map_err(db_error)?;
+        if rows_affected == 0 {
+            return Err(RepositoryError::NotFound(
+                "line_account_links".to_string(),
+                primary_user_id.value().to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl DatabaseRepositoryImpl<User> {
+    async fn get_email_user(&self, source: EmailAddress) -> Result<User, RepositoryError> {
+        let email = source.0;
+        let email_user_row = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, EmailUserTable>(&sql(pool, r#"
+                select eu.primary_user_id, eu.email, eu.password_hash, eu.created_at, eu.updated_at, al.external_member_id from email_users eu
+                left join line_account_links al on al.primary_user_id = eu.primary_user_id
+                where eu.email = ?
+                "#))
+            .bind(email.clone())
+            .fetch_one(&**pool)
+            .await
+        })
         .map_err(|e| match e {
             sqlx::Error::RowNotFound => {
-                anyhow!(RepositoryError::NotFound(
-                    "line_users".to_string(),
-                    "LAST_INSERT_ID()".to_string()
-                ))
+                RepositoryError::NotFound("email_users".to_string(), email)
             }
-            _ => anyhow!(RepositoryError::Unexpected(e.to_string())),
+            _ => db_error(e),
+        })?;
+
+        email_user_row.try_into().map_err(unexpected_error)
+    }
+
+    async fn get_line_login_user(&self, source: LineLoginId) -> Result<User, RepositoryError> {
+        let line_login_id = source.0;
+        let line_login_user_row = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, LineLoginUserTable>(&sql(pool, r#"
+                select llu.primary_user_id, llu.line_login_id, llu.display_name, llu.picture_url, llu.email, llu.created_at, llu.updated_at, al.external_member_id from line_login_users llu
+                left join line_account_links al on al.primary_user_id = llu.primary_user_id
+                where llu.line_login_id = ?
+                "#))
+            .bind(line_login_id.clone())
+            .fetch_one(&**pool)
+            .await
+        })
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => RepositoryError::NotFound(
+                "line_login_users".to_string(),
+                line_login_id
+            ),
+            _ => db_error(e),
         })?;
 
-        Ok(line_user_row.try_into()?)
+        line_login_user_row.try_into().map_err(unexpected_error)
+    }
+}
+
+// 統合や紐づけの途中でユーザーが削除されないように、primary_usersの行をロックする
+async fn lock_primary_user<DB: Backend>(
+    tx: &mut Transaction<'_, DB>,
+    primary_user_id: &str,
+) -> Result<(), RepositoryError>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+{
+    sqlx::query(&DB::sql(
+        "select id from primary_users where id = ? for update",
+    ))
+    .bind(primary_user_id.to_string())
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => {
+            RepositoryError::NotFound("primary_users".to_string(), primary_user_id.to_string())
+        }
+        _ => db_error(e),
+    })?;
+
+    Ok(())
+}
+
+/// fromに紐づく認証プロバイダーのユーザーとタグ、talkRoomをintoに付け替え、fromを削除する
+/// 同じプロバイダーのユーザーやtalkRoomが両方にある場合は、どちらを残すか決められないので失敗させる
+/// talkRoomのfirestoreへの書き込みと同じトランザクションで行えるように、トランザクションを受け取る
+///
+/// # Arguments
+/// * `into` - 残すユーザー
+/// * `from` - intoに統合して削除するユーザー
+///
+/// # Returns
+/// 付け替えたtalkRoomのdocument_id。fromがtalkRoomを持っていなければNone
+///
+pub(crate) async fn merge_primary_users<DB: Backend>(
+    tx: &mut Transaction<'_, DB>,
+    into: &PrimaryUserId,
+    from: &PrimaryUserId,
+) -> Result<Option<String>, RepositoryError>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'r> (String,): sqlx::FromRow<'r, DB::Row>,
+{
+    if into == from {
+        return Err(RepositoryError::InvalidInput(
+            "Cannot merge a user into itself".to_string(),
+        ));
+    }
+    lock_primary_user(tx, into.value()).await?;
+    lock_primary_user(tx, from.value()).await?;
+    let talk_room_document_id: Option<(String,)> = sqlx::query_as(&DB::sql(
+        "select document_id from talk_rooms where primary_user_id = ? for update",
+    ))
+    .bind(from.value().to_string())
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(db_error)?;
+    // talk_roomsはprimary_user_idが一意なので、intoもtalkRoomを持っていればConflictになる
+    for table in USER_IDENTITY_TABLES.into_iter().chain(["talk_rooms"]) {
+        let query = format!(
+            "update {} set primary_user_id = ? where primary_user_id = ?",
+            table
+        );
+        sqlx::query(&DB::sql(&query))
+            .bind(into.value().to_string())
+            .bind(from.value().to_string())
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| {
+                insert_error(
+                    e,
+                    RepositoryError::Conflict(
+                        table.to_string(),
+                        "primary_user_id".to_string(),
+                        into.value().to_string(),
+                    ),
+                )
+            })?;
+    }
+    // タグは両方に付いていれば1つにまとめる
+    sqlx::query(&DB::sql(
+        r#"
+        insert into user_tags (primary_user_id, tag, created_at)
+        select ?, t.tag, t.created_at from user_tags t
+        where t.primary_user_id = ?
+        and not exists (select 1 from user_tags u where u.primary_user_id = ? and u.tag = t.tag)
+        "#,
+    ))
+    .bind(into.value().to_string())
+    .bind(from.value().to_string())
+    .bind(into.value().to_string())
+    .execute(&mut **tx)
+    .await
+    .map_err(db_error)?;
+    sqlx::query(&DB::sql("delete from user_tags where primary_user_id = ?"))
+        .bind(from.value().to_string())
+        .execute(&mut **tx)
+        .await
+        .map_err(db_error)?;
+    sqlx::query(&DB::sql("delete from primary_users where id = ?"))
+        .bind(from.value().to_string())
+        .execute(&mut **tx)
+        .await
+        .map_err(db_error)?;
+
+    Ok(talk_room_document_id.map(|(document_id,)| document_id))
+}
+
+async fn insert_user_profile<DB: Backend>(
+    tx: &mut Transaction<'_, DB>,
+    primary_user_id: &str,
+    source: UserProfile,
+) -> Result<(), RepositoryError>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<String>: Encode<'q, DB> + Type<DB>,
+{
+    let (table, result) = match source {
+        UserProfile::Line(line_user) => {
+            let query = DB::sql(
+                r#"
+                insert into line_users(primary_user_id, line_id, display_name, picture_url, status_message, language)
+                values (?, ?, ?, ?, ?, ?)
+                "#,
+            );
+            let result = sqlx::query(&query)
+                .bind(primary_user_id.to_string())
+                .bind(line_user.auth_id.0)
+                .bind(line_user.display_name)
+                .bind(line_user.picture_url)
+                .bind(line_user.status_message)
+                .bind(line_user.language)
+                .execute(&mut **tx)
+                .await;
+            ("line_users", result)
+        }
+        UserProfile::Email(email_user) => {
+            let query = DB::sql(
+                r#"
+                insert into email_users(primary_user_id, email, password_hash)
+                values (?, ?, ?)
+                "#,
+            );
+            let result = sqlx::query(&query)
+                .bind(primary_user_id.to_string())
+                .bind(email_user.auth_id.0)
+                .bind(email_user.password_hash.0)
+                .execute(&mut **tx)
+                .await;
+            ("email_users", result)
+        }
+        UserProfile::LineLogin(line_login_user) => {
+            let query = DB::sql(
+                r#"
+                insert into line_login_users(primary_user_id, line_login_id, display_name, picture_url, email)
+                values (?, ?, ?, ?, ?)
+                "#,
+            );
+            let result = sqlx::query(&query)
+                .bind(primary_user_id.to_string())
+                .bind(line_login_user.auth_id.0)
+                .bind(line_login_user.display_name)
+                .bind(line_login_user.picture_url)
+                .bind(line_login_user.email)
+                .execute(&mut **tx)
+                .await;
+            ("line_login_users", result)
+        }
+    };
+    result.map_err(|e| {
+        insert_error(
+            e,
+            RepositoryError::Conflict(
+                table.to_string(),
+                "primary_user_id".to_string(),
+                primary_user_id.to_string(),
+            ),
+        )
+    })?;
+
+    Ok(())
+}
+
+// 外部のサービスがなくても実行できるように、インメモリのSQLiteで確かめる
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Local};
+    use domain::model::account_link::{
+        AccountLinkNonce, ExternalMemberId, NewAccountLink, NewAccountLinkNonce,
+    };
+    use domain::model::email_user::{EmailAddress, EmailUserProfile, PasswordHash};
+    use domain::model::line_user::LineUserProfile;
+    use domain::model::user::{User, UserProfile};
+    use domain::model::user_auth::{AuthUserId, LineId};
+    use domain::model::user_tag::UserTag;
+    use domain::repository::{user::UserRepository, user_tag::UserTagRepository};
+
+    use domain::model::primary_user_id::PrimaryUserId;
+
+    use super::merge_primary_users;
+    use crate::persistance::db::{with_pool, Db};
+    use crate::repository::{db_error, DatabaseRepositoryImpl, RepositoryError};
+
+    async fn sqlite() -> Db {
+        let db = Db::connect("sqlite::memory:")
+            .await
+            .expect("failed to connect to sqlite");
+        db.run_migrations().await.expect("failed to migrate sqlite");
+        db
+    }
+
+    async fn merge_users(
+        db: &Db,
+        into: &PrimaryUserId,
+        from: &PrimaryUserId,
+    ) -> Result<Option<String>, RepositoryError> {
+        with_pool!(db, pool => {
+            let mut tx = pool.begin().await.map_err(db_error)?;
+            let document_id = merge_primary_users(&mut tx, into, from).await?;
+            tx.commit().await.map_err(db_error)?;
+            Ok(document_id)
+        })
+    }
+
+    async fn insert_talk_room(db: &Db, document_id: &str, primary_user_id: &PrimaryUserId) {
+        with_pool!(db, pool => {
+            sqlx::query("insert into talk_rooms (document_id, primary_user_id) values (?, ?)")
+                .bind(document_id.to_string())
+                .bind(primary_user_id.value().to_string())
+                .execute(&**pool)
+                .await
+                .unwrap();
+        });
+    }
+
+    async fn talk_room_primary_user_id(db: &Db, document_id: &str) -> String {
+        with_pool!(db, pool => {
+            sqlx::query_scalar("select primary_user_id from talk_rooms where document_id = ?")
+                .bind(document_id.to_string())
+                .fetch_one(&**pool)
+                .await
+                .unwrap()
+        })
+    }
+
+    fn line_user_profile(line_id: &str) -> LineUserProfile {
+        LineUserProfile::new(
+            LineId::new(line_id.to_string()),
+            "テストユーザー".to_string(),
+            "https://example.com/picture.png".to_string(),
+            None,
+            Some("ja".to_string()),
+        )
+    }
+
+    fn email_user_profile(email: &str) -> EmailUserProfile {
+        EmailUserProfile::new(
+            EmailAddress::new(email.to_string()),
+            PasswordHash::new("hash".to_string()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_create_and_update_line_user() {
+        let repository = DatabaseRepositoryImpl::<User>::new(sqlite().await);
+
+        let created = repository
+            .create_line_user(line_user_profile("U1"))
+            .await
+            .unwrap();
+        let user = repository
+            .get_user(AuthUserId::Line(LineId::new("U1".to_string())))
+            .await
+            .unwrap();
+        assert_eq!(user.id, created.id);
+        assert_eq!(
+            user.user_profile,
+            UserProfile::Line(line_user_profile("U1"))
+        );
+        let user = repository
+            .get_line_user_by_primary_user_id(created.id.clone())
+            .await
+            .unwrap();
+        assert_eq!(user.id, created.id);
+
+        let mut profile = line_user_profile("U1");
+        profile.display_name = "変更後".to_string();
+        profile.status_message = Some("よろしくお願いします".to_string());
+        let user = repository.update_line_user(profile.clone()).await.unwrap();
+        assert_eq!(user.user_profile, UserProfile::Line(profile));
+
+        // 更新したばかりなので、定期的な取得の対象にはならない
+        let users = repository
+            .get_line_users_updated_before(Local::now() - Duration::hours(1), 10)
+            .await
+            .unwrap();
+        assert!(users.is_empty());
+        let users = repository
+            .get_line_users_updated_before(Local::now() + Duration::hours(1), 10)
+            .await
+            .unwrap();
+        assert_eq!(users.len(), 1);
+
+        let err = repository
+            .update_line_user(line_user_profile("U2"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::NotFound(..)));
+        let err = repository
+            .create_line_user(line_user_profile("U1"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::Conflict(..)));
+    }
+
+    #[tokio::test]
+    async fn test_get_or_create_user() {
+        let repository = DatabaseRepositoryImpl::<User>::new(sqlite().await);
+        let profile = UserProfile::Line(line_user_profile("U1"));
+
+        // 同時にフォローイベントが届いても、ユーザーは1人だけ作成する
+        let (first, second) = tokio::join!(
+            repository.get_or_create_user(profile.clone()),
+            repository.get_or_create_user(profile.clone())
+        );
+        let first = first.unwrap();
+        assert_eq!(first, second.unwrap());
+        assert_eq!(first.user_profile, profile);
+
+        // 作成済みのときは、プロフィールを上書きせずに返す
+        let mut changed = line_user_profile("U1");
+        changed.display_name = "変更後".to_string();
+        let user = repository
+            .get_or_create_user(UserProfile::Line(changed))
+            .await
+            .unwrap();
+        assert_eq!(user, first);
+    }
+
+    #[tokio::test]
+    async fn test_link_and_merge_users() {
+        let db = sqlite().await;
+        let repository = DatabaseRepositoryImpl::<User>::new(db.clone());
+        let user_tag_repository = DatabaseRepositoryImpl::<UserTag>::new(db.clone());
+
+        let line_user = repository
+            .create_line_user(line_user_profile("U1"))
+            .await
+            .unwrap();
+        let email_user = repository
+            .create_user(UserProfile::Email(email_user_profile("test@example.com")))
+            .await
+            .unwrap();
+        user_tag_repository
+            .add_user_tags(line_user.id.clone(), vec![UserTag::NewFollower])
+            .await
+            .unwrap();
+        user_tag_repository
+            .add_user_tags(
+                email_user.id.clone(),
+                vec![UserTag::NewFollower, UserTag::HasAppointment],
+            )
+            .await
+            .unwrap();
+
+        insert_talk_room(&db, "room1", &email_user.id).await;
+
+        let document_id = merge_users(&db, &line_user.id, &email_user.id)
+            .await
+            .unwrap();
+        // talkRoomも同じトランザクションで付け替える
+        assert_eq!(document_id, Some("room1".to_string()));
+        assert_eq!(
+            talk_room_primary_user_id(&db, "room1").await,
+            line_user.id.value().to_string()
+        );
+        let user = repository
+            .get_user(AuthUserId::Email(EmailAddress::new(
+                "test@example.com".to_string(),
+            )))
+            .await
+            .unwrap();
+        assert_eq!(user.id, line_user.id);
+        let mut tags = user_tag_repository
+            .get_line_user_tags(line_user.id.clone())
+            .await
+            .unwrap()
+            .tags;
+        tags.sort_by_key(|tag| tag.as_str());
+        assert_eq!(tags, vec![UserTag::HasAppointment, UserTag::NewFollower]);
+
+        // 統合したユーザーは削除されているので、紐づけられない
+        let err = repository
+            .link_user(
+                email_user.id.clone(),
+                UserProfile::Email(email_user_profile("other@example.com")),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::NotFound(..)));
+        // 同じプロバイダーのユーザーは1つしか紐づけられない
+        assert!(repository
+            .link_user(
+                line_user.id.clone(),
+                UserProfile::Email(email_user_profile("other@example.com")),
+            )
+            .await
+            .is_err());
+        let err = merge_users(&db, &line_user.id, &line_user.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::InvalidInput(..)));
+
+        // talkRoomが両方にあるときは、どちらも変えずに失敗させる
+        let other_user = repository
+            .create_line_user(line_user_profile("U2"))
+            .await
+            .unwrap();
+        insert_talk_room(&db, "room2", &other_user.id).await;
+        let err = merge_users(&db, &line_user.id, &other_user.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::Conflict(..)));
+        assert_eq!(
+            talk_room_primary_user_id(&db, "room2").await,
+            other_user.id.value().to_string()
+        );
+        assert_eq!(
+            repository
+                .get_line_user(LineId::new("U2".to_string()))
+                .await
+                .unwrap()
+                .id,
+            other_user.id
+        );
+        // 統合済みのユーザーをもう一度統合しようとしても、付け替えは起きない
+        let err = merge_users(&db, &line_user.id, &email_user.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::NotFound(..)));
+    }
+
+    #[tokio::test]
+    async fn test_link_account() {
+        let repository = DatabaseRepositoryImpl::<User>::new(sqlite().await);
+        let user = repository
+            .create_line_user(line_user_profile("U1"))
+            .await
+            .unwrap();
+        let link = |nonce: &AccountLinkNonce| NewAccountLink::new(user.id.clone(), nonce.clone());
+
+        let nonce = NewAccountLinkNonce::issue(ExternalMemberId::new("member1".to_string()));
+        repository
+            .create_account_link_nonce(nonce.clone())
+            .await
+            .unwrap();
+        let external_member_id = repository.link_account(link(&nonce.nonce)).await.unwrap();
+        assert_eq!(external_member_id.0, "member1");
+        // nonceは一度しか使えない
+        let err = repository
+            .link_account(link(&nonce.nonce))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::NotFound(..)));
+
+        // 連携し直したときは上書きする
+        let nonce = NewAccountLinkNonce::issue(ExternalMemberId::new("member2".to_string()));
+        repository
+            .create_account_link_nonce(nonce.clone())
+            .await
+            .unwrap();
+        repository.link_account(link(&nonce.nonce)).await.unwrap();
+        let linked = repository
+            .get_line_user(LineId::new("U1".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(
+            linked.external_member_id,
+            Some(ExternalMemberId::new("member2".to_string()))
+        );
+
+        let expired = NewAccountLinkNonce::new(
+            AccountLinkNonce::gen(),
+            ExternalMemberId::new("member3".to_string()),
+            Local::now() - Duration::minutes(1),
+        );
+        repository
+            .create_account_link_nonce(expired.clone())
+            .await
+            .unwrap();
+        let err = repository
+            .link_account(link(&expired.nonce))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::NotFound(..)));
+
+        repository.unlink_account(user.id.clone()).await.unwrap();
+        // 連携していないユーザーの解除はNotFound
+        let err = repository
+            .unlink_account(user.id.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RepositoryError::NotFound(..)));
     }
 }
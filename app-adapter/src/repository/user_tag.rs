@@ -0,0 +1,133 @@
+use crate::model::user_tag::{into_line_user_tags_vec, LineUserTagRow};
+use crate::persistance::db::{sql, with_pool};
+use crate::repository::DatabaseRepositoryImpl;
+use async_trait::async_trait;
+use domain::model::primary_user_id::PrimaryUserId;
+use domain::model::user_tag::{LineUserTags, UserTag};
+use domain::repository::user_tag::UserTagRepository;
+
+use super::{db_error, insert_error, unexpected_error, RepositoryError};
+
+#[async_trait]
+impl UserTagRepository for DatabaseRepositoryImpl<UserTag> {
+    async fn get_line_user_tags(
+        &self,
+        source: PrimaryUserId,
+    ) -> Result<LineUserTags, RepositoryError> {
+        let primary_user_id = source.value().to_string();
+        let rows = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, LineUserTagRow>(&sql(
+                pool,
+                r#"
+                select l.primary_user_id, l.line_id, t.tag from line_users l
+                left join user_tags t on t.primary_user_id = l.primary_user_id
+                where l.primary_user_id = ?
+                "#,
+            ))
+            .bind(primary_user_id.clone())
+            .fetch_all(&**pool)
+            .await
+        })
+        .map_err(db_error)?;
+
+        into_line_user_tags_vec(rows)
+            .map_err(unexpected_error)?
+            .into_iter()
+            .next()
+            .ok_or(RepositoryError::NotFound(
+                "line_users".to_string(),
+                primary_user_id,
+            ))
+    }
+
+    async fn get_all_line_user_tags(&self) -> Result<Vec<LineUserTags>, RepositoryError> {
+        let rows = with_pool!(&self.pool, pool => {
+            sqlx::query_as::<_, LineUserTagRow>(
+                r#"
+                select l.primary_user_id, l.line_id, t.tag from line_users l
+                left join user_tags t on t.primary_user_id = l.primary_user_id
+                order by l.primary_user_id
+                "#,
+            )
+            .fetch_all(&**pool)
+            .await
+        })
+        .map_err(db_error)?;
+
+        into_line_user_tags_vec(rows).map_err(unexpected_error)
+    }
+
+    async fn add_user_tags(
+        &self,
+        primary_user_id: PrimaryUserId,
+        tags: Vec<UserTag>,
+    ) -> Result<(), RepositoryError> {
+        with_pool!(&self.pool, pool => {
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(db_error)?;
+            // 既に付いているタグは無視する
+            for tag in tags {
+                sqlx::query(&sql(
+                    pool,
+                    r#"
+                    insert into user_tags (primary_user_id, tag)
+                    select ?, ? where not exists (
+                        select 1 from user_tags where primary_user_id = ? and tag = ?
+                    )
+                    "#,
+                ))
+                .bind(primary_user_id.value())
+                .bind(tag.as_str())
+                .bind(primary_user_id.value())
+                .bind(tag.as_str())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    insert_error(e, RepositoryError::Conflict(
+                        "user_tags".to_string(),
+                        "tag".to_string(),
+                        tag.as_str().to_string(),
+                    ))
+                })?;
+            }
+            tx.commit()
+                .await
+                .map_err(db_error)?;
+        });
+
+        Ok(())
+    }
+
+    async fn remove_user_tags(
+        &self,
+        primary_user_id: PrimaryUserId,
+        tags: Vec<UserTag>,
+    ) -> Result<(), RepositoryError> {
+        with_pool!(&self.pool, pool => {
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(db_error)?;
+            for tag in tags {
+                sqlx::query(&sql(
+                    pool,
+                    r#"
+                    delete from user_tags where primary_user_id = ? and tag = ?
+                    "#,
+                ))
+                .bind(primary_user_id.value())
+                .bind(tag.as_str())
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+            }
+            tx.commit()
+                .await
+                .map_err(db_error)?;
+        });
+
+        Ok(())
+    }
+}
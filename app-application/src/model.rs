@@ -1,2 +1,11 @@
+pub mod account_link;
+pub mod admin;
 pub mod event;
 pub mod line_user_auth;
+pub mod manual_message;
+pub mod rich_menu;
+pub mod rich_menu_rule;
+pub mod staff;
+pub mod talk_room_reconcile;
+pub mod user_identity;
+pub mod user_profile;
@@ -0,0 +1,54 @@
+use anyhow::anyhow;
+use derive_new::new;
+use domain::model::account_link::{
+    AccountLinkNonce, ExternalMemberId, LineLinkToken, NewAccountLinkNonce,
+};
+
+// nonceを付けてユーザーをリダイレクトさせる、LINEのアカウント連携のエンドポイント
+const LINE_ACCOUNT_LINK_URL: &str = "https://access.line.me/dialog/bot/accountLink";
+
+/// 会員システムのログインが終わったときに、会員システムから受け取る
+#[derive(new, Clone, Debug)]
+pub struct CreateAccountLinkNonce {
+    pub link_token: String,
+    pub external_member_id: String,
+}
+
+impl TryFrom<CreateAccountLinkNonce> for (LineLinkToken, NewAccountLinkNonce) {
+    type Error = anyhow::Error;
+    fn try_from(c: CreateAccountLinkNonce) -> anyhow::Result<Self> {
+        // 連携トークンはそのままリダイレクト先のクエリに入れるので、想定外の文字は弾く
+        if c.link_token.is_empty()
+            || !c
+                .link_token
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+        {
+            return Err(anyhow!("Invalid link token: {}", c.link_token));
+        }
+        if c.external_member_id.is_empty() || c.external_member_id.len() > 255 {
+            return Err(anyhow!(
+                "Invalid external member id: {}",
+                c.external_member_id
+            ));
+        }
+
+        Ok((
+            LineLinkToken::new(c.link_token),
+            NewAccountLinkNonce::issue(ExternalMemberId::new(c.external_member_id)),
+        ))
+    }
+}
+
+/// 連携トークンを付けた、会員システムのログインページのURL
+pub fn account_link_login_url(login_url: &str, link_token: &LineLinkToken) -> String {
+    format!("{}?linkToken={}", login_url, link_token.0)
+}
+
+/// ログインが終わったユーザーを、nonceを付けてLINEにリダイレクトさせるURL
+pub fn line_account_link_url(link_token: &LineLinkToken, nonce: &AccountLinkNonce) -> String {
+    format!(
+        "{}?linkToken={}&nonce={}",
+        LINE_ACCOUNT_LINK_URL, link_token.0, nonce.0
+    )
+}
@@ -0,0 +1,11 @@
+use derive_new::new;
+use domain::model::{message::Messages, talk_room::TalkRoom, user::User};
+
+// 管理用のコマンドで調べるための、ユーザーのtalkRoomと最新のメッセージ
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct TalkRoomDump {
+    pub user: User,
+    pub talk_room: TalkRoom,
+    // 新しい順
+    pub messages: Vec<Messages>,
+}
@@ -4,14 +4,15 @@ use derive_new::new;
 
 use domain::model::{
     message::event::{
-        NewEvent, NewEventContentProvider, NewEventContentProviderExternal,
-        NewEventDeliveryContext, NewEventEmoji, NewEventFollow, NewEventImageSet, NewEventMessage,
-        NewEventMessageContent, NewEventMessageContentAudio, NewEventMessageContentFile,
-        NewEventMessageContentImage, NewEventMessageContentLocation, NewEventMessageContentSticker,
-        NewEventMessageContentText, NewEventMessageContentVideo, NewEventPostback,
-        NewEventPostbackContent, NewEventPostbackParams, NewEventPostbackParamsDatetime,
-        NewEventPostbackParamsRichMenu, NewEventStickerResourceType, NewEventUnfollow,
-        NewEventVideoPlayComplete, NewEventVideoPlayCompleteContent,
+        NewEvent, NewEventAccountLink, NewEventAccountLinkContent, NewEventAccountLinkResult,
+        NewEventContentProvider, NewEventContentProviderExternal, NewEventDeliveryContext,
+        NewEventEmoji, NewEventFollow, NewEventImageSet, NewEventMessage, NewEventMessageContent,
+        NewEventMessageContentAudio, NewEventMessageContentFile, NewEventMessageContentImage,
+        NewEventMessageContentLocation, NewEventMessageContentSticker, NewEventMessageContentText,
+        NewEventMessageContentVideo, NewEventPostback, NewEventPostbackContent,
+        NewEventPostbackParams, NewEventPostbackParamsDatetime, NewEventPostbackParamsRichMenu,
+        NewEventStickerResourceType, NewEventUnfollow, NewEventVideoPlayComplete,
+        NewEventVideoPlayCompleteContent,
     },
     Id,
 };
@@ -30,6 +31,7 @@ pub enum CreateEvent {
     Postback(CreateEventPostback),
     VideoPlayComplete(CreateEventVideoPlayComplete),
     Message(CreateEventMessage),
+    AccountLink(CreateEventAccountLink),
 }
 
 #[derive(new, Clone)]
@@ -79,6 +81,16 @@ pub struct CreateEventMessage {
     pub timestamp: i64,
 }
 
+#[derive(new, Clone)]
+pub struct CreateEventAccountLink {
+    pub reply_token: Option<String>,
+    pub delivery_context: CreateEventDeliveryContext,
+    pub link: CreateEventAccountLinkContent,
+    pub mode: String,
+    pub webhook_event_id: String,
+    pub timestamp: i64,
+}
+
 #[derive(new, Debug, Clone)]
 pub struct CreateEventDeliveryContext {
     pub is_redelivery: bool,
@@ -87,7 +99,7 @@ pub struct CreateEventDeliveryContext {
 #[derive(new, Clone)]
 pub struct CreateEventPostbackContent {
     pub data: String,
-    pub params: CreateEventPostbackParams,
+    pub params: Option<CreateEventPostbackParams>,
 }
 
 #[derive(new, Clone)]
@@ -114,6 +126,18 @@ pub struct CreateEventVideoPlayCompleteContent {
     pub tracking_id: String,
 }
 
+#[derive(new, Clone)]
+pub struct CreateEventAccountLinkContent {
+    pub result: CreateEventAccountLinkResult,
+    pub nonce: String,
+}
+
+#[derive(Clone)]
+pub enum CreateEventAccountLinkResult {
+    Ok,
+    Failed,
+}
+
 #[derive(new, Clone)]
 pub enum CreateEventMessageContent {
     Text(CreateEventMessageContentText),
@@ -226,6 +250,7 @@ impl From<CreateEvent> for NewEvent {
             CreateEvent::Postback(s) => NewEvent::Postback(s.into()),
             CreateEvent::VideoPlayComplete(s) => NewEvent::VideoPlayComplete(s.into()),
             CreateEvent::Message(s) => NewEvent::Message(s.into()),
+            CreateEvent::AccountLink(s) => NewEvent::AccountLink(s.into()),
         }
     }
 }
@@ -288,7 +313,7 @@ impl From<CreateEventPostbackContent> for NewEventPostbackContent {
     fn from(s: CreateEventPostbackContent) -> Self {
         Self {
             data: s.data,
-            params: NewEventPostbackParams::from(s.params),
+            params: s.params.map(NewEventPostbackParams::from),
         }
     }
 }
@@ -351,6 +376,34 @@ impl From<CreateEventVideoPlayCompleteContent> for NewEventVideoPlayCompleteCont
     }
 }
 
+impl From<CreateEventAccountLink> for NewEventAccountLink {
+    fn from(s: CreateEventAccountLink) -> Self {
+        let id = Id::gen();
+        let created_at = Local.timestamp_opt(s.timestamp / 1000, 0).unwrap();
+        Self {
+            id,
+            reply_token: s.reply_token,
+            delivery_context: NewEventDeliveryContext::from(s.delivery_context),
+            link: NewEventAccountLinkContent::from(s.link),
+            mode: s.mode,
+            webhook_event_id: s.webhook_event_id,
+            created_at,
+        }
+    }
+}
+
+impl From<CreateEventAccountLinkContent> for NewEventAccountLinkContent {
+    fn from(s: CreateEventAccountLinkContent) -> Self {
+        Self {
+            result: match s.result {
+                CreateEventAccountLinkResult::Ok => NewEventAccountLinkResult::Ok,
+                CreateEventAccountLinkResult::Failed => NewEventAccountLinkResult::Failed,
+            },
+            nonce: s.nonce,
+        }
+    }
+}
+
 impl From<CreateEventMessage> for NewEventMessage {
     fn from(s: CreateEventMessage) -> Self {
         let id = Id::gen();
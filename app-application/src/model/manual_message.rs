@@ -0,0 +1,9 @@
+use domain::model::canned_response::CannedResponseId;
+
+/// スタッフが手動で送るメッセージ
+/// 定型文を選んだときは、送る相手に合わせてプレースホルダーを置き換えてから送る
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CreateManualMessage {
+    Text(String),
+    CannedResponse(CannedResponseId),
+}
@@ -0,0 +1,26 @@
+use derive_new::new;
+use domain::model::rich_menu::{NewRichMenu, RichMenu, RichMenuAliasId, RichMenuImage};
+
+// デプロイするリッチメニューの定義
+#[derive(new, Clone)]
+pub struct CreateRichMenu {
+    pub rich_menu: NewRichMenu,
+    pub image: RichMenuImage,
+    pub alias_id: Option<RichMenuAliasId>,
+    pub is_default: bool,
+}
+
+// デプロイ結果。LINEで払い出されたidを含む
+#[derive(new, Clone, Debug)]
+pub struct DeployedRichMenu {
+    pub rich_menu: RichMenu,
+    pub alias_id: Option<RichMenuAliasId>,
+    pub is_default: bool,
+}
+
+// 一括で再リンクした結果。alias_idがNoneのものはリンクを解除したユーザー
+#[derive(new, Clone, Debug)]
+pub struct SyncedRichMenu {
+    pub alias_id: Option<RichMenuAliasId>,
+    pub user_count: usize,
+}
@@ -0,0 +1,55 @@
+use derive_new::new;
+use domain::model::{rich_menu::RichMenuAliasId, user_tag::UserTag};
+
+// 必要なタグを全て持っているユーザーに、alias_idのリッチメニューをリンクする
+#[derive(new, Debug, Clone)]
+pub struct RichMenuRule {
+    pub required_tags: Vec<UserTag>,
+    pub alias_id: RichMenuAliasId,
+}
+
+impl RichMenuRule {
+    pub fn matches(&self, tags: &[UserTag]) -> bool {
+        self.required_tags.iter().all(|t| tags.contains(t))
+    }
+}
+
+#[derive(new, Debug, Clone)]
+pub struct RichMenuRules {
+    // 先頭にあるほど優先される
+    pub rules: Vec<RichMenuRule>,
+}
+
+impl RichMenuRules {
+    /// ユーザーのタグからリンクするリッチメニューのaliasを決める
+    /// どのルールにも当てはまらない場合はNoneを返し、デフォルトのリッチメニューに戻す
+    ///
+    /// # Arguments
+    /// * `tags` - ユーザーに付いているタグ
+    ///
+    pub fn resolve(&self, tags: &[UserTag]) -> Option<&RichMenuAliasId> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(tags))
+            .map(|rule| &rule.alias_id)
+    }
+}
+
+impl Default for RichMenuRules {
+    fn default() -> Self {
+        Self::new(vec![
+            RichMenuRule::new(
+                vec![UserTag::HasAppointment],
+                RichMenuAliasId::new("has-appointment".to_string()),
+            ),
+            RichMenuRule::new(
+                vec![UserTag::MedicationReminderSubscriber],
+                RichMenuAliasId::new("medication-reminder".to_string()),
+            ),
+            RichMenuRule::new(
+                vec![UserTag::NewFollower],
+                RichMenuAliasId::new("new-follower".to_string()),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Duration, Local, TimeZone};
+use derive_new::new;
+use domain::model::{
+    email_user::{EmailAddress, PasswordHash},
+    staff::{NewStaff, Staff, StaffId},
+};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+// スタッフのセッションの有効期間
+const STAFF_SESSION_HOURS: i64 = 12;
+
+#[derive(new, Clone, Debug)]
+pub struct CreateStaff {
+    pub name: String,
+    pub email: String,
+    pub password: String,
+    pub picture_url: String,
+    pub role: String,
+}
+
+impl TryFrom<CreateStaff> for NewStaff {
+    type Error = anyhow::Error;
+    fn try_from(c: CreateStaff) -> anyhow::Result<Self> {
+        if c.name.is_empty() {
+            return Err(anyhow!("Staff name is empty"));
+        }
+        Ok(NewStaff::new(
+            c.name,
+            EmailAddress::try_from(c.email)?,
+            c.picture_url,
+            c.role.parse()?,
+            PasswordHash::from_plain(&c.password)?,
+        ))
+    }
+}
+
+#[derive(new, Clone, Debug)]
+pub struct StaffSession {
+    pub access_token: String,
+    pub expires_at: DateTime<Local>,
+    pub staff: Staff,
+}
+
+// JWTのペイロード
+// ロールはトークンに入れず、リクエストごとにDBから読むので、変更がすぐに反映される
+#[derive(Serialize, Deserialize, Debug)]
+struct StaffClaims {
+    sub: String,
+    iat: i64,
+    exp: i64,
+}
+
+impl StaffSession {
+    pub fn issue(staff: Staff, jwt_secret: &str) -> anyhow::Result<Self> {
+        let issued_at = Local::now();
+        let expires_at = issued_at + Duration::hours(STAFF_SESSION_HOURS);
+        let claims = StaffClaims {
+            sub: staff.id.0.to_string(),
+            iat: issued_at.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        let access_token = encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(jwt_secret.as_bytes()),
+        )?;
+        // JWTのexpは秒単位なので、それに揃える
+        let expires_at = Local
+            .timestamp_opt(claims.exp, 0)
+            .single()
+            .ok_or(anyhow!("Invalid expires_at: {}", claims.exp))?;
+
+        Ok(StaffSession::new(access_token, expires_at, staff))
+    }
+}
+
+/// アクセストークンを検証し、スタッフのIDを返す
+pub fn verify_staff_access_token(access_token: &str, jwt_secret: &str) -> anyhow::Result<StaffId> {
+    let token_data = decode::<StaffClaims>(
+        access_token,
+        &DecodingKey::from_secret(jwt_secret.as_bytes()),
+        &Validation::new(Algorithm::HS256),
+    )?;
+    let staff_id = token_data.claims.sub.parse::<i64>()?;
+
+    Ok(StaffId::new(staff_id))
+}
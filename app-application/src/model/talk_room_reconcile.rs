@@ -0,0 +1,11 @@
+use derive_new::new;
+use domain::model::talk_room_consistency::TalkRoomInconsistency;
+
+// MySQLとfirestoreを突き合わせて直した結果
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct TalkRoomReconcileReport {
+    pub checked_count: usize,
+    pub inconsistencies: Vec<TalkRoomInconsistency>,
+    pub repaired_count: usize,
+    pub failed_count: usize,
+}
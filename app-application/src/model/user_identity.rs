@@ -0,0 +1,41 @@
+use crate::config::LineConfig;
+use derive_new::new;
+use domain::model::{
+    email_user::EmailAddress,
+    user_auth::{EmailUserAuthData, LineLoginAuthData, UserAuthData},
+};
+
+// primary_user_idに紐づける認証プロバイダーのユーザー
+#[derive(Clone, Debug)]
+pub enum CreateUserIdentity {
+    Email(CreateEmailUserAuth),
+    LineLogin(CreateLineLoginUserAuth),
+}
+
+#[derive(new, Clone, Debug)]
+pub struct CreateEmailUserAuth {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(new, Clone, Debug)]
+pub struct CreateLineLoginUserAuth {
+    pub id_token: String,
+}
+
+impl CreateUserIdentity {
+    /// LINEログインのIDトークンは、LINEログインのチャネルIDと一緒に検証する
+    pub fn into_user_auth_data(self, line: &LineConfig) -> anyhow::Result<UserAuthData> {
+        let user_auth_data = match self {
+            CreateUserIdentity::Email(e) => UserAuthData::Email(EmailUserAuthData::new(
+                EmailAddress::try_from(e.email)?,
+                e.password,
+            )),
+            CreateUserIdentity::LineLogin(l) => UserAuthData::LineLogin(LineLoginAuthData::new(
+                l.id_token,
+                line.login_channel_id()?.to_string(),
+            )),
+        };
+        Ok(user_auth_data)
+    }
+}
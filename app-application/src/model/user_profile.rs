@@ -0,0 +1,8 @@
+use derive_new::new;
+
+// プロフィールを定期的に取得し直した結果
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct RefreshedUserProfiles {
+    pub refreshed_count: usize,
+    pub failed_count: usize,
+}
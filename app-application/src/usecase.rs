@@ -1 +1,44 @@
+pub mod account_link_usecase;
+pub mod admin_usecase;
+pub mod business_hours_usecase;
+pub mod canned_response_usecase;
+pub mod health_check_usecase;
 pub mod linebot_webhook_usecase;
+pub mod rich_menu_assignment_usecase;
+pub mod rich_menu_usecase;
+pub mod staff_usecase;
+pub mod talk_room_note_usecase;
+pub mod talk_room_reconcile_usecase;
+pub mod talk_room_usecase;
+pub mod talk_room_workflow_usecase;
+pub mod user_identity_usecase;
+pub mod user_profile_usecase;
+
+use domain::{gateway::GatewayError, repository::RepositoryError};
+use thiserror::Error;
+
+/// ユースケースのエラー
+/// 呼び出し側は種類でレスポンスを分けるので、anyhowにまとめずにリポジトリやゲートウェイのエラーを持つ
+#[derive(Debug, Error)]
+pub enum UseCaseError {
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+    #[error(transparent)]
+    Gateway(#[from] GatewayError),
+    /// 入力を変換できなかった、または使えない値だった
+    #[error("InvalidInput: {0}")]
+    InvalidInput(String),
+    #[error("Unexpected Error: {0}")]
+    Unexpected(String),
+}
+
+impl UseCaseError {
+    /// 入力の検証はanyhowで返すので、ユースケースの境界でInvalidInputにする
+    pub fn invalid_input(e: anyhow::Error) -> Self {
+        UseCaseError::InvalidInput(format!("{:#}", e))
+    }
+
+    pub fn unexpected(e: anyhow::Error) -> Self {
+        UseCaseError::Unexpected(format!("{:#}", e))
+    }
+}
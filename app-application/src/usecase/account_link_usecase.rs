@@ -0,0 +1,82 @@
+use crate::config::AppConfig;
+use crate::model::account_link::{account_link_login_url, line_account_link_url};
+use crate::usecase::UseCaseError;
+use adapter::module::AdaptersModuleExt;
+use derive_new::new;
+use domain::{
+    gateway::user_auth::UserAuthGateway,
+    model::{
+        account_link::{LineLinkToken, NewAccountLinkNonce},
+        primary_user_id::PrimaryUserId,
+        user_auth::{LineId, LineUserAuthData},
+    },
+    repository::user::UserRepository,
+};
+use std::sync::Arc;
+
+#[derive(new)]
+pub struct AccountLinkUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+    pub config: Arc<AppConfig>,
+}
+
+impl<R: AdaptersModuleExt> AccountLinkUseCase<R> {
+    /// LINEの連携トークンを発行し、会員システムのログインページのURLを返す
+    ///
+    /// # Arguments
+    /// * `line_id` - 連携を始めるLINEのユーザー
+    ///
+    pub async fn issue_login_url(&self, line_id: LineId) -> Result<String, UseCaseError> {
+        // 友だち追加していないユーザーは、accountLinkイベントを受け取っても紐づけられない
+        self.adapters
+            .user_repository()
+            .get_line_user(line_id.clone())
+            .await?;
+        let link_token = self
+            .adapters
+            .user_auth_gateway()
+            .issue_line_link_token(LineUserAuthData::new(
+                line_id,
+                self.config.line.auth_token(),
+            ))
+            .await?;
+
+        Ok(account_link_login_url(
+            self.config
+                .account_link_login_url()
+                .map_err(UseCaseError::unexpected)?,
+            &link_token,
+        ))
+    }
+
+    /// 会員システムでログインしたアカウントのnonceを保存し、LINEへのリダイレクト先を返す
+    /// nonceはaccountLinkイベントを受け取ったときに照合する
+    ///
+    /// # Arguments
+    /// * `link_token` - ログインページで受け取った連携トークン
+    /// * `source` - ログインしたアカウントに対して発行したnonce
+    ///
+    pub async fn issue_nonce(
+        &self,
+        link_token: LineLinkToken,
+        source: NewAccountLinkNonce,
+    ) -> Result<String, UseCaseError> {
+        let nonce = source.nonce.clone();
+        self.adapters
+            .user_repository()
+            .create_account_link_nonce(source)
+            .await?;
+
+        Ok(line_account_link_url(&link_token, &nonce))
+    }
+
+    /// 会員システムとの連携を解除する
+    /// LINE側には連携を解除するAPIがないので、こちらの紐づけを消すだけ
+    pub async fn unlink_account(&self, primary_user_id: PrimaryUserId) -> Result<(), UseCaseError> {
+        Ok(self
+            .adapters
+            .user_repository()
+            .unlink_account(primary_user_id)
+            .await?)
+    }
+}
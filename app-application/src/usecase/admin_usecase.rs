@@ -0,0 +1,88 @@
+use crate::config::AppConfig;
+use crate::model::{admin::TalkRoomDump, manual_message::CreateManualMessage};
+use crate::usecase::talk_room_usecase::TalkRoomUseCase;
+use adapter::module::AdaptersModuleExt;
+use derive_new::new;
+use domain::{
+    model::{
+        message::send_message::NewSendMessages,
+        staff::StaffId,
+        user::User,
+        user_auth::{AuthUserId, LineId},
+    },
+    repository::{staff::StaffRepository, talk_room::TalkRoomRepository, user::UserRepository},
+};
+use std::sync::Arc;
+
+#[derive(new)]
+pub struct AdminUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+    pub config: Arc<AppConfig>,
+}
+
+impl<R: AdaptersModuleExt> AdminUseCase<R> {
+    /// LINEのユーザーIDでユーザーを取得する
+    pub async fn get_line_user(&self, line_id: LineId) -> anyhow::Result<User> {
+        Ok(self
+            .adapters
+            .user_repository()
+            .get_user(AuthUserId::Line(line_id))
+            .await?)
+    }
+
+    /// LINEのユーザーのtalkRoomと、最新のメッセージを取得する
+    ///
+    /// # Arguments
+    /// * `line_id` - LINEのユーザーID
+    /// * `limit` - 取得するメッセージの件数
+    ///
+    pub async fn dump_talk_room(
+        &self,
+        line_id: LineId,
+        limit: u32,
+    ) -> anyhow::Result<TalkRoomDump> {
+        let user = self.get_line_user(line_id).await?;
+        let talk_room = self
+            .adapters
+            .talk_room_repository()
+            .get_talk_room(user.id.clone())
+            .await?;
+        let messages = self
+            .adapters
+            .talk_room_repository()
+            .get_messages(talk_room.id.clone(), None, limit)
+            .await?
+            .messages;
+
+        Ok(TalkRoomDump::new(user, talk_room, messages))
+    }
+
+    /// 動作確認のために、スタッフとしてLINEのユーザーにテキストを送る
+    /// スタッフが送ったときと同じく、talkRoomのメッセージとして保存する
+    ///
+    /// # Arguments
+    /// * `line_id` - 送り先のLINEのユーザーID
+    /// * `staff_id` - 送るスタッフ。メッセージのsenderになる
+    /// * `text` - 送るテキスト
+    ///
+    pub async fn push_message(
+        &self,
+        line_id: LineId,
+        staff_id: StaffId,
+        text: String,
+    ) -> anyhow::Result<Vec<NewSendMessages>> {
+        let user = self.get_line_user(line_id).await?;
+        let talk_room = self
+            .adapters
+            .talk_room_repository()
+            .get_talk_room(user.id)
+            .await?;
+        let staff = self.adapters.staff_repository().get_staff(staff_id).await?;
+
+        Ok(
+            TalkRoomUseCase::new(self.adapters.clone(), self.config.clone())
+                .send_manual_message(talk_room.id, staff, CreateManualMessage::Text(text))
+                .await?,
+        )
+    }
+}
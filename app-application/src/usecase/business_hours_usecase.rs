@@ -0,0 +1,58 @@
+use crate::config::AppConfig;
+use crate::usecase::UseCaseError;
+use adapter::module::AdaptersModuleExt;
+use chrono::{DateTime, Local};
+use derive_new::new;
+use domain::{
+    model::business_hours::{BusinessHoursCalendar, BusinessHoursState, NewBusinessHoursCalendar},
+    repository::business_hours::BusinessHoursRepository,
+};
+use std::sync::Arc;
+
+#[derive(new)]
+pub struct BusinessHoursUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+    pub config: Arc<AppConfig>,
+}
+
+impl<R: AdaptersModuleExt> BusinessHoursUseCase<R> {
+    pub async fn get_calendar(&self) -> Result<Option<BusinessHoursCalendar>, UseCaseError> {
+        Ok(self
+            .adapters
+            .business_hours_repository()
+            .get_calendar(self.config.line.channel_id.clone())
+            .await?)
+    }
+
+    /// チャネルの営業時間を置き換える
+    pub async fn save_calendar(
+        &self,
+        source: NewBusinessHoursCalendar,
+    ) -> Result<BusinessHoursCalendar, UseCaseError> {
+        Ok(self
+            .adapters
+            .business_hours_repository()
+            .save_calendar(self.config.line.channel_id.clone(), source)
+            .await?)
+    }
+
+    pub async fn get_state(&self) -> Result<BusinessHoursState, UseCaseError> {
+        get_business_hours_state(&*self.adapters, &self.config.line.channel_id, Local::now()).await
+    }
+}
+
+/// チャネルがnowに営業時間内かどうか
+/// 引き継ぎやシナリオなど、営業時間で振る舞いを変える処理はここから状態を取る
+pub async fn get_business_hours_state<R: AdaptersModuleExt>(
+    adapters: &R,
+    channel_id: &str,
+    now: DateTime<Local>,
+) -> Result<BusinessHoursState, UseCaseError> {
+    let calendar = adapters
+        .business_hours_repository()
+        .get_calendar(channel_id.to_string())
+        .await?;
+    Ok(calendar
+        .map(|c| c.state(now))
+        .unwrap_or_else(BusinessHoursState::always_open))
+}
@@ -0,0 +1,72 @@
+use crate::usecase::UseCaseError;
+use adapter::module::AdaptersModuleExt;
+use derive_new::new;
+use domain::{
+    model::{
+        canned_response::{CannedResponse, CannedResponseId, NewCannedResponse},
+        staff::Staff,
+    },
+    repository::canned_response::CannedResponseRepository,
+};
+use std::sync::Arc;
+
+#[derive(new)]
+pub struct CannedResponseUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+}
+
+impl<R: AdaptersModuleExt> CannedResponseUseCase<R> {
+    pub async fn get_canned_responses(&self) -> Result<Vec<CannedResponse>, UseCaseError> {
+        Ok(self
+            .adapters
+            .canned_response_repository()
+            .get_canned_responses()
+            .await?)
+    }
+
+    /// 定型文を作る
+    ///
+    /// # Arguments
+    /// * `title` - 一覧で選ぶときの名前
+    /// * `body` - 本文。使えないプレースホルダーがあるときはエラーにする
+    /// * `staff` - 定型文を作るスタッフ
+    ///
+    pub async fn create_canned_response(
+        &self,
+        title: String,
+        body: String,
+        staff: Staff,
+    ) -> Result<CannedResponse, UseCaseError> {
+        CannedResponse::validate_body(&body).map_err(UseCaseError::invalid_input)?;
+        Ok(self
+            .adapters
+            .canned_response_repository()
+            .create_canned_response(NewCannedResponse::new(title, body, staff.id))
+            .await?)
+    }
+
+    pub async fn update_canned_response(
+        &self,
+        canned_response_id: CannedResponseId,
+        title: String,
+        body: String,
+    ) -> Result<CannedResponse, UseCaseError> {
+        CannedResponse::validate_body(&body).map_err(UseCaseError::invalid_input)?;
+        Ok(self
+            .adapters
+            .canned_response_repository()
+            .update_canned_response(canned_response_id, title, body)
+            .await?)
+    }
+
+    pub async fn delete_canned_response(
+        &self,
+        canned_response_id: CannedResponseId,
+    ) -> Result<(), UseCaseError> {
+        Ok(self
+            .adapters
+            .canned_response_repository()
+            .delete_canned_response(canned_response_id)
+            .await?)
+    }
+}
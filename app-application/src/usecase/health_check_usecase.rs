@@ -0,0 +1,15 @@
+use adapter::module::AdaptersModuleExt;
+use derive_new::new;
+use std::sync::Arc;
+
+#[derive(new)]
+pub struct HealthCheckUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+}
+
+impl<R: AdaptersModuleExt> HealthCheckUseCase<R> {
+    /// データベースやfirestoreに接続でき、リクエストを処理できる状態か確かめる
+    pub async fn check_readiness(&self) -> anyhow::Result<()> {
+        self.adapters.check_readiness().await
+    }
+}
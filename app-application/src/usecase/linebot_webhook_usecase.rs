@@ -1,13 +1,21 @@
+use crate::config::AppConfig;
 use crate::model::event::CreateUserEvent;
-use adapter::{module::AdaptersModuleExt, repository::RepositoryError};
+use crate::usecase::business_hours_usecase::get_business_hours_state;
+use crate::usecase::user_profile_usecase::save_line_user_profile;
+use adapter::module::AdaptersModuleExt;
+use chrono::Local;
 use derive_new::new;
 use domain::{
     gateway::{send_message::SendMessageGateway, user_auth::UserAuthGateway},
     model::{
-        message::event::NewEvent,
-        user_auth::{AuthUserId, LineId, LineUserAuthData, UserAuthData},
+        account_link::{AccountLinkNonce, ExternalMemberId, NewAccountLink},
+        message::event::{NewEvent, NewEventAccountLinkResult},
+        talk_room::{TalkRoom, TalkRoomMode},
+        user::{User, UserProfile},
+        user_auth::{LineId, LineUserAuthData, UserAuthData},
+        user_event::{UserEvent, UserFollowed},
     },
-    repository::{talk_room::TalkRoomRepository, user::UserRepository},
+    repository::{talk_room::TalkRoomRepository, user::UserRepository, RepositoryError},
 };
 use futures::future;
 use std::sync::Arc;
@@ -15,77 +23,47 @@ use std::sync::Arc;
 #[derive(new)]
 pub struct LinebotWebhookUseCase<R: AdaptersModuleExt> {
     pub adapters: Arc<R>,
+    pub config: Arc<AppConfig>,
 }
 
 impl<R: AdaptersModuleExt> LinebotWebhookUseCase<R> {
-    pub async fn create_follow_event(&self, source: CreateUserEvent) -> anyhow::Result<()> {
+    /// フォローイベントを保存してメッセージを送信し、発生したドメインイベントを返す
+    /// リッチメニューの割り当てなど、ユーザーの属性に応じた処理は返したイベントを受けて行う
+    pub async fn create_follow_event(
+        &self,
+        source: CreateUserEvent,
+    ) -> anyhow::Result<Vec<UserEvent>> {
         /*
-         * userを取得、なければ作成する
+         * フォローのたびにLINEのプロフィールを取得し直す
+         * userがあればプロフィールを更新し、なければ作成する
+         * 同じユーザーのフォローイベントが同時に届いても、userは1人だけ作成される
          */
         let create_line_user_auth = source.clone().create_line_user_auth;
-        let res_user = self
+        let line_user_auth_data = LineUserAuthData::new(
+            LineId::from(create_line_user_auth),
+            self.config.line.auth_token(),
+        );
+        let line_user_profile = self
+            .adapters
+            .user_auth_gateway()
+            .get_line_user_profile(line_user_auth_data.clone())
+            .await?;
+        let user = self
             .adapters
             .user_repository()
-            .get_user(AuthUserId::Line(LineId::from(
-                create_line_user_auth.clone(),
-            )))
-            .await;
-
-        let line_user_auth_data = LineUserAuthData::try_from(create_line_user_auth)?;
-        let user = match res_user {
-            Ok(s) => s,
-            Err(anyhow_err) => {
-                if let Some(RepositoryError::NotFound(_, _)) =
-                    anyhow_err.downcast_ref::<RepositoryError>()
-                {
-                    let user_profile = self
-                        .adapters
-                        .user_auth_gateway()
-                        .get_user_profile(UserAuthData::Line(line_user_auth_data.clone()))
-                        .await?;
-                    self.adapters
-                        .user_repository()
-                        .create_user(user_profile)
-                        .await?
-                } else {
-                    // anyhow_errがRepositoryErrorではない場合
-                    return Err(anyhow_err);
-                }
-            }
-        };
+            .get_or_create_user(UserProfile::Line(line_user_profile.clone()))
+            .await?;
+        let user = save_line_user_profile(&*self.adapters, &user, line_user_profile).await?;
 
-        /*
-         * talk_roomを取得し、
-         * あればtalk_roomをupdateし、talk_roomのサブコレクションにmessagesを追加する
-         * なければtalk_roomを作成し、talk_roomのサブコレクションmessagesを追加する
-         */
         let new_event = NewEvent::from(source.create_event);
-        let res_talk_room = self
-            .adapters
-            .talk_room_repository()
-            .get_talk_room(user.clone().id)
-            .await;
-        let updated_talk_room = match res_talk_room {
-            Ok(talk_room) => {
-                // talk_roomをupdateし、talk_roomのサブコレクションにmessagesを追加する
-                self.adapters
-                    .talk_room_repository()
-                    .create_messages((talk_room, new_event.clone()).into())
-                    .await?
-            }
-            Err(anyhow_err) => {
-                if let Some(RepositoryError::NotFound(_, _)) =
-                    anyhow_err.downcast_ref::<RepositoryError>()
-                {
-                    self.adapters
-                        .talk_room_repository()
-                        .create_talk_room((user, new_event.clone()).into())
-                        .await?
-                } else {
-                    return Err(anyhow_err);
-                }
-            }
-        };
+        let updated_talk_room = self.save_event(user, new_event.clone()).await?;
+        let user_events = vec![UserEvent::Followed(UserFollowed::new(
+            updated_talk_room.primary_user_id.clone(),
+        ))];
+        // スタッフが対応しているtalkRoomでは、フォローし直してもあいさつを送らない
+        if !bot_replies(&*self.adapters, &self.config, &updated_talk_room).await? {
+            return Ok(user_events);
+        }
         /*
          * メッセージを作成し、送信し、保存する
          * この時点ではtalk_roomはあることが保証されているので、talk_roomをupdateし、talk_roomのサブコレクションにmessagesを追加する
@@ -108,6 +86,202 @@ impl<R: AdaptersModuleExt> LinebotWebhookUseCase<R> {
 
         future::try_join_all(works).await?;
 
+        Ok(user_events)
+    }
+
+    /// ユーザーから届いたメッセージを保存する
+    /// 対応が終わったtalk_roomは、save_eventでopenに戻る
+    /// 営業時間外のときは、不在メッセージを返す
+    pub async fn create_message_event(&self, source: CreateUserEvent) -> anyhow::Result<TalkRoom> {
+        let create_line_user_auth = source.create_line_user_auth;
+        let user = self
+            .adapters
+            .user_repository()
+            .get_line_user(LineId::from(create_line_user_auth.clone()))
+            .await?;
+        let new_event = NewEvent::from(source.create_event);
+        let talk_room = self.save_event(user, new_event.clone()).await?;
+        if let NewEvent::Message(message) = new_event {
+            self.reply_away_message(
+                &talk_room,
+                LineUserAuthData::new(
+                    LineId::from(create_line_user_auth),
+                    self.config.line.auth_token(),
+                ),
+                message.reply_token,
+            )
+            .await?;
+        }
+
+        Ok(talk_room)
+    }
+
+    /// postbackイベントを保存する
+    /// 「薬剤師と話す」のpostbackのときは、botの返信を止めてスタッフが対応するtalkRoomにする
+    /// モードの変更はtalkRoomCardsとsystem_eventに残るので、受信箱を見ているスタッフに届く
+    pub async fn create_postback_event(&self, source: CreateUserEvent) -> anyhow::Result<TalkRoom> {
+        let user = self
+            .adapters
+            .user_repository()
+            .get_line_user(LineId::from(source.create_line_user_auth))
+            .await?;
+        let new_event = NewEvent::from(source.create_event);
+        let talk_room = self.save_event(user, new_event.clone()).await?;
+        if !new_event.is_handoff_request() {
+            return Ok(talk_room);
+        }
+        match talk_room.request_handoff() {
+            Some(workflow) => {
+                self.adapters
+                    .talk_room_repository()
+                    .update_workflow(workflow.clone())
+                    .await?;
+                Ok(talk_room.apply_workflow(&workflow))
+            }
+            None => Ok(talk_room),
+        }
+    }
+
+    /// アカウント連携イベントを保存し、nonceを照合して会員システムのアカウントと紐づける
+    /// 連携に失敗したときや、nonceが見つからないときは紐づけずにNoneを返す
+    pub async fn create_account_link_event(
+        &self,
+        source: CreateUserEvent,
+    ) -> anyhow::Result<Option<ExternalMemberId>> {
+        let user = self
+            .adapters
+            .user_repository()
+            .get_line_user(LineId::from(source.create_line_user_auth))
+            .await?;
+        let new_event = NewEvent::from(source.create_event);
+        self.save_event(user.clone(), new_event.clone()).await?;
+
+        let NewEvent::AccountLink(account_link) = new_event else {
+            return Ok(None);
+        };
+        if account_link.link.result == NewEventAccountLinkResult::Failed {
+            return Ok(None);
+        }
+        let res_external_member_id = self
+            .adapters
+            .user_repository()
+            .link_account(NewAccountLink::new(
+                user.id,
+                AccountLinkNonce::new(account_link.link.nonce),
+            ))
+            .await;
+        match res_external_member_id {
+            Ok(external_member_id) => Ok(Some(external_member_id)),
+            // 期限切れや発行していないnonceは、なりすましの可能性があるので紐づけない
+            Err(RepositoryError::NotFound(_, _)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// 営業時間外の区切りごとに一度だけ、不在メッセージを返信して保存する
+    /// 同じ営業時間外に続けて届いたメッセージには返さない
+    /// スタッフが対応しているtalkRoomには返さない
+    async fn reply_away_message(
+        &self,
+        talk_room: &TalkRoom,
+        line_user_auth_data: LineUserAuthData,
+        reply_token: String,
+    ) -> anyhow::Result<()> {
+        let state =
+            get_business_hours_state(&*self.adapters, &self.config.line.channel_id, Local::now())
+                .await?;
+        let (false, Some(off_hours_since), Some(away_message)) =
+            (state.open, state.off_hours_since, state.away_message)
+        else {
+            return Ok(());
+        };
+        if !bot_replies(&*self.adapters, &self.config, talk_room).await? {
+            return Ok(());
+        }
+        let claimed = self
+            .adapters
+            .talk_room_repository()
+            .claim_away_message(talk_room.id.clone(), off_hours_since)
+            .await?;
+        if !claimed {
+            return Ok(());
+        }
+        let new_send_messages_vec = self
+            .adapters
+            .send_message_gateway()
+            .reply_bot_messages(
+                UserAuthData::Line(line_user_auth_data),
+                reply_token,
+                vec![away_message],
+            )
+            .await?;
+
+        let works: Vec<_> = new_send_messages_vec
+            .into_iter()
+            .map(|new_send_messages| {
+                self.adapters
+                    .talk_room_repository()
+                    .create_messages((talk_room.clone(), new_send_messages).into())
+            })
+            .collect();
+        future::try_join_all(works).await?;
+
         Ok(())
     }
+
+    /*
+     * talk_roomを取得し、
+     * あればtalk_roomをupdateし、talk_roomのサブコレクションにmessagesを追加する
+     * なければtalk_roomを作成し、talk_roomのサブコレクションmessagesを追加する
+     */
+    async fn save_event(&self, user: User, new_event: NewEvent) -> anyhow::Result<TalkRoom> {
+        let res_talk_room = self
+            .adapters
+            .talk_room_repository()
+            .get_talk_room(user.clone().id)
+            .await;
+        match res_talk_room {
+            Ok(talk_room) => {
+                let reopen_workflow = talk_room.reopen(&new_event);
+                // talk_roomをupdateし、talk_roomのサブコレクションにmessagesを追加する
+                let updated_talk_room = self
+                    .adapters
+                    .talk_room_repository()
+                    .create_messages((talk_room, new_event).into())
+                    .await?;
+                // 対応が終わったtalk_roomでも、ユーザーからメッセージが届いたらopenに戻す
+                match reopen_workflow {
+                    Some(workflow) => {
+                        self.adapters
+                            .talk_room_repository()
+                            .update_workflow(workflow.clone())
+                            .await?;
+                        Ok(updated_talk_room.apply_workflow(&workflow))
+                    }
+                    None => Ok(updated_talk_room),
+                }
+            }
+            Err(RepositoryError::NotFound(_, _)) => Ok(self
+                .adapters
+                .talk_room_repository()
+                .create_talk_room((user, new_event).into())
+                .await?),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// botが自動で返信してよいか
+/// あいさつなど自動で返信するときは、送る前に必ずこれを確かめる
+/// 営業時間を見るのは、営業時間外だけbotが返信するモードのときだけ
+pub async fn bot_replies<R: AdaptersModuleExt>(
+    adapters: &R,
+    config: &AppConfig,
+    talk_room: &TalkRoom,
+) -> anyhow::Result<bool> {
+    if talk_room.mode != TalkRoomMode::BotOutsideBusinessHours {
+        return Ok(talk_room.mode.bot_replies(true));
+    }
+    let state = get_business_hours_state(adapters, &config.line.channel_id, Local::now()).await?;
+    Ok(talk_room.mode.bot_replies(state.open))
 }
@@ -0,0 +1,145 @@
+use crate::config::AppConfig;
+use crate::model::{rich_menu::SyncedRichMenu, rich_menu_rule::RichMenuRules};
+use crate::usecase::UseCaseError;
+use adapter::module::AdaptersModuleExt;
+use derive_new::new;
+use domain::{
+    gateway::{rich_menu::RichMenuGateway, GatewayError},
+    model::{
+        primary_user_id::PrimaryUserId,
+        rich_menu::{RichMenuAliasId, RichMenuId},
+        user_auth::{LineAuthToken, LineId},
+        user_event::UserEvent,
+        user_tag::UserTag,
+    },
+    repository::user_tag::UserTagRepository,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(new)]
+pub struct RichMenuAssignmentUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+    pub rules: RichMenuRules,
+    pub config: Arc<AppConfig>,
+}
+
+impl<R: AdaptersModuleExt> RichMenuAssignmentUseCase<R> {
+    /// ドメインイベントに応じてユーザーのタグを更新し、ルールに従ってリッチメニューをリンクし直す
+    ///
+    /// # Arguments
+    /// * `source` - ユーザーの属性が変わるきっかけになったイベント
+    ///
+    pub async fn handle_user_event(&self, source: UserEvent) -> Result<(), UseCaseError> {
+        let primary_user_id = source.primary_user_id().clone();
+        let (added_tags, removed_tags) = match source {
+            UserEvent::Followed(_) => (vec![UserTag::NewFollower], vec![]),
+            UserEvent::TaggedByStaff(e) => (e.added_tags, e.removed_tags),
+        };
+        let user_tag_repository = self.adapters.user_tag_repository();
+        if !added_tags.is_empty() {
+            user_tag_repository
+                .add_user_tags(primary_user_id.clone(), added_tags)
+                .await?;
+        }
+        if !removed_tags.is_empty() {
+            user_tag_repository
+                .remove_user_tags(primary_user_id.clone(), removed_tags)
+                .await?;
+        }
+
+        self.assign_rich_menu(primary_user_id).await
+    }
+
+    /// 全ユーザーのリッチメニューをルールに従ってリンクし直す
+    /// ルールやリッチメニューのaliasを変更したときに使う
+    pub async fn resync_rich_menus(&self) -> Result<Vec<SyncedRichMenu>, UseCaseError> {
+        let auth_token = self.config.line.auth_token();
+        let line_user_tags_vec = self
+            .adapters
+            .user_tag_repository()
+            .get_all_line_user_tags()
+            .await?;
+
+        /*
+         * リンクするリッチメニューごとにユーザーをまとめ、一括でリンクする
+         */
+        let mut line_ids_by_alias: HashMap<Option<RichMenuAliasId>, Vec<LineId>> = HashMap::new();
+        for line_user_tags in line_user_tags_vec {
+            line_ids_by_alias
+                .entry(self.rules.resolve(&line_user_tags.tags).cloned())
+                .or_default()
+                .push(line_user_tags.line_id);
+        }
+
+        let rich_menu_gateway = self.adapters.rich_menu_gateway();
+        let mut synced_rich_menus = Vec::new();
+        for (alias_id, line_ids) in line_ids_by_alias {
+            let user_count = line_ids.len();
+            match alias_id.clone() {
+                Some(alias_id) => {
+                    let rich_menu_id = self.rich_menu_id(auth_token.clone(), alias_id).await?;
+                    rich_menu_gateway
+                        .link_rich_menu_to_users(auth_token.clone(), line_ids, rich_menu_id)
+                        .await?
+                }
+                None => {
+                    rich_menu_gateway
+                        .unlink_rich_menu_from_users(auth_token.clone(), line_ids)
+                        .await?
+                }
+            }
+            synced_rich_menus.push(SyncedRichMenu::new(alias_id, user_count));
+        }
+
+        Ok(synced_rich_menus)
+    }
+
+    async fn assign_rich_menu(&self, primary_user_id: PrimaryUserId) -> Result<(), UseCaseError> {
+        let auth_token = self.config.line.auth_token();
+        let line_user_tags = self
+            .adapters
+            .user_tag_repository()
+            .get_line_user_tags(primary_user_id)
+            .await?;
+
+        let rich_menu_gateway = self.adapters.rich_menu_gateway();
+        match self.rules.resolve(&line_user_tags.tags) {
+            Some(alias_id) => {
+                let rich_menu_id = self
+                    .rich_menu_id(auth_token.clone(), alias_id.clone())
+                    .await?;
+                rich_menu_gateway
+                    .link_rich_menu_to_user(auth_token, line_user_tags.line_id, rich_menu_id)
+                    .await?
+            }
+            // どのルールにも当てはまらなければ、リンクを解除してデフォルトのリッチメニューに戻す
+            None => {
+                rich_menu_gateway
+                    .unlink_rich_menu_from_user(auth_token, line_user_tags.line_id)
+                    .await?
+            }
+        }
+
+        Ok(())
+    }
+
+    // ルールはaliasで書いているので、リンクする前に実際のリッチメニューのidを引く
+    async fn rich_menu_id(
+        &self,
+        auth_token: LineAuthToken,
+        alias_id: RichMenuAliasId,
+    ) -> Result<RichMenuId, UseCaseError> {
+        let alias = self
+            .adapters
+            .rich_menu_gateway()
+            .get_rich_menu_alias(auth_token, alias_id.clone())
+            .await?
+            .ok_or(GatewayError::NotFound(format!(
+                "Rich menu alias is not found: {}",
+                alias_id.0
+            )))?;
+
+        Ok(alias.rich_menu_id)
+    }
+}
@@ -0,0 +1,75 @@
+use crate::config::AppConfig;
+use crate::model::rich_menu::{CreateRichMenu, DeployedRichMenu};
+use adapter::module::AdaptersModuleExt;
+use derive_new::new;
+use domain::{gateway::rich_menu::RichMenuGateway, model::rich_menu::RichMenuAlias};
+use std::sync::Arc;
+
+#[derive(new)]
+pub struct RichMenuUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+    pub config: Arc<AppConfig>,
+}
+
+impl<R: AdaptersModuleExt> RichMenuUseCase<R> {
+    /// リッチメニューの定義をLINEにデプロイする
+    ///
+    /// # Arguments
+    /// * `source` - デプロイするリッチメニューの定義。aliasがあれば作成し、既にあれば新しいメニューに付け替える
+    ///
+    pub async fn deploy_rich_menus(
+        &self,
+        source: Vec<CreateRichMenu>,
+    ) -> anyhow::Result<Vec<DeployedRichMenu>> {
+        let auth_token = self.config.line.auth_token();
+        let rich_menu_gateway = self.adapters.rich_menu_gateway();
+        let mut deployed_rich_menus = Vec::new();
+        // aliasの付け替えでリッチメニューが切り替わるので、順番に処理する
+        for create_rich_menu in source {
+            let rich_menu = rich_menu_gateway
+                .create_rich_menu(auth_token.clone(), create_rich_menu.rich_menu)
+                .await?;
+            rich_menu_gateway
+                .upload_rich_menu_image(
+                    auth_token.clone(),
+                    rich_menu.id.clone(),
+                    create_rich_menu.image,
+                )
+                .await?;
+            /*
+             * aliasがなければ作成し、あれば新しいリッチメニューに付け替える
+             * 古いリッチメニューは個別にリンクされているユーザーがいるかもしれないので、ここでは削除しない
+             */
+            if let Some(alias_id) = create_rich_menu.alias_id.clone() {
+                let alias = RichMenuAlias::new(alias_id.clone(), rich_menu.id.clone());
+                match rich_menu_gateway
+                    .get_rich_menu_alias(auth_token.clone(), alias_id)
+                    .await?
+                {
+                    Some(_) => {
+                        rich_menu_gateway
+                            .update_rich_menu_alias(auth_token.clone(), alias)
+                            .await?
+                    }
+                    None => {
+                        rich_menu_gateway
+                            .create_rich_menu_alias(auth_token.clone(), alias)
+                            .await?
+                    }
+                }
+            }
+            if create_rich_menu.is_default {
+                rich_menu_gateway
+                    .set_default_rich_menu(auth_token.clone(), rich_menu.id.clone())
+                    .await?;
+            }
+            deployed_rich_menus.push(DeployedRichMenu::new(
+                rich_menu,
+                create_rich_menu.alias_id,
+                create_rich_menu.is_default,
+            ));
+        }
+
+        Ok(deployed_rich_menus)
+    }
+}
@@ -0,0 +1,97 @@
+use crate::config::AppConfig;
+use crate::model::staff::{verify_staff_access_token, StaffSession};
+use crate::usecase::UseCaseError;
+use adapter::module::AdaptersModuleExt;
+use derive_new::new;
+use domain::{
+    model::{
+        email_user::EmailAddress,
+        staff::{NewStaff, Staff, StaffId, StaffRole},
+    },
+    repository::{staff::StaffRepository, RepositoryError},
+};
+use std::sync::Arc;
+
+#[derive(new)]
+pub struct StaffUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+    pub config: Arc<AppConfig>,
+}
+
+impl<R: AdaptersModuleExt> StaffUseCase<R> {
+    /// メールアドレスとパスワードでスタッフを認証し、アクセストークンを発行する
+    ///
+    /// # Arguments
+    /// * `email` - スタッフのメールアドレス
+    /// * `password` - スタッフのパスワード
+    ///
+    pub async fn login(
+        &self,
+        email: String,
+        password: String,
+    ) -> Result<StaffSession, UseCaseError> {
+        let email = EmailAddress::try_from(email.clone())
+            .map_err(|_| RepositoryError::NotAuthFound(email))?;
+        let auth_id = email.0.clone();
+        let staff = self
+            .adapters
+            .staff_repository()
+            .get_staff_by_email(email)
+            .await
+            .map_err(|err| not_found_to_not_auth_found(err, &auth_id))?;
+        if !staff.password_hash.verify(&password) {
+            return Err(RepositoryError::NotAuthFound(auth_id).into());
+        }
+
+        StaffSession::issue(staff, &self.config.staff_jwt_secret).map_err(UseCaseError::unexpected)
+    }
+
+    /// アクセストークンからスタッフを取り出す
+    /// ロールはトークンではなくDBのものを使う
+    ///
+    /// # Arguments
+    /// * `access_token` - loginで発行したアクセストークン
+    ///
+    pub async fn authenticate(&self, access_token: &str) -> Result<Staff, UseCaseError> {
+        let staff_id = verify_staff_access_token(access_token, &self.config.staff_jwt_secret)
+            .map_err(|_| RepositoryError::NotAuthFound("staff".to_string()))?;
+        Ok(self
+            .adapters
+            .staff_repository()
+            .get_staff(staff_id)
+            .await
+            .map_err(|err| not_found_to_not_auth_found(err, &staff_id.0.to_string()))?)
+    }
+
+    pub async fn get_staffs(&self) -> Result<Vec<Staff>, UseCaseError> {
+        Ok(self.adapters.staff_repository().get_staffs().await?)
+    }
+
+    pub async fn create_staff(&self, source: NewStaff) -> Result<Staff, UseCaseError> {
+        Ok(self
+            .adapters
+            .staff_repository()
+            .create_staff(source)
+            .await?)
+    }
+
+    pub async fn update_staff_role(
+        &self,
+        staff_id: StaffId,
+        role: StaffRole,
+    ) -> Result<Staff, UseCaseError> {
+        Ok(self
+            .adapters
+            .staff_repository()
+            .update_staff_role(staff_id, role)
+            .await?)
+    }
+}
+
+// スタッフがいないことと、パスワードやトークンが違うことは区別しない
+fn not_found_to_not_auth_found(err: RepositoryError, auth_id: &str) -> RepositoryError {
+    match err {
+        RepositoryError::NotFound(_, _) => RepositoryError::NotAuthFound(auth_id.to_string()),
+        err => err,
+    }
+}
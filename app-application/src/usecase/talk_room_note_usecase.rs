@@ -0,0 +1,114 @@
+use crate::usecase::UseCaseError;
+use adapter::module::AdaptersModuleExt;
+use chrono::Local;
+use derive_new::new;
+use domain::{
+    model::{
+        staff::Staff,
+        talk_room::TalkRoom,
+        talk_room_note::{NewTalkRoomNote, TalkRoomNote},
+        Id,
+    },
+    repository::{talk_room::TalkRoomRepository, talk_room_note::TalkRoomNoteRepository},
+};
+use std::sync::Arc;
+
+#[derive(new)]
+pub struct TalkRoomNoteUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+}
+
+impl<R: AdaptersModuleExt> TalkRoomNoteUseCase<R> {
+    /// talkRoomのメモを新しい順に取得する
+    pub async fn get_notes(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+    ) -> Result<Vec<TalkRoomNote>, UseCaseError> {
+        // 存在しないtalkRoomのときはNotFoundを返す
+        self.get_talk_room(talk_room_id.clone()).await?;
+        Ok(self
+            .adapters
+            .talk_room_note_repository()
+            .get_notes(talk_room_id)
+            .await?)
+    }
+
+    pub async fn get_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+    ) -> Result<TalkRoomNote, UseCaseError> {
+        Ok(self
+            .adapters
+            .talk_room_note_repository()
+            .get_note(talk_room_id, note_id)
+            .await?)
+    }
+
+    /// talkRoomにメモを残す
+    ///
+    /// # Arguments
+    /// * `talk_room_id` - メモを残すtalkRoom
+    /// * `staff` - メモを書いたスタッフ
+    /// * `body` - メモの本文
+    ///
+    pub async fn create_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        staff: Staff,
+        body: String,
+    ) -> Result<TalkRoomNote, UseCaseError> {
+        self.get_talk_room(talk_room_id.clone()).await?;
+        Ok(self
+            .adapters
+            .talk_room_note_repository()
+            .create_note(NewTalkRoomNote::new(
+                Id::gen(),
+                talk_room_id,
+                staff.into(),
+                body,
+                Local::now(),
+            ))
+            .await?)
+    }
+
+    pub async fn update_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+        body: String,
+    ) -> Result<TalkRoomNote, UseCaseError> {
+        let note = self.get_note(talk_room_id.clone(), note_id.clone()).await?;
+        self.adapters
+            .talk_room_note_repository()
+            .update_note(talk_room_id, note_id, body.clone())
+            .await?;
+
+        Ok(TalkRoomNote {
+            body,
+            updated_at: Local::now(),
+            ..note
+        })
+    }
+
+    pub async fn delete_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+    ) -> Result<(), UseCaseError> {
+        self.get_note(talk_room_id.clone(), note_id.clone()).await?;
+        Ok(self
+            .adapters
+            .talk_room_note_repository()
+            .delete_note(talk_room_id, note_id)
+            .await?)
+    }
+
+    async fn get_talk_room(&self, talk_room_id: Id<TalkRoom>) -> Result<TalkRoom, UseCaseError> {
+        Ok(self
+            .adapters
+            .talk_room_repository()
+            .get_talk_room_by_id(talk_room_id)
+            .await?)
+    }
+}
@@ -0,0 +1,54 @@
+use crate::model::talk_room_reconcile::TalkRoomReconcileReport;
+use adapter::module::AdaptersModuleExt;
+use derive_new::new;
+use domain::repository::talk_room::TalkRoomRepository;
+use std::sync::Arc;
+
+#[derive(new)]
+pub struct TalkRoomReconcileUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+}
+
+impl<R: AdaptersModuleExt> TalkRoomReconcileUseCase<R> {
+    /// MySQLのtalk_roomsとfirestoreのドキュメントを突き合わせる
+    /// repairがtrueのときは、自動で直せる食い違いだけ直す
+    ///
+    /// # Arguments
+    /// * `repair` - falseのときは報告だけする
+    ///
+    pub async fn reconcile_talk_rooms(
+        &self,
+        repair: bool,
+    ) -> anyhow::Result<TalkRoomReconcileReport> {
+        let scan = self
+            .adapters
+            .talk_room_repository()
+            .scan_consistency()
+            .await?;
+
+        let mut repaired_count = 0;
+        let mut failed_count = 0;
+        for inconsistency in &scan.inconsistencies {
+            if !repair || !inconsistency.repairable() {
+                continue;
+            }
+            // 1件直せなくても、残りは直す
+            match self
+                .adapters
+                .talk_room_repository()
+                .repair_inconsistency(inconsistency.clone())
+                .await
+            {
+                Ok(()) => repaired_count += 1,
+                Err(_) => failed_count += 1,
+            }
+        }
+
+        Ok(TalkRoomReconcileReport::new(
+            scan.checked_count,
+            scan.inconsistencies,
+            repaired_count,
+            failed_count,
+        ))
+    }
+}
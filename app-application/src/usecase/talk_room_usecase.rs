@@ -0,0 +1,344 @@
+use crate::config::AppConfig;
+use crate::model::manual_message::CreateManualMessage;
+use crate::usecase::UseCaseError;
+use adapter::module::AdaptersModuleExt;
+use chrono::Local;
+use derive_new::new;
+use domain::{
+    gateway::send_message::SendMessageGateway,
+    model::{
+        canned_response::CannedResponseContext,
+        message::{send_message::NewSendMessages, MessageCursor, MessagesPage},
+        staff::{Staff, StaffId},
+        talk_room::TalkRoom,
+        talk_room_card::{TalkRoomCardPage, TalkRoomCursor, TalkRoomFilter, TalkRoomReadMarker},
+        talk_room_change::{TalkRoomChange, TalkRoomChangeResumeToken, TalkRoomChangeStream},
+        user::UserProfile,
+        user_auth::{LineUserAuthData, UserAuthData},
+        Id,
+    },
+    repository::{
+        canned_response::CannedResponseRepository, talk_room::TalkRoomRepository,
+        user::UserRepository, RepositoryError,
+    },
+};
+use futures::{stream, StreamExt};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// 1ページの件数の上限。指定がないときはデフォルトの件数を返す
+const DEFAULT_PAGE_LIMIT: u32 = 20;
+const MAX_PAGE_LIMIT: u32 = 100;
+
+#[derive(new)]
+pub struct TalkRoomUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+    pub config: Arc<AppConfig>,
+}
+
+impl<R: AdaptersModuleExt> TalkRoomUseCase<R> {
+    /// talkRoomの一覧を取得する
+    ///
+    /// # Arguments
+    /// * `filter` - 絞り込み条件
+    /// * `cursor` - 前のページのnext_cursor。Noneのときは先頭から取得する
+    /// * `limit` - 1ページの件数。1から100の範囲に丸める
+    ///
+    pub async fn get_talk_room_cards(
+        &self,
+        filter: TalkRoomFilter,
+        cursor: Option<TalkRoomCursor>,
+        limit: Option<u32>,
+    ) -> Result<TalkRoomCardPage, UseCaseError> {
+        Ok(self
+            .adapters
+            .talk_room_repository()
+            .get_talk_room_cards(filter, cursor, page_limit(limit))
+            .await?)
+    }
+
+    /// talkRoomのメッセージ履歴を新しい順に取得する
+    ///
+    /// # Arguments
+    /// * `talk_room_id` - 履歴を取得するtalkRoom
+    /// * `cursor` - 前のページのnext_cursor。Noneのときは最新から取得する
+    /// * `limit` - 1ページの件数。1から100の範囲に丸める
+    ///
+    pub async fn get_messages(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        cursor: Option<MessageCursor>,
+        limit: Option<u32>,
+    ) -> Result<MessagesPage, UseCaseError> {
+        Ok(self
+            .adapters
+            .talk_room_repository()
+            .get_messages(talk_room_id, cursor, page_limit(limit))
+            .await?)
+    }
+
+    /// 受信箱に即時に反映するために、talkRoomの変更を受け取り続ける
+    /// スタッフが見られないtalkRoomの変更は除く
+    ///
+    /// # Arguments
+    /// * `resume_token` - 前の接続で最後に受け取ったCheckpoint。Noneのときは今から後の変更だけを受け取る
+    /// * `staff` - 変更を受け取るスタッフ
+    ///
+    pub async fn listen_changes(
+        &self,
+        resume_token: Option<TalkRoomChangeResumeToken>,
+        staff: Staff,
+    ) -> Result<TalkRoomChangeStream, UseCaseError> {
+        let changes = self
+            .adapters
+            .talk_room_repository()
+            .listen_changes(resume_token)
+            .await?;
+
+        let filter = TalkRoomChangeFilter {
+            adapters: self.adapters.clone(),
+            staff,
+            assignees: HashMap::new(),
+        };
+        Ok(
+            stream::unfold((changes, filter), |(mut changes, mut filter)| async move {
+                loop {
+                    let change = changes.next().await?;
+                    if let Some(change) = filter.filter(change).await {
+                        return Some((change, (changes, filter)));
+                    }
+                }
+            })
+            .boxed(),
+        )
+    }
+
+    /// スタッフが書いたメッセージか定型文を、talkRoomのユーザーに送る
+    /// 送ったメッセージはtalkRoomのメッセージとして保存する
+    ///
+    /// # Arguments
+    /// * `talk_room_id` - 送り先のtalkRoom
+    /// * `staff` - 送るスタッフ。メッセージのsenderになる
+    /// * `source` - 送るテキストか定型文
+    ///
+    pub async fn send_manual_message(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        staff: Staff,
+        source: CreateManualMessage,
+    ) -> Result<Vec<NewSendMessages>, UseCaseError> {
+        let talk_room = self.get_talk_room(talk_room_id).await?;
+        let text = match source {
+            CreateManualMessage::Text(text) => text,
+            CreateManualMessage::CannedResponse(canned_response_id) => self
+                .adapters
+                .canned_response_repository()
+                .get_canned_response(canned_response_id)
+                .await?
+                .render(&CannedResponseContext::from((&talk_room, &staff))),
+        };
+        let user = self
+            .adapters
+            .user_repository()
+            .get_line_user_by_primary_user_id(talk_room.primary_user_id.clone())
+            .await?;
+        let UserProfile::Line(line_user_profile) = user.user_profile else {
+            return Err(UseCaseError::Unexpected(format!(
+                "Messages can be sent only to LINE users: {}",
+                talk_room.primary_user_id.value()
+            )));
+        };
+        let new_send_messages_vec = self
+            .adapters
+            .send_message_gateway()
+            .send_manual_messages(
+                UserAuthData::Line(LineUserAuthData::new(
+                    line_user_profile.auth_id,
+                    self.config.line.auth_token(),
+                )),
+                staff.into(),
+                vec![text],
+            )
+            .await?;
+
+        // メッセージの順番を保つために、送った順に保存する
+        for new_send_messages in new_send_messages_vec.iter() {
+            self.adapters
+                .talk_room_repository()
+                .create_messages((talk_room.clone(), new_send_messages.clone()).into())
+                .await?;
+        }
+
+        Ok(new_send_messages_vec)
+    }
+
+    /// talkRoomをピン留めする、またはピン留めを外す
+    pub async fn update_pinned(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        pinned: bool,
+    ) -> Result<TalkRoom, UseCaseError> {
+        let talk_room = self.get_talk_room(talk_room_id.clone()).await?;
+        self.adapters
+            .talk_room_repository()
+            .update_pinned(talk_room_id, pinned)
+            .await?;
+
+        Ok(TalkRoom {
+            pinned,
+            updated_at: Local::now(),
+            ..talk_room
+        })
+    }
+
+    /// talkRoomのrsvpを変更する
+    pub async fn update_rsvp(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        rsvp: bool,
+    ) -> Result<TalkRoom, UseCaseError> {
+        let talk_room = self.get_talk_room(talk_room_id.clone()).await?;
+        self.adapters
+            .talk_room_repository()
+            .update_rsvp(talk_room_id, rsvp)
+            .await?;
+
+        Ok(TalkRoom {
+            rsvp,
+            updated_at: Local::now(),
+            ..talk_room
+        })
+    }
+
+    /// talkRoomにスタッフが決めた呼び名を付ける
+    ///
+    /// # Arguments
+    /// * `talk_room_id` - 呼び名を付けるtalkRoom
+    /// * `nickname` - 呼び名。Noneのときは呼び名を外し、LINEの表示名に戻す
+    ///
+    pub async fn update_nickname(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        nickname: Option<String>,
+    ) -> Result<TalkRoom, UseCaseError> {
+        let talk_room = self.get_talk_room(talk_room_id.clone()).await?;
+        self.adapters
+            .talk_room_repository()
+            .update_nickname(talk_room_id, nickname.clone())
+            .await?;
+
+        Ok(TalkRoom {
+            nickname,
+            updated_at: Local::now(),
+            ..talk_room
+        })
+    }
+
+    /// スタッフがtalkRoomを既読にする
+    /// LINE_MARK_AS_READ_ENABLEDがtrueのときは、ユーザーのLINEのトーク画面でも既読にする
+    ///
+    /// # Arguments
+    /// * `talk_room_id` - 既読にするtalkRoom
+    /// * `staff_id` - 既読にしたスタッフ
+    ///
+    pub async fn mark_as_read(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        staff_id: StaffId,
+    ) -> Result<TalkRoomReadMarker, UseCaseError> {
+        let talk_room = self.get_talk_room(talk_room_id.clone()).await?;
+        let read_marker = self
+            .adapters
+            .talk_room_repository()
+            .mark_as_read(talk_room_id, staff_id)
+            .await?;
+
+        if self.config.line.mark_as_read_enabled {
+            let user = self
+                .adapters
+                .user_repository()
+                .get_line_user_by_primary_user_id(talk_room.primary_user_id)
+                .await?;
+            if let UserProfile::Line(line_user_profile) = user.user_profile {
+                self.adapters
+                    .send_message_gateway()
+                    .mark_as_read(UserAuthData::Line(LineUserAuthData::new(
+                        line_user_profile.auth_id,
+                        self.config.line.auth_token(),
+                    )))
+                    .await?;
+            }
+        }
+
+        Ok(read_marker)
+    }
+
+    // 存在しないtalkRoomを更新しないように、先に取得する
+    async fn get_talk_room(&self, talk_room_id: Id<TalkRoom>) -> Result<TalkRoom, UseCaseError> {
+        Ok(self
+            .adapters
+            .talk_room_repository()
+            .get_talk_room_by_id(talk_room_id)
+            .await?)
+    }
+}
+
+fn page_limit(limit: Option<u32>) -> u32 {
+    limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT)
+}
+
+/// スタッフが見られるtalkRoomの変更だけを通す
+/// メッセージの追加には担当者が含まれないので、talkRoomCardsの更新で覚えた担当者を使う
+/// まだ覚えていないtalkRoomは、talkRoomを取得して担当者を確かめる
+struct TalkRoomChangeFilter<R: AdaptersModuleExt> {
+    adapters: Arc<R>,
+    staff: Staff,
+    // talkRoomのIDごとの担当者
+    assignees: HashMap<String, Option<StaffId>>,
+}
+
+impl<R: AdaptersModuleExt> TalkRoomChangeFilter<R> {
+    async fn filter(
+        &mut self,
+        change: Result<TalkRoomChange, RepositoryError>,
+    ) -> Option<Result<TalkRoomChange, RepositoryError>> {
+        // 変換できなかった変更は、呼び出し側でログに残す
+        let Ok(change) = change else {
+            return Some(change);
+        };
+        let visible = match &change {
+            // ほかのスタッフに担当が移ったときも、受信箱から外せるように届ける
+            TalkRoomChange::CardUpdated(card) => {
+                let previous_assignee = self
+                    .assignees
+                    .insert(card.id.value.to_string(), card.assignee);
+                self.staff.can_view_talk_room(card.assignee)
+                    || previous_assignee
+                        .is_some_and(|assignee| self.staff.can_view_talk_room(assignee))
+            }
+            TalkRoomChange::MessageAdded(message) => {
+                let assignee = match self.assignees.get(&message.talk_room_id.value.to_string()) {
+                    Some(assignee) => *assignee,
+                    None => {
+                        let talk_room = match self
+                            .adapters
+                            .talk_room_repository()
+                            .get_talk_room_by_id(message.talk_room_id.clone())
+                            .await
+                        {
+                            Ok(talk_room) => talk_room,
+                            Err(err) => return Some(Err(err)),
+                        };
+                        self.assignees
+                            .insert(talk_room.id.value.to_string(), talk_room.assignee);
+                        talk_room.assignee
+                    }
+                };
+                self.staff.can_view_talk_room(assignee)
+            }
+            TalkRoomChange::Checkpoint(_) => true,
+        };
+
+        visible.then_some(Ok(change))
+    }
+}
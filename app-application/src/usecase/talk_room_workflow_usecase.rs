@@ -0,0 +1,100 @@
+use crate::usecase::UseCaseError;
+use adapter::module::AdaptersModuleExt;
+use derive_new::new;
+use domain::{
+    model::{
+        staff::{Staff, StaffId},
+        talk_room::{NewTalkRoomWorkflow, TalkRoom, TalkRoomMode, TalkRoomStatus},
+        Id,
+    },
+    repository::{staff::StaffRepository, talk_room::TalkRoomRepository},
+};
+use std::sync::Arc;
+
+#[derive(new)]
+pub struct TalkRoomWorkflowUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+}
+
+impl<R: AdaptersModuleExt> TalkRoomWorkflowUseCase<R> {
+    /// talkRoomの担当者を変更する
+    ///
+    /// # Arguments
+    /// * `talk_room_id` - 担当者を変更するtalkRoom
+    /// * `operator` - 変更するスタッフ
+    /// * `assignee` - 新しい担当者
+    ///
+    pub async fn assign(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        operator: Staff,
+        assignee: StaffId,
+    ) -> Result<TalkRoom, UseCaseError> {
+        // 存在しないスタッフを担当者にしない
+        self.adapters.staff_repository().get_staff(assignee).await?;
+        let talk_room = self.get_talk_room(talk_room_id).await?;
+        let workflow = talk_room.assign(operator.id, assignee);
+        self.update_workflow(talk_room, workflow).await
+    }
+
+    /// talkRoomの担当者を外す
+    pub async fn unassign(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        operator: Staff,
+    ) -> Result<TalkRoom, UseCaseError> {
+        let talk_room = self.get_talk_room(talk_room_id).await?;
+        let workflow = talk_room.unassign(operator.id);
+        self.update_workflow(talk_room, workflow).await
+    }
+
+    /// talkRoomのステータスを変更する
+    pub async fn change_status(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        operator: Staff,
+        status: TalkRoomStatus,
+    ) -> Result<TalkRoom, UseCaseError> {
+        let talk_room = self.get_talk_room(talk_room_id).await?;
+        let workflow = talk_room.change_status(Some(operator.id), status);
+        self.update_workflow(talk_room, workflow).await
+    }
+
+    /// talkRoomのモードを変更する
+    /// 薬剤師が対応を引き継ぐときはhumanにして、botの自動返信を止める
+    pub async fn change_mode(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        operator: Staff,
+        mode: TalkRoomMode,
+    ) -> Result<TalkRoom, UseCaseError> {
+        let talk_room = self.get_talk_room(talk_room_id).await?;
+        let workflow = talk_room.change_mode(Some(operator.id), mode);
+        self.update_workflow(talk_room, workflow).await
+    }
+
+    async fn get_talk_room(&self, talk_room_id: Id<TalkRoom>) -> Result<TalkRoom, UseCaseError> {
+        Ok(self
+            .adapters
+            .talk_room_repository()
+            .get_talk_room_by_id(talk_room_id)
+            .await?)
+    }
+
+    // 変更がないときは、履歴を残さずにそのまま返す
+    async fn update_workflow(
+        &self,
+        talk_room: TalkRoom,
+        workflow: Option<NewTalkRoomWorkflow>,
+    ) -> Result<TalkRoom, UseCaseError> {
+        let Some(workflow) = workflow else {
+            return Ok(talk_room);
+        };
+        self.adapters
+            .talk_room_repository()
+            .update_workflow(workflow.clone())
+            .await?;
+
+        Ok(talk_room.apply_workflow(&workflow))
+    }
+}
@@ -0,0 +1,129 @@
+use crate::config::AppConfig;
+use crate::model::user_identity::CreateUserIdentity;
+use crate::usecase::UseCaseError;
+use adapter::module::AdaptersModuleExt;
+use derive_new::new;
+use domain::{
+    gateway::user_auth::UserAuthGateway,
+    model::{
+        primary_user_id::PrimaryUserId,
+        user::{User, UserProfile},
+        user_auth::{AuthUserId, UserAuthData},
+    },
+    repository::{talk_room::TalkRoomRepository, user::UserRepository, RepositoryError},
+};
+use std::sync::Arc;
+
+#[derive(new)]
+pub struct UserIdentityUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+    pub config: Arc<AppConfig>,
+}
+
+impl<R: AdaptersModuleExt> UserIdentityUseCase<R> {
+    /// メールアドレスとパスワード、またはLINEログインのIDトークンでユーザーを認証する
+    /// どの認証プロバイダーでも、紐づいているprimary_user_idのユーザーが返る
+    ///
+    /// # Arguments
+    /// * `source` - 認証に使う認証プロバイダーの情報
+    ///
+    pub async fn authenticate(&self, source: CreateUserIdentity) -> Result<User, UseCaseError> {
+        let user_auth_data = source
+            .into_user_auth_data(&self.config.line)
+            .map_err(UseCaseError::invalid_input)?;
+        match user_auth_data {
+            UserAuthData::Email(email_user_auth) => {
+                let auth_id = email_user_auth.auth_id.0.clone();
+                let user = self
+                    .adapters
+                    .user_repository()
+                    .get_user(AuthUserId::Email(email_user_auth.auth_id))
+                    .await
+                    .map_err(|err| not_found_to_not_auth_found(err, &auth_id))?;
+                match &user.user_profile {
+                    UserProfile::Email(email_user)
+                        if email_user.password_hash.verify(&email_user_auth.password) =>
+                    {
+                        Ok(user)
+                    }
+                    _ => Err(RepositoryError::NotAuthFound(auth_id).into()),
+                }
+            }
+            UserAuthData::LineLogin(line_login_auth) => {
+                let line_login_user = self
+                    .adapters
+                    .user_auth_gateway()
+                    .verify_line_login_id_token(line_login_auth)
+                    .await?;
+                let auth_id = line_login_user.auth_id.0.clone();
+                Ok(self
+                    .adapters
+                    .user_repository()
+                    .get_user(AuthUserId::LineLogin(line_login_user.auth_id))
+                    .await
+                    .map_err(|err| not_found_to_not_auth_found(err, &auth_id))?)
+            }
+            UserAuthData::Line(line_user_auth) => {
+                Err(RepositoryError::NotAuthFound(line_user_auth.auth_id.0).into())
+            }
+        }
+    }
+
+    /// 既存のユーザーに認証プロバイダーのユーザーを紐づける
+    /// 既に別のユーザーに紐づいている場合は、merge_usersで統合する
+    ///
+    /// # Arguments
+    /// * `primary_user_id` - 紐づけ先のユーザー
+    /// * `source` - 紐づける認証プロバイダーの情報
+    ///
+    pub async fn link_identity(
+        &self,
+        primary_user_id: PrimaryUserId,
+        source: CreateUserIdentity,
+    ) -> Result<User, UseCaseError> {
+        let user_profile = self
+            .adapters
+            .user_auth_gateway()
+            .get_user_profile(
+                source
+                    .into_user_auth_data(&self.config.line)
+                    .map_err(UseCaseError::invalid_input)?,
+            )
+            .await?;
+
+        Ok(self
+            .adapters
+            .user_repository()
+            .link_user(primary_user_id, user_profile)
+            .await?)
+    }
+
+    /// 同じ人が2つのprimary_user_idを持っていたときに、fromをintoに統合する
+    /// talk_roomは両方にあるとどちらを残すか決められないので、統合できない
+    /// talk_roomの付け替えもリポジトリが同じトランザクションで行うので、先に確かめずにConflictで判断する
+    ///
+    /// # Arguments
+    /// * `into` - 残すユーザー
+    /// * `from` - intoに統合して削除するユーザー
+    ///
+    pub async fn merge_users(
+        &self,
+        into: PrimaryUserId,
+        from: PrimaryUserId,
+    ) -> Result<(), UseCaseError> {
+        self.adapters
+            .talk_room_repository()
+            .merge_users(into, from)
+            .await?;
+
+        Ok(())
+    }
+}
+
+// 認証に失敗した理由がユーザーがいないことなのか、パスワードが違うことなのかは区別しない
+fn not_found_to_not_auth_found(err: RepositoryError, auth_id: &str) -> RepositoryError {
+    match err {
+        RepositoryError::NotFound(_, _) => RepositoryError::NotAuthFound(auth_id.to_string()),
+        err => err,
+    }
+}
@@ -0,0 +1,141 @@
+use crate::config::AppConfig;
+use crate::model::user_profile::RefreshedUserProfiles;
+use adapter::module::AdaptersModuleExt;
+use chrono::{Duration, Local};
+use derive_new::new;
+use domain::{
+    gateway::user_auth::UserAuthGateway,
+    model::{
+        line_user::LineUserProfile,
+        user::{User, UserProfile},
+        user_auth::{LineId, LineUserAuthData},
+    },
+    repository::{talk_room::TalkRoomRepository, user::UserRepository, RepositoryError},
+};
+use std::sync::Arc;
+use std::time;
+
+#[derive(new)]
+pub struct UserProfileUseCase<R: AdaptersModuleExt> {
+    pub adapters: Arc<R>,
+    pub config: Arc<AppConfig>,
+}
+
+impl<R: AdaptersModuleExt> UserProfileUseCase<R> {
+    /// 最後にプロフィールを取得してからstale_after以上経ったLINEユーザーのプロフィールを取得し直す
+    ///
+    /// # Arguments
+    /// * `stale_after` - プロフィールを取得し直すまでの期間
+    /// * `limit` - 一度に取得し直すユーザー数の上限
+    ///
+    pub async fn refresh_stale_line_user_profiles(
+        &self,
+        stale_after: time::Duration,
+        limit: i64,
+    ) -> anyhow::Result<RefreshedUserProfiles> {
+        let users = self
+            .adapters
+            .user_repository()
+            .get_line_users_updated_before(Local::now() - Duration::from_std(stale_after)?, limit)
+            .await?;
+
+        let mut refreshed_count = 0;
+        let mut failed_count = 0;
+        for user in users {
+            let UserProfile::Line(line_user_profile) = user.user_profile.clone() else {
+                continue;
+            };
+            let line_user_auth_data = LineUserAuthData::new(
+                line_user_profile.auth_id.clone(),
+                self.config.line.auth_token(),
+            );
+            match self
+                .adapters
+                .user_auth_gateway()
+                .get_line_user_profile(line_user_auth_data)
+                .await
+            {
+                Ok(latest_line_user_profile) => {
+                    save_line_user_profile(&*self.adapters, &user, latest_line_user_profile)
+                        .await?;
+                    refreshed_count += 1;
+                }
+                // レート制限やLINEにつながらないときは、残りのユーザーも取得できないので次の実行に回す
+                // updated_atを更新しないので、次の実行でも取得の対象になる
+                Err(err) if err.is_retryable() => {
+                    failed_count += 1;
+                    break;
+                }
+                /*
+                 * ブロックされているユーザーなどはプロフィールを取得できない
+                 * 毎回同じユーザーで詰まらないように、保存済みのプロフィールのままupdated_atだけ更新する
+                 */
+                Err(_) => {
+                    self.adapters
+                        .user_repository()
+                        .update_line_user(line_user_profile)
+                        .await?;
+                    failed_count += 1;
+                }
+            }
+        }
+
+        Ok(RefreshedUserProfiles::new(refreshed_count, failed_count))
+    }
+
+    /// LINEのユーザーのプロフィールを、期限を待たずにLINEから取得し直す
+    ///
+    /// # Arguments
+    /// * `line_id` - プロフィールを取得し直すLINEのユーザーID
+    ///
+    pub async fn refresh_line_user_profile(&self, line_id: LineId) -> anyhow::Result<User> {
+        let user = self
+            .adapters
+            .user_repository()
+            .get_line_user(line_id.clone())
+            .await?;
+        let latest_line_user_profile = self
+            .adapters
+            .user_auth_gateway()
+            .get_line_user_profile(LineUserAuthData::new(
+                line_id,
+                self.config.line.auth_token(),
+            ))
+            .await?;
+
+        save_line_user_profile(&*self.adapters, &user, latest_line_user_profile).await
+    }
+}
+
+/// LINEから取得したプロフィールを保存し、表示名が変わっていればtalkRoomCardsにも反映する
+///
+/// # Arguments
+/// * `adapters` - アダプター
+/// * `user` - 保存済みのユーザー
+/// * `latest_line_user_profile` - LINEから取得したプロフィール
+///
+pub(crate) async fn save_line_user_profile<R: AdaptersModuleExt>(
+    adapters: &R,
+    user: &User,
+    latest_line_user_profile: LineUserProfile,
+) -> anyhow::Result<User> {
+    let display_name = latest_line_user_profile.display_name.clone();
+    let updated_user = adapters
+        .user_repository()
+        .update_line_user(latest_line_user_profile)
+        .await?;
+    if user.user_profile.display_name() == Some(&display_name) {
+        return Ok(updated_user);
+    }
+
+    match adapters
+        .talk_room_repository()
+        .update_display_name(user.id.clone(), display_name)
+        .await
+    {
+        Ok(()) => Ok(updated_user),
+        // talk_roomがまだなければ、作成時にユーザーの表示名が使われる
+        Err(RepositoryError::NotFound(_, _)) => Ok(updated_user),
+        Err(err) => Err(err.into()),
+    }
+}
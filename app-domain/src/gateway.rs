@@ -1,2 +1,52 @@
+pub mod rich_menu;
 pub mod send_message;
 pub mod user_auth;
+
+use thiserror::Error;
+
+/// LINEなど外部のAPIを呼んだときのエラー
+/// ユースケースは種類で処理を分け、RateLimitedやUnavailableのときはリトライする
+#[derive(Debug, Error)]
+pub enum GatewayError {
+    #[error("NotFound: {0}")]
+    NotFound(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("InvalidInput: {0}")]
+    InvalidInput(String),
+    #[error("Unsupported user auth: {0}")]
+    UnsupportedUserAuth(String),
+    /// retry_afterは、APIがRetry-Afterで待つ秒数を返したときだけ入る
+    #[error("RateLimited, retry after {0:?} seconds")]
+    RateLimited(Option<u64>),
+    /// タイムアウトなど、APIにつながらなかった
+    #[error("Unavailable: {0}")]
+    Unavailable(String),
+    #[error("Request failed with status {0}, body is {1}")]
+    Upstream(u16, String),
+    #[error("Failed to convert response {0} to {1}")]
+    FailedConvertResponse(String, String),
+    #[error("Unexpected Error: {0}")]
+    Unexpected(String),
+}
+
+impl GatewayError {
+    /// 成功しなかったレスポンスのステータスで、エラーの種類を決める
+    pub fn from_status(status: u16, body: String) -> Self {
+        match status {
+            400 | 422 => GatewayError::InvalidInput(body),
+            404 => GatewayError::NotFound(body),
+            409 => GatewayError::Conflict(body),
+            429 => GatewayError::RateLimited(None),
+            _ => GatewayError::Upstream(status, body),
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            GatewayError::RateLimited(_) | GatewayError::Unavailable(_) => true,
+            GatewayError::Upstream(status, _) => *status >= 500,
+            _ => false,
+        }
+    }
+}
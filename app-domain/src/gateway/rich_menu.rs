@@ -0,0 +1,90 @@
+use crate::gateway::GatewayError;
+use crate::model::{
+    rich_menu::{NewRichMenu, RichMenu, RichMenuAlias, RichMenuAliasId, RichMenuId, RichMenuImage},
+    user_auth::{LineAuthToken, LineId},
+};
+use async_trait::async_trait;
+
+#[mockall::automock]
+#[async_trait]
+pub trait RichMenuGateway {
+    async fn create_rich_menu(
+        &self,
+        auth_token: LineAuthToken,
+        source: NewRichMenu,
+    ) -> Result<RichMenu, GatewayError>;
+
+    async fn upload_rich_menu_image(
+        &self,
+        auth_token: LineAuthToken,
+        rich_menu_id: RichMenuId,
+        image: RichMenuImage,
+    ) -> Result<(), GatewayError>;
+
+    async fn get_rich_menu_list(
+        &self,
+        auth_token: LineAuthToken,
+    ) -> Result<Vec<RichMenu>, GatewayError>;
+
+    async fn delete_rich_menu(
+        &self,
+        auth_token: LineAuthToken,
+        rich_menu_id: RichMenuId,
+    ) -> Result<(), GatewayError>;
+
+    async fn set_default_rich_menu(
+        &self,
+        auth_token: LineAuthToken,
+        rich_menu_id: RichMenuId,
+    ) -> Result<(), GatewayError>;
+
+    async fn link_rich_menu_to_user(
+        &self,
+        auth_token: LineAuthToken,
+        line_id: LineId,
+        rich_menu_id: RichMenuId,
+    ) -> Result<(), GatewayError>;
+
+    async fn link_rich_menu_to_users(
+        &self,
+        auth_token: LineAuthToken,
+        line_ids: Vec<LineId>,
+        rich_menu_id: RichMenuId,
+    ) -> Result<(), GatewayError>;
+
+    async fn unlink_rich_menu_from_user(
+        &self,
+        auth_token: LineAuthToken,
+        line_id: LineId,
+    ) -> Result<(), GatewayError>;
+
+    async fn unlink_rich_menu_from_users(
+        &self,
+        auth_token: LineAuthToken,
+        line_ids: Vec<LineId>,
+    ) -> Result<(), GatewayError>;
+
+    async fn get_rich_menu_alias(
+        &self,
+        auth_token: LineAuthToken,
+        alias_id: RichMenuAliasId,
+    ) -> Result<Option<RichMenuAlias>, GatewayError>;
+
+    async fn create_rich_menu_alias(
+        &self,
+        auth_token: LineAuthToken,
+        source: RichMenuAlias,
+    ) -> Result<(), GatewayError>;
+
+    async fn update_rich_menu_alias(
+        &self,
+        auth_token: LineAuthToken,
+        source: RichMenuAlias,
+    ) -> Result<(), GatewayError>;
+
+    async fn delete_rich_menu_alias(
+        &self,
+        auth_token: LineAuthToken,
+        alias_id: RichMenuAliasId,
+    ) -> Result<(), GatewayError>;
+}
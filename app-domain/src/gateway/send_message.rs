@@ -1,3 +1,4 @@
+use crate::gateway::GatewayError;
 use crate::model::{
     message::{
         event::NewEvent,
@@ -15,5 +16,19 @@ pub trait SendMessageGateway {
         user_auth_data: UserAuthData,
         sender: Option<NewSendSender>,
         event: NewEvent,
-    ) -> anyhow::Result<Vec<NewSendMessages>>;
+    ) -> Result<Vec<NewSendMessages>, GatewayError>;
+    async fn send_manual_messages(
+        &self,
+        user_auth_data: UserAuthData,
+        sender: NewSendSender,
+        texts: Vec<String>,
+    ) -> Result<Vec<NewSendMessages>, GatewayError>;
+    /// botがテキストを返信する。返信しきれないメッセージはpushで送る
+    async fn reply_bot_messages(
+        &self,
+        user_auth_data: UserAuthData,
+        reply_token: String,
+        texts: Vec<String>,
+    ) -> Result<Vec<NewSendMessages>, GatewayError>;
+    async fn mark_as_read(&self, user_auth_data: UserAuthData) -> Result<(), GatewayError>;
 }
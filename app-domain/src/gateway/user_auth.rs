@@ -1,17 +1,30 @@
+use crate::gateway::GatewayError;
 use crate::model::{
+    account_link::LineLinkToken,
+    line_login_user::LineLoginUserProfile,
     line_user::LineUserProfile,
     user::UserProfile,
-    user_auth::{LineUserAuthData, UserAuthData},
+    user_auth::{LineLoginAuthData, LineUserAuthData, UserAuthData},
 };
 use async_trait::async_trait;
 
 #[mockall::automock]
 #[async_trait]
 pub trait UserAuthGateway {
-    async fn get_user_profile(&self, source: UserAuthData) -> anyhow::Result<UserProfile>;
+    async fn get_user_profile(&self, source: UserAuthData) -> Result<UserProfile, GatewayError>;
 
     async fn get_line_user_profile(
         &self,
         source: LineUserAuthData,
-    ) -> anyhow::Result<LineUserProfile>;
+    ) -> Result<LineUserProfile, GatewayError>;
+
+    async fn verify_line_login_id_token(
+        &self,
+        source: LineLoginAuthData,
+    ) -> Result<LineLoginUserProfile, GatewayError>;
+
+    async fn issue_line_link_token(
+        &self,
+        source: LineUserAuthData,
+    ) -> Result<LineLinkToken, GatewayError>;
 }
@@ -1,9 +1,22 @@
+pub mod account_link;
+pub mod business_hours;
+pub mod canned_response;
+pub mod email_user;
+pub mod line_login_user;
 pub mod line_user;
 pub mod message;
 pub mod primary_user_id;
+pub mod rich_menu;
+pub mod staff;
 pub mod talk_room;
+pub mod talk_room_card;
+pub mod talk_room_change;
+pub mod talk_room_consistency;
+pub mod talk_room_note;
 pub mod user;
 pub mod user_auth;
+pub mod user_event;
+pub mod user_tag;
 
 use anyhow::anyhow;
 use derive_new::new;
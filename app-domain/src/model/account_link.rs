@@ -0,0 +1,51 @@
+use crate::model::primary_user_id::PrimaryUserId;
+use chrono::{DateTime, Duration, Local};
+use derive_new::new;
+use uuid::Uuid;
+
+// 連携用のURLを開いてからログインを終えるまでの猶予
+const ACCOUNT_LINK_NONCE_TTL_MINUTES: i64 = 10;
+
+/// 会員システムのアカウントのID
+#[derive(new, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExternalMemberId(pub String);
+
+/// LINEのアカウント連携APIで発行される連携トークン
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct LineLinkToken(pub String);
+
+/// 会員システムのログインが終わったときに発行し、accountLinkイベントで照合するnonce
+/// LINEの仕様で10文字以上255文字以下の推測できない文字列である必要がある
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct AccountLinkNonce(pub String);
+
+impl AccountLinkNonce {
+    pub fn gen() -> Self {
+        Self(Uuid::new_v4().simple().to_string())
+    }
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct NewAccountLinkNonce {
+    pub nonce: AccountLinkNonce,
+    pub external_member_id: ExternalMemberId,
+    pub expires_at: DateTime<Local>,
+}
+
+impl NewAccountLinkNonce {
+    /// 会員システムでログインしたアカウントに対して、新しいnonceを発行する
+    pub fn issue(external_member_id: ExternalMemberId) -> Self {
+        Self {
+            nonce: AccountLinkNonce::gen(),
+            external_member_id,
+            expires_at: Local::now() + Duration::minutes(ACCOUNT_LINK_NONCE_TTL_MINUTES),
+        }
+    }
+}
+
+/// accountLinkイベントのnonceを照合して、ユーザーと会員システムのアカウントを紐づける
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct NewAccountLink {
+    pub primary_user_id: PrimaryUserId,
+    pub nonce: AccountLinkNonce,
+}
@@ -0,0 +1,162 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use derive_new::new;
+use std::str::FromStr;
+
+// 営業時間外になった時刻を、何日前まで遡って探すか
+const OFF_HOURS_LOOKBACK_DAYS: i64 = 14;
+
+/// チャネルごとの営業時間
+/// 曜日ごとの営業時間と、祝日や臨時休業などの例外の日を持つ
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct BusinessHoursCalendar {
+    pub channel_id: String,
+    pub time_zone: BusinessHoursTimeZone,
+    pub weekly_hours: Vec<WeeklyBusinessHours>,
+    pub exceptions: Vec<BusinessHoursException>,
+    // 営業時間外にユーザーからメッセージが届いたときに送る文面。Noneのときは送らない
+    pub away_message: Option<String>,
+    pub updated_at: DateTime<Local>,
+}
+
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct NewBusinessHoursCalendar {
+    pub time_zone: BusinessHoursTimeZone,
+    pub weekly_hours: Vec<WeeklyBusinessHours>,
+    pub exceptions: Vec<BusinessHoursException>,
+    pub away_message: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BusinessHoursTimeZone(Tz);
+
+impl BusinessHoursTimeZone {
+    // Asia/Tokyoなど、IANAのタイムゾーン名
+    pub fn name(&self) -> &'static str {
+        self.0.name()
+    }
+}
+
+impl FromStr for BusinessHoursTimeZone {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        s.parse::<Tz>()
+            .map(BusinessHoursTimeZone)
+            .map_err(|err| anyhow!("Unknown time zone {}: {}", s, err))
+    }
+}
+
+/// 開店から閉店まで。日をまたぐ営業時間は扱わない
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BusinessHoursRange {
+    pub open: NaiveTime,
+    pub close: NaiveTime,
+}
+
+impl BusinessHoursRange {
+    pub fn new(open: NaiveTime, close: NaiveTime) -> anyhow::Result<Self> {
+        if open >= close {
+            return Err(anyhow!(
+                "Business hours must close after they open: {} - {}",
+                open,
+                close
+            ));
+        }
+        Ok(BusinessHoursRange { open, close })
+    }
+
+    fn contains(&self, time: NaiveTime) -> bool {
+        self.open <= time && time < self.close
+    }
+}
+
+/// 昼休みがあるときなどは、同じ曜日に複数の営業時間を登録する
+#[derive(new, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WeeklyBusinessHours {
+    pub weekday: Weekday,
+    pub hours: BusinessHoursRange,
+}
+
+/// 曜日の営業時間の代わりに使う日ごとの営業時間
+/// hoursが空のときは休業日
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct BusinessHoursException {
+    pub date: NaiveDate,
+    pub hours: Vec<BusinessHoursRange>,
+}
+
+/// 保存やAPIで使う曜日の名前。mon、tueなど
+pub fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "mon",
+        Weekday::Tue => "tue",
+        Weekday::Wed => "wed",
+        Weekday::Thu => "thu",
+        Weekday::Fri => "fri",
+        Weekday::Sat => "sat",
+        Weekday::Sun => "sun",
+    }
+}
+
+/// ある時点で営業時間内かどうか
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct BusinessHoursState {
+    pub open: bool,
+    // 営業時間外になった時刻。同じ営業時間外の間は同じ値なので、営業時間外の区切りとして使う
+    pub off_hours_since: Option<DateTime<Local>>,
+    pub away_message: Option<String>,
+}
+
+impl BusinessHoursState {
+    /// 営業時間を登録していないチャネルは、常に営業時間内として扱う
+    pub fn always_open() -> Self {
+        BusinessHoursState::new(true, None, None)
+    }
+}
+
+impl BusinessHoursCalendar {
+    /// その日の営業時間。例外の日があれば曜日の営業時間より優先する
+    pub fn hours_on(&self, date: NaiveDate) -> Vec<BusinessHoursRange> {
+        if let Some(exception) = self.exceptions.iter().find(|e| e.date == date) {
+            return exception.hours.clone();
+        }
+        self.weekly_hours
+            .iter()
+            .filter(|w| w.weekday == date.weekday())
+            .map(|w| w.hours)
+            .collect()
+    }
+
+    /// nowが営業時間内かどうかと、営業時間外ならいつから営業時間外かを返す
+    pub fn state(&self, now: DateTime<Local>) -> BusinessHoursState {
+        let tz = self.time_zone.0;
+        let local_now = now.with_timezone(&tz);
+        let today = local_now.date_naive();
+        let open = self
+            .hours_on(today)
+            .iter()
+            .any(|h| h.contains(local_now.time()));
+        if open {
+            return BusinessHoursState::new(true, None, self.away_message.clone());
+        }
+
+        // 直前に閉店した時刻を遡って探す。見つからないときはその日の0時から営業時間外とする
+        let last_close = (0..=OFF_HOURS_LOOKBACK_DAYS)
+            .filter_map(|days| today.checked_sub_signed(Duration::days(days)))
+            .find_map(|date| {
+                self.hours_on(date)
+                    .iter()
+                    .filter_map(|h| tz.from_local_datetime(&date.and_time(h.close)).earliest())
+                    .filter(|close| *close <= local_now)
+                    .max()
+            });
+        let off_hours_since = last_close
+            .or_else(|| {
+                tz.from_local_datetime(&today.and_time(NaiveTime::MIN))
+                    .earliest()
+            })
+            .map(|t| t.with_timezone(&Local));
+        BusinessHoursState::new(false, off_hours_since, self.away_message.clone())
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Local};
+use derive_new::new;
+
+use crate::model::{
+    staff::{Staff, StaffId},
+    talk_room::TalkRoom,
+};
+
+// 定型文で使えるプレースホルダー
+const PLACEHOLDERS: [&str; 3] = ["display_name", "nickname", "staff_name"];
+
+#[derive(new, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CannedResponseId(pub i64);
+
+/// スタッフ全員で使う定型文
+/// 本文の{display_name}などのプレースホルダーは、送るときに置き換える
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct CannedResponse {
+    pub id: CannedResponseId,
+    pub title: String,
+    pub body: String,
+    pub created_by: StaffId,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+impl CannedResponse {
+    /// プレースホルダーを、送る相手とスタッフの値に置き換える
+    /// 表示名などに{nickname}のような文字が入っていても置き換えないように、本文を1回だけ読み進める
+    pub fn render(&self, context: &CannedResponseContext) -> String {
+        let mut rendered = String::with_capacity(self.body.len());
+        let mut rest = self.body.as_str();
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            let placeholder = rest[start..]
+                .find('}')
+                .map(|end| &rest[start + 1..start + end])
+                .filter(|placeholder| PLACEHOLDERS.contains(placeholder));
+            match placeholder {
+                Some(placeholder) => {
+                    rendered.push_str(context.value(placeholder));
+                    rest = &rest[start + placeholder.len() + 2..];
+                }
+                // プレースホルダーでなければ、そのまま残す
+                None => {
+                    rendered.push('{');
+                    rest = &rest[start + 1..];
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+
+    /// 本文に使えないプレースホルダーがないかを確かめる
+    pub fn validate_body(body: &str) -> anyhow::Result<()> {
+        let mut rest = body;
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else {
+                return Err(anyhow!("Unclosed placeholder in canned response"));
+            };
+            let placeholder = &rest[start + 1..start + end];
+            if !PLACEHOLDERS.contains(&placeholder) {
+                return Err(anyhow!("Unknown placeholder: {{{}}}", placeholder));
+            }
+            rest = &rest[start + end + 1..];
+        }
+        Ok(())
+    }
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct NewCannedResponse {
+    pub title: String,
+    pub body: String,
+    pub created_by: StaffId,
+}
+
+/// プレースホルダーに入れる値
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct CannedResponseContext {
+    // LINEの表示名
+    pub display_name: String,
+    // スタッフが付けた呼び名。ないときは表示名を使う
+    pub nickname: Option<String>,
+    // 送るスタッフの名前
+    pub staff_name: String,
+}
+
+impl CannedResponseContext {
+    fn value(&self, placeholder: &str) -> &str {
+        match placeholder {
+            "display_name" => &self.display_name,
+            "nickname" => self.nickname.as_deref().unwrap_or(&self.display_name),
+            "staff_name" => &self.staff_name,
+            _ => "",
+        }
+    }
+}
+
+impl From<(&TalkRoom, &Staff)> for CannedResponseContext {
+    fn from((talk_room, staff): (&TalkRoom, &Staff)) -> Self {
+        CannedResponseContext::new(
+            talk_room.display_name.clone(),
+            talk_room.nickname.clone(),
+            staff.name.clone(),
+        )
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::anyhow;
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use derive_new::new;
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct EmailAddress(pub String);
+
+impl TryFrom<String> for EmailAddress {
+    type Error = anyhow::Error;
+    // 厳密なチェックは確認メールなどで行う前提で、ここでは最低限の形式だけ確認する
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        let email = value.trim().to_lowercase();
+        match email.split_once('@') {
+            Some((local, domain)) if !local.is_empty() && domain.contains('.') => {
+                Ok(EmailAddress::new(email))
+            }
+            _ => Err(anyhow!("Invalid email address: {}", value)),
+        }
+    }
+}
+
+// argon2でハッシュ化したパスワード。PHC文字列形式で保存する
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct PasswordHash(pub String);
+
+impl PasswordHash {
+    pub fn from_plain(password: &str) -> anyhow::Result<Self> {
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|err| anyhow!("Failed to hash password: {}", err))?;
+        Ok(PasswordHash::new(password_hash.to_string()))
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        argon2::PasswordHash::new(&self.0)
+            .map(|parsed_hash| {
+                Argon2::default()
+                    .verify_password(password.as_bytes(), &parsed_hash)
+                    .is_ok()
+            })
+            .unwrap_or(false)
+    }
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct EmailUserProfile {
+    pub auth_id: EmailAddress,
+    pub password_hash: PasswordHash,
+}
@@ -0,0 +1,13 @@
+use derive_new::new;
+
+// LINEログインのIDトークンのsub。Messaging APIと同じプロバイダーのチャネルであればLINEのユーザーIDと一致する
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct LineLoginId(pub String);
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct LineLoginUserProfile {
+    pub auth_id: LineLoginId,
+    pub display_name: String,
+    pub picture_url: String,
+    pub email: Option<String>,
+}
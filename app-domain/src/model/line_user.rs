@@ -7,4 +7,6 @@ pub struct LineUserProfile {
     pub auth_id: LineId,
     pub display_name: String,
     pub picture_url: String,
+    pub status_message: Option<String>,
+    pub language: Option<String>,
 }
@@ -1,15 +1,39 @@
+use chrono::{DateTime, Local};
+use derive_new::new;
+
 use crate::model::message::{
     event::{Event, NewEvent},
     send_message::{NewSendMessages, SendMessages},
+    system_event::SystemEvent,
 };
 
 pub mod event;
 pub mod send_message;
+pub mod system_event;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Messages {
     Event(Event),
     SendMessages(SendMessages),
+    SystemEvent(SystemEvent),
+}
+
+impl Messages {
+    // messagesのドキュメントのID
+    pub fn id(&self) -> String {
+        match self {
+            Messages::Event(e) => e.id().value.to_string(),
+            Messages::SendMessages(m) => m.id.value.to_string(),
+            Messages::SystemEvent(s) => s.id.value.to_string(),
+        }
+    }
+    pub fn created_at(&self) -> &DateTime<Local> {
+        match self {
+            Messages::Event(e) => e.created_at(),
+            Messages::SendMessages(m) => m.messages[0].created_at(),
+            Messages::SystemEvent(s) => &s.created_at,
+        }
+    }
 }
 
 // talkRoomのupdate時にも使う
@@ -18,3 +42,35 @@ pub enum NewMessages {
     Event(NewEvent),
     SendMessages(NewSendMessages),
 }
+
+impl NewMessages {
+    // 未読数に数えるユーザーからのメッセージかどうか
+    pub fn is_user_message(&self) -> bool {
+        match self {
+            NewMessages::Event(e) => e.is_user_message(),
+            NewMessages::SendMessages(_) => false,
+        }
+    }
+}
+
+/// talkRoomのメッセージ履歴の続きを取得するためのカーソル
+/// 履歴は作成日時とドキュメントのIDの降順に並べるので、最後のメッセージのそれらの値を持つ
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct MessageCursor {
+    pub created_at: DateTime<Local>,
+    pub id: String,
+}
+
+impl From<&Messages> for MessageCursor {
+    fn from(m: &Messages) -> Self {
+        MessageCursor::new(*m.created_at(), m.id())
+    }
+}
+
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct MessagesPage {
+    // 新しい順
+    pub messages: Vec<Messages>,
+    // 続きがないときはNone
+    pub next_cursor: Option<MessageCursor>,
+}
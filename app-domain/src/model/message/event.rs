@@ -4,6 +4,9 @@ use rust_decimal::Decimal;
 
 use crate::model::Id;
 
+// リッチメニューの「薬剤師と話す」ボタンが送るpostbackのdata
+pub const HANDOFF_POSTBACK_DATA: &str = "action=talk_to_pharmacist";
+
 #[derive(new, Clone, Debug, PartialEq, Eq)]
 pub enum Event {
     Follow(EventFollow),
@@ -11,6 +14,30 @@ pub enum Event {
     Postback(EventPostback),
     VideoPlayComplete(EventVideoPlayComplete),
     Message(EventMessage),
+    AccountLink(EventAccountLink),
+}
+
+impl Event {
+    pub fn id(&self) -> &Id<Event> {
+        match self {
+            Event::Follow(e) => &e.id,
+            Event::Unfollow(e) => &e.id,
+            Event::Postback(e) => &e.id,
+            Event::VideoPlayComplete(e) => &e.id,
+            Event::Message(e) => &e.id,
+            Event::AccountLink(e) => &e.id,
+        }
+    }
+    pub fn created_at(&self) -> &DateTime<Local> {
+        match self {
+            Event::Follow(e) => &e.created_at,
+            Event::Unfollow(e) => &e.created_at,
+            Event::Postback(e) => &e.created_at,
+            Event::VideoPlayComplete(e) => &e.created_at,
+            Event::Message(e) => &e.created_at,
+            Event::AccountLink(e) => &e.created_at,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -65,6 +92,18 @@ pub struct EventMessage {
     pub created_at: DateTime<Local>,
 }
 
+// 連携に失敗したときはreplyTokenが含まれない
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct EventAccountLink {
+    pub id: Id<Event>,
+    pub reply_token: Option<String>,
+    pub delivery_context: EventDeliveryContext,
+    pub link: EventAccountLinkContent,
+    pub mode: String,
+    pub webhook_event_id: String,
+    pub created_at: DateTime<Local>,
+}
+
 #[derive(new, Clone, Debug, PartialEq, Eq)]
 pub struct EventDeliveryContext {
     pub is_redelivery: bool,
@@ -73,7 +112,7 @@ pub struct EventDeliveryContext {
 #[derive(new, Clone, Debug, PartialEq, Eq)]
 pub struct EventPostbackContent {
     pub data: String,
-    pub params: EventPostbackParams,
+    pub params: Option<EventPostbackParams>,
 }
 
 #[derive(new, Clone, Debug, PartialEq, Eq)]
@@ -100,6 +139,18 @@ pub struct EventVideoPlayCompleteContent {
     pub tracking_id: String,
 }
 
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct EventAccountLinkContent {
+    pub result: EventAccountLinkResult,
+    pub nonce: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EventAccountLinkResult {
+    Ok,
+    Failed,
+}
+
 #[derive(new, Clone, Debug, PartialEq, Eq)]
 pub enum EventMessageContent {
     Text(EventMessageContentText),
@@ -111,6 +162,21 @@ pub enum EventMessageContent {
     Sticker(EventMessageContentSticker),
 }
 
+impl EventMessageContent {
+    // LINEのメッセージID
+    pub fn id(&self) -> &String {
+        match self {
+            EventMessageContent::Text(m) => &m.id,
+            EventMessageContent::Image(m) => &m.id,
+            EventMessageContent::Video(m) => &m.id,
+            EventMessageContent::Audio(m) => &m.id,
+            EventMessageContent::File(m) => &m.id,
+            EventMessageContent::Location(m) => &m.id,
+            EventMessageContent::Sticker(m) => &m.id,
+        }
+    }
+}
+
 #[derive(new, Clone, Debug, PartialEq, Eq)]
 pub struct EventMessageContentText {
     pub id: String,
@@ -211,6 +277,7 @@ pub enum NewEvent {
     Postback(NewEventPostback),
     VideoPlayComplete(NewEventVideoPlayComplete),
     Message(NewEventMessage),
+    AccountLink(NewEventAccountLink),
 }
 
 impl NewEvent {
@@ -221,6 +288,7 @@ impl NewEvent {
             NewEvent::Postback(e) => &e.id,
             NewEvent::VideoPlayComplete(e) => &e.id,
             NewEvent::Message(e) => &e.id,
+            NewEvent::AccountLink(e) => &e.id,
         }
     }
     pub fn created_at(&self) -> &DateTime<Local> {
@@ -230,6 +298,7 @@ impl NewEvent {
             NewEvent::Postback(e) => &e.created_at,
             NewEvent::VideoPlayComplete(e) => &e.created_at,
             NewEvent::Message(e) => &e.created_at,
+            NewEvent::AccountLink(e) => &e.created_at,
         }
     }
     pub fn follow(&self) -> bool {
@@ -238,6 +307,14 @@ impl NewEvent {
             _ => true,
         }
     }
+    // スタッフの返信が必要なユーザーからのメッセージかどうか
+    pub fn is_user_message(&self) -> bool {
+        matches!(self, NewEvent::Message(_))
+    }
+    // ユーザーが薬剤師との会話を求めたかどうか
+    pub fn is_handoff_request(&self) -> bool {
+        matches!(self, NewEvent::Postback(e) if e.postback.data == HANDOFF_POSTBACK_DATA)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -292,6 +369,17 @@ pub struct NewEventMessage {
     pub created_at: DateTime<Local>,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewEventAccountLink {
+    pub id: Id<Event>,
+    pub reply_token: Option<String>,
+    pub delivery_context: NewEventDeliveryContext,
+    pub link: NewEventAccountLinkContent,
+    pub mode: String,
+    pub webhook_event_id: String,
+    pub created_at: DateTime<Local>,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct NewEventDeliveryContext {
     pub is_redelivery: bool,
@@ -300,7 +388,7 @@ pub struct NewEventDeliveryContext {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct NewEventPostbackContent {
     pub data: String,
-    pub params: NewEventPostbackParams,
+    pub params: Option<NewEventPostbackParams>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -327,6 +415,18 @@ pub struct NewEventVideoPlayCompleteContent {
     pub tracking_id: String,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewEventAccountLinkContent {
+    pub result: NewEventAccountLinkResult,
+    pub nonce: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NewEventAccountLinkResult {
+    Ok,
+    Failed,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum NewEventMessageContent {
     Text(NewEventMessageContentText),
@@ -51,6 +51,34 @@ pub enum SendMessage {
     Template(SendTemplateMessage),
 }
 
+impl SendMessage {
+    // LINEのメッセージID
+    pub fn message_id(&self) -> &String {
+        match self {
+            SendMessage::Text(s) => &s.message_id,
+            SendMessage::Sticker(s) => &s.message_id,
+            SendMessage::Image(s) => &s.message_id,
+            SendMessage::Video(s) => &s.message_id,
+            SendMessage::Audio(s) => &s.message_id,
+            SendMessage::Location(s) => &s.message_id,
+            SendMessage::Imagemap(s) => &s.message_id,
+            SendMessage::Template(s) => &s.message_id,
+        }
+    }
+    pub fn created_at(&self) -> &DateTime<Local> {
+        match self {
+            SendMessage::Text(s) => &s.created_at,
+            SendMessage::Sticker(s) => &s.created_at,
+            SendMessage::Image(s) => &s.created_at,
+            SendMessage::Video(s) => &s.created_at,
+            SendMessage::Audio(s) => &s.created_at,
+            SendMessage::Location(s) => &s.created_at,
+            SendMessage::Imagemap(s) => &s.created_at,
+            SendMessage::Template(s) => &s.created_at,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SendMessageText {
     pub message_id: String,
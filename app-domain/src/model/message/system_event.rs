@@ -0,0 +1,64 @@
+use chrono::{DateTime, Local};
+use derive_new::new;
+
+use crate::model::{
+    staff::StaffId,
+    talk_room::{TalkRoomMode, TalkRoomStatus},
+    Id,
+};
+
+/// スタッフの対応の履歴として、talkRoomのmessagesに残すイベント
+/// ユーザーには送らず、talkRoomの最新メッセージにもしない
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct SystemEvent {
+    pub id: Id<SystemEvent>,
+    // 自動で変更したときはNone
+    pub operator: Option<StaffId>,
+    pub content: SystemEventContent,
+    pub created_at: DateTime<Local>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SystemEventContent {
+    Assigned(SystemEventAssigned),
+    Unassigned(SystemEventUnassigned),
+    StatusChanged(SystemEventStatusChanged),
+    ModeChanged(SystemEventModeChanged),
+}
+
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct SystemEventAssigned {
+    pub assignee: StaffId,
+    pub previous_assignee: Option<StaffId>,
+}
+
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct SystemEventUnassigned {
+    pub previous_assignee: StaffId,
+}
+
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct SystemEventStatusChanged {
+    pub from: TalkRoomStatus,
+    pub to: TalkRoomStatus,
+}
+
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct SystemEventModeChanged {
+    pub from: TalkRoomMode,
+    pub to: TalkRoomMode,
+}
+
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct NewSystemEvent {
+    pub id: Id<SystemEvent>,
+    pub operator: Option<StaffId>,
+    pub content: SystemEventContent,
+    pub created_at: DateTime<Local>,
+}
+
+impl NewSystemEvent {
+    pub fn gen(operator: Option<StaffId>, content: SystemEventContent) -> Self {
+        NewSystemEvent::new(Id::gen(), operator, content, Local::now())
+    }
+}
@@ -0,0 +1,140 @@
+use derive_new::new;
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct RichMenuId(pub String);
+
+#[derive(new, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RichMenuAliasId(pub String);
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct RichMenu {
+    pub id: RichMenuId,
+    pub size: RichMenuSize,
+    pub selected: bool,
+    pub name: String,
+    pub chat_bar_text: String,
+    pub areas: Vec<RichMenuArea>,
+}
+
+// LINEにはidがない状態で作成を依頼し、レスポンスでidが払い出される
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct NewRichMenu {
+    pub size: RichMenuSize,
+    pub selected: bool,
+    pub name: String,
+    pub chat_bar_text: String,
+    pub areas: Vec<RichMenuArea>,
+}
+
+impl NewRichMenu {
+    pub fn into_rich_menu(self, id: RichMenuId) -> RichMenu {
+        RichMenu::new(
+            id,
+            self.size,
+            self.selected,
+            self.name,
+            self.chat_bar_text,
+            self.areas,
+        )
+    }
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct RichMenuSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct RichMenuArea {
+    pub bounds: RichMenuBounds,
+    pub action: RichMenuAction,
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct RichMenuBounds {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// リッチメニューで使えるアクションはテンプレートメッセージより少ない
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RichMenuAction {
+    Postback(RichMenuPostbackAction),
+    Message(RichMenuMessageAction),
+    Uri(RichMenuUriAction),
+    Datetimepicker(RichMenuDatetimepickerAction),
+    Richmenuswitch(RichMenuRichmenuswitchAction),
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct RichMenuPostbackAction {
+    pub label: Option<String>,
+    pub data: String,
+    pub display_text: Option<String>,
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct RichMenuMessageAction {
+    pub label: Option<String>,
+    pub text: String,
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct RichMenuUriAction {
+    pub label: Option<String>,
+    pub uri: String,
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct RichMenuDatetimepickerAction {
+    pub label: Option<String>,
+    pub data: String,
+    pub mode: RichMenuDatetimepickerMode,
+    pub initial: Option<String>,
+    pub max: Option<String>,
+    pub min: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RichMenuDatetimepickerMode {
+    Date,
+    Time,
+    Datetime,
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct RichMenuRichmenuswitchAction {
+    pub label: Option<String>,
+    pub rich_menu_alias_id: RichMenuAliasId,
+    pub data: String,
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct RichMenuAlias {
+    pub alias_id: RichMenuAliasId,
+    pub rich_menu_id: RichMenuId,
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct RichMenuImage {
+    pub content_type: RichMenuImageContentType,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RichMenuImageContentType {
+    Png,
+    Jpeg,
+}
+
+impl RichMenuImageContentType {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            RichMenuImageContentType::Png => "image/png",
+            RichMenuImageContentType::Jpeg => "image/jpeg",
+        }
+    }
+}
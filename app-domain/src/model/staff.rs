@@ -0,0 +1,130 @@
+use crate::model::{
+    email_user::{EmailAddress, PasswordHash},
+    message::send_message::{NewSendSender, NewSendSenderRole},
+};
+use anyhow::anyhow;
+use derive_new::new;
+use std::str::FromStr;
+
+#[derive(new, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StaffId(pub i64);
+
+/// 薬局のスタッフ(オペレーター)
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct Staff {
+    pub id: StaffId,
+    pub name: String,
+    pub email: EmailAddress,
+    pub picture_url: String,
+    pub role: StaffRole,
+    pub password_hash: PasswordHash,
+}
+
+impl Staff {
+    pub fn can(&self, permission: StaffPermission) -> bool {
+        self.role.can(permission)
+    }
+
+    /// talkRoomを見てよいか
+    /// 担当者がいないtalkRoomと自分が担当しているtalkRoomは見られる。ほかのスタッフが担当しているものは、ViewAllTalkRoomsが必要
+    ///
+    /// # Arguments
+    /// * `assignee` - talkRoomの担当者
+    ///
+    pub fn can_view_talk_room(&self, assignee: Option<StaffId>) -> bool {
+        if !self.can(StaffPermission::ViewTalkRooms) {
+            return false;
+        }
+        match assignee {
+            None => true,
+            Some(assignee) => assignee == self.id || self.can(StaffPermission::ViewAllTalkRooms),
+        }
+    }
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct NewStaff {
+    pub name: String,
+    pub email: EmailAddress,
+    pub picture_url: String,
+    pub role: StaffRole,
+    pub password_hash: PasswordHash,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StaffRole {
+    Admin,
+    Pharmacist,
+    Viewer,
+}
+
+impl StaffRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StaffRole::Admin => "admin",
+            StaffRole::Pharmacist => "pharmacist",
+            StaffRole::Viewer => "viewer",
+        }
+    }
+
+    /// ロールごとにできる操作
+    /// adminはすべて、pharmacistはユーザーとのやりとり、viewerは閲覧だけできる
+    pub fn can(&self, permission: StaffPermission) -> bool {
+        match self {
+            StaffRole::Admin => true,
+            StaffRole::Pharmacist => matches!(
+                permission,
+                StaffPermission::ViewTalkRooms
+                    | StaffPermission::SendMessages
+                    | StaffPermission::HandleTalkRooms
+                    | StaffPermission::ManageUsers
+            ),
+            StaffRole::Viewer => matches!(permission, StaffPermission::ViewTalkRooms),
+        }
+    }
+}
+
+impl FromStr for StaffRole {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "admin" => Ok(StaffRole::Admin),
+            "pharmacist" => Ok(StaffRole::Pharmacist),
+            "viewer" => Ok(StaffRole::Viewer),
+            _ => Err(anyhow!("Unknown staff role: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaffPermission {
+    // トークルームやメッセージを見る
+    ViewTalkRooms,
+    // ほかのスタッフが担当しているトークルームも見る
+    ViewAllTalkRooms,
+    // ユーザーにメッセージを送る
+    SendMessages,
+    // トークルームの担当者やステータスを変更する
+    HandleTalkRooms,
+    // ユーザーのタグや認証プロバイダー、アカウント連携を変更する
+    ManageUsers,
+    // リッチメニューを作成、割り当てる
+    ManageRichMenus,
+    // スタッフを作成し、ロールを変更する
+    ManageStaffs,
+    // 営業時間と不在メッセージを変更する
+    ManageBusinessHours,
+}
+
+// 手動で送るメッセージの送信者は、ログインしているスタッフから作る
+impl From<Staff> for NewSendSender {
+    fn from(s: Staff) -> Self {
+        NewSendSender {
+            id: s.id.0,
+            name: s.name,
+            picture_url: s.picture_url,
+            email: s.email.0,
+            sender_role: NewSendSenderRole::Sender,
+        }
+    }
+}
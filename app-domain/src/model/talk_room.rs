@@ -1,9 +1,20 @@
+use anyhow::anyhow;
 use chrono::{DateTime, Local};
 use derive_new::new;
+use std::str::FromStr;
 
 use crate::model::{
-    message::{event::NewEvent, send_message::NewSendMessages, Messages, NewMessages},
+    message::{
+        event::NewEvent,
+        send_message::{NewSendMessages, NewSendSendingType},
+        system_event::{
+            NewSystemEvent, SystemEventAssigned, SystemEventContent, SystemEventModeChanged,
+            SystemEventStatusChanged, SystemEventUnassigned,
+        },
+        Messages, NewMessages,
+    },
     primary_user_id::PrimaryUserId,
+    staff::StaffId,
     user::User,
     Id,
 };
@@ -21,6 +32,19 @@ pub struct TalkRoom {
     pub sort_time: DateTime<Local>,
     pub created_at: DateTime<Local>,
     pub updated_at: DateTime<Local>,
+    #[new(default)]
+    pub assignee: Option<StaffId>,
+    #[new(default)]
+    pub status: TalkRoomStatus,
+    // 最後のメッセージがユーザーからで、スタッフがまだ返信していない
+    #[new(default)]
+    pub unread: bool,
+    // スタッフが付けた呼び名。LINEの表示名より優先して表示する
+    #[new(default)]
+    pub nickname: Option<String>,
+    // botが自動で返信するかどうか
+    #[new(default)]
+    pub mode: TalkRoomMode,
 }
 
 // talkRoomのupdate時にも使う
@@ -37,6 +61,19 @@ pub struct NewTalkRoom {
     pub sort_time: DateTime<Local>,
     pub created_at: DateTime<Local>,
     pub updated_at: DateTime<Local>,
+    // 担当者とステータスはupdate_workflowでだけ書き込み、メッセージの追加では上書きしない
+    #[new(default)]
+    pub assignee: Option<StaffId>,
+    #[new(default)]
+    pub status: TalkRoomStatus,
+    #[new(default)]
+    pub unread: bool,
+    // ピン留め、rsvp、呼び名はスタッフの操作でだけ書き込み、メッセージの追加では上書きしない
+    #[new(default)]
+    pub nickname: Option<String>,
+    // モードはupdate_workflowでだけ書き込む
+    #[new(default)]
+    pub mode: TalkRoomMode,
 }
 
 impl From<(User, NewEvent)> for NewTalkRoom {
@@ -51,19 +88,23 @@ impl From<(User, NewEvent)> for NewTalkRoom {
         let new_event = s.1;
         let event_created_at = *new_event.created_at();
         let follow = new_event.follow();
-        NewTalkRoom::new(
-            Id::gen(),
-            primary_user_id,
-            display_name.clone(),
-            false,
-            false,
-            follow,
-            NewMessages::Event(new_event),
-            event_created_at,
-            event_created_at,
-            event_created_at,
-            event_created_at,
-        )
+        let unread = new_event.is_user_message();
+        NewTalkRoom {
+            unread,
+            ..NewTalkRoom::new(
+                Id::gen(),
+                primary_user_id,
+                display_name.clone(),
+                false,
+                false,
+                follow,
+                NewMessages::Event(new_event),
+                event_created_at,
+                event_created_at,
+                event_created_at,
+                event_created_at,
+            )
+        }
     }
 }
 
@@ -73,19 +114,27 @@ impl From<(TalkRoom, NewEvent)> for NewTalkRoom {
         let new_event = s.1;
         let event_created_at = *new_event.created_at();
         let follow = new_event.follow();
-        NewTalkRoom::new(
-            talk_room.id,
-            talk_room.primary_user_id,
-            talk_room.display_name,
-            talk_room.rsvp,
-            talk_room.pinned,
-            follow,
-            NewMessages::Event(new_event),
-            event_created_at,
-            talk_room.sort_time,
-            talk_room.created_at,
-            event_created_at,
-        )
+        let unread = talk_room.unread || new_event.is_user_message();
+        NewTalkRoom {
+            assignee: talk_room.assignee,
+            status: talk_room.status,
+            unread,
+            nickname: talk_room.nickname,
+            mode: talk_room.mode,
+            ..NewTalkRoom::new(
+                talk_room.id,
+                talk_room.primary_user_id,
+                talk_room.display_name,
+                talk_room.rsvp,
+                talk_room.pinned,
+                follow,
+                NewMessages::Event(new_event),
+                event_created_at,
+                talk_room.sort_time,
+                talk_room.created_at,
+                event_created_at,
+            )
+        }
     }
 }
 
@@ -95,18 +144,277 @@ impl From<(TalkRoom, NewSendMessages)> for NewTalkRoom {
         let new_send_messages = s.1;
         // send_messagesはすべてのsend_messageのcreated_atが同じ
         let send_messages_created_at = *new_send_messages.messages[0].created_at();
-        NewTalkRoom::new(
-            talk_room.id,
-            talk_room.primary_user_id,
-            talk_room.display_name,
-            talk_room.rsvp,
-            talk_room.pinned,
-            talk_room.follow,
-            NewMessages::SendMessages(new_send_messages),
-            send_messages_created_at,
-            talk_room.sort_time,
-            talk_room.created_at,
-            send_messages_created_at,
+        // スタッフが返信したら未読ではなくなる。botの自動返信では変えない
+        let unread =
+            talk_room.unread && new_send_messages.sending_type != NewSendSendingType::Manual;
+        NewTalkRoom {
+            assignee: talk_room.assignee,
+            status: talk_room.status,
+            unread,
+            nickname: talk_room.nickname,
+            mode: talk_room.mode,
+            ..NewTalkRoom::new(
+                talk_room.id,
+                talk_room.primary_user_id,
+                talk_room.display_name,
+                talk_room.rsvp,
+                talk_room.pinned,
+                talk_room.follow,
+                NewMessages::SendMessages(new_send_messages),
+                send_messages_created_at,
+                talk_room.sort_time,
+                talk_room.created_at,
+                send_messages_created_at,
+            )
+        }
+    }
+}
+
+/// talkRoomの対応状況
+/// ユーザーからメッセージが届くと、pendingやresolvedからopenに戻る
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TalkRoomStatus {
+    #[default]
+    Open,
+    Pending,
+    Resolved,
+}
+
+impl TalkRoomStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TalkRoomStatus::Open => "open",
+            TalkRoomStatus::Pending => "pending",
+            TalkRoomStatus::Resolved => "resolved",
+        }
+    }
+}
+
+impl FromStr for TalkRoomStatus {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "open" => Ok(TalkRoomStatus::Open),
+            "pending" => Ok(TalkRoomStatus::Pending),
+            "resolved" => Ok(TalkRoomStatus::Resolved),
+            _ => Err(anyhow!("Unknown talk room status: {}", s)),
+        }
+    }
+}
+
+/// botが自動で返信するかどうか
+/// 薬剤師が対応を引き継いだtalkRoomでは、botは返信しない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TalkRoomMode {
+    // botが常に返信する
+    #[default]
+    Bot,
+    // スタッフが対応するので、botは返信しない
+    Human,
+    // 営業時間内はスタッフが対応し、営業時間外だけbotが返信する
+    BotOutsideBusinessHours,
+}
+
+impl TalkRoomMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TalkRoomMode::Bot => "bot",
+            TalkRoomMode::Human => "human",
+            TalkRoomMode::BotOutsideBusinessHours => "botOutsideBusinessHours",
+        }
+    }
+
+    /// botが自動で返信してよいか
+    /// 自動返信するときは、必ずこれを確かめる
+    ///
+    /// # Arguments
+    /// * `within_business_hours` - 今が営業時間内かどうか
+    ///
+    pub fn bot_replies(&self, within_business_hours: bool) -> bool {
+        match self {
+            TalkRoomMode::Bot => true,
+            TalkRoomMode::Human => false,
+            TalkRoomMode::BotOutsideBusinessHours => !within_business_hours,
+        }
+    }
+}
+
+impl FromStr for TalkRoomMode {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "bot" => Ok(TalkRoomMode::Bot),
+            "human" => Ok(TalkRoomMode::Human),
+            "botOutsideBusinessHours" => Ok(TalkRoomMode::BotOutsideBusinessHours),
+            _ => Err(anyhow!("Unknown talk room mode: {}", s)),
+        }
+    }
+}
+
+/// 担当者、ステータス、モードの変更
+/// 変更の履歴はsystem_eventsとしてtalkRoomのmessagesに残す
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct NewTalkRoomWorkflow {
+    pub id: Id<TalkRoom>,
+    pub assignee: Option<StaffId>,
+    pub status: TalkRoomStatus,
+    pub mode: TalkRoomMode,
+    pub system_events: Vec<NewSystemEvent>,
+    pub updated_at: DateTime<Local>,
+}
+
+/// NewTalkRoomWorkflowのうち、変更した項目
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TalkRoomWorkflowFields {
+    pub assignee: bool,
+    pub status: bool,
+    pub mode: bool,
+}
+
+impl NewTalkRoomWorkflow {
+    /// system_eventsから、変更した項目を返す
+    /// 変更していない項目は読み込んだときの値なので、書き込むと同時に行われたほかの変更を上書きしてしまう
+    pub fn changed_fields(&self) -> TalkRoomWorkflowFields {
+        self.system_events
+            .iter()
+            .fold(TalkRoomWorkflowFields::default(), |fields, e| {
+                match e.content {
+                    SystemEventContent::Assigned(_) | SystemEventContent::Unassigned(_) => {
+                        TalkRoomWorkflowFields {
+                            assignee: true,
+                            ..fields
+                        }
+                    }
+                    SystemEventContent::StatusChanged(_) => TalkRoomWorkflowFields {
+                        status: true,
+                        ..fields
+                    },
+                    SystemEventContent::ModeChanged(_) => TalkRoomWorkflowFields {
+                        mode: true,
+                        ..fields
+                    },
+                }
+            })
+    }
+}
+
+impl TalkRoom {
+    /// 担当者を変更する。同じ担当者のときは何もしないのでNoneを返す
+    pub fn assign(&self, operator: StaffId, assignee: StaffId) -> Option<NewTalkRoomWorkflow> {
+        if self.assignee == Some(assignee) {
+            return None;
+        }
+        let system_event = NewSystemEvent::gen(
+            Some(operator),
+            SystemEventContent::Assigned(SystemEventAssigned::new(assignee, self.assignee)),
+        );
+        Some(self.workflow(Some(assignee), self.status, system_event))
+    }
+
+    /// 担当者を外す。担当者がいないときは何もしないのでNoneを返す
+    pub fn unassign(&self, operator: StaffId) -> Option<NewTalkRoomWorkflow> {
+        let previous_assignee = self.assignee?;
+        let system_event = NewSystemEvent::gen(
+            Some(operator),
+            SystemEventContent::Unassigned(SystemEventUnassigned::new(previous_assignee)),
+        );
+        Some(self.workflow(None, self.status, system_event))
+    }
+
+    /// ステータスを変更する。同じステータスのときは何もしないのでNoneを返す
+    ///
+    /// # Arguments
+    /// * `operator` - 変更したスタッフ。自動で変更したときはNone
+    /// * `status` - 変更後のステータス
+    ///
+    pub fn change_status(
+        &self,
+        operator: Option<StaffId>,
+        status: TalkRoomStatus,
+    ) -> Option<NewTalkRoomWorkflow> {
+        if self.status == status {
+            return None;
+        }
+        let system_event = NewSystemEvent::gen(
+            operator,
+            SystemEventContent::StatusChanged(SystemEventStatusChanged::new(self.status, status)),
+        );
+        Some(self.workflow(self.assignee, status, system_event))
+    }
+
+    /// ユーザーからメッセージが届いたら、対応が終わったtalkRoomでもopenに戻す
+    pub fn reopen(&self, new_event: &NewEvent) -> Option<NewTalkRoomWorkflow> {
+        if new_event.is_user_message() {
+            self.change_status(None, TalkRoomStatus::Open)
+        } else {
+            None
+        }
+    }
+
+    /// モードを変更する。同じモードのときは何もしないのでNoneを返す
+    ///
+    /// # Arguments
+    /// * `operator` - 変更したスタッフ。ユーザーが薬剤師との会話を求めたときはNone
+    /// * `mode` - 変更後のモード
+    ///
+    pub fn change_mode(
+        &self,
+        operator: Option<StaffId>,
+        mode: TalkRoomMode,
+    ) -> Option<NewTalkRoomWorkflow> {
+        if self.mode == mode {
+            return None;
+        }
+        let system_event = NewSystemEvent::gen(
+            operator,
+            SystemEventContent::ModeChanged(SystemEventModeChanged::new(self.mode, mode)),
+        );
+        Some(NewTalkRoomWorkflow {
+            mode,
+            ..self.workflow(self.assignee, self.status, system_event)
+        })
+    }
+
+    /// ユーザーが薬剤師との会話を求めたら、botの返信を止めてスタッフが対応するtalkRoomにする
+    /// 対応が終わったtalkRoomでもopenに戻し、スタッフが気づけるようにする
+    pub fn request_handoff(&self) -> Option<NewTalkRoomWorkflow> {
+        let mode_changed = self.change_mode(None, TalkRoomMode::Human);
+        let status_changed = self.change_status(None, TalkRoomStatus::Open);
+        match (mode_changed, status_changed) {
+            (Some(mode_changed), Some(status_changed)) => Some(NewTalkRoomWorkflow {
+                status: status_changed.status,
+                system_events: [mode_changed.system_events, status_changed.system_events].concat(),
+                ..mode_changed
+            }),
+            (mode_changed, status_changed) => mode_changed.or(status_changed),
+        }
+    }
+
+    /// 担当者、ステータス、モードの変更を反映したtalkRoomを返す
+    pub fn apply_workflow(self, workflow: &NewTalkRoomWorkflow) -> TalkRoom {
+        TalkRoom {
+            assignee: workflow.assignee,
+            status: workflow.status,
+            mode: workflow.mode,
+            updated_at: workflow.updated_at,
+            ..self
+        }
+    }
+
+    fn workflow(
+        &self,
+        assignee: Option<StaffId>,
+        status: TalkRoomStatus,
+        system_event: NewSystemEvent,
+    ) -> NewTalkRoomWorkflow {
+        let updated_at = system_event.created_at;
+        NewTalkRoomWorkflow::new(
+            self.id.clone(),
+            assignee,
+            status,
+            self.mode,
+            vec![system_event],
+            updated_at,
         )
     }
 }
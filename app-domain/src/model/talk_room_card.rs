@@ -0,0 +1,106 @@
+use chrono::{DateTime, Local};
+use derive_new::new;
+use std::collections::HashMap;
+
+use crate::model::{
+    primary_user_id::PrimaryUserId,
+    staff::StaffId,
+    talk_room::{TalkRoom, TalkRoomMode, TalkRoomStatus},
+    Id,
+};
+
+/// talkRoomの一覧に表示する情報
+/// 最新メッセージは本文を取得せず、talkRoomCardsに持っているプレビューだけを使う
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TalkRoomCard {
+    pub id: Id<TalkRoom>,
+    pub primary_user_id: PrimaryUserId,
+    pub display_name: String,
+    pub nickname: Option<String>,
+    pub rsvp: bool,
+    pub pinned: bool,
+    pub follow: bool,
+    pub unread: bool,
+    pub assignee: Option<StaffId>,
+    pub status: TalkRoomStatus,
+    pub mode: TalkRoomMode,
+    pub latest_message: LatestMessagePreview,
+    pub latest_messaged_at: DateTime<Local>,
+    pub sort_time: DateTime<Local>,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+    // ユーザーから届いたメッセージの累計
+    pub user_message_count: i64,
+    // スタッフごとの、最後に既読にしたときのuser_message_count
+    pub read_message_counts: HashMap<StaffId, i64>,
+}
+
+impl TalkRoomCard {
+    /// スタッフが最後に既読にしてから届いたユーザーのメッセージの数
+    pub fn unread_count(&self, staff_id: StaffId) -> i64 {
+        let read_message_count = self
+            .read_message_counts
+            .get(&staff_id)
+            .copied()
+            .unwrap_or_default();
+        (self.user_message_count - read_message_count).max(0)
+    }
+}
+
+/// スタッフがtalkRoomをどこまで読んだか
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct TalkRoomReadMarker {
+    pub talk_room_id: Id<TalkRoom>,
+    pub staff_id: StaffId,
+    // 既読にしたときのユーザーのメッセージの累計
+    pub read_message_count: i64,
+    pub read_at: DateTime<Local>,
+}
+
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct LatestMessagePreview {
+    pub message_id: String,
+    // follow、unfollow、postback、text、imageなど
+    pub message_type: String,
+    // テキストメッセージのときだけ本文が入る
+    pub text: Option<String>,
+}
+
+/// talkRoomの一覧の絞り込み条件。Noneの条件では絞り込まない
+#[derive(new, Clone, Debug, PartialEq, Eq, Default)]
+pub struct TalkRoomFilter {
+    pub pinned: Option<bool>,
+    pub follow: Option<bool>,
+    pub rsvp: Option<bool>,
+    pub assignee: Option<TalkRoomAssigneeFilter>,
+    pub status: Option<TalkRoomStatus>,
+    pub unread: Option<bool>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TalkRoomAssigneeFilter {
+    Staff(StaffId),
+    Unassigned,
+}
+
+/// 一覧の続きを取得するためのカーソル
+/// 一覧はピン留め、sort_time、talkRoomのIDの降順に並べるので、最後のtalkRoomのそれらの値を持つ
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct TalkRoomCursor {
+    pub pinned: bool,
+    pub sort_time: DateTime<Local>,
+    pub id: Id<TalkRoom>,
+}
+
+impl From<&TalkRoomCard> for TalkRoomCursor {
+    fn from(c: &TalkRoomCard) -> Self {
+        TalkRoomCursor::new(c.pinned, c.sort_time, c.id.clone())
+    }
+}
+
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct TalkRoomCardPage {
+    pub talk_room_cards: Vec<TalkRoomCard>,
+    // 続きがないときはNone
+    pub next_cursor: Option<TalkRoomCursor>,
+}
@@ -0,0 +1,42 @@
+use derive_new::new;
+use futures::stream::BoxStream;
+
+use crate::model::{message::Messages, talk_room::TalkRoom, talk_room_card::TalkRoomCard, Id};
+use crate::repository::RepositoryError;
+
+/// talkRoomの変更を受け取るストリーム
+/// 受け取る側がストリームを破棄すると、変更の監視も止まる
+pub type TalkRoomChangeStream = BoxStream<'static, Result<TalkRoomChange, RepositoryError>>;
+
+/// 受信箱に即時に反映するtalkRoomの変更
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TalkRoomChange {
+    // talkRoomCardsが作成、更新された
+    CardUpdated(TalkRoomCard),
+    // talkRoomにメッセージが追加された
+    MessageAdded(TalkRoomMessage),
+    // ここまでの変更を受け取り終えた位置。再接続するときはこの位置から再開する
+    Checkpoint(TalkRoomChangeResumeToken),
+}
+
+impl TalkRoomChange {
+    /// 変更されたtalkRoom。再開する位置はどのtalkRoomの変更でもない
+    pub fn talk_room_id(&self) -> Option<&Id<TalkRoom>> {
+        match self {
+            TalkRoomChange::CardUpdated(card) => Some(&card.id),
+            TalkRoomChange::MessageAdded(message) => Some(&message.talk_room_id),
+            TalkRoomChange::Checkpoint(_) => None,
+        }
+    }
+}
+
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct TalkRoomMessage {
+    pub talk_room_id: Id<TalkRoom>,
+    pub message: Messages,
+}
+
+/// 変更の監視を再開する位置
+/// 中身は監視の仕組みによって違うので、受け取った値をそのまま返してもらう
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct TalkRoomChangeResumeToken(pub String);
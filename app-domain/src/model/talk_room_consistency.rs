@@ -0,0 +1,80 @@
+use derive_new::new;
+
+/// MySQLのtalk_roomsと、firestoreのtalkRooms、talkRoomCards、messagesの食い違い
+/// talkRoomはどれもドキュメントのIDで表す
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TalkRoomInconsistency {
+    // talk_roomsの行はあるが、talkRoomsのドキュメントがない
+    MissingTalkRoomDocument(String),
+    // talk_roomsの行はあるが、talkRoomCardsのドキュメントがない
+    MissingCard(String),
+    // talkRoomCardsのlatestMessageが指すメッセージがない
+    MissingLatestMessage {
+        talk_room_document_id: String,
+        message_document_id: String,
+    },
+    // talkRoomCardsに、一覧の絞り込みに使う項目がない。項目を追加する前に作ったドキュメント
+    MissingCardFields {
+        talk_room_document_id: String,
+        fields: Vec<String>,
+    },
+    // talk_roomsの行がない、talkRoomsやtalkRoomCardsのドキュメント
+    OrphanedDocuments {
+        talk_room_document_id: String,
+        has_messages: bool,
+    },
+    // talkRoomsやtalkRoomCardsのドキュメントが、読み込めない形で保存されている
+    UndeserializableDocument {
+        talk_room_document_id: String,
+        collection_id: String,
+        error: String,
+    },
+}
+
+impl TalkRoomInconsistency {
+    pub fn talk_room_document_id(&self) -> &String {
+        match self {
+            TalkRoomInconsistency::MissingTalkRoomDocument(id) => id,
+            TalkRoomInconsistency::MissingCard(id) => id,
+            TalkRoomInconsistency::MissingLatestMessage {
+                talk_room_document_id,
+                ..
+            } => talk_room_document_id,
+            TalkRoomInconsistency::MissingCardFields {
+                talk_room_document_id,
+                ..
+            } => talk_room_document_id,
+            TalkRoomInconsistency::OrphanedDocuments {
+                talk_room_document_id,
+                ..
+            } => talk_room_document_id,
+            TalkRoomInconsistency::UndeserializableDocument {
+                talk_room_document_id,
+                ..
+            } => talk_room_document_id,
+        }
+    }
+
+    /// 自動で直してよいか
+    /// なくなったtalkRoomCardsは、メッセージから作り直す。担当者などスタッフが変更した内容は戻らない
+    /// メッセージが残っているドキュメントは、統合などで必要になるかもしれないので消さない
+    /// 読み込めないドキュメントは、どう直すかを人が決める
+    pub fn repairable(&self) -> bool {
+        match self {
+            TalkRoomInconsistency::MissingTalkRoomDocument(_) => true,
+            TalkRoomInconsistency::MissingCard(_) => true,
+            TalkRoomInconsistency::MissingLatestMessage { .. } => true,
+            TalkRoomInconsistency::MissingCardFields { .. } => true,
+            TalkRoomInconsistency::OrphanedDocuments { has_messages, .. } => !has_messages,
+            TalkRoomInconsistency::UndeserializableDocument { .. } => false,
+        }
+    }
+}
+
+/// MySQLとfirestoreを突き合わせた結果
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct TalkRoomConsistencyScan {
+    // 突き合わせたtalkRoomの数
+    pub checked_count: usize,
+    pub inconsistencies: Vec<TalkRoomInconsistency>,
+}
@@ -0,0 +1,49 @@
+use chrono::{DateTime, Local};
+use derive_new::new;
+
+use crate::model::{
+    staff::{Staff, StaffId, StaffRole},
+    talk_room::TalkRoom,
+    Id,
+};
+
+/// スタッフだけが見るtalkRoomのメモ
+/// メッセージと同じtalkRoomに保存するが、LINEには送らない
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct TalkRoomNote {
+    pub id: Id<TalkRoomNote>,
+    pub talk_room_id: Id<TalkRoom>,
+    pub author: TalkRoomNoteAuthor,
+    pub body: String,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+impl TalkRoomNote {
+    /// メモを書いたスタッフと管理者だけが、メモを変更、削除できる
+    pub fn can_be_edited_by(&self, staff: &Staff) -> bool {
+        self.author.id == staff.id || staff.role == StaffRole::Admin
+    }
+}
+
+/// メモを書いたスタッフ。名前は書いたときのものを残す
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct TalkRoomNoteAuthor {
+    pub id: StaffId,
+    pub name: String,
+}
+
+impl From<Staff> for TalkRoomNoteAuthor {
+    fn from(s: Staff) -> Self {
+        TalkRoomNoteAuthor::new(s.id, s.name)
+    }
+}
+
+#[derive(new, Clone, Debug, PartialEq, Eq)]
+pub struct NewTalkRoomNote {
+    pub id: Id<TalkRoomNote>,
+    pub talk_room_id: Id<TalkRoom>,
+    pub author: TalkRoomNoteAuthor,
+    pub body: String,
+    pub created_at: DateTime<Local>,
+}
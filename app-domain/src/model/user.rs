@@ -1,5 +1,7 @@
 use crate::model::{
-    line_user::LineUserProfile, primary_user_id::PrimaryUserId, user_auth::AuthUserId,
+    account_link::ExternalMemberId, email_user::EmailUserProfile,
+    line_login_user::LineLoginUserProfile, line_user::LineUserProfile,
+    primary_user_id::PrimaryUserId, user_auth::AuthUserId,
 };
 use derive_new::new;
 
@@ -7,22 +9,35 @@ use derive_new::new;
 pub struct User {
     pub id: PrimaryUserId,
     pub user_profile: UserProfile,
+    // アカウント連携している会員システムのアカウント
+    #[new(default)]
+    pub external_member_id: Option<ExternalMemberId>,
 }
 
 #[derive(new, Debug, Clone, PartialEq, Eq)]
 pub enum UserProfile {
     Line(LineUserProfile),
+    Email(EmailUserProfile),
+    LineLogin(LineLoginUserProfile),
 }
 
 impl UserProfile {
     pub fn auth_id(&self) -> Option<AuthUserId> {
         match self {
             UserProfile::Line(user_profile) => Some(AuthUserId::Line(user_profile.auth_id.clone())),
+            UserProfile::Email(user_profile) => {
+                Some(AuthUserId::Email(user_profile.auth_id.clone()))
+            }
+            UserProfile::LineLogin(user_profile) => {
+                Some(AuthUserId::LineLogin(user_profile.auth_id.clone()))
+            }
         }
     }
     pub fn display_name(&self) -> Option<&String> {
         match self {
             UserProfile::Line(user_profile) => Some(&user_profile.display_name),
+            UserProfile::Email(_) => None,
+            UserProfile::LineLogin(user_profile) => Some(&user_profile.display_name),
         }
     }
 }
@@ -1,14 +1,19 @@
+use crate::model::{email_user::EmailAddress, line_login_user::LineLoginId};
 use derive_new::new;
 
 #[derive(new, Debug, Clone, PartialEq, Eq)]
 pub enum AuthUserId {
     Line(LineId),
+    Email(EmailAddress),
+    LineLogin(LineLoginId),
 }
 
 impl AuthUserId {
     pub fn value(&self) -> &String {
         match self {
             Self::Line(s) => &s.0,
+            Self::Email(s) => &s.0,
+            Self::LineLogin(s) => &s.0,
         }
     }
 }
@@ -35,6 +40,8 @@ pub struct LineAuthToken(pub String);
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UserAuthData {
     Line(LineUserAuthData),
+    Email(EmailUserAuthData),
+    LineLogin(LineLoginAuthData),
 }
 
 #[derive(new, Debug, Clone, PartialEq, Eq)]
@@ -42,3 +49,17 @@ pub struct LineUserAuthData {
     pub auth_id: LineId,
     pub auth_token: LineAuthToken,
 }
+
+// パスワードは平文のまま持つので、ハッシュ化するまでの間だけ使う
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct EmailUserAuthData {
+    pub auth_id: EmailAddress,
+    pub password: String,
+}
+
+// LINEログインで取得したIDトークン。LINEで検証してからsubを取り出す
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct LineLoginAuthData {
+    pub id_token: String,
+    pub channel_id: String,
+}
@@ -0,0 +1,33 @@
+use crate::model::{primary_user_id::PrimaryUserId, user_tag::UserTag};
+use derive_new::new;
+
+/*
+ * ユーザーの属性が変わるきっかけになったドメインイベント
+ * リッチメニューの割り当てなど、ユーザーの属性に応じた処理はこのイベントを受けて行う
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserEvent {
+    Followed(UserFollowed),
+    TaggedByStaff(UserTaggedByStaff),
+}
+
+impl UserEvent {
+    pub fn primary_user_id(&self) -> &PrimaryUserId {
+        match self {
+            UserEvent::Followed(e) => &e.primary_user_id,
+            UserEvent::TaggedByStaff(e) => &e.primary_user_id,
+        }
+    }
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct UserFollowed {
+    pub primary_user_id: PrimaryUserId,
+}
+
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct UserTaggedByStaff {
+    pub primary_user_id: PrimaryUserId,
+    pub added_tags: Vec<UserTag>,
+    pub removed_tags: Vec<UserTag>,
+}
@@ -0,0 +1,42 @@
+use crate::model::{primary_user_id::PrimaryUserId, user_auth::LineId};
+use anyhow::anyhow;
+use derive_new::new;
+use std::str::FromStr;
+
+// ユーザーのセグメントを表すタグ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UserTag {
+    NewFollower,
+    HasAppointment,
+    MedicationReminderSubscriber,
+}
+
+impl UserTag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserTag::NewFollower => "new_follower",
+            UserTag::HasAppointment => "has_appointment",
+            UserTag::MedicationReminderSubscriber => "medication_reminder_subscriber",
+        }
+    }
+}
+
+impl FromStr for UserTag {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "new_follower" => Ok(UserTag::NewFollower),
+            "has_appointment" => Ok(UserTag::HasAppointment),
+            "medication_reminder_subscriber" => Ok(UserTag::MedicationReminderSubscriber),
+            _ => Err(anyhow!("Unknown user tag: {}", s)),
+        }
+    }
+}
+
+// リッチメニューをリンクするために、LINEのユーザーIDとタグをまとめて扱う
+#[derive(new, Debug, Clone, PartialEq, Eq)]
+pub struct LineUserTags {
+    pub primary_user_id: PrimaryUserId,
+    pub line_id: LineId,
+    pub tags: Vec<UserTag>,
+}
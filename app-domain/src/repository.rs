@@ -1,2 +1,35 @@
+pub mod business_hours;
+pub mod canned_response;
+pub mod staff;
 pub mod talk_room;
+pub mod talk_room_note;
 pub mod user;
+pub mod user_tag;
+
+use thiserror::Error;
+
+/// リポジトリのエラー
+/// ユースケースは種類で処理を分け、Unavailableのときだけリトライする
+#[derive(Debug, Error)]
+pub enum RepositoryError {
+    #[error("Unexpected Error: {0}")]
+    Unexpected(String),
+    #[error("NotAuthFound, auth_id is {0}")]
+    NotAuthFound(String),
+    #[error("NotFound, table is {0}, id is {1}")]
+    NotFound(String, String),
+    /// 一意キーなどがほかのデータと競合して、書き込めなかった
+    #[error("Conflict, table is {0}, column {1} is {2}")]
+    Conflict(String, String, String),
+    #[error("InvalidInput: {0}")]
+    InvalidInput(String),
+    /// MySQLやfirestoreにつながらなかった。時間をおけば成功するかもしれない
+    #[error("Unavailable: {0}")]
+    Unavailable(String),
+}
+
+impl RepositoryError {
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, RepositoryError::Unavailable(_))
+    }
+}
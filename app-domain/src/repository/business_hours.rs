@@ -0,0 +1,18 @@
+use crate::model::business_hours::{BusinessHoursCalendar, NewBusinessHoursCalendar};
+use crate::repository::RepositoryError;
+use async_trait::async_trait;
+
+#[mockall::automock]
+#[async_trait]
+pub trait BusinessHoursRepository {
+    // 営業時間を登録していないチャネルのときはNoneを返す
+    async fn get_calendar(
+        &self,
+        channel_id: String,
+    ) -> Result<Option<BusinessHoursCalendar>, RepositoryError>;
+    async fn save_calendar(
+        &self,
+        channel_id: String,
+        source: NewBusinessHoursCalendar,
+    ) -> Result<BusinessHoursCalendar, RepositoryError>;
+}
@@ -0,0 +1,27 @@
+use crate::model::canned_response::{CannedResponse, CannedResponseId, NewCannedResponse};
+use crate::repository::RepositoryError;
+use async_trait::async_trait;
+
+#[mockall::automock]
+#[async_trait]
+pub trait CannedResponseRepository {
+    async fn get_canned_responses(&self) -> Result<Vec<CannedResponse>, RepositoryError>;
+    async fn get_canned_response(
+        &self,
+        canned_response_id: CannedResponseId,
+    ) -> Result<CannedResponse, RepositoryError>;
+    async fn create_canned_response(
+        &self,
+        source: NewCannedResponse,
+    ) -> Result<CannedResponse, RepositoryError>;
+    async fn update_canned_response(
+        &self,
+        canned_response_id: CannedResponseId,
+        title: String,
+        body: String,
+    ) -> Result<CannedResponse, RepositoryError>;
+    async fn delete_canned_response(
+        &self,
+        canned_response_id: CannedResponseId,
+    ) -> Result<(), RepositoryError>;
+}
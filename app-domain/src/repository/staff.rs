@@ -0,0 +1,20 @@
+use crate::model::{
+    email_user::EmailAddress,
+    staff::{NewStaff, Staff, StaffId, StaffRole},
+};
+use crate::repository::RepositoryError;
+use async_trait::async_trait;
+
+#[mockall::automock]
+#[async_trait]
+pub trait StaffRepository {
+    async fn get_staff(&self, staff_id: StaffId) -> Result<Staff, RepositoryError>;
+    async fn get_staff_by_email(&self, email: EmailAddress) -> Result<Staff, RepositoryError>;
+    async fn get_staffs(&self) -> Result<Vec<Staff>, RepositoryError>;
+    async fn create_staff(&self, source: NewStaff) -> Result<Staff, RepositoryError>;
+    async fn update_staff_role(
+        &self,
+        staff_id: StaffId,
+        role: StaffRole,
+    ) -> Result<Staff, RepositoryError>;
+}
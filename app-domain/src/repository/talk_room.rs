@@ -1,13 +1,89 @@
 use crate::model::{
+    message::{MessageCursor, MessagesPage},
     primary_user_id::PrimaryUserId,
-    talk_room::{NewTalkRoom, TalkRoom},
+    staff::StaffId,
+    talk_room::{NewTalkRoom, NewTalkRoomWorkflow, TalkRoom},
+    talk_room_card::{TalkRoomCardPage, TalkRoomCursor, TalkRoomFilter, TalkRoomReadMarker},
+    talk_room_change::{TalkRoomChangeResumeToken, TalkRoomChangeStream},
+    talk_room_consistency::{TalkRoomConsistencyScan, TalkRoomInconsistency},
+    Id,
 };
+use crate::repository::RepositoryError;
 use async_trait::async_trait;
+use chrono::{DateTime, Local};
 
 #[mockall::automock]
 #[async_trait]
 pub trait TalkRoomRepository {
-    async fn get_talk_room(&self, source: PrimaryUserId) -> anyhow::Result<TalkRoom>;
-    async fn create_talk_room(&self, source: NewTalkRoom) -> anyhow::Result<TalkRoom>;
-    async fn create_messages(&self, source: NewTalkRoom) -> anyhow::Result<TalkRoom>;
+    async fn get_talk_room(&self, source: PrimaryUserId) -> Result<TalkRoom, RepositoryError>;
+    async fn get_talk_room_by_id(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+    ) -> Result<TalkRoom, RepositoryError>;
+    async fn get_talk_room_cards(
+        &self,
+        filter: TalkRoomFilter,
+        cursor: Option<TalkRoomCursor>,
+        limit: u32,
+    ) -> Result<TalkRoomCardPage, RepositoryError>;
+    async fn get_messages(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        cursor: Option<MessageCursor>,
+        limit: u32,
+    ) -> Result<MessagesPage, RepositoryError>;
+    async fn listen_changes(
+        &self,
+        resume_token: Option<TalkRoomChangeResumeToken>,
+    ) -> Result<TalkRoomChangeStream, RepositoryError>;
+    async fn create_talk_room(&self, source: NewTalkRoom) -> Result<TalkRoom, RepositoryError>;
+    async fn create_messages(&self, source: NewTalkRoom) -> Result<TalkRoom, RepositoryError>;
+    async fn update_display_name(
+        &self,
+        primary_user_id: PrimaryUserId,
+        display_name: String,
+    ) -> Result<(), RepositoryError>;
+    /// fromのユーザーをintoに統合し、fromのtalkRoomをintoに付け替える
+    /// 途中で失敗してtalkRoomが削除されたユーザーを指したままにならないように、同じトランザクションで行う
+    /// talkRoomが両方にあるときは、どちらを残すか決められないのでConflictにする
+    async fn merge_users(
+        &self,
+        into: PrimaryUserId,
+        from: PrimaryUserId,
+    ) -> Result<(), RepositoryError>;
+    async fn update_pinned(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        pinned: bool,
+    ) -> Result<(), RepositoryError>;
+    async fn update_rsvp(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        rsvp: bool,
+    ) -> Result<(), RepositoryError>;
+    async fn update_nickname(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        nickname: Option<String>,
+    ) -> Result<(), RepositoryError>;
+    async fn mark_as_read(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        staff_id: StaffId,
+    ) -> Result<TalkRoomReadMarker, RepositoryError>;
+    async fn update_workflow(&self, source: NewTalkRoomWorkflow) -> Result<(), RepositoryError>;
+    /// 営業時間外の区切りごとに、不在メッセージを送る権利を1回だけ得る
+    /// すでに同じ区切りで送っていたときはfalseを返す
+    async fn claim_away_message(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        off_hours_since: DateTime<Local>,
+    ) -> Result<bool, RepositoryError>;
+    /// MySQLのtalk_roomsと、firestoreのドキュメントの食い違いを探す
+    async fn scan_consistency(&self) -> Result<TalkRoomConsistencyScan, RepositoryError>;
+    /// 食い違いを1件直す。自動で直せないものはエラーにする
+    async fn repair_inconsistency(
+        &self,
+        inconsistency: TalkRoomInconsistency,
+    ) -> Result<(), RepositoryError>;
 }
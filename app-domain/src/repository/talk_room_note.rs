@@ -0,0 +1,33 @@
+use crate::model::{
+    talk_room::TalkRoom,
+    talk_room_note::{NewTalkRoomNote, TalkRoomNote},
+    Id,
+};
+use crate::repository::RepositoryError;
+use async_trait::async_trait;
+
+#[mockall::automock]
+#[async_trait]
+pub trait TalkRoomNoteRepository {
+    async fn get_notes(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+    ) -> Result<Vec<TalkRoomNote>, RepositoryError>;
+    async fn get_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+    ) -> Result<TalkRoomNote, RepositoryError>;
+    async fn create_note(&self, source: NewTalkRoomNote) -> Result<TalkRoomNote, RepositoryError>;
+    async fn update_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+        body: String,
+    ) -> Result<(), RepositoryError>;
+    async fn delete_note(
+        &self,
+        talk_room_id: Id<TalkRoom>,
+        note_id: Id<TalkRoomNote>,
+    ) -> Result<(), RepositoryError>;
+}
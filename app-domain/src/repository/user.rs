@@ -1,15 +1,44 @@
 use crate::model::{
+    account_link::{ExternalMemberId, NewAccountLink, NewAccountLinkNonce},
     line_user::LineUserProfile,
+    primary_user_id::PrimaryUserId,
     user::{User, UserProfile},
     user_auth::{AuthUserId, LineId},
 };
+use crate::repository::RepositoryError;
 use async_trait::async_trait;
+use chrono::{DateTime, Local};
 
 #[mockall::automock]
 #[async_trait]
 pub trait UserRepository {
-    async fn get_user(&self, source: AuthUserId) -> anyhow::Result<User>;
-    async fn get_line_user(&self, source: LineId) -> anyhow::Result<User>;
-    async fn create_user(&self, source: UserProfile) -> anyhow::Result<User>;
-    async fn create_line_user(&self, source: LineUserProfile) -> anyhow::Result<User>;
+    async fn get_user(&self, source: AuthUserId) -> Result<User, RepositoryError>;
+    async fn get_line_user(&self, source: LineId) -> Result<User, RepositoryError>;
+    async fn get_line_user_by_primary_user_id(
+        &self,
+        primary_user_id: PrimaryUserId,
+    ) -> Result<User, RepositoryError>;
+    async fn create_user(&self, source: UserProfile) -> Result<User, RepositoryError>;
+    async fn get_or_create_user(&self, source: UserProfile) -> Result<User, RepositoryError>;
+    async fn create_line_user(&self, source: LineUserProfile) -> Result<User, RepositoryError>;
+    async fn update_line_user(&self, source: LineUserProfile) -> Result<User, RepositoryError>;
+    async fn get_line_users_updated_before(
+        &self,
+        updated_before: DateTime<Local>,
+        limit: i64,
+    ) -> Result<Vec<User>, RepositoryError>;
+    async fn link_user(
+        &self,
+        primary_user_id: PrimaryUserId,
+        source: UserProfile,
+    ) -> Result<User, RepositoryError>;
+    async fn create_account_link_nonce(
+        &self,
+        source: NewAccountLinkNonce,
+    ) -> Result<(), RepositoryError>;
+    async fn link_account(
+        &self,
+        source: NewAccountLink,
+    ) -> Result<ExternalMemberId, RepositoryError>;
+    async fn unlink_account(&self, primary_user_id: PrimaryUserId) -> Result<(), RepositoryError>;
 }
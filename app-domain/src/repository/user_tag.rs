@@ -0,0 +1,26 @@
+use crate::model::{
+    primary_user_id::PrimaryUserId,
+    user_tag::{LineUserTags, UserTag},
+};
+use crate::repository::RepositoryError;
+use async_trait::async_trait;
+
+#[mockall::automock]
+#[async_trait]
+pub trait UserTagRepository {
+    async fn get_line_user_tags(
+        &self,
+        source: PrimaryUserId,
+    ) -> Result<LineUserTags, RepositoryError>;
+    async fn get_all_line_user_tags(&self) -> Result<Vec<LineUserTags>, RepositoryError>;
+    async fn add_user_tags(
+        &self,
+        primary_user_id: PrimaryUserId,
+        tags: Vec<UserTag>,
+    ) -> Result<(), RepositoryError>;
+    async fn remove_user_tags(
+        &self,
+        primary_user_id: PrimaryUserId,
+        tags: Vec<UserTag>,
+    ) -> Result<(), RepositoryError>;
+}
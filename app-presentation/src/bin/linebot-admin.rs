@@ -0,0 +1,220 @@
+use adapter::persistance::db::Db;
+use anyhow::anyhow;
+use application::config::{AdaptersKind, AppConfig};
+use domain::model::{staff::StaffId, user_auth::LineId};
+use dotenv::dotenv;
+use presentation::module::{Modules, ModulesExt};
+use std::{env, process};
+
+const USAGE: &str = "\
+Usage: linebot-admin <command>
+
+Commands:
+  migrate run                          適用していないマイグレーションを適用する
+  migrate revert                       最後に適用したマイグレーションを1つ戻す
+  user <line_id>                       LINEのユーザーIDでユーザーを表示する
+  talk-room <line_id> [limit]          ユーザーのtalkRoomと最新のメッセージを表示する
+  push <line_id> <staff_id> <text>     スタッフとしてユーザーにテキストを送る
+  refresh-profile <line_id>            ユーザーのプロフィールをLINEから取得し直す";
+
+// talk-roomで表示するメッセージの件数のデフォルト
+const DEFAULT_MESSAGE_LIMIT: u32 = 20;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    MigrateRun,
+    MigrateRevert,
+    User(LineId),
+    TalkRoom(LineId, u32),
+    Push(LineId, StaffId, String),
+    RefreshProfile(LineId),
+}
+
+impl Command {
+    fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let command = match args.as_slice() {
+            ["migrate", "run"] => Command::MigrateRun,
+            ["migrate", "revert"] => Command::MigrateRevert,
+            ["user", line_id] => Command::User(LineId::new(line_id.to_string())),
+            ["talk-room", line_id] => {
+                Command::TalkRoom(LineId::new(line_id.to_string()), DEFAULT_MESSAGE_LIMIT)
+            }
+            ["talk-room", line_id, limit] => Command::TalkRoom(
+                LineId::new(line_id.to_string()),
+                limit
+                    .parse()
+                    .map_err(|_| anyhow!("limit must be a positive number: {}", limit))?,
+            ),
+            // テキストはクォートしなくても送れるように、残りの引数をつなげる
+            ["push", line_id, staff_id, text @ ..] if !text.is_empty() => Command::Push(
+                LineId::new(line_id.to_string()),
+                StaffId(
+                    staff_id
+                        .parse()
+                        .map_err(|_| anyhow!("staff_id must be a number: {}", staff_id))?,
+                ),
+                text.join(" "),
+            ),
+            ["refresh-profile", line_id] => {
+                Command::RefreshProfile(LineId::new(line_id.to_string()))
+            }
+            _ => return Err(anyhow!(USAGE)),
+        };
+
+        Ok(command)
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    init_app();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = Command::parse(&args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(2);
+    });
+    let result = match command {
+        // マイグレーションはfirestoreなどに接続しないので、サーバーと同じ設定からDATABASE_URLだけを読む
+        Command::MigrateRun | Command::MigrateRevert => {
+            let database_url = AppConfig::load_database_url().unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                process::exit(1);
+            });
+            migrate(&database_url, command).await
+        }
+        // サーバーと同じ設定を読み込む
+        command => {
+            let config = AppConfig::load().unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                process::exit(1);
+            });
+            run(config, command).await
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("Error: {:?}", err);
+        process::exit(1);
+    }
+}
+
+async fn migrate(database_url: &str, command: Command) -> anyhow::Result<()> {
+    let db = Db::connect(database_url).await?;
+    match command {
+        Command::MigrateRun => {
+            db.run_migrations().await?;
+            println!("Migrations are up to date");
+        }
+        Command::MigrateRevert => match db.revert_migration().await? {
+            Some(version) => println!("Reverted migration {}", version),
+            None => println!("No migrations to revert"),
+        },
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+async fn run(config: AppConfig, command: Command) -> anyhow::Result<()> {
+    // サーバーと同じ設定で、同じアダプターを選ぶ
+    match config.adapters {
+        AdaptersKind::InMemory => run_with_modules(Modules::in_memory(config), command).await,
+        AdaptersKind::MySqlTalkRooms => {
+            run_with_modules(Modules::with_mysql_talk_rooms(config).await?, command).await
+        }
+        AdaptersKind::Default => run_with_modules(Modules::new(config).await?, command).await,
+    }
+}
+
+async fn run_with_modules<M: ModulesExt>(modules: M, command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::User(line_id) => {
+            let user = modules.admin_usecase().get_line_user(line_id).await?;
+            println!("{:#?}", user);
+        }
+        Command::TalkRoom(line_id, limit) => {
+            let talk_room_dump = modules
+                .admin_usecase()
+                .dump_talk_room(line_id, limit)
+                .await?;
+            println!("{:#?}", talk_room_dump);
+        }
+        Command::Push(line_id, staff_id, text) => {
+            let new_send_messages_vec = modules
+                .admin_usecase()
+                .push_message(line_id, staff_id, text)
+                .await?;
+            println!("{:#?}", new_send_messages_vec);
+        }
+        Command::RefreshProfile(line_id) => {
+            let user = modules
+                .user_profile_usecase()
+                .refresh_line_user_profile(line_id)
+                .await?;
+            println!("{:#?}", user);
+        }
+        Command::MigrateRun | Command::MigrateRevert => unreachable!(),
+    }
+
+    Ok(())
+}
+
+fn init_app() {
+    dotenv().ok();
+    //logging
+    let log_level = env::var("RUST_LOG").unwrap_or("info".to_string());
+    env::set_var("RUST_LOG", log_level);
+    tracing_subscriber::fmt::init();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_command() {
+        let line_id = LineId::new("U1".to_string());
+        assert_eq!(
+            Command::parse(&args(&["migrate", "run"])).unwrap(),
+            Command::MigrateRun
+        );
+        assert_eq!(
+            Command::parse(&args(&["migrate", "revert"])).unwrap(),
+            Command::MigrateRevert
+        );
+        assert_eq!(
+            Command::parse(&args(&["user", "U1"])).unwrap(),
+            Command::User(line_id.clone())
+        );
+        assert_eq!(
+            Command::parse(&args(&["talk-room", "U1"])).unwrap(),
+            Command::TalkRoom(line_id.clone(), DEFAULT_MESSAGE_LIMIT)
+        );
+        assert_eq!(
+            Command::parse(&args(&["talk-room", "U1", "5"])).unwrap(),
+            Command::TalkRoom(line_id.clone(), 5)
+        );
+        assert_eq!(
+            Command::parse(&args(&["push", "U1", "1", "テスト", "です"])).unwrap(),
+            Command::Push(line_id.clone(), StaffId(1), "テスト です".to_string())
+        );
+        assert_eq!(
+            Command::parse(&args(&["refresh-profile", "U1"])).unwrap(),
+            Command::RefreshProfile(line_id)
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_command() {
+        assert!(Command::parse(&args(&[])).is_err());
+        assert!(Command::parse(&args(&["migrate"])).is_err());
+        assert!(Command::parse(&args(&["talk-room", "U1", "many"])).is_err());
+        assert!(Command::parse(&args(&["push", "U1", "staff", "テスト"])).is_err());
+        assert!(Command::parse(&args(&["push", "U1", "1"])).is_err());
+    }
+}
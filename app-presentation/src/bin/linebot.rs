@@ -1,37 +1,272 @@
+use application::config::{AdaptersKind, AppConfig};
 use axum::{
     extract::Extension,
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post, put},
     Router,
 };
 use dotenv::dotenv;
-use presentation::{module::Modules, routes::line_webhook::line_webhook_handler};
+use presentation::{
+    context::{
+        admin_auth::require_admin_api_key, event_tasks::EventTasks,
+        staff_auth::require_staff_session,
+    },
+    jobs::{
+        talk_room_reconcile::spawn_reconcile_talk_rooms,
+        user_profile::spawn_refresh_line_user_profiles,
+    },
+    module::{Modules, ModulesExt},
+    routes::{
+        account_link::{
+            issue_account_link_login_url_handler, issue_account_link_nonce_handler,
+            unlink_account_handler,
+        },
+        business_hours::{get_business_hours_handler, save_business_hours_handler},
+        canned_response::{
+            create_canned_response_handler, delete_canned_response_handler,
+            get_canned_responses_handler, update_canned_response_handler,
+        },
+        health::{healthz_handler, readyz_handler},
+        line_webhook::line_webhook_handler,
+        rich_menu::{deploy_rich_menus_handler, resync_rich_menus_handler},
+        staff::{
+            bootstrap_staff_handler, create_staff_handler, get_me_handler, get_staffs_handler,
+            staff_login_handler, update_staff_role_handler,
+        },
+        talk_room::{
+            assign_talk_room_handler, change_talk_room_mode_handler,
+            change_talk_room_status_handler, get_talk_room_changes_handler,
+            get_talk_room_messages_handler, get_talk_rooms_handler, mark_talk_room_as_read_handler,
+            send_talk_room_message_handler, unassign_talk_room_handler,
+            update_talk_room_nickname_handler, update_talk_room_pinned_handler,
+            update_talk_room_rsvp_handler,
+        },
+        talk_room_note::{
+            create_talk_room_note_handler, delete_talk_room_note_handler,
+            get_talk_room_notes_handler, update_talk_room_note_handler,
+        },
+        talk_room_reconcile::reconcile_talk_rooms_handler,
+        user_identity::{
+            authenticate_user_handler, link_user_identity_handler, merge_users_handler,
+        },
+        user_tag::change_user_tags_handler,
+    },
+};
 use std::env;
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, process, sync::Arc, time::Duration};
+use tokio::signal::unix::{signal, SignalKind};
 
 #[tokio::main]
 async fn main() {
     init_app();
 
+    // 設定は起動時に一度だけ読み込む。足りない値や読めない値は、まとめて表示して終了する
+    let config = AppConfig::load().unwrap_or_else(|err| {
+        tracing::error!("{}", err);
+        process::exit(1);
+    });
+
     // DI
-    let modules = Modules::new().await;
+    match config.adapters {
+        AdaptersKind::InMemory => {
+            tracing::info!("Using in-memory adapters");
+            serve(Arc::new(Modules::in_memory(config))).await
+        }
+        AdaptersKind::MySqlTalkRooms => {
+            tracing::info!("Using MySQL talk room repository");
+            serve(Arc::new(or_exit(
+                Modules::with_mysql_talk_rooms(config).await,
+            )))
+            .await
+        }
+        AdaptersKind::Default => serve(Arc::new(or_exit(Modules::new(config).await))).await,
+    }
+}
 
-    let root = Router::new().route("/", get(root));
-    let line_webhook_router = Router::new().route("/", post(line_webhook_handler));
+fn or_exit<T>(result: anyhow::Result<T>) -> T {
+    result.unwrap_or_else(|err| {
+        tracing::error!("{:?}", err);
+        process::exit(1);
+    })
+}
 
-    let app = Router::new()
-        .nest("/", root)
-        .nest("/linebot-webhook", line_webhook_router)
-        .layer(Extension(Arc::new(modules)));
+async fn serve<M: ModulesExt>(modules: Arc<M>) {
+    // バックグラウンドジョブ
+    spawn_refresh_line_user_profiles(modules.clone());
+    spawn_reconcile_talk_rooms(modules.clone());
 
-    // localhost:3000
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
+    let addr = SocketAddr::from(([0, 0, 0, 0], modules.config().port));
+    let shutdown_timeout = modules.config().shutdown_timeout;
+    let shutdown_delay = modules.config().shutdown_delay;
+    let event_tasks = EventTasks::new();
+    let app = router(modules, event_tasks.clone());
 
     tracing::debug!("Server listening on {}", addr);
 
-    axum::Server::bind(&addr)
+    /*
+     * 終了のシグナルを受けたら、webhookを受け付けないようにして/readyzを503にする
+     * ロードバランサーが振り分けをやめるまでshutdown_delayだけ待ってから、新しい接続を受けないようにする
+     * 処理中のリクエストとイベントの処理が終わるのを待ってから終了する
+     * SSEのように終わらない接続があっても、shutdown_timeoutが過ぎたら待たずに終了する
+     */
+    let server = axum::Server::bind(&addr)
         .serve(app.into_make_service())
-        .await
-        .unwrap_or_else(|_| panic!("Server cannot launch!"))
+        .with_graceful_shutdown(shutdown_signal(event_tasks.clone(), shutdown_delay));
+    let drain = async {
+        let result = server.await;
+        event_tasks.wait().await;
+        result
+    };
+    let deadline = async {
+        event_tasks.closed().await;
+        tokio::time::sleep(shutdown_timeout).await;
+    };
+    tokio::select! {
+        result = drain => {
+            result.unwrap_or_else(|_| panic!("Server cannot launch!"));
+            tracing::info!("Server stopped gracefully");
+        }
+        _ = deadline => {
+            tracing::warn!(
+                "Server stopped before all requests and events finished in {:?}",
+                shutdown_timeout
+            );
+        }
+    }
+}
+
+// SIGTERMかCtrl+Cを受けたら、webhookのイベントを受け付けないようにする
+// 閉じると/readyzが503になるので、ロードバランサーに伝わるまで待ってから接続を閉じ始める
+async fn shutdown_signal(event_tasks: EventTasks, shutdown_delay: Duration) {
+    let mut sigterm = signal(SignalKind::terminate()).expect("Cannot listen SIGTERM");
+    tokio::select! {
+        _ = sigterm.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+    tracing::info!("Shutting down");
+    event_tasks.close();
+    tokio::time::sleep(shutdown_delay).await;
+}
+
+fn router<M: ModulesExt>(modules: Arc<M>, event_tasks: EventTasks) -> Router {
+    let root = Router::new()
+        .route("/", get(root))
+        .route("/healthz", get(healthz_handler))
+        .route("/readyz", get(readyz_handler::<M>));
+    let line_webhook_router = Router::new().route("/", post(line_webhook_handler::<M>));
+    let auth_router = Router::new()
+        .route("/login", post(staff_login_handler::<M>))
+        // 会員向けの画面から、メールアドレスやLINEログインで認証する
+        .route("/users/login", post(authenticate_user_handler::<M>));
+    // スタッフが使うエンドポイントは、ロールごとの権限をハンドラーで確認する
+    let staff_router = Router::new()
+        .route("/staffs/me", get(get_me_handler))
+        .route(
+            "/staffs",
+            get(get_staffs_handler::<M>).post(create_staff_handler::<M>),
+        )
+        .route(
+            "/staffs/:staff_id/role",
+            put(update_staff_role_handler::<M>),
+        )
+        .route("/talk-rooms", get(get_talk_rooms_handler::<M>))
+        .route(
+            "/talk-rooms/changes",
+            get(get_talk_room_changes_handler::<M>),
+        )
+        .route(
+            "/talk-rooms/:talk_room_id/messages",
+            get(get_talk_room_messages_handler::<M>).post(send_talk_room_message_handler::<M>),
+        )
+        .route(
+            "/talk-rooms/:talk_room_id/read",
+            post(mark_talk_room_as_read_handler::<M>),
+        )
+        .route(
+            "/talk-rooms/:talk_room_id/assignee",
+            put(assign_talk_room_handler::<M>).delete(unassign_talk_room_handler::<M>),
+        )
+        .route(
+            "/talk-rooms/:talk_room_id/mode",
+            put(change_talk_room_mode_handler::<M>),
+        )
+        .route(
+            "/talk-rooms/:talk_room_id/pinned",
+            put(update_talk_room_pinned_handler::<M>),
+        )
+        .route(
+            "/talk-rooms/:talk_room_id/rsvp",
+            put(update_talk_room_rsvp_handler::<M>),
+        )
+        .route(
+            "/talk-rooms/:talk_room_id/nickname",
+            put(update_talk_room_nickname_handler::<M>),
+        )
+        .route(
+            "/talk-rooms/:talk_room_id/status",
+            put(change_talk_room_status_handler::<M>),
+        )
+        .route(
+            "/talk-rooms/:talk_room_id/notes",
+            get(get_talk_room_notes_handler::<M>).post(create_talk_room_note_handler::<M>),
+        )
+        .route(
+            "/talk-rooms/:talk_room_id/notes/:note_id",
+            put(update_talk_room_note_handler::<M>).delete(delete_talk_room_note_handler::<M>),
+        )
+        .route(
+            "/business-hours",
+            get(get_business_hours_handler::<M>).put(save_business_hours_handler::<M>),
+        )
+        .route(
+            "/canned-responses",
+            get(get_canned_responses_handler::<M>).post(create_canned_response_handler::<M>),
+        )
+        .route(
+            "/canned-responses/:canned_response_id",
+            put(update_canned_response_handler::<M>).delete(delete_canned_response_handler::<M>),
+        )
+        .route("/rich-menus/deploy", post(deploy_rich_menus_handler::<M>))
+        .route("/rich-menus/resync", post(resync_rich_menus_handler::<M>))
+        .route(
+            "/users/:primary_user_id/tags",
+            post(change_user_tags_handler::<M>),
+        )
+        .route(
+            "/users/:primary_user_id/identities",
+            post(link_user_identity_handler::<M>),
+        )
+        .route("/users/merge", post(merge_users_handler::<M>))
+        .route(
+            "/users/:primary_user_id/account-link",
+            delete(unlink_account_handler::<M>),
+        )
+        .layer(middleware::from_fn(require_staff_session::<_, M>));
+    // 会員システムなど、他のシステムから呼ばれるエンドポイント
+    let admin_router = Router::new()
+        .route("/staffs", post(bootstrap_staff_handler::<M>))
+        .route(
+            "/line-users/:line_id/account-link",
+            post(issue_account_link_login_url_handler::<M>),
+        )
+        .route(
+            "/account-link/nonces",
+            post(issue_account_link_nonce_handler::<M>),
+        )
+        .route(
+            "/talk-rooms/reconcile",
+            post(reconcile_talk_rooms_handler::<M>),
+        )
+        .layer(middleware::from_fn(require_admin_api_key::<_, M>));
+
+    Router::new()
+        .nest("/", root)
+        .nest("/linebot-webhook", line_webhook_router)
+        .nest("/auth", auth_router)
+        .nest("/staff", staff_router)
+        .nest("/admin", admin_router)
+        .layer(Extension(modules))
+        .layer(Extension(event_tasks))
 }
 
 async fn root() -> &'static str {
@@ -49,45 +284,54 @@ pub fn init_app() {
 #[cfg(test)]
 mod test {
     use super::*;
+    use adapter::module::{AdaptersModuleExt, InMemoryAdaptersModule};
     use axum_test::{
-        http::header::{HeaderName, HeaderValue},
+        http::{
+            header::{HeaderName, HeaderValue},
+            StatusCode,
+        },
         TestServer,
     };
     use base64::{engine::general_purpose, Engine as _};
+    use domain::{
+        model::user_auth::LineId,
+        repository::{talk_room::TalkRoomRepository, user::UserRepository},
+    };
     use hmac::{Hmac, Mac};
-    use presentation::model::line_webhook::LineWebhookEventRequests;
+    use presentation::{model::line_webhook::LineWebhookEventRequests, module::test::test_config};
     use sha2::Sha256;
+    use std::time::Duration;
+
+    fn sign(http_request_body: &[u8]) -> String {
+        let channel_secret = test_config().line.channel_secret;
+        // Compute the expected signature using the test channel secret and request body
+        let mut mac = Hmac::<Sha256>::new_from_slice(channel_secret.as_bytes()).unwrap();
+        mac.update(http_request_body);
+        let expected_signature = mac.finalize().into_bytes();
+        general_purpose::STANDARD.encode(expected_signature)
+    }
 
     /*
      * 空のリクエストを受信できるかテストする
      */
     #[tokio::test]
     async fn test_line_webhook_signature() {
-        init_app();
+        dotenv().ok();
         // DI
-        let modules = Modules::new().await;
+        // MySQLやfirestoreがなくても動かせるように、メモリを使う
+        let modules = Modules::in_memory(test_config());
         /*
          * テスト用のサーバーを作成する
          */
-        let line_webhook_router = Router::new().route("/", post(line_webhook_handler));
-        let test_app = Router::new()
-            .nest("/linebot-webhook", line_webhook_router)
-            .layer(Extension(Arc::new(modules)));
+        let test_app = router(Arc::new(modules), EventTasks::new());
         let test_server = TestServer::new(test_app.into_make_service()).unwrap();
         /*
          * signatureを作成する
          */
         let request =
             LineWebhookEventRequests::new("U00000000000000000000000000000000".to_string(), vec![]);
-        let channel_secret = env::var("LINE_CHANNEL_SECRET")
-            .unwrap_or_else(|_| panic!("LINE_CHANNEL_SECRET must be set!"));
-        let http_request_body_vec = serde_json::to_vec(&request).unwrap();
-        let http_request_body = http_request_body_vec.as_slice();
-        // Compute the expected signature using the test channel secret and request body
-        let mut mac = Hmac::<Sha256>::new_from_slice(channel_secret.as_bytes()).unwrap();
-        mac.update(http_request_body);
-        let expected_signature = mac.finalize().into_bytes();
-        let expected_signature_str = general_purpose::STANDARD.encode(expected_signature);
+        let http_request_body = serde_json::to_vec(&request).unwrap();
+        let expected_signature_str = sign(&http_request_body);
         /*
          * signatureの検証に成功するテスト用のリクエストを作成する
          */
@@ -113,4 +357,114 @@ mod test {
             .await;
         response.assert_status_unauthorized();
     }
+
+    /*
+     * 終了処理を始めたら、readyzとwebhookが503を返すかテストする
+     */
+    #[tokio::test]
+    async fn test_health_and_shutdown() {
+        let event_tasks = EventTasks::new();
+        let modules = Modules::in_memory(test_config());
+        let test_server =
+            TestServer::new(router(Arc::new(modules), event_tasks.clone()).into_make_service())
+                .unwrap();
+        test_server.get("/healthz").await.assert_status_ok();
+        test_server.get("/readyz").await.assert_status_ok();
+
+        event_tasks.close();
+        // 終了処理中でも、プロセスは動いている
+        test_server.get("/healthz").await.assert_status_ok();
+        test_server
+            .get("/readyz")
+            .await
+            .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        let request =
+            LineWebhookEventRequests::new("U00000000000000000000000000000000".to_string(), vec![]);
+        let http_request_body = serde_json::to_vec(&request).unwrap();
+        test_server
+            .post("/linebot-webhook")
+            .add_header(
+                HeaderName::from_lowercase(b"x-line-signature").unwrap(),
+                HeaderValue::from_str(&sign(&http_request_body)).unwrap(),
+            )
+            .json(&request)
+            .await
+            .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        event_tasks.wait().await;
+    }
+
+    /*
+     * フォローされたら、ユーザーとtalk_roomを作成し、あいさつを送るかテストする
+     */
+    #[tokio::test]
+    async fn test_follow_event_with_in_memory_adapters() {
+        dotenv().ok();
+        let adapters_module = Arc::new(InMemoryAdaptersModule::default());
+        let modules = Modules::from_adapters_module(adapters_module.clone(), test_config());
+        let test_server =
+            TestServer::new(router(Arc::new(modules), EventTasks::new()).into_make_service())
+                .unwrap();
+
+        let user_id = "U11111111111111111111111111111111";
+        let json = format!(
+            r#"
+            {{
+                "destination": "xxxxxxxxxx",
+                "events": [
+                    {{
+                        "replyToken": "nHuyWiB7yP5Zw52FIkcQobQuGDXCTA",
+                        "type": "follow",
+                        "mode": "active",
+                        "timestamp": 1462629479859,
+                        "source": {{
+                            "type": "user",
+                            "userId": "{}"
+                            }},
+                        "webhookEventId": "01FZ74A0TDDPYRVKNK77XKC3ZR",
+                        "deliveryContext": {{
+                            "isRedelivery": false
+                        }}
+                    }}
+                ]
+            }}
+            "#,
+            user_id
+        );
+        let response = test_server
+            .post("/linebot-webhook")
+            .add_header(
+                HeaderName::from_lowercase(b"x-line-signature").unwrap(),
+                HeaderValue::from_str(&sign(json.as_bytes())).unwrap(),
+            )
+            .bytes(json.into())
+            .await;
+        response.assert_status_ok();
+        /*
+         * イベントは非同期で処理されるので、あいさつを送り終わるまで待つ
+         */
+        let line_id = LineId(user_id.to_string());
+        let mut sent_messages = vec![];
+        for _ in 0..50 {
+            sent_messages = adapters_module.line().sent_messages().unwrap();
+            if !sent_messages.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        // LINEには送らず、送ったメッセージはメモリに残る
+        assert!(!sent_messages.is_empty());
+        assert!(sent_messages.iter().all(|m| m.to == line_id));
+
+        let user = adapters_module
+            .user_repository()
+            .get_line_user(line_id)
+            .await
+            .unwrap();
+        let talk_room = adapters_module
+            .talk_room_repository()
+            .get_talk_room(user.id)
+            .await
+            .unwrap();
+        assert!(talk_room.follow);
+    }
 }
@@ -1,3 +1,6 @@
+pub mod admin_auth;
 pub mod axum_helper;
 pub mod errors;
+pub mod event_tasks;
+pub mod staff_auth;
 pub mod validate;
@@ -0,0 +1,62 @@
+use crate::module::ModulesExt;
+use axum::{
+    extract::Extension,
+    http::{HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+use tracing::error;
+
+/// 管理用のエンドポイントはx-admin-api-keyヘッダーでADMIN_API_KEYと一致するか検証する
+pub async fn require_admin_api_key<B, M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    headers: HeaderMap,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, StatusCode> {
+    // ADMIN_API_KEYが設定されていない環境では管理用のエンドポイントを使えないようにする
+    let admin_api_key = modules.config().admin_api_key.as_ref().ok_or_else(|| {
+        error!("ADMIN_API_KEY is not set");
+        StatusCode::FORBIDDEN
+    })?;
+    let x_admin_api_key = headers
+        .get("x-admin-api-key")
+        .ok_or(StatusCode::UNAUTHORIZED)?
+        .as_bytes();
+    if !verify_admin_api_key(admin_api_key, x_admin_api_key) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(next.run(request).await)
+}
+
+// 一致した長さから推測されないように、HMACを取ってから一定時間で比べる
+fn verify_admin_api_key(admin_api_key: &str, x_admin_api_key: &[u8]) -> bool {
+    let mac = || {
+        Hmac::<Sha256>::new_from_slice(admin_api_key.as_bytes())
+            .expect("HMAC can take a key of any size")
+    };
+    let mut expected = mac();
+    expected.update(admin_api_key.as_bytes());
+    let mut actual = mac();
+    actual.update(x_admin_api_key);
+    actual
+        .verify_slice(&expected.finalize().into_bytes())
+        .is_ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_verify_admin_api_key() {
+        assert!(verify_admin_api_key("admin_api_key", b"admin_api_key"));
+        assert!(!verify_admin_api_key("admin_api_key", b"admin_api_kez"));
+        assert!(!verify_admin_api_key("admin_api_key", b"admin_api"));
+        assert!(!verify_admin_api_key("admin_api_key", b""));
+    }
+}
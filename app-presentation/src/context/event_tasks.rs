@@ -0,0 +1,96 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+/// webhookで受けたイベントを処理する、バックグラウンドのタスク
+/// 終了するときは新しいイベントを受け付けないようにして、処理中のタスクが終わるのを待つ
+#[derive(Clone)]
+pub struct EventTasks {
+    closed: CancellationToken,
+    // タスクはsenderのクローンを持ち、すべてのタスクが終わるとreceiverがNoneを受け取る
+    sender: Arc<Mutex<Option<mpsc::Sender<()>>>>,
+    receiver: Arc<tokio::sync::Mutex<mpsc::Receiver<()>>>,
+}
+
+impl EventTasks {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel(1);
+        Self {
+            closed: CancellationToken::new(),
+            sender: Arc::new(Mutex::new(Some(sender))),
+            receiver: Arc::new(tokio::sync::Mutex::new(receiver)),
+        }
+    }
+
+    /// タスクをバックグラウンドで実行する。closeしたあとは実行せず、falseを返す
+    pub fn spawn<F>(&self, future: F) -> bool
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let Some(sender) = self.sender.lock().unwrap().clone() else {
+            return false;
+        };
+        tokio::spawn(async move {
+            let output = future.await;
+            drop(sender);
+            output
+        });
+        true
+    }
+
+    /// 新しいタスクを受け付けないようにする
+    pub fn close(&self) {
+        self.sender.lock().unwrap().take();
+        self.closed.cancel();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.is_cancelled()
+    }
+
+    /// closeされるまで待つ
+    pub async fn closed(&self) {
+        self.closed.cancelled().await
+    }
+
+    /// closeしたあと、処理中のタスクがすべて終わるまで待つ
+    pub async fn wait(&self) {
+        self.receiver.lock().await.recv().await;
+    }
+}
+
+impl Default for EventTasks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_wait_for_running_tasks_after_close() {
+        let event_tasks = EventTasks::new();
+        let finished = Arc::new(AtomicBool::new(false));
+        let cloned_finished = finished.clone();
+        assert!(event_tasks.spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cloned_finished.store(true, Ordering::SeqCst);
+        }));
+
+        event_tasks.close();
+        assert!(event_tasks.is_closed());
+        // closeしたあとは新しいタスクを実行しない
+        assert!(!event_tasks.spawn(async {}));
+
+        tokio::time::timeout(Duration::from_secs(1), event_tasks.wait())
+            .await
+            .unwrap();
+        assert!(finished.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::module::ModulesExt;
+use application::usecase::UseCaseError;
+use axum::{
+    extract::Extension,
+    http::{header::AUTHORIZATION, HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use domain::model::{
+    message::send_message::NewSendSender,
+    staff::{Staff, StaffPermission},
+};
+use domain::repository::RepositoryError;
+use std::sync::Arc;
+use tracing::{error, warn};
+
+/// ログインしているスタッフ
+/// require_staff_sessionを通ったハンドラーでExtensionとして受け取れる
+#[derive(Debug, Clone)]
+pub struct AuthenticatedStaff(pub Staff);
+
+impl AuthenticatedStaff {
+    /// スタッフのロールで許可されていない操作は403にする
+    pub fn require(&self, permission: StaffPermission) -> Result<(), StatusCode> {
+        if self.0.can(permission) {
+            Ok(())
+        } else {
+            warn!(
+                "Staff {} ({}) is not allowed to {:?}",
+                self.0.id.0,
+                self.0.role.as_str(),
+                permission
+            );
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+
+    /// 手動で送るメッセージの送信者
+    pub fn sender(&self) -> NewSendSender {
+        NewSendSender::from(self.0.clone())
+    }
+}
+
+/// オペレーター用のエンドポイントはAuthorization: Bearerのアクセストークンでスタッフを認証する
+pub async fn require_staff_session<B, M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    headers: HeaderMap,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, StatusCode> {
+    let access_token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let staff = modules
+        .staff_usecase()
+        .authenticate(access_token)
+        .await
+        .map_err(|err| match err {
+            UseCaseError::Repository(RepositoryError::NotAuthFound(_)) => StatusCode::UNAUTHORIZED,
+            _ => {
+                error!("Failed to authenticate staff: {:?}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+    request.extensions_mut().insert(AuthenticatedStaff(staff));
+
+    Ok(next.run(request).await)
+}
@@ -0,0 +1,2 @@
+pub mod talk_room_reconcile;
+pub mod user_profile;
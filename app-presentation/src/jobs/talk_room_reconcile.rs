@@ -0,0 +1,37 @@
+use crate::module::ModulesExt;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// MySQLのtalk_roomsとfirestoreのドキュメントの食い違いを、定期的に探すジョブを起動する
+/// TALK_ROOM_RECONCILE_INTERVAL_SECSごとに実行し、TALK_ROOM_RECONCILE_REPAIRがtrueのときは自動で直せるものを直す
+pub fn spawn_reconcile_talk_rooms<M: ModulesExt>(modules: Arc<M>) -> JoinHandle<()> {
+    let reconcile_interval = modules.config().jobs.talk_room_reconcile_interval;
+    let repair = modules.config().jobs.talk_room_reconcile_repair;
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(reconcile_interval);
+        loop {
+            interval.tick().await;
+            match modules
+                .talk_room_reconcile_usecase()
+                .reconcile_talk_rooms(repair)
+                .await
+            {
+                Ok(report) => {
+                    for inconsistency in &report.inconsistencies {
+                        warn!("Found talk room inconsistency: {:?}", inconsistency);
+                    }
+                    info!(
+                        "Reconciled talk rooms: checked {}, inconsistent {}, repaired {}, failed {}",
+                        report.checked_count,
+                        report.inconsistencies.len(),
+                        report.repaired_count,
+                        report.failed_count
+                    );
+                }
+                Err(err) => error!("Failed to reconcile talk rooms: {:?}", err),
+            }
+        }
+    })
+}
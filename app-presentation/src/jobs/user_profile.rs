@@ -0,0 +1,32 @@
+use crate::module::ModulesExt;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+// 1回の実行で取得し直すユーザー数。LINEのプロフィール取得APIのレート制限に余裕をもたせる
+const REFRESH_BATCH_SIZE: i64 = 500;
+
+/// LINEのプロフィールを定期的に取得し直すジョブを起動する
+/// LINE_PROFILE_REFRESH_INTERVAL_SECSごとに、LINE_PROFILE_STALE_AFTER_SECS以上更新されていないユーザーを対象にする
+pub fn spawn_refresh_line_user_profiles<M: ModulesExt>(modules: Arc<M>) -> JoinHandle<()> {
+    let refresh_interval = modules.config().jobs.line_profile_refresh_interval;
+    let stale_after = modules.config().jobs.line_profile_stale_after;
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(refresh_interval);
+        loop {
+            interval.tick().await;
+            match modules
+                .user_profile_usecase()
+                .refresh_stale_line_user_profiles(stale_after, REFRESH_BATCH_SIZE)
+                .await
+            {
+                Ok(refreshed) => info!(
+                    "Refreshed line user profiles: refreshed {}, failed {}",
+                    refreshed.refreshed_count, refreshed.failed_count
+                ),
+                Err(err) => error!("Failed to refresh line user profiles: {:?}", err),
+            }
+        }
+    })
+}
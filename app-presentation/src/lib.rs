@@ -1,4 +1,5 @@
 pub mod context;
+pub mod jobs;
 pub mod model;
 pub mod module;
 pub mod routes;
@@ -0,0 +1,27 @@
+use application::model::account_link::CreateAccountLinkNonce;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountLinkNonceRequest {
+    pub link_token: String,
+    pub external_member_id: String,
+}
+
+impl From<AccountLinkNonceRequest> for CreateAccountLinkNonce {
+    fn from(r: AccountLinkNonceRequest) -> Self {
+        CreateAccountLinkNonce::new(r.link_token, r.external_member_id)
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountLinkLoginUrlResponse {
+    pub login_url: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountLinkRedirectResponse {
+    pub redirect_url: String,
+}
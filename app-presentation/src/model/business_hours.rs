@@ -0,0 +1,211 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Weekday};
+use domain::model::business_hours::{
+    weekday_name, BusinessHoursCalendar, BusinessHoursException, BusinessHoursRange,
+    BusinessHoursState, NewBusinessHoursCalendar, WeeklyBusinessHours,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+const TIME_FORMAT: &str = "%H:%M";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+// LINEのテキストメッセージの上限
+const MAX_AWAY_MESSAGE_LENGTH: usize = 5000;
+
+/// 時刻はtimeZoneの現地時刻をHH:MMで、日付はYYYY-MM-DDで指定する
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessHoursCalendarRequest {
+    pub time_zone: String,
+    pub weekly_hours: Vec<WeeklyBusinessHoursRequest>,
+    #[serde(default)]
+    pub exceptions: Vec<BusinessHoursExceptionRequest>,
+    pub away_message: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklyBusinessHoursRequest {
+    pub weekday: String,
+    pub open: String,
+    pub close: String,
+}
+
+/// hoursを空にすると休業日になる
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessHoursExceptionRequest {
+    pub date: String,
+    pub hours: Vec<BusinessHoursRangeRequest>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessHoursRangeRequest {
+    pub open: String,
+    pub close: String,
+}
+
+impl TryFrom<BusinessHoursCalendarRequest> for NewBusinessHoursCalendar {
+    type Error = anyhow::Error;
+    fn try_from(r: BusinessHoursCalendarRequest) -> anyhow::Result<Self> {
+        let weekly_hours = r
+            .weekly_hours
+            .into_iter()
+            .map(|w| {
+                let weekday = w
+                    .weekday
+                    .parse::<Weekday>()
+                    .map_err(|_| anyhow!("Unknown weekday: {}", w.weekday))?;
+                Ok(WeeklyBusinessHours::new(
+                    weekday,
+                    parse_range(&w.open, &w.close)?,
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let exceptions = r
+            .exceptions
+            .into_iter()
+            .map(|e| {
+                let hours = e
+                    .hours
+                    .iter()
+                    .map(|h| parse_range(&h.open, &h.close))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                Ok(BusinessHoursException::new(
+                    NaiveDate::parse_from_str(&e.date, DATE_FORMAT)?,
+                    hours,
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        // 同じ日の例外が複数あると、どれを使うか決まらない
+        let mut dates = HashSet::new();
+        if let Some(e) = exceptions.iter().find(|e| !dates.insert(e.date)) {
+            return Err(anyhow!("Duplicate exception date: {}", e.date));
+        }
+        // 空の不在メッセージは送らない
+        let away_message = r
+            .away_message
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty());
+        if away_message
+            .as_ref()
+            .is_some_and(|m| m.chars().count() > MAX_AWAY_MESSAGE_LENGTH)
+        {
+            return Err(anyhow!(
+                "Away message must be at most {} characters",
+                MAX_AWAY_MESSAGE_LENGTH
+            ));
+        }
+
+        Ok(NewBusinessHoursCalendar::new(
+            r.time_zone.parse()?,
+            weekly_hours,
+            exceptions,
+            away_message,
+        ))
+    }
+}
+
+fn parse_range(open: &str, close: &str) -> anyhow::Result<BusinessHoursRange> {
+    BusinessHoursRange::new(
+        NaiveTime::parse_from_str(open, TIME_FORMAT)?,
+        NaiveTime::parse_from_str(close, TIME_FORMAT)?,
+    )
+}
+
+/// 営業時間を登録していないときは、calendarはnullで常に営業時間内になる
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessHoursResponse {
+    pub calendar: Option<BusinessHoursCalendarResponse>,
+    pub state: BusinessHoursStateResponse,
+}
+
+impl BusinessHoursResponse {
+    pub fn new(calendar: Option<BusinessHoursCalendar>, now: DateTime<Local>) -> Self {
+        let state = calendar
+            .as_ref()
+            .map(|c| c.state(now))
+            .unwrap_or_else(BusinessHoursState::always_open);
+        Self {
+            calendar: calendar.map(BusinessHoursCalendarResponse::from),
+            state: state.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessHoursCalendarResponse {
+    pub time_zone: String,
+    pub weekly_hours: Vec<WeeklyBusinessHoursResponse>,
+    pub exceptions: Vec<BusinessHoursExceptionResponse>,
+    pub away_message: Option<String>,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklyBusinessHoursResponse {
+    pub weekday: String,
+    pub open: String,
+    pub close: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessHoursExceptionResponse {
+    pub date: String,
+    pub hours: Vec<BusinessHoursRangeRequest>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessHoursStateResponse {
+    pub open: bool,
+    pub off_hours_since: Option<String>,
+}
+
+impl From<BusinessHoursCalendar> for BusinessHoursCalendarResponse {
+    fn from(c: BusinessHoursCalendar) -> Self {
+        Self {
+            time_zone: c.time_zone.name().to_string(),
+            weekly_hours: c
+                .weekly_hours
+                .into_iter()
+                .map(|w| WeeklyBusinessHoursResponse {
+                    weekday: weekday_name(w.weekday).to_string(),
+                    open: w.hours.open.format(TIME_FORMAT).to_string(),
+                    close: w.hours.close.format(TIME_FORMAT).to_string(),
+                })
+                .collect(),
+            exceptions: c
+                .exceptions
+                .into_iter()
+                .map(|e| BusinessHoursExceptionResponse {
+                    date: e.date.format(DATE_FORMAT).to_string(),
+                    hours: e
+                        .hours
+                        .into_iter()
+                        .map(|h| BusinessHoursRangeRequest {
+                            open: h.open.format(TIME_FORMAT).to_string(),
+                            close: h.close.format(TIME_FORMAT).to_string(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            away_message: c.away_message,
+            updated_at: c.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+impl From<BusinessHoursState> for BusinessHoursStateResponse {
+    fn from(s: BusinessHoursState) -> Self {
+        Self {
+            open: s.open,
+            off_hours_since: s.off_hours_since.map(|t| t.to_rfc3339()),
+        }
+    }
+}
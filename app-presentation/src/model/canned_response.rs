@@ -0,0 +1,57 @@
+use anyhow::anyhow;
+use domain::model::canned_response::CannedResponse;
+use serde::{Deserialize, Serialize};
+
+const MAX_TITLE_LENGTH: usize = 100;
+// LINEのテキストメッセージの上限
+const MAX_BODY_LENGTH: usize = 5000;
+
+/// 本文には{display_name}、{nickname}、{staff_name}のプレースホルダーを使える
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CannedResponseRequest {
+    pub title: String,
+    pub body: String,
+}
+
+impl CannedResponseRequest {
+    /// 空のタイトルや本文、長すぎる本文、使えないプレースホルダーはエラーにする
+    pub fn validate(&self) -> anyhow::Result<(String, String)> {
+        let title = self.title.trim();
+        if title.is_empty() || title.chars().count() > MAX_TITLE_LENGTH {
+            return Err(anyhow!(
+                "Title must be 1 to {} characters",
+                MAX_TITLE_LENGTH
+            ));
+        }
+        if self.body.trim().is_empty() || self.body.chars().count() > MAX_BODY_LENGTH {
+            return Err(anyhow!("Body must be 1 to {} characters", MAX_BODY_LENGTH));
+        }
+        CannedResponse::validate_body(&self.body)?;
+        Ok((title.to_string(), self.body.clone()))
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CannedResponseResponse {
+    pub id: i64,
+    pub title: String,
+    pub body: String,
+    pub created_by: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<CannedResponse> for CannedResponseResponse {
+    fn from(c: CannedResponse) -> Self {
+        Self {
+            id: c.id.0,
+            title: c.title,
+            body: c.body,
+            created_by: c.created_by.0,
+            created_at: c.created_at.to_rfc3339(),
+            updated_at: c.updated_at.to_rfc3339(),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde::{de::DeserializeOwned, Serialize};
+
+// ページングのカーソルは、クライアントに中身を見せないようにbase64でエンコードしたJSONにする
+pub fn encode_cursor<T: Serialize>(cursor: &T) -> String {
+    let json = serde_json::to_vec(cursor).unwrap_or_default();
+    general_purpose::URL_SAFE_NO_PAD.encode(json)
+}
+
+pub fn decode_cursor<T: DeserializeOwned>(cursor: &str) -> anyhow::Result<T> {
+    let json = general_purpose::URL_SAFE_NO_PAD.decode(cursor)?;
+    Ok(serde_json::from_slice(&json)?)
+}
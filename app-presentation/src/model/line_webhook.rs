@@ -1,12 +1,14 @@
 use application::model::{
     event::{
-        CreateEvent, CreateEventContentProvider, CreateEventContentProviderExternal,
-        CreateEventDeliveryContext, CreateEventEmoji, CreateEventFollow, CreateEventImageSet,
-        CreateEventMessage, CreateEventMessageContent, CreateEventMessageContentAudio,
-        CreateEventMessageContentFile, CreateEventMessageContentImage,
-        CreateEventMessageContentLocation, CreateEventMessageContentSticker,
-        CreateEventMessageContentText, CreateEventMessageContentVideo, CreateEventPostback,
-        CreateEventPostbackContent, CreateEventPostbackParams, CreateEventPostbackParamsDatetime,
+        CreateEvent, CreateEventAccountLink, CreateEventAccountLinkContent,
+        CreateEventAccountLinkResult, CreateEventContentProvider,
+        CreateEventContentProviderExternal, CreateEventDeliveryContext, CreateEventEmoji,
+        CreateEventFollow, CreateEventImageSet, CreateEventMessage, CreateEventMessageContent,
+        CreateEventMessageContentAudio, CreateEventMessageContentFile,
+        CreateEventMessageContentImage, CreateEventMessageContentLocation,
+        CreateEventMessageContentSticker, CreateEventMessageContentText,
+        CreateEventMessageContentVideo, CreateEventPostback, CreateEventPostbackContent,
+        CreateEventPostbackParams, CreateEventPostbackParamsDatetime,
         CreateEventPostbackParamsRichMenu, CreateEventStickerResourceType, CreateEventUnfollow,
         CreateEventVideoPlayComplete, CreateEventVideoPlayCompleteContent, CreateUserEvent,
     },
@@ -53,6 +55,8 @@ pub enum LineWebhookEvent {
     VideoPlayComplete(LineWebhookEventVideoPlayComplete),
     #[serde(rename(deserialize = "message"))]
     Message(LineWebhookEventMessage),
+    #[serde(rename(deserialize = "accountLink"))]
+    AccountLink(LineWebhookEventAccountLink),
 }
 
 impl LineWebhookEvent {
@@ -63,6 +67,7 @@ impl LineWebhookEvent {
             LineWebhookEvent::Postback(e) => e.user_id(),
             LineWebhookEvent::VideoPlayComplete(e) => e.user_id(),
             LineWebhookEvent::Message(e) => e.user_id(),
+            LineWebhookEvent::AccountLink(e) => e.user_id(),
         }
     }
 }
@@ -211,6 +216,45 @@ impl LineWebhookEventMessage {
     }
 }
 
+// 連携に失敗したときはreplyTokenが含まれない
+// https://developers.line.biz/ja/reference/messaging-api/#account-link-event
+#[derive(Serialize, Deserialize, Debug, Clone, Validate)]
+#[cfg_attr(test, derive(Dummy))]
+pub struct LineWebhookEventAccountLink {
+    #[serde(rename(deserialize = "replyToken"))]
+    reply_token: Option<String>,
+    mode: String,
+    timestamp: i64,
+    source: LineWebhookEventSource,
+    #[serde(rename(deserialize = "webhookEventId"))]
+    webhook_event_id: String,
+    #[serde(rename(deserialize = "deliveryContext"))]
+    delivery_context: LineWebhookEventDeliveryContext,
+    link: LineWebhookEventAccountLinkContent,
+}
+
+impl LineWebhookEventAccountLink {
+    pub fn user_id(&self) -> &String {
+        self.source.user_id()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(Dummy))]
+struct LineWebhookEventAccountLinkContent {
+    result: LineWebhookEventAccountLinkResult,
+    nonce: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(test, derive(Dummy))]
+enum LineWebhookEventAccountLinkResult {
+    #[serde(rename(deserialize = "ok"))]
+    Ok,
+    #[serde(rename(deserialize = "failed"))]
+    Failed,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Display)]
 #[cfg_attr(test, derive(Dummy))]
 #[serde(tag = "type")]
@@ -290,6 +334,8 @@ enum LineWebhookEventMessageContent {
 struct LineWebhookEventMessageContentText {
     id: String,
     text: String,
+    // 絵文字を含まないメッセージではemojisが送られてこない
+    #[serde(default)]
     emojis: Vec<LineWebhookEventEmoji>,
     mention: Option<LineWebhookEventMention>,
 }
@@ -467,6 +513,7 @@ impl From<LineWebhookEventRequest> for CreateUserEvent {
             LineWebhookEvent::Postback(s) => CreateEvent::Postback(s.into()),
             LineWebhookEvent::VideoPlayComplete(s) => CreateEvent::VideoPlayComplete(s.into()),
             LineWebhookEvent::Message(s) => CreateEvent::Message(s.into()),
+            LineWebhookEvent::AccountLink(s) => CreateEvent::AccountLink(s.into()),
         };
         Self {
             create_line_user_auth: CreateLineUserAuth {
@@ -513,7 +560,8 @@ impl From<LineWebhookEventPostback> for CreateEventPostback {
             },
             postback: CreateEventPostbackContent {
                 data: s.postback.clone().data,
-                params: s.postback.clone().params.unwrap().into(),
+                // リッチメニューのpostbackなど、日時を選ばないpostbackにはparamsがない
+                params: s.postback.clone().params.map(|p| p.into()),
             },
             mode: s.mode,
             webhook_event_id: s.webhook_event_id,
@@ -577,6 +625,29 @@ impl From<LineWebhookEventVideoPlayComplete> for CreateEventVideoPlayComplete {
     }
 }
 
+impl From<LineWebhookEventAccountLink> for CreateEventAccountLink {
+    fn from(s: LineWebhookEventAccountLink) -> Self {
+        Self {
+            reply_token: s.reply_token,
+            delivery_context: CreateEventDeliveryContext {
+                is_redelivery: s.delivery_context.is_redelivery,
+            },
+            link: CreateEventAccountLinkContent {
+                result: match s.link.result {
+                    LineWebhookEventAccountLinkResult::Ok => CreateEventAccountLinkResult::Ok,
+                    LineWebhookEventAccountLinkResult::Failed => {
+                        CreateEventAccountLinkResult::Failed
+                    }
+                },
+                nonce: s.link.nonce,
+            },
+            mode: s.mode,
+            webhook_event_id: s.webhook_event_id,
+            timestamp: s.timestamp,
+        }
+    }
+}
+
 impl From<LineWebhookEventMessage> for CreateEventMessage {
     fn from(s: LineWebhookEventMessage) -> Self {
         Self {
@@ -0,0 +1,329 @@
+use crate::model::cursor::{decode_cursor, encode_cursor};
+use anyhow::anyhow;
+use application::model::manual_message::CreateManualMessage;
+use chrono::{DateTime, Local};
+use domain::model::{
+    canned_response::CannedResponseId,
+    message::{
+        event::{Event, EventAccountLinkResult, EventMessageContent},
+        send_message::{NewSendMessages, SendMessage, SendMessages, SendSendingMethod},
+        system_event::{SystemEvent, SystemEventContent},
+        MessageCursor, Messages, MessagesPage,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagesQuery {
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+impl MessagesQuery {
+    pub fn cursor(&self) -> anyhow::Result<Option<MessageCursor>> {
+        self.cursor
+            .as_deref()
+            .map(decode_message_cursor)
+            .transpose()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct MessageCursorJson {
+    created_at: String,
+    id: String,
+}
+
+pub fn encode_message_cursor(cursor: &MessageCursor) -> String {
+    encode_cursor(&MessageCursorJson {
+        created_at: cursor.created_at.to_rfc3339(),
+        id: cursor.id.clone(),
+    })
+}
+
+pub fn decode_message_cursor(cursor: &str) -> anyhow::Result<MessageCursor> {
+    let cursor: MessageCursorJson = decode_cursor(cursor)?;
+    let created_at = DateTime::parse_from_rfc3339(&cursor.created_at)?.with_timezone(&Local);
+    Ok(MessageCursor::new(created_at, cursor.id))
+}
+
+// LINEのテキストメッセージの上限
+const MAX_TEXT_LENGTH: usize = 5000;
+
+/// スタッフが手動で送るメッセージ
+/// textか、定型文のcannedResponseIdのどちらかを指定する
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SendManualMessageRequest {
+    pub text: Option<String>,
+    pub canned_response_id: Option<i64>,
+}
+
+impl TryFrom<SendManualMessageRequest> for CreateManualMessage {
+    type Error = anyhow::Error;
+    fn try_from(r: SendManualMessageRequest) -> anyhow::Result<Self> {
+        match (r.text, r.canned_response_id) {
+            (Some(text), None) => {
+                if text.trim().is_empty() || text.chars().count() > MAX_TEXT_LENGTH {
+                    return Err(anyhow!("Text must be 1 to {} characters", MAX_TEXT_LENGTH));
+                }
+                Ok(CreateManualMessage::Text(text))
+            }
+            (None, Some(id)) => Ok(CreateManualMessage::CannedResponse(CannedResponseId::new(
+                id,
+            ))),
+            _ => Err(anyhow!("Specify either text or cannedResponseId")),
+        }
+    }
+}
+
+/// 手動で送ったメッセージのID
+/// 送ったメッセージの中身は、メッセージ履歴か受信箱の変更で受け取る
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SentManualMessagesResponse {
+    pub message_ids: Vec<String>,
+}
+
+impl From<Vec<NewSendMessages>> for SentManualMessagesResponse {
+    fn from(v: Vec<NewSendMessages>) -> Self {
+        Self {
+            message_ids: v.into_iter().map(|m| m.id.value.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagesResponse {
+    // 新しい順
+    pub messages: Vec<MessageResponse>,
+    // 続きがないときはnull
+    pub next_cursor: Option<String>,
+}
+
+impl From<MessagesPage> for MessagesResponse {
+    fn from(p: MessagesPage) -> Self {
+        Self {
+            messages: p.messages.into_iter().map(|m| m.into()).collect(),
+            next_cursor: p.next_cursor.as_ref().map(encode_message_cursor),
+        }
+    }
+}
+
+/// イベント、送信メッセージ、システムイベントを同じ形で返す
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageResponse {
+    pub id: String,
+    // event、sendMessages、systemEventのいずれか
+    pub kind: String,
+    pub sender: MessageSenderResponse,
+    // 送信メッセージのときだけreplyかpushが入る
+    pub sending_method: Option<String>,
+    // 送信メッセージは1回で複数送れるので配列にする
+    pub contents: Vec<MessageContentResponse>,
+    pub system_event: Option<SystemEventResponse>,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageSenderResponse {
+    // user、bot、staff、systemのいずれか
+    pub role: String,
+    pub staff_id: Option<i64>,
+    pub name: Option<String>,
+    pub picture_url: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageContentResponse {
+    // follow、postback、text、imageなど
+    #[serde(rename = "type")]
+    pub content_type: String,
+    // LINEのメッセージID。メッセージ以外のイベントにはない
+    pub line_message_id: Option<String>,
+    // 一覧に表示するテキスト。テキストがない種類ではnull
+    pub text: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemEventResponse {
+    // assigned、unassigned、statusChanged、modeChangedのいずれか
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub assignee: Option<i64>,
+    pub previous_assignee: Option<i64>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl MessageSenderResponse {
+    fn role(role: &str, staff_id: Option<i64>) -> Self {
+        Self {
+            role: role.to_string(),
+            staff_id,
+            name: None,
+            picture_url: None,
+        }
+    }
+}
+
+impl MessageContentResponse {
+    fn new(content_type: &str, line_message_id: Option<String>, text: Option<String>) -> Self {
+        Self {
+            content_type: content_type.to_string(),
+            line_message_id,
+            text,
+        }
+    }
+}
+
+impl From<Messages> for MessageResponse {
+    fn from(m: Messages) -> Self {
+        let id = m.id();
+        let created_at = m.created_at().to_rfc3339();
+        match m {
+            Messages::Event(e) => MessageResponse {
+                id,
+                kind: "event".to_string(),
+                sender: MessageSenderResponse::role("user", None),
+                sending_method: None,
+                contents: vec![MessageContentResponse::from(e)],
+                system_event: None,
+                created_at,
+            },
+            Messages::SendMessages(s) => MessageResponse::from_send_messages(id, s, created_at),
+            Messages::SystemEvent(s) => MessageResponse {
+                id,
+                kind: "systemEvent".to_string(),
+                sender: MessageSenderResponse::role("system", s.operator.map(|o| o.0)),
+                sending_method: None,
+                contents: vec![],
+                system_event: Some(s.into()),
+                created_at,
+            },
+        }
+    }
+}
+
+impl MessageResponse {
+    fn from_send_messages(id: String, s: SendMessages, created_at: String) -> Self {
+        // botが送ったメッセージにはsenderがない
+        let sender = match s.sender {
+            Some(sender) => MessageSenderResponse {
+                role: "staff".to_string(),
+                staff_id: Some(sender.id),
+                name: Some(sender.name),
+                picture_url: Some(sender.picture_url),
+            },
+            None => MessageSenderResponse::role("bot", None),
+        };
+        let sending_method = match s.sending_method {
+            SendSendingMethod::Reply => "reply",
+            SendSendingMethod::Push => "push",
+        };
+        MessageResponse {
+            id,
+            kind: "sendMessages".to_string(),
+            sender,
+            sending_method: Some(sending_method.to_string()),
+            contents: s.messages.into_iter().map(|m| m.into()).collect(),
+            system_event: None,
+            created_at,
+        }
+    }
+}
+
+impl From<Event> for MessageContentResponse {
+    fn from(e: Event) -> Self {
+        match e {
+            Event::Follow(_) => MessageContentResponse::new("follow", None, None),
+            Event::Unfollow(_) => MessageContentResponse::new("unfollow", None, None),
+            Event::Postback(p) => {
+                MessageContentResponse::new("postback", None, Some(p.postback.data))
+            }
+            Event::VideoPlayComplete(_) => {
+                MessageContentResponse::new("videoPlayComplete", None, None)
+            }
+            Event::AccountLink(a) => {
+                let result = match a.link.result {
+                    EventAccountLinkResult::Ok => "ok",
+                    EventAccountLinkResult::Failed => "failed",
+                };
+                MessageContentResponse::new("accountLink", None, Some(result.to_string()))
+            }
+            Event::Message(m) => {
+                let line_message_id = Some(m.message.id().clone());
+                let (content_type, text) = match m.message {
+                    EventMessageContent::Text(c) => ("text", Some(c.text)),
+                    EventMessageContent::Image(_) => ("image", None),
+                    EventMessageContent::Video(_) => ("video", None),
+                    EventMessageContent::Audio(_) => ("audio", None),
+                    EventMessageContent::File(c) => ("file", Some(c.file_name)),
+                    EventMessageContent::Location(c) => ("location", Some(c.title)),
+                    EventMessageContent::Sticker(c) => ("sticker", c.text),
+                };
+                MessageContentResponse::new(content_type, line_message_id, text)
+            }
+        }
+    }
+}
+
+impl From<SendMessage> for MessageContentResponse {
+    fn from(m: SendMessage) -> Self {
+        let line_message_id = Some(m.message_id().clone());
+        let (content_type, text) = match m {
+            SendMessage::Text(c) => ("text", Some(c.text)),
+            SendMessage::Sticker(_) => ("sticker", None),
+            SendMessage::Image(_) => ("image", None),
+            SendMessage::Video(_) => ("video", None),
+            SendMessage::Audio(_) => ("audio", None),
+            SendMessage::Location(c) => ("location", Some(c.title)),
+            SendMessage::Imagemap(c) => ("imagemap", Some(c.alt_text)),
+            SendMessage::Template(c) => ("template", Some(c.alt_text)),
+        };
+        MessageContentResponse::new(content_type, line_message_id, text)
+    }
+}
+
+impl From<SystemEvent> for SystemEventResponse {
+    fn from(s: SystemEvent) -> Self {
+        match s.content {
+            SystemEventContent::Assigned(a) => SystemEventResponse {
+                event_type: "assigned".to_string(),
+                assignee: Some(a.assignee.0),
+                previous_assignee: a.previous_assignee.map(|p| p.0),
+                from: None,
+                to: None,
+            },
+            SystemEventContent::Unassigned(u) => SystemEventResponse {
+                event_type: "unassigned".to_string(),
+                assignee: None,
+                previous_assignee: Some(u.previous_assignee.0),
+                from: None,
+                to: None,
+            },
+            SystemEventContent::StatusChanged(c) => SystemEventResponse {
+                event_type: "statusChanged".to_string(),
+                assignee: None,
+                previous_assignee: None,
+                from: Some(c.from.as_str().to_string()),
+                to: Some(c.to.as_str().to_string()),
+            },
+            // operatorがないときは、ユーザーが薬剤師との会話を求めた
+            SystemEventContent::ModeChanged(c) => SystemEventResponse {
+                event_type: "modeChanged".to_string(),
+                assignee: None,
+                previous_assignee: None,
+                from: Some(c.from.as_str().to_string()),
+                to: Some(c.to.as_str().to_string()),
+            },
+        }
+    }
+}
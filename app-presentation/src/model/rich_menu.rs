@@ -0,0 +1,87 @@
+use adapter::model::rich_menu::{RichMenuAreaRequest, RichMenuSizeRequest};
+use application::model::rich_menu::{DeployedRichMenu, SyncedRichMenu};
+use domain::model::rich_menu::{NewRichMenu, RichMenuImageContentType};
+use serde::{Deserialize, Serialize};
+
+/*
+ * リッチメニューの定義ファイル
+ * size, areasなどはLINEのリッチメニューオブジェクトと同じ形式で書く
+ */
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RichMenuDefinitionsRequest {
+    pub rich_menus: Vec<RichMenuDefinitionRequest>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RichMenuDefinitionRequest {
+    pub name: String,
+    pub chat_bar_text: String,
+    #[serde(default)]
+    pub selected: bool,
+    pub size: RichMenuSizeRequest,
+    pub areas: Vec<RichMenuAreaRequest>,
+    // 定義ファイルからの相対パス
+    pub image_path: String,
+    pub alias_id: Option<String>,
+    #[serde(default)]
+    pub default: bool,
+}
+
+impl RichMenuDefinitionRequest {
+    pub fn new_rich_menu(&self) -> NewRichMenu {
+        NewRichMenu::new(
+            self.size.clone().into(),
+            self.selected,
+            self.name.clone(),
+            self.chat_bar_text.clone(),
+            self.areas.iter().map(|a| a.clone().into()).collect(),
+        )
+    }
+
+    pub fn image_content_type(&self) -> Option<RichMenuImageContentType> {
+        let extension = self.image_path.rsplit('.').next()?.to_lowercase();
+        match extension.as_str() {
+            "png" => Some(RichMenuImageContentType::Png),
+            "jpg" | "jpeg" => Some(RichMenuImageContentType::Jpeg),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployedRichMenuResponse {
+    pub rich_menu_id: String,
+    pub name: String,
+    pub alias_id: Option<String>,
+    pub default: bool,
+}
+
+impl From<DeployedRichMenu> for DeployedRichMenuResponse {
+    fn from(s: DeployedRichMenu) -> Self {
+        Self {
+            rich_menu_id: s.rich_menu.id.0,
+            name: s.rich_menu.name,
+            alias_id: s.alias_id.map(|a| a.0),
+            default: s.is_default,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncedRichMenuResponse {
+    pub alias_id: Option<String>,
+    pub user_count: usize,
+}
+
+impl From<SyncedRichMenu> for SyncedRichMenuResponse {
+    fn from(s: SyncedRichMenu) -> Self {
+        Self {
+            alias_id: s.alias_id.map(|a| a.0),
+            user_count: s.user_count,
+        }
+    }
+}
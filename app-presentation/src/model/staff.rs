@@ -0,0 +1,76 @@
+use application::model::staff::{CreateStaff, StaffSession};
+use domain::model::staff::Staff;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StaffLoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateStaffRequest {
+    pub name: String,
+    pub email: String,
+    pub password: String,
+    #[serde(default)]
+    pub picture_url: String,
+    pub role: String,
+}
+
+impl From<CreateStaffRequest> for CreateStaff {
+    fn from(r: CreateStaffRequest) -> Self {
+        CreateStaff::new(r.name, r.email, r.password, r.picture_url, r.role)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StaffRoleRequest {
+    pub role: String,
+}
+
+// パスワードのハッシュは返さない
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StaffResponse {
+    pub id: i64,
+    pub name: String,
+    pub email: String,
+    pub picture_url: String,
+    pub role: String,
+}
+
+impl From<Staff> for StaffResponse {
+    fn from(s: Staff) -> Self {
+        Self {
+            id: s.id.0,
+            name: s.name,
+            email: s.email.0,
+            picture_url: s.picture_url,
+            role: s.role.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StaffSessionResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_at: String,
+    pub staff: StaffResponse,
+}
+
+impl From<StaffSession> for StaffSessionResponse {
+    fn from(s: StaffSession) -> Self {
+        Self {
+            access_token: s.access_token,
+            token_type: "Bearer".to_string(),
+            expires_at: s.expires_at.to_rfc3339(),
+            staff: StaffResponse::from(s.staff),
+        }
+    }
+}
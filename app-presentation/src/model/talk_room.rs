@@ -0,0 +1,300 @@
+use crate::model::cursor::{decode_cursor, encode_cursor};
+use anyhow::anyhow;
+use chrono::{DateTime, Local};
+use domain::model::{
+    staff::StaffId,
+    talk_room::{TalkRoom, TalkRoomStatus},
+    talk_room_card::{
+        LatestMessagePreview, TalkRoomAssigneeFilter, TalkRoomCard, TalkRoomCardPage,
+        TalkRoomCursor, TalkRoomFilter, TalkRoomReadMarker,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomAssigneeRequest {
+    pub staff_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomStatusRequest {
+    pub status: String,
+}
+
+/// bot、human、botOutsideBusinessHoursのいずれか
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomModeRequest {
+    pub mode: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomWorkflowResponse {
+    pub talk_room_id: String,
+    pub primary_user_id: String,
+    pub assignee: Option<i64>,
+    pub status: String,
+    pub mode: String,
+    pub updated_at: String,
+}
+
+impl From<TalkRoom> for TalkRoomWorkflowResponse {
+    fn from(t: TalkRoom) -> Self {
+        Self {
+            talk_room_id: t.id.value.to_string(),
+            primary_user_id: t.primary_user_id.value().to_string(),
+            assignee: t.assignee.map(|a| a.0),
+            status: t.status.as_str().to_string(),
+            mode: t.mode.as_str().to_string(),
+            updated_at: t.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomPinnedRequest {
+    pub pinned: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomRsvpRequest {
+    pub rsvp: bool,
+}
+
+// 呼び名の最大文字数
+const MAX_NICKNAME_LENGTH: usize = 50;
+
+/// nicknameがnullか空文字のときは呼び名を外す
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomNicknameRequest {
+    pub nickname: Option<String>,
+}
+
+impl TalkRoomNicknameRequest {
+    pub fn nickname(&self) -> anyhow::Result<Option<String>> {
+        let nickname = self
+            .nickname
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty());
+        match nickname {
+            Some(n) if n.chars().count() > MAX_NICKNAME_LENGTH => Err(anyhow!(
+                "Nickname must be at most {} characters",
+                MAX_NICKNAME_LENGTH
+            )),
+            _ => Ok(nickname.map(|n| n.to_string())),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomSettingsResponse {
+    pub talk_room_id: String,
+    pub primary_user_id: String,
+    pub display_name: String,
+    pub nickname: Option<String>,
+    pub pinned: bool,
+    pub rsvp: bool,
+    pub updated_at: String,
+}
+
+impl From<TalkRoom> for TalkRoomSettingsResponse {
+    fn from(t: TalkRoom) -> Self {
+        Self {
+            talk_room_id: t.id.value.to_string(),
+            primary_user_id: t.primary_user_id.value().to_string(),
+            display_name: t.display_name,
+            nickname: t.nickname,
+            pinned: t.pinned,
+            rsvp: t.rsvp,
+            updated_at: t.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+/// talkRoomの一覧のクエリパラメータ
+/// assigneeはスタッフのIDか、担当者なしのときはnoneを指定する
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomCardsQuery {
+    pub pinned: Option<bool>,
+    pub follow: Option<bool>,
+    pub rsvp: Option<bool>,
+    pub unread: Option<bool>,
+    pub assignee: Option<String>,
+    pub status: Option<String>,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+impl TalkRoomCardsQuery {
+    pub fn filter(&self) -> anyhow::Result<TalkRoomFilter> {
+        let assignee = match self.assignee.as_deref() {
+            None => None,
+            Some("none") => Some(TalkRoomAssigneeFilter::Unassigned),
+            Some(id) => Some(TalkRoomAssigneeFilter::Staff(StaffId::new(id.parse()?))),
+        };
+        let status = self
+            .status
+            .as_deref()
+            .map(|s| s.parse::<TalkRoomStatus>())
+            .transpose()?;
+        Ok(TalkRoomFilter::new(
+            self.pinned,
+            self.follow,
+            self.rsvp,
+            assignee,
+            status,
+            self.unread,
+        ))
+    }
+
+    pub fn cursor(&self) -> anyhow::Result<Option<TalkRoomCursor>> {
+        self.cursor
+            .as_deref()
+            .map(decode_talk_room_cursor)
+            .transpose()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TalkRoomCursorJson {
+    pinned: bool,
+    sort_time: String,
+    id: String,
+}
+
+pub fn encode_talk_room_cursor(cursor: &TalkRoomCursor) -> String {
+    encode_cursor(&TalkRoomCursorJson {
+        pinned: cursor.pinned,
+        sort_time: cursor.sort_time.to_rfc3339(),
+        id: cursor.id.value.to_string(),
+    })
+}
+
+pub fn decode_talk_room_cursor(cursor: &str) -> anyhow::Result<TalkRoomCursor> {
+    let cursor: TalkRoomCursorJson = decode_cursor(cursor)?;
+    let sort_time = DateTime::parse_from_rfc3339(&cursor.sort_time)?.with_timezone(&Local);
+    let id = cursor
+        .id
+        .try_into()
+        .map_err(|e| anyhow!("Invalid talk room id in cursor: {:?}", e))?;
+    Ok(TalkRoomCursor::new(cursor.pinned, sort_time, id))
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomCardsResponse {
+    pub talk_rooms: Vec<TalkRoomCardResponse>,
+    // 続きがないときはnull
+    pub next_cursor: Option<String>,
+}
+
+// 未読数はスタッフごとに違うので、一覧を取得したスタッフの未読数を返す
+impl From<(TalkRoomCardPage, StaffId)> for TalkRoomCardsResponse {
+    fn from((p, staff_id): (TalkRoomCardPage, StaffId)) -> Self {
+        Self {
+            talk_rooms: p
+                .talk_room_cards
+                .into_iter()
+                .map(|c| (c, staff_id).into())
+                .collect(),
+            next_cursor: p.next_cursor.as_ref().map(encode_talk_room_cursor),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomCardResponse {
+    pub talk_room_id: String,
+    pub primary_user_id: String,
+    pub display_name: String,
+    pub nickname: Option<String>,
+    pub rsvp: bool,
+    pub pinned: bool,
+    pub follow: bool,
+    pub unread: bool,
+    // 一覧を取得したスタッフが最後に既読にしてから届いたユーザーのメッセージの数
+    pub unread_count: i64,
+    pub user_message_count: i64,
+    pub assignee: Option<i64>,
+    pub status: String,
+    pub mode: String,
+    pub latest_message: LatestMessagePreviewResponse,
+    pub latest_messaged_at: String,
+    pub sort_time: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<(TalkRoomCard, StaffId)> for TalkRoomCardResponse {
+    fn from((c, staff_id): (TalkRoomCard, StaffId)) -> Self {
+        Self {
+            unread_count: c.unread_count(staff_id),
+            user_message_count: c.user_message_count,
+            talk_room_id: c.id.value.to_string(),
+            primary_user_id: c.primary_user_id.value().to_string(),
+            display_name: c.display_name,
+            nickname: c.nickname,
+            rsvp: c.rsvp,
+            pinned: c.pinned,
+            follow: c.follow,
+            unread: c.unread,
+            assignee: c.assignee.map(|a| a.0),
+            status: c.status.as_str().to_string(),
+            mode: c.mode.as_str().to_string(),
+            latest_message: c.latest_message.into(),
+            latest_messaged_at: c.latest_messaged_at.to_rfc3339(),
+            sort_time: c.sort_time.to_rfc3339(),
+            created_at: c.created_at.to_rfc3339(),
+            updated_at: c.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomReadMarkerResponse {
+    pub talk_room_id: String,
+    pub staff_id: i64,
+    pub read_message_count: i64,
+    pub read_at: String,
+}
+
+impl From<TalkRoomReadMarker> for TalkRoomReadMarkerResponse {
+    fn from(r: TalkRoomReadMarker) -> Self {
+        Self {
+            talk_room_id: r.talk_room_id.value.to_string(),
+            staff_id: r.staff_id.0,
+            read_message_count: r.read_message_count,
+            read_at: r.read_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestMessagePreviewResponse {
+    pub message_id: String,
+    pub message_type: String,
+    pub text: Option<String>,
+}
+
+impl From<LatestMessagePreview> for LatestMessagePreviewResponse {
+    fn from(p: LatestMessagePreview) -> Self {
+        Self {
+            message_id: p.message_id,
+            message_type: p.message_type,
+            text: p.text,
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::model::{
+    cursor::{decode_cursor, encode_cursor},
+    message::MessageResponse,
+    talk_room::TalkRoomCardResponse,
+};
+use axum::response::sse::Event;
+use domain::model::{
+    staff::StaffId,
+    talk_room_change::{TalkRoomChange, TalkRoomChangeResumeToken, TalkRoomMessage},
+};
+use serde::{Deserialize, Serialize};
+
+/// 受信箱の変更のクエリパラメータ
+/// EventSourceを使わないクライアントは、最後に受け取ったcheckpointのresumeTokenを指定して再接続する
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomChangesQuery {
+    pub resume_token: Option<String>,
+}
+
+pub fn encode_resume_token(resume_token: &TalkRoomChangeResumeToken) -> String {
+    encode_cursor(&resume_token.0)
+}
+
+pub fn decode_resume_token(resume_token: &str) -> anyhow::Result<TalkRoomChangeResumeToken> {
+    let resume_token: String = decode_cursor(resume_token)?;
+    Ok(TalkRoomChangeResumeToken::new(resume_token))
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomMessageResponse {
+    pub talk_room_id: String,
+    pub message: MessageResponse,
+}
+
+impl From<TalkRoomMessage> for TalkRoomMessageResponse {
+    fn from(m: TalkRoomMessage) -> Self {
+        Self {
+            talk_room_id: m.talk_room_id.value.to_string(),
+            message: m.message.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomChangeCheckpointResponse {
+    pub resume_token: String,
+}
+
+/// talkRoomの変更をSSEのイベントにする
+/// checkpointにだけidを付けるので、EventSourceは再接続するときにLast-Event-IDで最後のcheckpointを送る
+/// 未読数はスタッフごとに違うので、受け取るスタッフの未読数にする
+pub fn talk_room_change_event(
+    change: TalkRoomChange,
+    staff_id: StaffId,
+) -> Result<Event, serde_json::Error> {
+    match change {
+        TalkRoomChange::CardUpdated(c) => Event::default()
+            .event("talkRoomCard")
+            .json_data(TalkRoomCardResponse::from((c, staff_id))),
+        TalkRoomChange::MessageAdded(m) => Event::default()
+            .event("message")
+            .json_data(TalkRoomMessageResponse::from(m)),
+        TalkRoomChange::Checkpoint(t) => {
+            let resume_token = encode_resume_token(&t);
+            Event::default()
+                .event("checkpoint")
+                .id(resume_token.clone())
+                .json_data(TalkRoomChangeCheckpointResponse { resume_token })
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::anyhow;
+use domain::model::talk_room_note::TalkRoomNote;
+use serde::{Deserialize, Serialize};
+
+const MAX_NOTE_BODY_LENGTH: usize = 2000;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomNoteRequest {
+    pub body: String,
+}
+
+impl TalkRoomNoteRequest {
+    pub fn body(&self) -> anyhow::Result<String> {
+        let body = self.body.trim();
+        if body.is_empty() {
+            return Err(anyhow!("Note body is empty"));
+        }
+        if body.chars().count() > MAX_NOTE_BODY_LENGTH {
+            return Err(anyhow!(
+                "Note body must be at most {} characters",
+                MAX_NOTE_BODY_LENGTH
+            ));
+        }
+        Ok(body.to_string())
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomNoteResponse {
+    pub id: String,
+    pub talk_room_id: String,
+    pub author: TalkRoomNoteAuthorResponse,
+    pub body: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomNoteAuthorResponse {
+    pub staff_id: i64,
+    pub name: String,
+}
+
+impl From<TalkRoomNote> for TalkRoomNoteResponse {
+    fn from(n: TalkRoomNote) -> Self {
+        Self {
+            id: n.id.value.to_string(),
+            talk_room_id: n.talk_room_id.value.to_string(),
+            author: TalkRoomNoteAuthorResponse {
+                staff_id: n.author.id.0,
+                name: n.author.name,
+            },
+            body: n.body,
+            created_at: n.created_at.to_rfc3339(),
+            updated_at: n.updated_at.to_rfc3339(),
+        }
+    }
+}
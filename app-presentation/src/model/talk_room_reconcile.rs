@@ -0,0 +1,84 @@
+use application::model::talk_room_reconcile::TalkRoomReconcileReport;
+use domain::model::talk_room_consistency::TalkRoomInconsistency;
+use serde::{Deserialize, Serialize};
+
+/// repairを指定しないときは、食い違いを報告するだけで直さない
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomReconcileQuery {
+    #[serde(default)]
+    pub repair: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomReconcileReportResponse {
+    pub checked_count: usize,
+    pub repaired_count: usize,
+    pub failed_count: usize,
+    pub inconsistencies: Vec<TalkRoomInconsistencyResponse>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TalkRoomInconsistencyResponse {
+    // missingTalkRoomDocument、missingCard、missingLatestMessage、missingCardFields、orphanedDocuments、
+    // undeserializableDocument
+    #[serde(rename = "type")]
+    pub inconsistency_type: String,
+    pub talk_room_id: String,
+    pub message_id: Option<String>,
+    // undeserializableDocumentのときの、ドキュメントのコレクションと読み込めなかった理由
+    pub error: Option<String>,
+    pub repairable: bool,
+}
+
+impl From<TalkRoomReconcileReport> for TalkRoomReconcileReportResponse {
+    fn from(r: TalkRoomReconcileReport) -> Self {
+        Self {
+            checked_count: r.checked_count,
+            repaired_count: r.repaired_count,
+            failed_count: r.failed_count,
+            inconsistencies: r
+                .inconsistencies
+                .into_iter()
+                .map(TalkRoomInconsistencyResponse::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<TalkRoomInconsistency> for TalkRoomInconsistencyResponse {
+    fn from(i: TalkRoomInconsistency) -> Self {
+        let repairable = i.repairable();
+        let talk_room_id = i.talk_room_document_id().clone();
+        let (inconsistency_type, message_id, error) = match i {
+            TalkRoomInconsistency::MissingTalkRoomDocument(_) => {
+                ("missingTalkRoomDocument", None, None)
+            }
+            TalkRoomInconsistency::MissingCard(_) => ("missingCard", None, None),
+            TalkRoomInconsistency::MissingLatestMessage {
+                message_document_id,
+                ..
+            } => ("missingLatestMessage", Some(message_document_id), None),
+            TalkRoomInconsistency::MissingCardFields { .. } => ("missingCardFields", None, None),
+            TalkRoomInconsistency::OrphanedDocuments { .. } => ("orphanedDocuments", None, None),
+            TalkRoomInconsistency::UndeserializableDocument {
+                collection_id,
+                error,
+                ..
+            } => (
+                "undeserializableDocument",
+                None,
+                Some(format!("{}: {}", collection_id, error)),
+            ),
+        };
+        Self {
+            inconsistency_type: inconsistency_type.to_string(),
+            talk_room_id,
+            message_id,
+            error,
+            repairable,
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use application::model::user_identity::{
+    CreateEmailUserAuth, CreateLineLoginUserAuth, CreateUserIdentity,
+};
+use domain::model::user::{User, UserProfile};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "provider", rename_all = "camelCase")]
+pub enum UserIdentityRequest {
+    Email(EmailIdentityRequest),
+    LineLogin(LineLoginIdentityRequest),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailIdentityRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LineLoginIdentityRequest {
+    pub id_token: String,
+}
+
+impl From<UserIdentityRequest> for CreateUserIdentity {
+    fn from(r: UserIdentityRequest) -> Self {
+        match r {
+            UserIdentityRequest::Email(e) => {
+                CreateUserIdentity::Email(CreateEmailUserAuth::new(e.email, e.password))
+            }
+            UserIdentityRequest::LineLogin(l) => {
+                CreateUserIdentity::LineLogin(CreateLineLoginUserAuth::new(l.id_token))
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeUsersRequest {
+    pub into_primary_user_id: String,
+    pub from_primary_user_id: String,
+}
+
+// パスワードのハッシュなどは返さない
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserIdentityResponse {
+    pub primary_user_id: String,
+    pub provider: String,
+    pub auth_id: Option<String>,
+    pub external_member_id: Option<String>,
+}
+
+impl From<User> for UserIdentityResponse {
+    fn from(u: User) -> Self {
+        let provider = match &u.user_profile {
+            UserProfile::Line(_) => "line",
+            UserProfile::Email(_) => "email",
+            UserProfile::LineLogin(_) => "lineLogin",
+        };
+        Self {
+            primary_user_id: u.id.value().to_string(),
+            provider: provider.to_string(),
+            auth_id: u.user_profile.auth_id().map(|a| a.value().to_string()),
+            external_member_id: u.external_member_id.map(|e| e.0),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use domain::model::{
+    primary_user_id::PrimaryUserId,
+    user_event::{UserEvent, UserTaggedByStaff},
+    user_tag::UserTag,
+};
+use serde::Deserialize;
+
+// スタッフがユーザーに付けるタグ、外すタグ
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserTagsRequest {
+    #[serde(default)]
+    pub added_tags: Vec<String>,
+    #[serde(default)]
+    pub removed_tags: Vec<String>,
+}
+
+impl UserTagsRequest {
+    pub fn into_user_event(self, primary_user_id: String) -> anyhow::Result<UserEvent> {
+        let added_tags = self
+            .added_tags
+            .iter()
+            .map(|t| t.parse::<UserTag>())
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let removed_tags = self
+            .removed_tags
+            .iter()
+            .map(|t| t.parse::<UserTag>())
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(UserEvent::TaggedByStaff(UserTaggedByStaff::new(
+            PrimaryUserId::new(primary_user_id),
+            added_tags,
+            removed_tags,
+        )))
+    }
+}
@@ -1,39 +1,212 @@
-use adapter::module::{AdaptersModule, AdaptersModuleExt};
-use adapter::persistance::{firestore::Firestore, mysql::Db};
-use application::usecase::linebot_webhook_usecase::LinebotWebhookUseCase;
+use adapter::module::{
+    AdaptersModule, AdaptersModuleExt, InMemoryAdaptersModule, MySqlAdaptersModule,
+};
+use adapter::persistance::{db::Db, firestore::Firestore};
+use anyhow::Context;
+use application::config::AppConfig;
+use application::model::rich_menu_rule::RichMenuRules;
+use application::usecase::{
+    account_link_usecase::AccountLinkUseCase, admin_usecase::AdminUseCase,
+    business_hours_usecase::BusinessHoursUseCase, canned_response_usecase::CannedResponseUseCase,
+    health_check_usecase::HealthCheckUseCase, linebot_webhook_usecase::LinebotWebhookUseCase,
+    rich_menu_assignment_usecase::RichMenuAssignmentUseCase, rich_menu_usecase::RichMenuUseCase,
+    staff_usecase::StaffUseCase, talk_room_note_usecase::TalkRoomNoteUseCase,
+    talk_room_reconcile_usecase::TalkRoomReconcileUseCase, talk_room_usecase::TalkRoomUseCase,
+    talk_room_workflow_usecase::TalkRoomWorkflowUseCase,
+    user_identity_usecase::UserIdentityUseCase, user_profile_usecase::UserProfileUseCase,
+};
 use reqwest::Client;
 use std::sync::Arc;
 
-pub trait ModulesExt {
+// ハンドラーはこのトレイトだけに依存し、どのアダプターを使うかは起動時に選ぶ
+pub trait ModulesExt: Send + Sync + 'static {
     type AdaptersModule: AdaptersModuleExt;
 
+    fn config(&self) -> &AppConfig;
     fn linebot_webhook_usecase(&self) -> &LinebotWebhookUseCase<Self::AdaptersModule>;
+    fn rich_menu_usecase(&self) -> &RichMenuUseCase<Self::AdaptersModule>;
+    fn rich_menu_assignment_usecase(&self) -> &RichMenuAssignmentUseCase<Self::AdaptersModule>;
+    fn user_profile_usecase(&self) -> &UserProfileUseCase<Self::AdaptersModule>;
+    fn user_identity_usecase(&self) -> &UserIdentityUseCase<Self::AdaptersModule>;
+    fn account_link_usecase(&self) -> &AccountLinkUseCase<Self::AdaptersModule>;
+    fn staff_usecase(&self) -> &StaffUseCase<Self::AdaptersModule>;
+    fn talk_room_workflow_usecase(&self) -> &TalkRoomWorkflowUseCase<Self::AdaptersModule>;
+    fn talk_room_usecase(&self) -> &TalkRoomUseCase<Self::AdaptersModule>;
+    fn talk_room_note_usecase(&self) -> &TalkRoomNoteUseCase<Self::AdaptersModule>;
+    fn canned_response_usecase(&self) -> &CannedResponseUseCase<Self::AdaptersModule>;
+    fn business_hours_usecase(&self) -> &BusinessHoursUseCase<Self::AdaptersModule>;
+    fn talk_room_reconcile_usecase(&self) -> &TalkRoomReconcileUseCase<Self::AdaptersModule>;
+    fn admin_usecase(&self) -> &AdminUseCase<Self::AdaptersModule>;
+    fn health_check_usecase(&self) -> &HealthCheckUseCase<Self::AdaptersModule>;
 }
 
-pub struct Modules {
-    linebot_webhook_usecase: LinebotWebhookUseCase<AdaptersModule>,
+pub struct Modules<A: AdaptersModuleExt = AdaptersModule> {
+    config: Arc<AppConfig>,
+    linebot_webhook_usecase: LinebotWebhookUseCase<A>,
+    rich_menu_usecase: RichMenuUseCase<A>,
+    rich_menu_assignment_usecase: RichMenuAssignmentUseCase<A>,
+    user_profile_usecase: UserProfileUseCase<A>,
+    user_identity_usecase: UserIdentityUseCase<A>,
+    account_link_usecase: AccountLinkUseCase<A>,
+    staff_usecase: StaffUseCase<A>,
+    talk_room_workflow_usecase: TalkRoomWorkflowUseCase<A>,
+    talk_room_usecase: TalkRoomUseCase<A>,
+    talk_room_note_usecase: TalkRoomNoteUseCase<A>,
+    canned_response_usecase: CannedResponseUseCase<A>,
+    business_hours_usecase: BusinessHoursUseCase<A>,
+    talk_room_reconcile_usecase: TalkRoomReconcileUseCase<A>,
+    admin_usecase: AdminUseCase<A>,
+    health_check_usecase: HealthCheckUseCase<A>,
 }
 
-impl ModulesExt for Modules {
-    type AdaptersModule = AdaptersModule;
+impl<A: AdaptersModuleExt> ModulesExt for Modules<A> {
+    type AdaptersModule = A;
 
+    fn config(&self) -> &AppConfig {
+        &self.config
+    }
     fn linebot_webhook_usecase(&self) -> &LinebotWebhookUseCase<Self::AdaptersModule> {
         &self.linebot_webhook_usecase
     }
+    fn rich_menu_usecase(&self) -> &RichMenuUseCase<Self::AdaptersModule> {
+        &self.rich_menu_usecase
+    }
+    fn rich_menu_assignment_usecase(&self) -> &RichMenuAssignmentUseCase<Self::AdaptersModule> {
+        &self.rich_menu_assignment_usecase
+    }
+    fn user_profile_usecase(&self) -> &UserProfileUseCase<Self::AdaptersModule> {
+        &self.user_profile_usecase
+    }
+    fn user_identity_usecase(&self) -> &UserIdentityUseCase<Self::AdaptersModule> {
+        &self.user_identity_usecase
+    }
+    fn account_link_usecase(&self) -> &AccountLinkUseCase<Self::AdaptersModule> {
+        &self.account_link_usecase
+    }
+    fn staff_usecase(&self) -> &StaffUseCase<Self::AdaptersModule> {
+        &self.staff_usecase
+    }
+    fn talk_room_workflow_usecase(&self) -> &TalkRoomWorkflowUseCase<Self::AdaptersModule> {
+        &self.talk_room_workflow_usecase
+    }
+    fn talk_room_usecase(&self) -> &TalkRoomUseCase<Self::AdaptersModule> {
+        &self.talk_room_usecase
+    }
+    fn talk_room_note_usecase(&self) -> &TalkRoomNoteUseCase<Self::AdaptersModule> {
+        &self.talk_room_note_usecase
+    }
+    fn canned_response_usecase(&self) -> &CannedResponseUseCase<Self::AdaptersModule> {
+        &self.canned_response_usecase
+    }
+    fn business_hours_usecase(&self) -> &BusinessHoursUseCase<Self::AdaptersModule> {
+        &self.business_hours_usecase
+    }
+    fn talk_room_reconcile_usecase(&self) -> &TalkRoomReconcileUseCase<Self::AdaptersModule> {
+        &self.talk_room_reconcile_usecase
+    }
+    fn admin_usecase(&self) -> &AdminUseCase<Self::AdaptersModule> {
+        &self.admin_usecase
+    }
+    fn health_check_usecase(&self) -> &HealthCheckUseCase<Self::AdaptersModule> {
+        &self.health_check_usecase
+    }
 }
 
 impl Modules {
-    pub async fn new() -> Self {
-        let client = Client::new();
-        let db = Db::new().await;
-        let firestore = Firestore::new().await;
-        let adapters_module: Arc<_> = Arc::new(AdaptersModule::new(client, db, firestore));
+    pub async fn new(config: AppConfig) -> anyhow::Result<Self> {
+        let db = connect_db(&config).await?;
+        let firestore = Firestore::connect(&config.firestore_project_id)
+            .await
+            .context("Cannot connect to the Firestore. Please check FIRESTORE_PROJECT_ID.")?;
+        Ok(Self::from_adapters_module(
+            Arc::new(AdaptersModule::new(Client::new(), db, firestore)),
+            config,
+        ))
+    }
+}
+
+impl Modules<MySqlAdaptersModule> {
+    /// talk roomとメモ、営業時間をfirestoreではなくMySQLに保存する。firestoreには接続しない
+    pub async fn with_mysql_talk_rooms(config: AppConfig) -> anyhow::Result<Self> {
+        let db = connect_db(&config).await?;
+        Ok(Self::from_adapters_module(
+            Arc::new(MySqlAdaptersModule::with_mysql_talk_rooms(
+                Client::new(),
+                db,
+            )),
+            config,
+        ))
+    }
+}
+
+async fn connect_db(config: &AppConfig) -> anyhow::Result<Db> {
+    Db::connect(&config.database_url)
+        .await
+        .context("Cannot connect to the database. Please check DATABASE_URL.")
+}
+
+impl Modules<InMemoryAdaptersModule> {
+    /// MySQLとfirestore、LINEのAPIの代わりにメモリを使う
+    /// 書き込んだ内容を確かめたいときは、from_adapters_moduleにアダプターを渡して作る
+    pub fn in_memory(config: AppConfig) -> Self {
+        Self::from_adapters_module(Arc::new(InMemoryAdaptersModule::default()), config)
+    }
+}
 
-        let linebot_webhook_usecase: LinebotWebhookUseCase<AdaptersModule> =
-            LinebotWebhookUseCase::new(adapters_module);
+impl<A: AdaptersModuleExt> Modules<A> {
+    pub fn from_adapters_module(adapters_module: Arc<A>, config: AppConfig) -> Self {
+        let config = Arc::new(config);
+        let linebot_webhook_usecase: LinebotWebhookUseCase<A> =
+            LinebotWebhookUseCase::new(adapters_module.clone(), config.clone());
+        let rich_menu_usecase: RichMenuUseCase<A> =
+            RichMenuUseCase::new(adapters_module.clone(), config.clone());
+        let rich_menu_assignment_usecase: RichMenuAssignmentUseCase<A> =
+            RichMenuAssignmentUseCase::new(
+                adapters_module.clone(),
+                RichMenuRules::default(),
+                config.clone(),
+            );
+        let user_profile_usecase: UserProfileUseCase<A> =
+            UserProfileUseCase::new(adapters_module.clone(), config.clone());
+        let user_identity_usecase: UserIdentityUseCase<A> =
+            UserIdentityUseCase::new(adapters_module.clone(), config.clone());
+        let account_link_usecase: AccountLinkUseCase<A> =
+            AccountLinkUseCase::new(adapters_module.clone(), config.clone());
+        let staff_usecase: StaffUseCase<A> =
+            StaffUseCase::new(adapters_module.clone(), config.clone());
+        let talk_room_workflow_usecase: TalkRoomWorkflowUseCase<A> =
+            TalkRoomWorkflowUseCase::new(adapters_module.clone());
+        let talk_room_usecase: TalkRoomUseCase<A> =
+            TalkRoomUseCase::new(adapters_module.clone(), config.clone());
+        let talk_room_note_usecase: TalkRoomNoteUseCase<A> =
+            TalkRoomNoteUseCase::new(adapters_module.clone());
+        let canned_response_usecase: CannedResponseUseCase<A> =
+            CannedResponseUseCase::new(adapters_module.clone());
+        let business_hours_usecase: BusinessHoursUseCase<A> =
+            BusinessHoursUseCase::new(adapters_module.clone(), config.clone());
+        let talk_room_reconcile_usecase: TalkRoomReconcileUseCase<A> =
+            TalkRoomReconcileUseCase::new(adapters_module.clone());
+        let admin_usecase: AdminUseCase<A> =
+            AdminUseCase::new(adapters_module.clone(), config.clone());
+        let health_check_usecase: HealthCheckUseCase<A> = HealthCheckUseCase::new(adapters_module);
 
         Self {
+            config,
             linebot_webhook_usecase,
+            rich_menu_usecase,
+            rich_menu_assignment_usecase,
+            user_profile_usecase,
+            user_identity_usecase,
+            account_link_usecase,
+            staff_usecase,
+            talk_room_workflow_usecase,
+            talk_room_usecase,
+            talk_room_note_usecase,
+            canned_response_usecase,
+            business_hours_usecase,
+            talk_room_reconcile_usecase,
+            admin_usecase,
+            health_check_usecase,
         }
     }
 }
@@ -41,42 +214,253 @@ impl Modules {
 pub mod test {
     use super::ModulesExt;
     use adapter::module::test::TestAdaptersModule;
-    use application::usecase::linebot_webhook_usecase::LinebotWebhookUseCase;
-    use domain::gateway::{send_message::MockSendMessageGateway, user_auth::MockUserAuthGateway};
-    use domain::repository::{talk_room::MockTalkRoomRepository, user::MockUserRepository};
+    use application::config::AppConfig;
+    use application::model::rich_menu_rule::RichMenuRules;
+    use application::usecase::{
+        account_link_usecase::AccountLinkUseCase, admin_usecase::AdminUseCase,
+        business_hours_usecase::BusinessHoursUseCase,
+        canned_response_usecase::CannedResponseUseCase, health_check_usecase::HealthCheckUseCase,
+        linebot_webhook_usecase::LinebotWebhookUseCase,
+        rich_menu_assignment_usecase::RichMenuAssignmentUseCase,
+        rich_menu_usecase::RichMenuUseCase, staff_usecase::StaffUseCase,
+        talk_room_note_usecase::TalkRoomNoteUseCase,
+        talk_room_reconcile_usecase::TalkRoomReconcileUseCase, talk_room_usecase::TalkRoomUseCase,
+        talk_room_workflow_usecase::TalkRoomWorkflowUseCase,
+        user_identity_usecase::UserIdentityUseCase, user_profile_usecase::UserProfileUseCase,
+    };
+    use chrono::Local;
+    use domain::gateway::{
+        rich_menu::MockRichMenuGateway, send_message::MockSendMessageGateway,
+        user_auth::MockUserAuthGateway,
+    };
+    use domain::model::{
+        email_user::{EmailAddress, PasswordHash},
+        message::{
+            event::{Event, EventDeliveryContext, EventFollow},
+            Messages,
+        },
+        primary_user_id::PrimaryUserId,
+        staff::{Staff, StaffId, StaffRole},
+        talk_room::TalkRoom,
+        Id,
+    };
+    use domain::repository::{
+        business_hours::MockBusinessHoursRepository, canned_response::MockCannedResponseRepository,
+        staff::MockStaffRepository, talk_room::MockTalkRoomRepository,
+        talk_room_note::MockTalkRoomNoteRepository, user::MockUserRepository,
+        user_tag::MockUserTagRepository,
+    };
+    use std::collections::HashMap;
     use std::sync::Arc;
 
+    /// テストで使う設定。環境変数や設定ファイルには依存しない
+    pub fn test_config() -> AppConfig {
+        let values = HashMap::from([
+            ("DATABASE_URL", "sqlite::memory:"),
+            ("FIRESTORE_PROJECT_ID", "test_project"),
+            ("LINE_CHANNEL_ID", "1234567890"),
+            ("LINE_CHANNEL_SECRET", "test_channel_secret"),
+            ("LINE_ACCESS_TOKEN", "test_access_token"),
+            ("LINE_LOGIN_CHANNEL_ID", "1234567891"),
+            ("STAFF_JWT_SECRET", "test_staff_jwt_secret"),
+        ])
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        AppConfig::from_values(&values).unwrap()
+    }
+
+    /// テストで使うスタッフ
+    pub fn fake_staff(id: i64, role: StaffRole) -> Staff {
+        Staff::new(
+            StaffId::new(id),
+            format!("staff{}", id),
+            EmailAddress::new(format!("staff{}@example.com", id)),
+            "".to_string(),
+            role,
+            PasswordHash::new("".to_string()),
+        )
+    }
+
+    /// テストで使う、友だち追加だけがあるtalkRoom
+    pub fn fake_talk_room() -> TalkRoom {
+        let now = Local::now();
+        let event = Event::Follow(EventFollow {
+            id: Id::gen(),
+            reply_token: "reply_token".to_string(),
+            delivery_context: EventDeliveryContext::new(false),
+            mode: "active".to_string(),
+            webhook_event_id: "webhook_event_id".to_string(),
+            created_at: now,
+        });
+        TalkRoom::new(
+            Id::gen(),
+            PrimaryUserId::new("primary_user_id".to_string()),
+            "display_name".to_string(),
+            false,
+            false,
+            true,
+            Messages::Event(event),
+            now,
+            now,
+            now,
+            now,
+        )
+    }
+
     pub struct TestModules {
+        config: Arc<AppConfig>,
         linebot_webhook_usecase: LinebotWebhookUseCase<TestAdaptersModule>,
+        rich_menu_usecase: RichMenuUseCase<TestAdaptersModule>,
+        rich_menu_assignment_usecase: RichMenuAssignmentUseCase<TestAdaptersModule>,
+        user_profile_usecase: UserProfileUseCase<TestAdaptersModule>,
+        user_identity_usecase: UserIdentityUseCase<TestAdaptersModule>,
+        account_link_usecase: AccountLinkUseCase<TestAdaptersModule>,
+        staff_usecase: StaffUseCase<TestAdaptersModule>,
+        talk_room_workflow_usecase: TalkRoomWorkflowUseCase<TestAdaptersModule>,
+        talk_room_usecase: TalkRoomUseCase<TestAdaptersModule>,
+        talk_room_note_usecase: TalkRoomNoteUseCase<TestAdaptersModule>,
+        canned_response_usecase: CannedResponseUseCase<TestAdaptersModule>,
+        business_hours_usecase: BusinessHoursUseCase<TestAdaptersModule>,
+        talk_room_reconcile_usecase: TalkRoomReconcileUseCase<TestAdaptersModule>,
+        admin_usecase: AdminUseCase<TestAdaptersModule>,
+        health_check_usecase: HealthCheckUseCase<TestAdaptersModule>,
     }
 
     impl ModulesExt for TestModules {
         type AdaptersModule = TestAdaptersModule;
 
+        fn config(&self) -> &AppConfig {
+            &self.config
+        }
         fn linebot_webhook_usecase(&self) -> &LinebotWebhookUseCase<Self::AdaptersModule> {
             &self.linebot_webhook_usecase
         }
+        fn rich_menu_usecase(&self) -> &RichMenuUseCase<Self::AdaptersModule> {
+            &self.rich_menu_usecase
+        }
+        fn rich_menu_assignment_usecase(&self) -> &RichMenuAssignmentUseCase<Self::AdaptersModule> {
+            &self.rich_menu_assignment_usecase
+        }
+        fn user_profile_usecase(&self) -> &UserProfileUseCase<Self::AdaptersModule> {
+            &self.user_profile_usecase
+        }
+        fn user_identity_usecase(&self) -> &UserIdentityUseCase<Self::AdaptersModule> {
+            &self.user_identity_usecase
+        }
+        fn account_link_usecase(&self) -> &AccountLinkUseCase<Self::AdaptersModule> {
+            &self.account_link_usecase
+        }
+        fn staff_usecase(&self) -> &StaffUseCase<Self::AdaptersModule> {
+            &self.staff_usecase
+        }
+        fn talk_room_workflow_usecase(&self) -> &TalkRoomWorkflowUseCase<Self::AdaptersModule> {
+            &self.talk_room_workflow_usecase
+        }
+        fn talk_room_usecase(&self) -> &TalkRoomUseCase<Self::AdaptersModule> {
+            &self.talk_room_usecase
+        }
+        fn talk_room_note_usecase(&self) -> &TalkRoomNoteUseCase<Self::AdaptersModule> {
+            &self.talk_room_note_usecase
+        }
+        fn canned_response_usecase(&self) -> &CannedResponseUseCase<Self::AdaptersModule> {
+            &self.canned_response_usecase
+        }
+        fn business_hours_usecase(&self) -> &BusinessHoursUseCase<Self::AdaptersModule> {
+            &self.business_hours_usecase
+        }
+        fn talk_room_reconcile_usecase(&self) -> &TalkRoomReconcileUseCase<Self::AdaptersModule> {
+            &self.talk_room_reconcile_usecase
+        }
+        fn admin_usecase(&self) -> &AdminUseCase<Self::AdaptersModule> {
+            &self.admin_usecase
+        }
+        fn health_check_usecase(&self) -> &HealthCheckUseCase<Self::AdaptersModule> {
+            &self.health_check_usecase
+        }
     }
 
     impl TestModules {
+        // テストではアダプターごとにモックを渡す
+        #[allow(clippy::too_many_arguments)]
         pub async fn new(
             user_auth_gateway: MockUserAuthGateway,
             user_repository: MockUserRepository,
             talk_room_repository: MockTalkRoomRepository,
             send_message_gateway: MockSendMessageGateway,
+            rich_menu_gateway: MockRichMenuGateway,
+            user_tag_repository: MockUserTagRepository,
+            staff_repository: MockStaffRepository,
+            talk_room_note_repository: MockTalkRoomNoteRepository,
+            canned_response_repository: MockCannedResponseRepository,
+            business_hours_repository: MockBusinessHoursRepository,
         ) -> Self {
             let adapters_module = Arc::new(TestAdaptersModule::new(
                 user_auth_gateway,
                 user_repository,
                 talk_room_repository,
                 send_message_gateway,
+                rich_menu_gateway,
+                user_tag_repository,
+                staff_repository,
+                talk_room_note_repository,
+                canned_response_repository,
+                business_hours_repository,
             ));
+            let config = Arc::new(test_config());
 
             let linebot_webhook_usecase: LinebotWebhookUseCase<TestAdaptersModule> =
-                LinebotWebhookUseCase::new(adapters_module);
+                LinebotWebhookUseCase::new(adapters_module.clone(), config.clone());
+            let rich_menu_usecase: RichMenuUseCase<TestAdaptersModule> =
+                RichMenuUseCase::new(adapters_module.clone(), config.clone());
+            let rich_menu_assignment_usecase: RichMenuAssignmentUseCase<TestAdaptersModule> =
+                RichMenuAssignmentUseCase::new(
+                    adapters_module.clone(),
+                    RichMenuRules::default(),
+                    config.clone(),
+                );
+            let user_profile_usecase: UserProfileUseCase<TestAdaptersModule> =
+                UserProfileUseCase::new(adapters_module.clone(), config.clone());
+            let user_identity_usecase: UserIdentityUseCase<TestAdaptersModule> =
+                UserIdentityUseCase::new(adapters_module.clone(), config.clone());
+            let account_link_usecase: AccountLinkUseCase<TestAdaptersModule> =
+                AccountLinkUseCase::new(adapters_module.clone(), config.clone());
+            let staff_usecase: StaffUseCase<TestAdaptersModule> =
+                StaffUseCase::new(adapters_module.clone(), config.clone());
+            let talk_room_workflow_usecase: TalkRoomWorkflowUseCase<TestAdaptersModule> =
+                TalkRoomWorkflowUseCase::new(adapters_module.clone());
+            let talk_room_usecase: TalkRoomUseCase<TestAdaptersModule> =
+                TalkRoomUseCase::new(adapters_module.clone(), config.clone());
+            let talk_room_note_usecase: TalkRoomNoteUseCase<TestAdaptersModule> =
+                TalkRoomNoteUseCase::new(adapters_module.clone());
+            let canned_response_usecase: CannedResponseUseCase<TestAdaptersModule> =
+                CannedResponseUseCase::new(adapters_module.clone());
+            let business_hours_usecase: BusinessHoursUseCase<TestAdaptersModule> =
+                BusinessHoursUseCase::new(adapters_module.clone(), config.clone());
+            let talk_room_reconcile_usecase: TalkRoomReconcileUseCase<TestAdaptersModule> =
+                TalkRoomReconcileUseCase::new(adapters_module.clone());
+            let admin_usecase: AdminUseCase<TestAdaptersModule> =
+                AdminUseCase::new(adapters_module.clone(), config.clone());
+            let health_check_usecase: HealthCheckUseCase<TestAdaptersModule> =
+                HealthCheckUseCase::new(adapters_module);
 
             Self {
+                config,
                 linebot_webhook_usecase,
+                rich_menu_usecase,
+                rich_menu_assignment_usecase,
+                user_profile_usecase,
+                user_identity_usecase,
+                account_link_usecase,
+                staff_usecase,
+                talk_room_workflow_usecase,
+                talk_room_usecase,
+                talk_room_note_usecase,
+                canned_response_usecase,
+                business_hours_usecase,
+                talk_room_reconcile_usecase,
+                admin_usecase,
+                health_check_usecase,
             }
         }
     }
@@ -1 +1,12 @@
+pub mod account_link;
+pub mod business_hours;
+pub mod canned_response;
+pub mod health;
 pub mod line_webhook;
+pub mod rich_menu;
+pub mod staff;
+pub mod talk_room;
+pub mod talk_room_note;
+pub mod talk_room_reconcile;
+pub mod user_identity;
+pub mod user_tag;
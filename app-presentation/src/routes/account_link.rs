@@ -0,0 +1,100 @@
+use crate::context::staff_auth::AuthenticatedStaff;
+use crate::model::account_link::{
+    AccountLinkLoginUrlResponse, AccountLinkNonceRequest, AccountLinkRedirectResponse,
+};
+use crate::module::ModulesExt;
+use application::model::account_link::CreateAccountLinkNonce;
+use application::usecase::UseCaseError;
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use domain::model::{
+    account_link::{LineLinkToken, NewAccountLinkNonce},
+    primary_user_id::PrimaryUserId,
+    staff::StaffPermission,
+    user_auth::LineId,
+};
+use domain::repository::RepositoryError;
+use std::sync::Arc;
+use tracing::error;
+
+/// LINEの連携トークンを発行し、会員システムのログインページのURLを返す
+#[tracing::instrument(skip(modules))]
+pub async fn issue_account_link_login_url_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Path(line_id): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let login_url = modules
+        .account_link_usecase()
+        .issue_login_url(LineId::new(line_id))
+        .await
+        .map_err(|err| {
+            error!("Failed to issue account link login url: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(AccountLinkLoginUrlResponse { login_url }))
+}
+
+/// 会員システムのログインが終わったときに呼ばれ、nonceを付けたLINEへのリダイレクト先を返す
+#[tracing::instrument(skip(modules))]
+pub async fn issue_account_link_nonce_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Json(request): Json<AccountLinkNonceRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let (link_token, new_nonce): (LineLinkToken, NewAccountLinkNonce) =
+        CreateAccountLinkNonce::from(request)
+            .try_into()
+            .map_err(|err| {
+                error!("Invalid account link nonce request: {:?}", err);
+                StatusCode::BAD_REQUEST
+            })?;
+    let redirect_url = modules
+        .account_link_usecase()
+        .issue_nonce(link_token, new_nonce)
+        .await
+        .map_err(|err| {
+            error!("Failed to issue account link nonce: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(AccountLinkRedirectResponse { redirect_url }),
+    ))
+}
+
+/// 会員システムとの連携を解除する
+#[tracing::instrument(skip(modules, staff))]
+pub async fn unlink_account_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(primary_user_id): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ManageUsers)?;
+    modules
+        .account_link_usecase()
+        .unlink_account(PrimaryUserId::new(primary_user_id))
+        .await
+        .map_err(|err| {
+            error!("Failed to unlink account: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn status_code_from_error(err: &UseCaseError) -> StatusCode {
+    match err {
+        UseCaseError::Repository(RepositoryError::NotFound(_, _)) => StatusCode::NOT_FOUND,
+        UseCaseError::Repository(RepositoryError::Conflict(_, _, _)) => StatusCode::CONFLICT,
+        // MySQLやfirestoreにつながらない。時間をおけば成功するかもしれない
+        UseCaseError::Repository(RepositoryError::Unavailable(_)) => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
@@ -0,0 +1,54 @@
+use crate::context::staff_auth::AuthenticatedStaff;
+use crate::model::business_hours::{BusinessHoursCalendarRequest, BusinessHoursResponse};
+use crate::module::ModulesExt;
+use axum::{extract::Extension, http::StatusCode, response::IntoResponse, Json};
+use chrono::Local;
+use domain::model::{business_hours::NewBusinessHoursCalendar, staff::StaffPermission};
+use std::sync::Arc;
+use tracing::error;
+
+/// チャネルの営業時間と、今が営業時間内かどうかを返す
+#[tracing::instrument(skip(modules, staff))]
+pub async fn get_business_hours_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ViewTalkRooms)?;
+    let calendar = modules
+        .business_hours_usecase()
+        .get_calendar()
+        .await
+        .map_err(|err| {
+            error!("Failed to get business hours: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(BusinessHoursResponse::new(calendar, Local::now())))
+}
+
+/// チャネルの営業時間と不在メッセージを置き換える
+#[tracing::instrument(skip(modules, staff))]
+pub async fn save_business_hours_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Json(request): Json<BusinessHoursCalendarRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ManageBusinessHours)?;
+    let source = NewBusinessHoursCalendar::try_from(request).map_err(|err| {
+        error!("Invalid business hours: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    let calendar = modules
+        .business_hours_usecase()
+        .save_calendar(source)
+        .await
+        .map_err(|err| {
+            error!("Failed to save business hours: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(BusinessHoursResponse::new(
+        Some(calendar),
+        Local::now(),
+    )))
+}
@@ -0,0 +1,289 @@
+use crate::context::staff_auth::AuthenticatedStaff;
+use crate::model::canned_response::{CannedResponseRequest, CannedResponseResponse};
+use crate::module::ModulesExt;
+use application::usecase::UseCaseError;
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use domain::model::{canned_response::CannedResponseId, staff::StaffPermission};
+use domain::repository::RepositoryError;
+use std::sync::Arc;
+use tracing::error;
+
+/// 定型文の一覧を返す
+#[tracing::instrument(skip(modules, staff))]
+pub async fn get_canned_responses_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::SendMessages)?;
+    let canned_responses = modules
+        .canned_response_usecase()
+        .get_canned_responses()
+        .await
+        .map_err(|err| {
+            error!("Failed to get canned responses: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(
+        canned_responses
+            .into_iter()
+            .map(CannedResponseResponse::from)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+#[tracing::instrument(skip(modules, staff))]
+pub async fn create_canned_response_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Json(request): Json<CannedResponseRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::SendMessages)?;
+    let (title, body) = request.validate().map_err(|err| {
+        error!("Invalid canned response: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    let canned_response = modules
+        .canned_response_usecase()
+        .create_canned_response(title, body, staff.0)
+        .await
+        .map_err(|err| {
+            error!("Failed to create canned response: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CannedResponseResponse::from(canned_response)),
+    ))
+}
+
+#[tracing::instrument(skip(modules, staff))]
+pub async fn update_canned_response_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(canned_response_id): Path<i64>,
+    Json(request): Json<CannedResponseRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::SendMessages)?;
+    let (title, body) = request.validate().map_err(|err| {
+        error!("Invalid canned response: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    let canned_response = modules
+        .canned_response_usecase()
+        .update_canned_response(CannedResponseId::new(canned_response_id), title, body)
+        .await
+        .map_err(|err| {
+            error!("Failed to update canned response: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(CannedResponseResponse::from(canned_response)))
+}
+
+#[tracing::instrument(skip(modules, staff))]
+pub async fn delete_canned_response_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(canned_response_id): Path<i64>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::SendMessages)?;
+    modules
+        .canned_response_usecase()
+        .delete_canned_response(CannedResponseId::new(canned_response_id))
+        .await
+        .map_err(|err| {
+            error!("Failed to delete canned response: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn status_code_from_error(err: &UseCaseError) -> StatusCode {
+    match err {
+        // 使えないプレースホルダーなど、入力が正しくない
+        UseCaseError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+        UseCaseError::Repository(RepositoryError::NotFound(_, _)) => StatusCode::NOT_FOUND,
+        // MySQLやfirestoreにつながらない。時間をおけば成功するかもしれない
+        UseCaseError::Repository(RepositoryError::Unavailable(_)) => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::module::test::{fake_staff, fake_talk_room, TestModules};
+    use application::model::manual_message::CreateManualMessage;
+    use chrono::Local;
+    use domain::{
+        gateway::{
+            rich_menu::MockRichMenuGateway, send_message::MockSendMessageGateway,
+            user_auth::MockUserAuthGateway,
+        },
+        model::{
+            canned_response::CannedResponse,
+            line_user::LineUserProfile,
+            message::send_message::{
+                NewSendMessage, NewSendMessageText, NewSendMessages, NewSendSender,
+                NewSendSendingMethod, NewSendSendingType,
+            },
+            staff::{Staff, StaffRole},
+            talk_room::TalkRoom,
+            user::{User, UserProfile},
+            user_auth::LineId,
+            Id,
+        },
+        repository::{
+            business_hours::MockBusinessHoursRepository,
+            canned_response::MockCannedResponseRepository, staff::MockStaffRepository,
+            talk_room::MockTalkRoomRepository, talk_room_note::MockTalkRoomNoteRepository,
+            user::MockUserRepository, user_tag::MockUserTagRepository,
+        },
+    };
+    use dotenv::dotenv;
+    use mockall::predicate;
+
+    #[tokio::test]
+    async fn test_send_canned_response_with_placeholders() {
+        dotenv().ok();
+        /*
+         * 使えないプレースホルダーや閉じていない括弧のある定型文は作れない
+         */
+        let request: CannedResponseRequest = serde_json::from_str(
+            r#"{ "title": "受付", "body": "{display_name}様、薬剤師の{staff_name}です" }"#,
+        )
+        .unwrap();
+        assert!(request.validate().is_ok());
+        for body in ["{address}様", "{display_name様", ""] {
+            let request = CannedResponseRequest {
+                title: "受付".to_string(),
+                body: body.to_string(),
+            };
+            assert!(request.validate().is_err());
+        }
+
+        let now = Local::now();
+        let staff = Staff {
+            name: "佐藤".to_string(),
+            ..fake_staff(1, StaffRole::Pharmacist)
+        };
+        // 表示名にプレースホルダーと同じ文字が入っていても、置き換えずにそのまま送る
+        let talk_room = TalkRoom {
+            display_name: "{staff_name}".to_string(),
+            ..fake_talk_room()
+        };
+        let talk_room_id = talk_room.id.clone();
+        let canned_response_id = CannedResponseId::new(1);
+
+        let mut canned_response_repository = MockCannedResponseRepository::new();
+        let canned_response = CannedResponse::new(
+            canned_response_id,
+            "受付".to_string(),
+            request.body.clone(),
+            staff.id,
+            now,
+            now,
+        );
+        canned_response_repository
+            .expect_get_canned_response()
+            .with(predicate::eq(canned_response_id))
+            .once()
+            .returning(move |_| Ok(canned_response.clone()));
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_get_talk_room_by_id()
+            .with(predicate::eq(talk_room_id.clone()))
+            .once()
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+        let mut user_repository = MockUserRepository::new();
+        let user = User::new(
+            talk_room.primary_user_id.clone(),
+            UserProfile::Line(LineUserProfile::new(
+                LineId::new("U00000000000000000000000000000000".to_string()),
+                "display_name".to_string(),
+                "picture_url".to_string(),
+                None,
+                None,
+            )),
+        );
+        user_repository
+            .expect_get_line_user_by_primary_user_id()
+            .with(predicate::eq(talk_room.primary_user_id.clone()))
+            .once()
+            .returning(move |_| Ok(user.clone()));
+
+        /*
+         * プレースホルダーを置き換えた本文を、スタッフをsenderにして送る
+         */
+        let mut send_message_gateway = MockSendMessageGateway::new();
+        let sender = NewSendSender::from(staff.clone());
+        send_message_gateway
+            .expect_send_manual_messages()
+            .with(
+                predicate::always(),
+                predicate::eq(sender),
+                predicate::eq(vec!["{staff_name}様、薬剤師の佐藤です".to_string()]),
+            )
+            .once()
+            .returning(move |_, sender, texts| {
+                Ok(vec![NewSendMessages {
+                    id: Id::gen(),
+                    sending_type: NewSendSendingType::Manual,
+                    sending_method: NewSendSendingMethod::Push,
+                    sender: Some(sender),
+                    messages: texts
+                        .into_iter()
+                        .map(|text| {
+                            NewSendMessage::Text(NewSendMessageText {
+                                message_id: "message_id".to_string(),
+                                text,
+                                emojis: None,
+                                quote_token: None,
+                                created_at: now,
+                            })
+                        })
+                        .collect(),
+                }])
+            });
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_create_messages()
+            .once()
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            user_repository,
+            talk_room_repository,
+            send_message_gateway,
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            MockStaffRepository::new(),
+            MockTalkRoomNoteRepository::new(),
+            canned_response_repository,
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let new_send_messages_vec = modules
+            .talk_room_usecase()
+            .send_manual_message(
+                talk_room_id,
+                staff,
+                CreateManualMessage::CannedResponse(canned_response_id),
+            )
+            .await
+            .unwrap();
+        assert_eq!(new_send_messages_vec.len(), 1);
+    }
+}
@@ -0,0 +1,42 @@
+use crate::context::event_tasks::EventTasks;
+use crate::module::ModulesExt;
+use axum::{extract::Extension, http::StatusCode};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, warn};
+
+// ロードバランサーのヘルスチェックがタイムアウトする前に、503を返す
+const READINESS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// プロセスが動いているか。外部のサービスには問い合わせない
+pub async fn healthz_handler() -> StatusCode {
+    StatusCode::OK
+}
+
+/// データベースとfirestoreに接続でき、リクエストを受けられるか
+/// 終了処理を始めたあとは、新しいリクエストが来ないように503を返す
+#[tracing::instrument(skip(modules, event_tasks))]
+pub async fn readyz_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(event_tasks): Extension<EventTasks>,
+) -> StatusCode {
+    if event_tasks.is_closed() {
+        return StatusCode::SERVICE_UNAVAILABLE;
+    }
+    match tokio::time::timeout(
+        READINESS_TIMEOUT,
+        modules.health_check_usecase().check_readiness(),
+    )
+    .await
+    {
+        Ok(Ok(())) => StatusCode::OK,
+        Ok(Err(err)) => {
+            error!("Not ready: {:?}", err);
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        Err(_) => {
+            warn!("Readiness check timed out");
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use crate::context::errors::SignatureVerificationError;
+use crate::context::event_tasks::EventTasks;
 use crate::model::line_webhook::{
     LineWebhookEvent, LineWebhookEventRequest, LineWebhookEventRequests,
 };
-use crate::module::{Modules, ModulesExt};
+use crate::module::ModulesExt;
 use axum::{
     body::Bytes,
     extract::Extension,
@@ -10,25 +11,25 @@ use axum::{
     response::IntoResponse,
 };
 use base64::{engine::general_purpose, Engine as _};
+use domain::model::user_event::UserEvent;
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
-use std::env;
 use std::sync::Arc;
-use tracing::error;
+use tracing::{error, warn};
 
 /*
  * Jsonを受け取るときは、引数の順番に気をつける必要がある
  * https://github.com/tokio-rs/axum/discussions/1755
  * https://docs.rs/axum/latest/axum/extract/index.html#the-order-of-extractors
 */
-#[tracing::instrument(skip(modules))]
-pub async fn line_webhook_handler(
-    Extension(modules): Extension<Arc<Modules>>,
+#[tracing::instrument(skip(modules, event_tasks))]
+pub async fn line_webhook_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(event_tasks): Extension<EventTasks>,
     headers: HeaderMap,
     body_bytes: Bytes,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let channel_secret = env::var("LINE_CHANNEL_SECRET")
-        .unwrap_or_else(|_| panic!("LINE_CHANNEL_SECRET must be set!"));
+    let channel_secret = &modules.config().line.channel_secret;
     // x-line-signature ヘッダーを文字列として取得します。
     let x_line_signature = headers
         .get("x-line-signature")
@@ -38,7 +39,7 @@ pub async fn line_webhook_handler(
     let http_request_body = body_bytes.as_ref();
     // 署名を検証します。
     if let Err(err) =
-        verify_line_webhook_signature(&channel_secret, http_request_body, x_line_signature)
+        verify_line_webhook_signature(channel_secret, http_request_body, x_line_signature)
     {
         error!("Error: {}", err);
         return Err(StatusCode::UNAUTHORIZED);
@@ -51,40 +52,79 @@ pub async fn line_webhook_handler(
     let requests: Vec<LineWebhookEventRequest> = payload.into();
 
     // すぐにstatus code 200で返すために、非同期で処理を行う
-    tokio::spawn(process_line_events(requests, modules));
+    // 終了処理を始めたあとは受け付けない。LINEから再送されたイベントは、次に起動したサーバーが処理する
+    if !event_tasks.spawn(process_line_events(requests, modules)) {
+        warn!("Rejected webhook events because the server is shutting down");
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
 
     Ok(StatusCode::OK)
 }
 
-async fn process_line_events(
+async fn process_line_events<M: ModulesExt>(
     requests: Vec<LineWebhookEventRequest>,
-    modules: Arc<Modules>,
+    modules: Arc<M>,
 ) -> anyhow::Result<()> {
     for request in requests {
         let event = &request.event;
         match event {
-            LineWebhookEvent::Follow(_) => modules
-                .linebot_webhook_usecase()
-                .create_follow_event(request.into())
-                .await
-                .map_err(|err| anyhow::anyhow!("Unexpected error: {:?}", err))?,
+            LineWebhookEvent::Follow(_) => {
+                let user_events = modules
+                    .linebot_webhook_usecase()
+                    .create_follow_event(request.into())
+                    .await
+                    .map_err(|err| anyhow::anyhow!("Unexpected error: {:?}", err))?;
+                handle_user_events(user_events, &modules).await;
+            }
             LineWebhookEvent::Unfollow(e) => {
                 println!("Unfollow event: {:?}", e);
             }
-            LineWebhookEvent::Message(e) => {
-                println!("Message event: {:?}", e);
+            LineWebhookEvent::Message(_) => {
+                modules
+                    .linebot_webhook_usecase()
+                    .create_message_event(request.into())
+                    .await
+                    .map_err(|err| anyhow::anyhow!("Unexpected error: {:?}", err))?;
             }
-            LineWebhookEvent::Postback(e) => {
-                println!("Postback event: {:?}", e);
+            LineWebhookEvent::Postback(_) => {
+                modules
+                    .linebot_webhook_usecase()
+                    .create_postback_event(request.into())
+                    .await
+                    .map_err(|err| anyhow::anyhow!("Unexpected error: {:?}", err))?;
             }
             LineWebhookEvent::VideoPlayComplete(e) => {
                 println!("Other event: {:?}", e);
             }
+            LineWebhookEvent::AccountLink(_) => {
+                let external_member_id = modules
+                    .linebot_webhook_usecase()
+                    .create_account_link_event(request.into())
+                    .await
+                    .map_err(|err| anyhow::anyhow!("Unexpected error: {:?}", err))?;
+                if external_member_id.is_none() {
+                    warn!("Account link was not completed");
+                }
+            }
         }
     }
     Ok(())
 }
 
+/// ユースケースから返ってきたドメインイベントを、リッチメニューの割り当てに渡す
+/// リッチメニューの割り当てに失敗しても、イベント自体の処理は終わっているので後続の処理は止めない
+async fn handle_user_events<M: ModulesExt>(user_events: Vec<UserEvent>, modules: &Arc<M>) {
+    for user_event in user_events {
+        if let Err(err) = modules
+            .rich_menu_assignment_usecase()
+            .handle_user_event(user_event)
+            .await
+        {
+            error!("Failed to assign rich menu: {:?}", err);
+        }
+    }
+}
+
 /// Verify LINE webhook signature
 ///
 /// # Arguments
@@ -117,7 +157,8 @@ fn verify_line_webhook_signature(
 
 #[cfg(test)]
 mod test {
-    use crate::module::test::TestModules;
+    use crate::module::test::{test_config, TestModules};
+    use crate::module::Modules;
 
     use super::*;
     use adapter::model::message::{
@@ -127,27 +168,50 @@ mod test {
             SendMessageTable,
         },
     };
-    use application::model::event::CreateUserEvent;
+    use application::{config::AppConfig, model::event::CreateUserEvent};
+    use chrono::Local;
     use domain::{
-        gateway::{send_message::MockSendMessageGateway, user_auth::MockUserAuthGateway},
+        gateway::{
+            rich_menu::MockRichMenuGateway, send_message::MockSendMessageGateway,
+            user_auth::MockUserAuthGateway,
+        },
         model::{
+            account_link::{AccountLinkNonce, ExternalMemberId, NewAccountLink},
+            business_hours::BusinessHoursCalendar,
             line_user::LineUserProfile,
-            message::{event::NewEvent, Messages},
+            message::{
+                event::NewEvent,
+                send_message::{
+                    NewSendMessage, NewSendMessageText, NewSendMessages, NewSendSendingMethod,
+                    NewSendSendingType,
+                },
+                system_event::{
+                    SystemEventContent, SystemEventModeChanged, SystemEventStatusChanged,
+                },
+                Messages,
+            },
             primary_user_id::PrimaryUserId,
-            talk_room::{NewTalkRoom, TalkRoom},
+            staff::StaffId,
+            talk_room::{NewTalkRoom, TalkRoom, TalkRoomMode, TalkRoomStatus},
             user::{User, UserProfile},
-            user_auth::{AuthUserId, LineId, LineUserAuthData},
+            user_auth::{LineId, LineUserAuthData},
+            Id,
+        },
+        repository::{
+            business_hours::MockBusinessHoursRepository,
+            canned_response::MockCannedResponseRepository, staff::MockStaffRepository,
+            talk_room::MockTalkRoomRepository, talk_room_note::MockTalkRoomNoteRepository,
+            user::MockUserRepository, user_tag::MockUserTagRepository,
         },
-        repository::{talk_room::MockTalkRoomRepository, user::MockUserRepository},
     };
     use dotenv::dotenv;
     use mockall::predicate;
+    use std::env;
 
     #[test]
     fn test_verify_line_webhook_signature() {
         dotenv().ok();
-        let channel_secret = env::var("LINE_CHANNEL_SECRET")
-            .unwrap_or_else(|_| panic!("LINE_CHANNEL_SECRET must be set!"));
+        let channel_secret = test_config().line.channel_secret;
         let http_request_body = b"test_request_body";
         let invalid_signature = b"invalid_signature";
 
@@ -178,7 +242,7 @@ mod test {
     #[tokio::test]
     async fn test_process_fake_follow_event() {
         dotenv().ok();
-        let user_auth_gateway = MockUserAuthGateway::new();
+        let mut user_auth_gateway = MockUserAuthGateway::new();
         let mut user_repository = MockUserRepository::new();
         let mut talk_room_repository = MockTalkRoomRepository::new();
         let mut send_message_gateway = MockSendMessageGateway::new();
@@ -216,26 +280,47 @@ mod test {
 
         let create_user_event = CreateUserEvent::from(request.clone());
         let create_line_user_auth = create_user_event.create_line_user_auth;
-        let line_user_auth_data =
-            LineUserAuthData::try_from(create_line_user_auth.clone()).unwrap();
+        let line_user_auth_data = LineUserAuthData::new(
+            LineId::from(create_line_user_auth.clone()),
+            test_config().line.auth_token(),
+        );
         /*
          * ユーザーが存在するパターン
          */
         let user_line_id = LineId::from(create_line_user_auth);
         let primary_user_id = PrimaryUserId::new("primay_user_id".to_string());
+        let line_user_profile = LineUserProfile::new(
+            user_line_id.clone(),
+            "display_name".to_string(),
+            "picture_url".to_string(),
+            Some("status_message".to_string()),
+            Some("ja".to_string()),
+        );
         let user = User::new(
             primary_user_id.clone(),
-            UserProfile::Line(LineUserProfile::new(
-                user_line_id.clone(),
-                "display_name".to_string(),
-                "picture_url".to_string(),
-            )),
+            UserProfile::Line(line_user_profile.clone()),
         );
+        /*
+         * フォローのたびにプロフィールを取得し直して保存する
+         * 表示名は変わっていないので、talk_roomの表示名は更新しない
+         */
+        let cloned_line_user_profile = line_user_profile.clone();
+        user_auth_gateway
+            .expect_get_line_user_profile()
+            .with(predicate::eq(line_user_auth_data.clone()))
+            .once()
+            .returning(move |_| Ok(cloned_line_user_profile.clone()));
+        let updated_user = user.clone();
+        user_repository
+            .expect_update_line_user()
+            .with(predicate::eq(line_user_profile.clone()))
+            .once()
+            .returning(move |_| Ok(updated_user.clone()));
         let new_event = NewEvent::from(create_user_event.create_event);
         let new_talk_room = NewTalkRoom::from((user.clone(), new_event.clone()));
         user_repository
-            .expect_get_user()
-            .with(predicate::eq(AuthUserId::Line(user_line_id)))
+            .expect_get_or_create_user()
+            .with(predicate::eq(UserProfile::Line(line_user_profile)))
             .once()
             .returning(move |_| Ok(user.clone()));
         /*
@@ -353,6 +438,12 @@ mod test {
                 user_repository,
                 talk_room_repository,
                 send_message_gateway,
+                MockRichMenuGateway::new(),
+                MockUserTagRepository::new(),
+                MockStaffRepository::new(),
+                MockTalkRoomNoteRepository::new(),
+                MockCannedResponseRepository::new(),
+                MockBusinessHoursRepository::new(),
             )
             .await,
         );
@@ -365,6 +456,615 @@ mod test {
         assert!(response.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_process_fake_account_link_event() {
+        dotenv().ok();
+        let mut user_repository = MockUserRepository::new();
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+
+        let user_id = env::var("DEVELOPERS_LINE_ID")
+            .unwrap_or_else(|_| panic!("DEVELOPERS_LINE_ID must be set!"));
+        let json = format!(
+            r#"
+            {{
+                "destination": "xxxxxxxxxx",
+                "events": [
+                    {{
+                        "replyToken": "b60d432864f44d079f6d8efe86cf404b",
+                        "type": "accountLink",
+                        "mode": "active",
+                        "timestamp": 1513669370317,
+                        "source": {{
+                            "type": "user",
+                            "userId": "{}"
+                            }},
+                        "webhookEventId": "01FZ74A0TDDPYRVKNK77XKC3ZR",
+                        "deliveryContext": {{
+                            "isRedelivery": false
+                        }},
+                        "link": {{
+                            "result": "ok",
+                            "nonce": "xxxxxxxxxxxxxxx"
+                        }}
+                    }}
+                ]
+            }}
+            "#,
+            user_id
+        );
+        let line_webhook_requests: LineWebhookEventRequests =
+            serde_json::from_str(&json).expect("Failed to deserialize");
+        let requests: Vec<LineWebhookEventRequest> = line_webhook_requests.into();
+        let request = requests.first().unwrap();
+
+        let create_user_event = CreateUserEvent::from(request.clone());
+        let user_line_id = LineId::from(create_user_event.clone().create_line_user_auth);
+        let primary_user_id = PrimaryUserId::new("primay_user_id".to_string());
+        let user = User::new(
+            primary_user_id.clone(),
+            UserProfile::Line(LineUserProfile::new(
+                user_line_id.clone(),
+                "display_name".to_string(),
+                "picture_url".to_string(),
+                None,
+                None,
+            )),
+        );
+        let cloned_user = user.clone();
+        user_repository
+            .expect_get_line_user()
+            .with(predicate::eq(user_line_id))
+            .once()
+            .returning(move |_| Ok(cloned_user.clone()));
+        /*
+         * イベントはtalk_roomに保存する
+         */
+        let new_event = NewEvent::from(create_user_event.create_event);
+        let new_talk_room = NewTalkRoom::from((user, new_event.clone()));
+        let event =
+            EventTable::from(new_event.clone()).into_event(&new_event.id().value.to_string());
+        let talk_room = TalkRoom::new(
+            new_talk_room.id,
+            new_talk_room.primary_user_id.clone(),
+            new_talk_room.display_name,
+            new_talk_room.rsvp,
+            new_talk_room.pinned,
+            new_talk_room.follow,
+            Messages::Event(event),
+            new_talk_room.latest_messaged_at,
+            new_talk_room.sort_time,
+            new_talk_room.created_at,
+            new_talk_room.updated_at,
+        );
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_get_talk_room()
+            .with(predicate::eq(primary_user_id.clone()))
+            .once()
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+        talk_room_repository
+            .expect_create_messages()
+            .withf(|_| true)
+            .once()
+            .returning(move |_| Ok(talk_room.clone()));
+        /*
+         * nonceを照合して、会員システムのアカウントと紐づける
+         */
+        user_repository
+            .expect_link_account()
+            .with(predicate::eq(NewAccountLink::new(
+                primary_user_id,
+                AccountLinkNonce::new("xxxxxxxxxxxxxxx".to_string()),
+            )))
+            .once()
+            .returning(|_| Ok(ExternalMemberId::new("member_id".to_string())));
+
+        let modules = Arc::new(
+            TestModules::new(
+                MockUserAuthGateway::new(),
+                user_repository,
+                talk_room_repository,
+                MockSendMessageGateway::new(),
+                MockRichMenuGateway::new(),
+                MockUserTagRepository::new(),
+                MockStaffRepository::new(),
+                MockTalkRoomNoteRepository::new(),
+                MockCannedResponseRepository::new(),
+                MockBusinessHoursRepository::new(),
+            )
+            .await,
+        );
+        let response = modules
+            .linebot_webhook_usecase()
+            .create_account_link_event(request.clone().into())
+            .await;
+
+        assert_eq!(
+            response.unwrap(),
+            Some(ExternalMemberId::new("member_id".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_fake_postback_event_hands_off_to_staff() {
+        dotenv().ok();
+        let mut user_repository = MockUserRepository::new();
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+
+        let user_id = env::var("DEVELOPERS_LINE_ID")
+            .unwrap_or_else(|_| panic!("DEVELOPERS_LINE_ID must be set!"));
+        // リッチメニューのpostbackにはparamsがない
+        let json = format!(
+            r#"
+            {{
+                "destination": "xxxxxxxxxx",
+                "events": [
+                    {{
+                        "replyToken": "b60d1f9bd5e4443b8a5e8b1b8d2bd9bd",
+                        "type": "postback",
+                        "mode": "active",
+                        "timestamp": 1462629479859,
+                        "source": {{
+                            "type": "user",
+                            "userId": "{}"
+                            }},
+                        "webhookEventId": "01FZ74A0TDDPYRVKNK77XKC3ZR",
+                        "deliveryContext": {{
+                            "isRedelivery": false
+                        }},
+                        "postback": {{
+                            "data": "action=talk_to_pharmacist"
+                        }}
+                    }}
+                ]
+            }}
+            "#,
+            user_id
+        );
+        let line_webhook_requests: LineWebhookEventRequests =
+            serde_json::from_str(&json).expect("Failed to deserialize");
+        let requests: Vec<LineWebhookEventRequest> = line_webhook_requests.into();
+        let request = requests.first().unwrap();
+
+        let create_user_event = CreateUserEvent::from(request.clone());
+        let user_line_id = LineId::from(create_user_event.clone().create_line_user_auth);
+        let primary_user_id = PrimaryUserId::new("primay_user_id".to_string());
+        let user = User::new(
+            primary_user_id.clone(),
+            UserProfile::Line(LineUserProfile::new(
+                user_line_id.clone(),
+                "display_name".to_string(),
+                "picture_url".to_string(),
+                None,
+                None,
+            )),
+        );
+        let cloned_user = user.clone();
+        user_repository
+            .expect_get_line_user()
+            .with(predicate::eq(user_line_id))
+            .once()
+            .returning(move |_| Ok(cloned_user.clone()));
+        /*
+         * botが返信している、対応が終わったtalk_roomで薬剤師との会話を求めるパターン
+         */
+        let new_event = NewEvent::from(create_user_event.create_event);
+        assert!(new_event.is_handoff_request());
+        let new_talk_room = NewTalkRoom::from((user, new_event.clone()));
+        let event =
+            EventTable::from(new_event.clone()).into_event(&new_event.id().value.to_string());
+        let talk_room = TalkRoom {
+            status: TalkRoomStatus::Resolved,
+            ..TalkRoom::new(
+                new_talk_room.id,
+                new_talk_room.primary_user_id.clone(),
+                new_talk_room.display_name,
+                new_talk_room.rsvp,
+                new_talk_room.pinned,
+                new_talk_room.follow,
+                Messages::Event(event),
+                new_talk_room.latest_messaged_at,
+                new_talk_room.sort_time,
+                new_talk_room.created_at,
+                new_talk_room.updated_at,
+            )
+        };
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_get_talk_room()
+            .with(predicate::eq(primary_user_id.clone()))
+            .once()
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_create_messages()
+            .withf(|new_talk_room| new_talk_room.mode == TalkRoomMode::Bot)
+            .once()
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+        /*
+         * botの返信を止めてopenに戻し、ユーザーが求めたので操作したスタッフはなしで履歴に残す
+         */
+        talk_room_repository
+            .expect_update_workflow()
+            .withf(|workflow| {
+                workflow.mode == TalkRoomMode::Human
+                    && workflow.status == TalkRoomStatus::Open
+                    && workflow.system_events.len() == 2
+                    && workflow.system_events.iter().all(|e| e.operator.is_none())
+                    && workflow.system_events[0].content
+                        == SystemEventContent::ModeChanged(SystemEventModeChanged::new(
+                            TalkRoomMode::Bot,
+                            TalkRoomMode::Human,
+                        ))
+                    && workflow.system_events[1].content
+                        == SystemEventContent::StatusChanged(SystemEventStatusChanged::new(
+                            TalkRoomStatus::Resolved,
+                            TalkRoomStatus::Open,
+                        ))
+            })
+            .once()
+            .returning(|_| Ok(()));
+
+        let modules = Arc::new(
+            TestModules::new(
+                MockUserAuthGateway::new(),
+                user_repository,
+                talk_room_repository,
+                MockSendMessageGateway::new(),
+                MockRichMenuGateway::new(),
+                MockUserTagRepository::new(),
+                MockStaffRepository::new(),
+                MockTalkRoomNoteRepository::new(),
+                MockCannedResponseRepository::new(),
+                MockBusinessHoursRepository::new(),
+            )
+            .await,
+        );
+        let updated_talk_room = modules
+            .linebot_webhook_usecase()
+            .create_postback_event(request.clone().into())
+            .await
+            .unwrap();
+
+        assert_eq!(updated_talk_room.mode, TalkRoomMode::Human);
+        assert_eq!(updated_talk_room.status, TalkRoomStatus::Open);
+        // スタッフが対応しているあいだは、営業時間外でもbotは返信しない
+        assert!(!updated_talk_room.mode.bot_replies(false));
+        assert!(!TalkRoomMode::BotOutsideBusinessHours.bot_replies(true));
+        assert!(TalkRoomMode::BotOutsideBusinessHours.bot_replies(false));
+    }
+
+    #[tokio::test]
+    async fn test_process_fake_message_event_reopens_resolved_talk_room() {
+        dotenv().ok();
+        let mut user_repository = MockUserRepository::new();
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+
+        let user_id = env::var("DEVELOPERS_LINE_ID")
+            .unwrap_or_else(|_| panic!("DEVELOPERS_LINE_ID must be set!"));
+        let json = format!(
+            r#"
+            {{
+                "destination": "xxxxxxxxxx",
+                "events": [
+                    {{
+                        "replyToken": "nHuyWiB7yP5Zw52FIkcQobQuGDXCTA",
+                        "type": "message",
+                        "mode": "active",
+                        "timestamp": 1462629479859,
+                        "source": {{
+                            "type": "user",
+                            "userId": "{}"
+                            }},
+                        "webhookEventId": "01FZ74A0TDDPYRVKNK77XKC3ZR",
+                        "deliveryContext": {{
+                            "isRedelivery": false
+                        }},
+                        "message": {{
+                            "id": "444573844083572737",
+                            "type": "text",
+                            "quoteToken": "q3Plxr4AgKd...",
+                            "text": "薬について相談したいです"
+                        }}
+                    }}
+                ]
+            }}
+            "#,
+            user_id
+        );
+        let line_webhook_requests: LineWebhookEventRequests =
+            serde_json::from_str(&json).expect("Failed to deserialize");
+        let requests: Vec<LineWebhookEventRequest> = line_webhook_requests.into();
+        let request = requests.first().unwrap();
+
+        let create_user_event = CreateUserEvent::from(request.clone());
+        let user_line_id = LineId::from(create_user_event.clone().create_line_user_auth);
+        let primary_user_id = PrimaryUserId::new("primay_user_id".to_string());
+        let user = User::new(
+            primary_user_id.clone(),
+            UserProfile::Line(LineUserProfile::new(
+                user_line_id.clone(),
+                "display_name".to_string(),
+                "picture_url".to_string(),
+                None,
+                None,
+            )),
+        );
+        let cloned_user = user.clone();
+        user_repository
+            .expect_get_line_user()
+            .with(predicate::eq(user_line_id))
+            .once()
+            .returning(move |_| Ok(cloned_user.clone()));
+        /*
+         * 担当者が対応を終えたtalk_roomにメッセージが届くパターン
+         */
+        let new_event = NewEvent::from(create_user_event.create_event);
+        let new_talk_room = NewTalkRoom::from((user, new_event.clone()));
+        let event =
+            EventTable::from(new_event.clone()).into_event(&new_event.id().value.to_string());
+        let assignee = StaffId::new(1);
+        let talk_room = TalkRoom {
+            assignee: Some(assignee),
+            status: TalkRoomStatus::Resolved,
+            ..TalkRoom::new(
+                new_talk_room.id,
+                new_talk_room.primary_user_id.clone(),
+                new_talk_room.display_name,
+                new_talk_room.rsvp,
+                new_talk_room.pinned,
+                new_talk_room.follow,
+                Messages::Event(event),
+                new_talk_room.latest_messaged_at,
+                new_talk_room.sort_time,
+                new_talk_room.created_at,
+                new_talk_room.updated_at,
+            )
+        };
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_get_talk_room()
+            .with(predicate::eq(primary_user_id.clone()))
+            .once()
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_create_messages()
+            .withf(|new_talk_room| new_talk_room.status == TalkRoomStatus::Resolved)
+            .once()
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+        /*
+         * 担当者はそのままでopenに戻し、自動で戻したことを履歴に残す
+         */
+        talk_room_repository
+            .expect_update_workflow()
+            .withf(move |workflow| {
+                workflow.assignee == Some(assignee)
+                    && workflow.status == TalkRoomStatus::Open
+                    && workflow.system_events.len() == 1
+                    && workflow.system_events[0].operator.is_none()
+                    && workflow.system_events[0].content
+                        == SystemEventContent::StatusChanged(SystemEventStatusChanged::new(
+                            TalkRoomStatus::Resolved,
+                            TalkRoomStatus::Open,
+                        ))
+            })
+            .once()
+            .returning(|_| Ok(()));
+        // 営業時間を登録していないチャネルは、常に営業時間内
+        let mut business_hours_repository = MockBusinessHoursRepository::new();
+        business_hours_repository
+            .expect_get_calendar()
+            .once()
+            .returning(|_| Ok(None));
+
+        let modules = Arc::new(
+            TestModules::new(
+                MockUserAuthGateway::new(),
+                user_repository,
+                talk_room_repository,
+                MockSendMessageGateway::new(),
+                MockRichMenuGateway::new(),
+                MockUserTagRepository::new(),
+                MockStaffRepository::new(),
+                MockTalkRoomNoteRepository::new(),
+                MockCannedResponseRepository::new(),
+                business_hours_repository,
+            )
+            .await,
+        );
+        let updated_talk_room = modules
+            .linebot_webhook_usecase()
+            .create_message_event(request.clone().into())
+            .await
+            .unwrap();
+
+        assert_eq!(updated_talk_room.status, TalkRoomStatus::Open);
+        assert_eq!(updated_talk_room.assignee, Some(assignee));
+    }
+
+    #[tokio::test]
+    async fn test_process_fake_message_event_replies_away_message_outside_business_hours() {
+        process_fake_message_event_outside_business_hours(TalkRoomMode::Bot).await;
+    }
+
+    #[tokio::test]
+    async fn test_process_fake_message_event_skips_away_message_for_human_mode() {
+        process_fake_message_event_outside_business_hours(TalkRoomMode::Human).await;
+    }
+
+    /// 営業時間外にメッセージが届いたときに、modeでbotが返信するときだけ不在メッセージを返す
+    async fn process_fake_message_event_outside_business_hours(mode: TalkRoomMode) {
+        dotenv().ok();
+        let bot_replies = mode.bot_replies(false);
+        let mut user_repository = MockUserRepository::new();
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+        let mut send_message_gateway = MockSendMessageGateway::new();
+        let mut business_hours_repository = MockBusinessHoursRepository::new();
+
+        let user_id = env::var("DEVELOPERS_LINE_ID")
+            .unwrap_or_else(|_| panic!("DEVELOPERS_LINE_ID must be set!"));
+        let json = format!(
+            r#"
+            {{
+                "destination": "xxxxxxxxxx",
+                "events": [
+                    {{
+                        "replyToken": "nHuyWiB7yP5Zw52FIkcQobQuGDXCTA",
+                        "type": "message",
+                        "mode": "active",
+                        "timestamp": 1462629479859,
+                        "source": {{
+                            "type": "user",
+                            "userId": "{}"
+                            }},
+                        "webhookEventId": "01FZ74A0TDDPYRVKNK77XKC3ZR",
+                        "deliveryContext": {{
+                            "isRedelivery": false
+                        }},
+                        "message": {{
+                            "id": "444573844083572737",
+                            "type": "text",
+                            "quoteToken": "q3Plxr4AgKd...",
+                            "text": "夜分にすみません"
+                        }}
+                    }}
+                ]
+            }}
+            "#,
+            user_id
+        );
+        let line_webhook_requests: LineWebhookEventRequests =
+            serde_json::from_str(&json).expect("Failed to deserialize");
+        let requests: Vec<LineWebhookEventRequest> = line_webhook_requests.into();
+        let request = requests.first().unwrap();
+
+        let create_user_event = CreateUserEvent::from(request.clone());
+        let user_line_id = LineId::from(create_user_event.clone().create_line_user_auth);
+        let primary_user_id = PrimaryUserId::new("primay_user_id".to_string());
+        let user = User::new(
+            primary_user_id.clone(),
+            UserProfile::Line(LineUserProfile::new(
+                user_line_id.clone(),
+                "display_name".to_string(),
+                "picture_url".to_string(),
+                None,
+                None,
+            )),
+        );
+        let cloned_user = user.clone();
+        user_repository
+            .expect_get_line_user()
+            .with(predicate::eq(user_line_id))
+            .once()
+            .returning(move |_| Ok(cloned_user.clone()));
+        let new_event = NewEvent::from(create_user_event.create_event);
+        let new_talk_room = NewTalkRoom::from((user, new_event.clone()));
+        let event =
+            EventTable::from(new_event.clone()).into_event(&new_event.id().value.to_string());
+        let talk_room = TalkRoom {
+            mode,
+            ..TalkRoom::new(
+                new_talk_room.id,
+                new_talk_room.primary_user_id.clone(),
+                new_talk_room.display_name,
+                new_talk_room.rsvp,
+                new_talk_room.pinned,
+                new_talk_room.follow,
+                Messages::Event(event),
+                new_talk_room.latest_messaged_at,
+                new_talk_room.sort_time,
+                new_talk_room.created_at,
+                new_talk_room.updated_at,
+            )
+        };
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_get_talk_room()
+            .with(predicate::eq(primary_user_id.clone()))
+            .once()
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+        /*
+         * 営業日がないカレンダーなので、いつでも営業時間外になる
+         * botが返信するときは、ユーザーのメッセージと不在メッセージの2回保存する
+         * スタッフが対応しているときは、不在メッセージを送らずにユーザーのメッセージだけを保存する
+         */
+        let away_message = "本日の営業は終了しました".to_string();
+        let cloned_away_message = away_message.clone();
+        business_hours_repository
+            .expect_get_calendar()
+            .once()
+            .returning(move |channel_id| {
+                Ok(Some(BusinessHoursCalendar::new(
+                    channel_id,
+                    "Asia/Tokyo".parse().unwrap(),
+                    vec![],
+                    vec![],
+                    Some(cloned_away_message.clone()),
+                    Local::now(),
+                )))
+            });
+        talk_room_repository
+            .expect_claim_away_message()
+            .times(bot_replies as usize)
+            .returning(|_, _| Ok(true));
+        let cloned_away_message = away_message.clone();
+        send_message_gateway
+            .expect_reply_bot_messages()
+            .withf(move |_, reply_token, texts| {
+                reply_token == "nHuyWiB7yP5Zw52FIkcQobQuGDXCTA"
+                    && texts == &vec![cloned_away_message.clone()]
+            })
+            .times(bot_replies as usize)
+            .returning(|_, _, texts| {
+                Ok(vec![NewSendMessages {
+                    id: Id::gen(),
+                    sending_type: NewSendSendingType::Bot,
+                    sending_method: NewSendSendingMethod::Reply,
+                    sender: None,
+                    messages: texts
+                        .into_iter()
+                        .map(|text| {
+                            NewSendMessage::Text(NewSendMessageText {
+                                message_id: "message_id".to_string(),
+                                text,
+                                emojis: None,
+                                quote_token: None,
+                                created_at: Local::now(),
+                            })
+                        })
+                        .collect(),
+                }])
+            });
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_create_messages()
+            .times(if bot_replies { 2 } else { 1 })
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+
+        let modules = Arc::new(
+            TestModules::new(
+                MockUserAuthGateway::new(),
+                user_repository,
+                talk_room_repository,
+                send_message_gateway,
+                MockRichMenuGateway::new(),
+                MockUserTagRepository::new(),
+                MockStaffRepository::new(),
+                MockTalkRoomNoteRepository::new(),
+                MockCannedResponseRepository::new(),
+                business_hours_repository,
+            )
+            .await,
+        );
+        let res = modules
+            .linebot_webhook_usecase()
+            .create_message_event(request.clone().into())
+            .await;
+
+        assert!(res.is_ok());
+    }
+
     #[tokio::test]
     #[cfg_attr(not(feature = "database-interaction-test"), ignore)]
     async fn test_process_follow_event() {
@@ -404,8 +1104,8 @@ mod test {
         let line_webhook_requests: LineWebhookEventRequests =
             serde_json::from_str(&json).expect("Failed to deserialize");
 
-        let response =
-            process_line_events(line_webhook_requests.into(), Arc::new(Modules::new().await)).await;
+        let modules = Modules::new(AppConfig::load().unwrap()).await.unwrap();
+        let response = process_line_events(line_webhook_requests.into(), Arc::new(modules)).await;
 
         assert!(response.is_ok());
     }
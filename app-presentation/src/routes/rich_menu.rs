@@ -0,0 +1,242 @@
+use crate::context::staff_auth::AuthenticatedStaff;
+use crate::model::rich_menu::{
+    DeployedRichMenuResponse, RichMenuDefinitionsRequest, SyncedRichMenuResponse,
+};
+use crate::module::ModulesExt;
+use anyhow::anyhow;
+use application::model::rich_menu::CreateRichMenu;
+use axum::{extract::Extension, http::StatusCode, response::IntoResponse, Json};
+use domain::model::{
+    rich_menu::{RichMenuAliasId, RichMenuImage},
+    staff::StaffPermission,
+};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::error;
+
+/// RICH_MENU_DEFINITION_PATHの定義ファイルを読み込み、リッチメニューをデプロイする
+#[tracing::instrument(skip(modules, staff))]
+pub async fn deploy_rich_menus_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ManageRichMenus)?;
+    let definition_path = &modules.config().rich_menu_definition_path;
+    let create_rich_menus = load_rich_menu_definitions(definition_path)
+        .await
+        .map_err(|err| {
+            error!("Failed to load rich menu definitions: {:?}", err);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+    let deployed_rich_menus = modules
+        .rich_menu_usecase()
+        .deploy_rich_menus(create_rich_menus)
+        .await
+        .map_err(|err| {
+            error!("Failed to deploy rich menus: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(
+        deployed_rich_menus
+            .into_iter()
+            .map(DeployedRichMenuResponse::from)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// 全ユーザーのリッチメニューを、タグとルールに従ってリンクし直す
+#[tracing::instrument(skip(modules, staff))]
+pub async fn resync_rich_menus_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ManageRichMenus)?;
+    let synced_rich_menus = modules
+        .rich_menu_assignment_usecase()
+        .resync_rich_menus()
+        .await
+        .map_err(|err| {
+            error!("Failed to resync rich menus: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(
+        synced_rich_menus
+            .into_iter()
+            .map(SyncedRichMenuResponse::from)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// リッチメニューの定義ファイルと画像を読み込む
+///
+/// # Arguments
+/// * `definition_path` - 定義ファイルのパス。画像のパスはこのファイルからの相対パスで解決する
+///
+async fn load_rich_menu_definitions(definition_path: &Path) -> anyhow::Result<Vec<CreateRichMenu>> {
+    let definition = tokio::fs::read_to_string(definition_path).await?;
+    let definitions: RichMenuDefinitionsRequest = serde_json::from_str(&definition)?;
+    let base_dir = definition_path.parent().unwrap_or(Path::new("."));
+
+    let mut create_rich_menus = Vec::new();
+    for definition in definitions.rich_menus {
+        let content_type = definition.image_content_type().ok_or(anyhow!(
+            "Rich menu image must be png or jpeg: {}",
+            definition.image_path
+        ))?;
+        let data = tokio::fs::read(base_dir.join(&definition.image_path)).await?;
+        create_rich_menus.push(CreateRichMenu::new(
+            definition.new_rich_menu(),
+            RichMenuImage::new(content_type, data),
+            definition.alias_id.clone().map(RichMenuAliasId::new),
+            definition.default,
+        ));
+    }
+
+    Ok(create_rich_menus)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::module::test::TestModules;
+    use domain::{
+        gateway::{
+            rich_menu::MockRichMenuGateway, send_message::MockSendMessageGateway,
+            user_auth::MockUserAuthGateway,
+        },
+        model::rich_menu::{RichMenuAlias, RichMenuId, RichMenuImageContentType},
+        repository::{
+            business_hours::MockBusinessHoursRepository,
+            canned_response::MockCannedResponseRepository, staff::MockStaffRepository,
+            talk_room::MockTalkRoomRepository, talk_room_note::MockTalkRoomNoteRepository,
+            user::MockUserRepository, user_tag::MockUserTagRepository,
+        },
+    };
+    use dotenv::dotenv;
+    use mockall::predicate;
+    use std::env;
+
+    #[tokio::test]
+    async fn test_deploy_rich_menus_from_definition_file() {
+        dotenv().ok();
+        /*
+         * 一時ディレクトリに定義ファイルと画像を作成する
+         */
+        let dir = env::temp_dir().join(format!("rich_menu_test_{}", unique_suffix()));
+        tokio::fs::create_dir_all(dir.join("images")).await.unwrap();
+        tokio::fs::write(dir.join("images/main.png"), b"png")
+            .await
+            .unwrap();
+        let definition = r#"
+            {
+                "richMenus": [
+                    {
+                        "name": "main",
+                        "chatBarText": "メニュー",
+                        "size": { "width": 2500, "height": 843 },
+                        "areas": [
+                            {
+                                "bounds": { "x": 0, "y": 0, "width": 1250, "height": 843 },
+                                "action": { "type": "postback", "data": "action=talk_to_pharmacist" }
+                            },
+                            {
+                                "bounds": { "x": 1250, "y": 0, "width": 1250, "height": 843 },
+                                "action": { "type": "richmenuswitch", "richMenuAliasId": "sub", "data": "switch=sub" }
+                            }
+                        ],
+                        "imagePath": "images/main.png",
+                        "aliasId": "main",
+                        "default": true
+                    }
+                ]
+            }
+        "#;
+        let definition_path = dir.join("rich_menus.json");
+        tokio::fs::write(&definition_path, definition)
+            .await
+            .unwrap();
+
+        let create_rich_menus = load_rich_menu_definitions(&definition_path).await.unwrap();
+        assert_eq!(create_rich_menus.len(), 1);
+        let create_rich_menu = create_rich_menus[0].clone();
+        assert_eq!(create_rich_menu.rich_menu.areas.len(), 2);
+        assert_eq!(
+            create_rich_menu.image,
+            RichMenuImage::new(RichMenuImageContentType::Png, b"png".to_vec())
+        );
+
+        /*
+         * 作成、画像のアップロード、aliasの作成、デフォルト設定の順で呼ばれる
+         */
+        let mut rich_menu_gateway = MockRichMenuGateway::new();
+        let rich_menu_id = RichMenuId::new("richmenu-0001".to_string());
+        let alias_id = RichMenuAliasId::new("main".to_string());
+        let cloned_rich_menu_id = rich_menu_id.clone();
+        rich_menu_gateway
+            .expect_create_rich_menu()
+            .with(
+                predicate::always(),
+                predicate::eq(create_rich_menu.rich_menu.clone()),
+            )
+            .once()
+            .returning(move |_, s| Ok(s.into_rich_menu(cloned_rich_menu_id.clone())));
+        rich_menu_gateway
+            .expect_upload_rich_menu_image()
+            .with(
+                predicate::always(),
+                predicate::eq(rich_menu_id.clone()),
+                predicate::eq(create_rich_menu.image.clone()),
+            )
+            .once()
+            .returning(|_, _, _| Ok(()));
+        rich_menu_gateway
+            .expect_get_rich_menu_alias()
+            .with(predicate::always(), predicate::eq(alias_id.clone()))
+            .once()
+            .returning(|_, _| Ok(None));
+        rich_menu_gateway
+            .expect_create_rich_menu_alias()
+            .with(
+                predicate::always(),
+                predicate::eq(RichMenuAlias::new(alias_id, rich_menu_id.clone())),
+            )
+            .once()
+            .returning(|_, _| Ok(()));
+        rich_menu_gateway
+            .expect_set_default_rich_menu()
+            .with(predicate::always(), predicate::eq(rich_menu_id.clone()))
+            .once()
+            .returning(|_, _| Ok(()));
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            MockTalkRoomRepository::new(),
+            MockSendMessageGateway::new(),
+            rich_menu_gateway,
+            MockUserTagRepository::new(),
+            MockStaffRepository::new(),
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let deployed_rich_menus = modules
+            .rich_menu_usecase()
+            .deploy_rich_menus(create_rich_menus)
+            .await
+            .unwrap();
+        assert_eq!(deployed_rich_menus[0].rich_menu.id, rich_menu_id);
+
+        tokio::fs::remove_dir_all(dir).await.ok();
+    }
+
+    fn unique_suffix() -> u128 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    }
+}
@@ -0,0 +1,253 @@
+use crate::context::staff_auth::AuthenticatedStaff;
+use crate::model::staff::{
+    CreateStaffRequest, StaffLoginRequest, StaffResponse, StaffRoleRequest, StaffSessionResponse,
+};
+use crate::module::ModulesExt;
+use application::model::staff::CreateStaff;
+use application::usecase::UseCaseError;
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use domain::model::staff::{NewStaff, StaffId, StaffPermission, StaffRole};
+use domain::repository::RepositoryError;
+use std::sync::Arc;
+use tracing::error;
+
+/// メールアドレスとパスワードでログインし、アクセストークンを返す
+#[tracing::instrument(skip(modules, request))]
+pub async fn staff_login_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Json(request): Json<StaffLoginRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let staff_session = modules
+        .staff_usecase()
+        .login(request.email, request.password)
+        .await
+        .map_err(|err| {
+            error!("Failed to login staff: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(StaffSessionResponse::from(staff_session)))
+}
+
+/// ログインしているスタッフを返す
+#[tracing::instrument(skip(staff))]
+pub async fn get_me_handler(
+    Extension(staff): Extension<AuthenticatedStaff>,
+) -> Result<impl IntoResponse, StatusCode> {
+    Ok(Json(StaffResponse::from(staff.0)))
+}
+
+#[tracing::instrument(skip(modules, staff))]
+pub async fn get_staffs_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ManageStaffs)?;
+    let staffs = modules.staff_usecase().get_staffs().await.map_err(|err| {
+        error!("Failed to get staffs: {:?}", err);
+        status_code_from_error(&err)
+    })?;
+
+    Ok(Json(
+        staffs
+            .into_iter()
+            .map(StaffResponse::from)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+#[tracing::instrument(skip(modules, staff, request))]
+pub async fn create_staff_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Json(request): Json<CreateStaffRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ManageStaffs)?;
+    create_staff(modules, request).await
+}
+
+/// 最初のadminを作るために、ADMIN_API_KEYでもスタッフを作れるようにする
+#[tracing::instrument(skip(modules, request))]
+pub async fn bootstrap_staff_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Json(request): Json<CreateStaffRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    create_staff(modules, request).await
+}
+
+#[tracing::instrument(skip(modules, staff))]
+pub async fn update_staff_role_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(staff_id): Path<i64>,
+    Json(request): Json<StaffRoleRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ManageStaffs)?;
+    let role = request.role.parse::<StaffRole>().map_err(|err| {
+        error!("Invalid staff role: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    let updated_staff = modules
+        .staff_usecase()
+        .update_staff_role(StaffId::new(staff_id), role)
+        .await
+        .map_err(|err| {
+            error!("Failed to update staff role: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(StaffResponse::from(updated_staff)))
+}
+
+async fn create_staff<M: ModulesExt>(
+    modules: Arc<M>,
+    request: CreateStaffRequest,
+) -> Result<(StatusCode, Json<StaffResponse>), StatusCode> {
+    let new_staff = NewStaff::try_from(CreateStaff::from(request)).map_err(|err| {
+        error!("Invalid staff: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    let created_staff = modules
+        .staff_usecase()
+        .create_staff(new_staff)
+        .await
+        .map_err(|err| {
+            error!("Failed to create staff: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(StaffResponse::from(created_staff)),
+    ))
+}
+
+fn status_code_from_error(err: &UseCaseError) -> StatusCode {
+    match err {
+        UseCaseError::Repository(RepositoryError::NotFound(_, _)) => StatusCode::NOT_FOUND,
+        // 同じメールアドレスのスタッフが既にいる
+        UseCaseError::Repository(RepositoryError::Conflict(_, _, _)) => StatusCode::CONFLICT,
+        UseCaseError::Repository(RepositoryError::NotAuthFound(_)) => StatusCode::UNAUTHORIZED,
+        // MySQLやfirestoreにつながらない。時間をおけば成功するかもしれない
+        UseCaseError::Repository(RepositoryError::Unavailable(_)) => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::module::test::TestModules;
+    use domain::{
+        gateway::{
+            rich_menu::MockRichMenuGateway, send_message::MockSendMessageGateway,
+            user_auth::MockUserAuthGateway,
+        },
+        model::{
+            email_user::{EmailAddress, PasswordHash},
+            staff::Staff,
+        },
+        repository::{
+            business_hours::MockBusinessHoursRepository,
+            canned_response::MockCannedResponseRepository, staff::MockStaffRepository,
+            talk_room::MockTalkRoomRepository, talk_room_note::MockTalkRoomNoteRepository,
+            user::MockUserRepository, user_tag::MockUserTagRepository,
+        },
+    };
+    use mockall::predicate;
+
+    #[tokio::test]
+    async fn test_staff_login_issues_token_for_authenticate() {
+        let email = EmailAddress::new("viewer@example.com".to_string());
+        let staff = Staff::new(
+            StaffId::new(1),
+            "viewer".to_string(),
+            email.clone(),
+            "".to_string(),
+            StaffRole::Viewer,
+            PasswordHash::from_plain("password").unwrap(),
+        );
+
+        let mut staff_repository = MockStaffRepository::new();
+        let cloned_staff = staff.clone();
+        staff_repository
+            .expect_get_staff_by_email()
+            .with(predicate::eq(email))
+            .times(2)
+            .returning(move |_| Ok(cloned_staff.clone()));
+        let cloned_staff = staff.clone();
+        staff_repository
+            .expect_get_staff()
+            .with(predicate::eq(staff.id))
+            .once()
+            .returning(move |_| Ok(cloned_staff.clone()));
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            MockTalkRoomRepository::new(),
+            MockSendMessageGateway::new(),
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            staff_repository,
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+
+        /*
+         * パスワードが違うときは認証エラーになる
+         */
+        let err = modules
+            .staff_usecase()
+            .login("viewer@example.com".to_string(), "wrong".to_string())
+            .await
+            .unwrap_err();
+        assert_eq!(status_code_from_error(&err), StatusCode::UNAUTHORIZED);
+
+        /*
+         * 発行したアクセストークンでスタッフを取り出せる
+         */
+        let staff_session = modules
+            .staff_usecase()
+            .login("viewer@example.com".to_string(), "password".to_string())
+            .await
+            .unwrap();
+        let authenticated_staff = modules
+            .staff_usecase()
+            .authenticate(&staff_session.access_token)
+            .await
+            .unwrap();
+        assert_eq!(authenticated_staff, staff);
+
+        /*
+         * viewerは閲覧しかできない
+         */
+        let authenticated_staff = AuthenticatedStaff(authenticated_staff);
+        assert!(authenticated_staff
+            .require(StaffPermission::ViewTalkRooms)
+            .is_ok());
+        assert_eq!(
+            authenticated_staff.require(StaffPermission::ManageStaffs),
+            Err(StatusCode::FORBIDDEN)
+        );
+
+        /*
+         * 改ざんしたトークンは認証エラーになる
+         */
+        let err = modules
+            .staff_usecase()
+            .authenticate(&format!("{}x", staff_session.access_token))
+            .await
+            .unwrap_err();
+        assert_eq!(status_code_from_error(&err), StatusCode::UNAUTHORIZED);
+    }
+}
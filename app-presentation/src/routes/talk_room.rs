@@ -0,0 +1,1030 @@
+use crate::context::staff_auth::AuthenticatedStaff;
+use crate::model::message::{
+    MessagesQuery, MessagesResponse, SendManualMessageRequest, SentManualMessagesResponse,
+};
+use crate::model::talk_room::{
+    TalkRoomAssigneeRequest, TalkRoomCardsQuery, TalkRoomCardsResponse, TalkRoomModeRequest,
+    TalkRoomNicknameRequest, TalkRoomPinnedRequest, TalkRoomReadMarkerResponse,
+    TalkRoomRsvpRequest, TalkRoomSettingsResponse, TalkRoomStatusRequest, TalkRoomWorkflowResponse,
+};
+use crate::model::talk_room_change::{
+    decode_resume_token, talk_room_change_event, TalkRoomChangesQuery,
+};
+use crate::module::ModulesExt;
+use application::model::manual_message::CreateManualMessage;
+use application::usecase::UseCaseError;
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{KeepAlive, Sse},
+        IntoResponse,
+    },
+    Json,
+};
+use domain::model::{
+    staff::{StaffId, StaffPermission},
+    talk_room::{TalkRoom, TalkRoomMode, TalkRoomStatus},
+    Id,
+};
+use domain::repository::RepositoryError;
+use futures::{future, StreamExt};
+use std::sync::Arc;
+use tracing::error;
+
+// EventSourceが再接続するときに、最後に受け取ったイベントのidを入れるヘッダー
+const LAST_EVENT_ID: &str = "last-event-id";
+
+/// talkRoomの一覧を、ピン留めしたものを先頭に新しい順で取得する
+/// 続きはレスポンスのnextCursorをcursorに指定して取得する
+#[tracing::instrument(skip(modules, staff))]
+pub async fn get_talk_rooms_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Query(query): Query<TalkRoomCardsQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ViewTalkRooms)?;
+    let (filter, cursor) = query
+        .filter()
+        .and_then(|filter| Ok((filter, query.cursor()?)))
+        .map_err(|err| {
+            error!("Invalid talk rooms query: {:?}", err);
+            StatusCode::BAD_REQUEST
+        })?;
+    let page = modules
+        .talk_room_usecase()
+        .get_talk_room_cards(filter, cursor, query.limit)
+        .await
+        .map_err(|err| {
+            error!("Failed to get talk rooms: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(TalkRoomCardsResponse::from((page, staff.0.id))))
+}
+
+/// talkRoomCardsの更新とメッセージの追加を、SSEで送り続ける
+/// 再接続するときは、Last-Event-IDかresumeTokenに最後のcheckpointを指定すると、その後の変更から送る
+#[tracing::instrument(skip(modules, staff, headers))]
+pub async fn get_talk_room_changes_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    headers: HeaderMap,
+    Query(query): Query<TalkRoomChangesQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ViewTalkRooms)?;
+    let resume_token = headers
+        .get(LAST_EVENT_ID)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+        .or(query.resume_token)
+        .map(|resume_token| decode_resume_token(&resume_token))
+        .transpose()
+        .map_err(|err| {
+            error!("Invalid resume token: {:?}", err);
+            StatusCode::BAD_REQUEST
+        })?;
+    let staff_id = staff.0.id;
+    let changes = modules
+        .talk_room_usecase()
+        .listen_changes(resume_token, staff.0)
+        .await
+        .map_err(|err| {
+            error!("Failed to listen talk room changes: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    // 変換できなかった変更は送らずに、監視は続ける
+    let events = changes.filter_map(move |change| {
+        future::ready(match change {
+            Ok(change) => Some(talk_room_change_event(change, staff_id)),
+            Err(err) => {
+                error!("Failed to convert talk room change: {:?}", err);
+                None
+            }
+        })
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// talkRoomのメッセージ履歴を新しい順に取得する
+/// 続きはレスポンスのnextCursorをcursorに指定して取得する
+#[tracing::instrument(skip(modules, staff))]
+pub async fn get_talk_room_messages_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(talk_room_id): Path<String>,
+    Query(query): Query<MessagesQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ViewTalkRooms)?;
+    let cursor = query.cursor().map_err(|err| {
+        error!("Invalid messages cursor: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    let page = modules
+        .talk_room_usecase()
+        .get_messages(talk_room_id_from_path(talk_room_id)?, cursor, query.limit)
+        .await
+        .map_err(|err| {
+            error!("Failed to get talk room messages: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(MessagesResponse::from(page)))
+}
+
+/// talkRoomの担当者を変更する
+#[tracing::instrument(skip(modules, staff))]
+pub async fn assign_talk_room_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(talk_room_id): Path<String>,
+    Json(request): Json<TalkRoomAssigneeRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::HandleTalkRooms)?;
+    let talk_room = modules
+        .talk_room_workflow_usecase()
+        .assign(
+            talk_room_id_from_path(talk_room_id)?,
+            staff.0,
+            StaffId::new(request.staff_id),
+        )
+        .await
+        .map_err(|err| {
+            error!("Failed to assign talk room: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(TalkRoomWorkflowResponse::from(talk_room)))
+}
+
+/// talkRoomの担当者を外す
+#[tracing::instrument(skip(modules, staff))]
+pub async fn unassign_talk_room_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(talk_room_id): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::HandleTalkRooms)?;
+    let talk_room = modules
+        .talk_room_workflow_usecase()
+        .unassign(talk_room_id_from_path(talk_room_id)?, staff.0)
+        .await
+        .map_err(|err| {
+            error!("Failed to unassign talk room: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(TalkRoomWorkflowResponse::from(talk_room)))
+}
+
+/// talkRoomのステータスをopen、pending、resolvedのいずれかに変更する
+#[tracing::instrument(skip(modules, staff))]
+pub async fn change_talk_room_status_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(talk_room_id): Path<String>,
+    Json(request): Json<TalkRoomStatusRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::HandleTalkRooms)?;
+    let status = request.status.parse::<TalkRoomStatus>().map_err(|err| {
+        error!("Invalid talk room status: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    let talk_room = modules
+        .talk_room_workflow_usecase()
+        .change_status(talk_room_id_from_path(talk_room_id)?, staff.0, status)
+        .await
+        .map_err(|err| {
+            error!("Failed to change talk room status: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(TalkRoomWorkflowResponse::from(talk_room)))
+}
+
+/// talkRoomをピン留めする、またはピン留めを外す
+/// talkRoomのモードを変更する
+/// 薬剤師が対応を引き継ぐときはhumanにして、botの自動返信を止める
+#[tracing::instrument(skip(modules, staff))]
+pub async fn change_talk_room_mode_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(talk_room_id): Path<String>,
+    Json(request): Json<TalkRoomModeRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::HandleTalkRooms)?;
+    let mode = request.mode.parse::<TalkRoomMode>().map_err(|err| {
+        error!("Invalid talk room mode: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    let talk_room = modules
+        .talk_room_workflow_usecase()
+        .change_mode(talk_room_id_from_path(talk_room_id)?, staff.0, mode)
+        .await
+        .map_err(|err| {
+            error!("Failed to change talk room mode: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(TalkRoomWorkflowResponse::from(talk_room)))
+}
+
+#[tracing::instrument(skip(modules, staff))]
+pub async fn update_talk_room_pinned_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(talk_room_id): Path<String>,
+    Json(request): Json<TalkRoomPinnedRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::HandleTalkRooms)?;
+    let talk_room = modules
+        .talk_room_usecase()
+        .update_pinned(talk_room_id_from_path(talk_room_id)?, request.pinned)
+        .await
+        .map_err(|err| {
+            error!("Failed to update talk room pinned: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(TalkRoomSettingsResponse::from(talk_room)))
+}
+
+/// talkRoomのrsvpを変更する
+#[tracing::instrument(skip(modules, staff))]
+pub async fn update_talk_room_rsvp_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(talk_room_id): Path<String>,
+    Json(request): Json<TalkRoomRsvpRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::HandleTalkRooms)?;
+    let talk_room = modules
+        .talk_room_usecase()
+        .update_rsvp(talk_room_id_from_path(talk_room_id)?, request.rsvp)
+        .await
+        .map_err(|err| {
+            error!("Failed to update talk room rsvp: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(TalkRoomSettingsResponse::from(talk_room)))
+}
+
+/// talkRoomにスタッフが決めた呼び名を付ける
+#[tracing::instrument(skip(modules, staff))]
+pub async fn update_talk_room_nickname_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(talk_room_id): Path<String>,
+    Json(request): Json<TalkRoomNicknameRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::HandleTalkRooms)?;
+    let nickname = request.nickname().map_err(|err| {
+        error!("Invalid nickname: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    let talk_room = modules
+        .talk_room_usecase()
+        .update_nickname(talk_room_id_from_path(talk_room_id)?, nickname)
+        .await
+        .map_err(|err| {
+            error!("Failed to update talk room nickname: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(TalkRoomSettingsResponse::from(talk_room)))
+}
+
+/// talkRoomを、ログインしているスタッフが既読にする
+/// 既読の位置はスタッフごとに持つので、他のスタッフの未読数は変わらない
+#[tracing::instrument(skip(modules, staff))]
+pub async fn mark_talk_room_as_read_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(talk_room_id): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ViewTalkRooms)?;
+    let read_marker = modules
+        .talk_room_usecase()
+        .mark_as_read(talk_room_id_from_path(talk_room_id)?, staff.0.id)
+        .await
+        .map_err(|err| {
+            error!("Failed to mark talk room as read: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(TalkRoomReadMarkerResponse::from(read_marker)))
+}
+
+/// スタッフが書いたテキストか定型文を、talkRoomのユーザーに送る
+#[tracing::instrument(skip(modules, staff))]
+pub async fn send_talk_room_message_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(talk_room_id): Path<String>,
+    Json(request): Json<SendManualMessageRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::SendMessages)?;
+    let source = CreateManualMessage::try_from(request).map_err(|err| {
+        error!("Invalid manual message: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    let new_send_messages_vec = modules
+        .talk_room_usecase()
+        .send_manual_message(talk_room_id_from_path(talk_room_id)?, staff.0, source)
+        .await
+        .map_err(|err| {
+            error!("Failed to send manual message: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(SentManualMessagesResponse::from(new_send_messages_vec)),
+    ))
+}
+
+fn talk_room_id_from_path(talk_room_id: String) -> Result<Id<TalkRoom>, StatusCode> {
+    Id::try_from(talk_room_id).map_err(|err| {
+        error!("Invalid talk room id: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+fn status_code_from_error(err: &UseCaseError) -> StatusCode {
+    match err {
+        // talkRoomか担当者にするスタッフ、送る定型文がない
+        UseCaseError::Repository(RepositoryError::NotFound(_, _)) => StatusCode::NOT_FOUND,
+        // MySQLやfirestoreにつながらない。時間をおけば成功するかもしれない
+        UseCaseError::Repository(RepositoryError::Unavailable(_)) => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{
+        message::decode_message_cursor,
+        talk_room::{decode_talk_room_cursor, encode_talk_room_cursor},
+        talk_room_change::encode_resume_token,
+    };
+    use crate::module::test::{fake_staff, fake_talk_room, TestModules};
+    use chrono::Local;
+    use domain::{
+        gateway::{
+            rich_menu::MockRichMenuGateway, send_message::MockSendMessageGateway,
+            user_auth::MockUserAuthGateway,
+        },
+        model::{
+            message::{
+                event::{
+                    Event, EventDeliveryContext, EventMessage, EventMessageContent,
+                    EventMessageContentText,
+                },
+                send_message::{
+                    SendMessage, SendMessageText, SendMessages, SendSender, SendSenderRole,
+                    SendSendingMethod, SendSendingType,
+                },
+                system_event::{SystemEvent, SystemEventAssigned, SystemEventContent},
+                MessageCursor, Messages, MessagesPage,
+            },
+            primary_user_id::PrimaryUserId,
+            staff::StaffRole,
+            talk_room_card::{
+                LatestMessagePreview, TalkRoomAssigneeFilter, TalkRoomCard, TalkRoomCardPage,
+                TalkRoomCursor, TalkRoomFilter, TalkRoomReadMarker,
+            },
+            talk_room_change::{TalkRoomChange, TalkRoomChangeResumeToken, TalkRoomMessage},
+        },
+        repository::{
+            business_hours::MockBusinessHoursRepository,
+            canned_response::MockCannedResponseRepository, staff::MockStaffRepository,
+            talk_room::MockTalkRoomRepository, talk_room_note::MockTalkRoomNoteRepository,
+            user::MockUserRepository, user_tag::MockUserTagRepository,
+        },
+    };
+    use dotenv::dotenv;
+    use mockall::predicate;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_assign_talk_room_records_system_event() {
+        dotenv().ok();
+        let operator = fake_staff(1, StaffRole::Pharmacist);
+        let assignee = fake_staff(2, StaffRole::Pharmacist);
+        let talk_room = fake_talk_room();
+        let talk_room_id = talk_room.id.clone();
+
+        let mut staff_repository = MockStaffRepository::new();
+        let cloned_assignee = assignee.clone();
+        staff_repository
+            .expect_get_staff()
+            .with(predicate::eq(assignee.id))
+            .once()
+            .returning(move |_| Ok(cloned_assignee.clone()));
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_get_talk_room_by_id()
+            .with(predicate::eq(talk_room_id.clone()))
+            .once()
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+        /*
+         * 誰が誰を担当者にしたかを履歴に残す
+         */
+        let (operator_id, assignee_id) = (operator.id, assignee.id);
+        talk_room_repository
+            .expect_update_workflow()
+            .withf(move |workflow| {
+                workflow.assignee == Some(assignee_id)
+                    && workflow.status == TalkRoomStatus::Open
+                    && workflow.system_events.len() == 1
+                    && workflow.system_events[0].operator == Some(operator_id)
+                    && workflow.system_events[0].content
+                        == SystemEventContent::Assigned(SystemEventAssigned::new(assignee_id, None))
+            })
+            .once()
+            .returning(|_| Ok(()));
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            talk_room_repository,
+            MockSendMessageGateway::new(),
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            staff_repository,
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let updated_talk_room = modules
+            .talk_room_workflow_usecase()
+            .assign(talk_room_id, operator, assignee.id)
+            .await
+            .unwrap();
+        assert_eq!(updated_talk_room.assignee, Some(assignee.id));
+
+        /*
+         * viewerは担当者を変更できない
+         */
+        let viewer = AuthenticatedStaff(fake_staff(3, StaffRole::Viewer));
+        assert_eq!(
+            viewer.require(StaffPermission::HandleTalkRooms),
+            Err(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_talk_rooms_with_filter_and_cursor() {
+        dotenv().ok();
+        let now = Local::now();
+        let card = fake_talk_room_card(now);
+        let cursor = TalkRoomCursor::from(&card);
+        let query = TalkRoomCardsQuery {
+            assignee: Some("none".to_string()),
+            status: Some("open".to_string()),
+            unread: Some(true),
+            limit: Some(500),
+            cursor: Some(encode_talk_room_cursor(&cursor)),
+            ..Default::default()
+        };
+
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+        /*
+         * 担当者なし、未対応、未読のtalkRoomを、前のページの続きから取得する
+         * 1ページの件数は上限に丸める
+         */
+        let expected_filter = TalkRoomFilter::new(
+            None,
+            None,
+            None,
+            Some(TalkRoomAssigneeFilter::Unassigned),
+            Some(TalkRoomStatus::Open),
+            Some(true),
+        );
+        let expected_cursor = cursor.clone();
+        let cloned_card = card.clone();
+        talk_room_repository
+            .expect_get_talk_room_cards()
+            .withf(move |filter, cursor, limit| {
+                *filter == expected_filter
+                    && cursor.as_ref() == Some(&expected_cursor)
+                    && *limit == 100
+            })
+            .once()
+            .returning(move |_, _, _| {
+                Ok(TalkRoomCardPage::new(
+                    vec![cloned_card.clone()],
+                    Some(TalkRoomCursor::from(&cloned_card)),
+                ))
+            });
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            talk_room_repository,
+            MockSendMessageGateway::new(),
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            MockStaffRepository::new(),
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let page = modules
+            .talk_room_usecase()
+            .get_talk_room_cards(
+                query.filter().unwrap(),
+                query.cursor().unwrap(),
+                query.limit,
+            )
+            .await
+            .unwrap();
+        let response = TalkRoomCardsResponse::from((page, StaffId::new(1)));
+        assert_eq!(response.talk_rooms.len(), 1);
+        assert_eq!(
+            response.talk_rooms[0].latest_message.text,
+            Some("こんにちは".to_string())
+        );
+        // 次のページのカーソルは、最後のtalkRoomの位置を表す
+        let next_cursor = decode_talk_room_cursor(&response.next_cursor.unwrap()).unwrap();
+        assert_eq!(next_cursor.id, card.id);
+        assert_eq!(
+            next_cursor.sort_time.timestamp_micros(),
+            now.timestamp_micros()
+        );
+
+        /*
+         * 不正なカーソルや担当者は400にする
+         */
+        let invalid = TalkRoomCardsQuery {
+            assignee: Some("someone".to_string()),
+            cursor: Some("invalid".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.filter().is_err());
+        assert!(invalid.cursor().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_talk_room_messages_in_unified_shape() {
+        dotenv().ok();
+        let now = Local::now();
+        let talk_room_id: Id<TalkRoom> = Id::gen();
+        let staff = fake_staff(1, StaffRole::Pharmacist);
+        let system_event = Messages::SystemEvent(SystemEvent::new(
+            Id::gen(),
+            Some(staff.id),
+            SystemEventContent::Assigned(SystemEventAssigned::new(staff.id, None)),
+            now,
+        ));
+        let send_messages = Messages::SendMessages(SendMessages {
+            id: Id::gen(),
+            sending_type: SendSendingType::Manual,
+            sending_method: SendSendingMethod::Push,
+            sender: Some(SendSender {
+                id: staff.id.0,
+                name: staff.name.clone(),
+                picture_url: "".to_string(),
+                email: "".to_string(),
+                sender_role: SendSenderRole::Sender,
+            }),
+            messages: vec![SendMessage::Text(SendMessageText {
+                message_id: "sent_line_message_id".to_string(),
+                text: "お薬の件です".to_string(),
+                emojis: None,
+                quote_token: None,
+                created_at: now,
+            })],
+        });
+        let event = Messages::Event(Event::Message(EventMessage::new(
+            Id::gen(),
+            "reply_token".to_string(),
+            EventDeliveryContext::new(false),
+            EventMessageContent::Text(EventMessageContentText::new(
+                "line_message_id".to_string(),
+                "こんにちは".to_string(),
+                vec![],
+            )),
+            "active".to_string(),
+            "webhook_event_id".to_string(),
+            now,
+        )));
+        let messages = vec![system_event, send_messages, event];
+
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+        let cloned_talk_room_id = talk_room_id.clone();
+        let cloned_messages = messages.clone();
+        talk_room_repository
+            .expect_get_messages()
+            .withf(move |id, cursor, limit| {
+                *id == cloned_talk_room_id && cursor.is_none() && *limit == 20
+            })
+            .once()
+            .returning(move |_, _, _| {
+                Ok(MessagesPage::new(
+                    cloned_messages.clone(),
+                    cloned_messages.last().map(MessageCursor::from),
+                ))
+            });
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            talk_room_repository,
+            MockSendMessageGateway::new(),
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            MockStaffRepository::new(),
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let page = modules
+            .talk_room_usecase()
+            .get_messages(talk_room_id, None, None)
+            .await
+            .unwrap();
+        let response = MessagesResponse::from(page);
+
+        /*
+         * 種類が違っても、送信者とLINEのメッセージIDを同じ形で返す
+         */
+        let kinds = response
+            .messages
+            .iter()
+            .map(|m| (m.kind.as_str(), m.sender.role.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ("systemEvent", "system"),
+                ("sendMessages", "staff"),
+                ("event", "user")
+            ]
+        );
+        assert_eq!(
+            response.messages[0].system_event.as_ref().unwrap().assignee,
+            Some(staff.id.0)
+        );
+        assert_eq!(response.messages[1].sender.staff_id, Some(staff.id.0));
+        assert_eq!(
+            response.messages[1].contents[0].line_message_id,
+            Some("sent_line_message_id".to_string())
+        );
+        assert_eq!(
+            response.messages[2].contents[0].line_message_id,
+            Some("line_message_id".to_string())
+        );
+        assert_eq!(
+            response.messages[2].contents[0].text,
+            Some("こんにちは".to_string())
+        );
+        // 次のページのカーソルは、最後のメッセージの位置を表す
+        let next_cursor = decode_message_cursor(&response.next_cursor.unwrap()).unwrap();
+        assert_eq!(next_cursor.id, messages[2].id());
+        assert_eq!(next_cursor.created_at, now);
+    }
+
+    #[tokio::test]
+    async fn test_listen_talk_room_changes_from_resume_token() {
+        dotenv().ok();
+        let staff = fake_staff(1, StaffRole::Viewer);
+        let card = TalkRoomCard {
+            user_message_count: 3,
+            ..fake_talk_room_card(Local::now())
+        };
+        let resume_token = TalkRoomChangeResumeToken::new("resume_token".to_string());
+        let next_resume_token = TalkRoomChangeResumeToken::new("next_resume_token".to_string());
+
+        /*
+         * クライアントが送った位置から監視を再開する
+         */
+        let query = TalkRoomChangesQuery {
+            resume_token: Some(encode_resume_token(&resume_token)),
+        };
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+        let changes = vec![
+            TalkRoomChange::CardUpdated(card.clone()),
+            TalkRoomChange::Checkpoint(next_resume_token.clone()),
+        ];
+        talk_room_repository
+            .expect_listen_changes()
+            .with(predicate::eq(Some(resume_token.clone())))
+            .once()
+            .returning(move |_| {
+                Ok(futures::stream::iter(changes.clone().into_iter().map(Ok)).boxed())
+            });
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            talk_room_repository,
+            MockSendMessageGateway::new(),
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            MockStaffRepository::new(),
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let changes = modules
+            .talk_room_usecase()
+            .listen_changes(
+                Some(decode_resume_token(&query.resume_token.unwrap()).unwrap()),
+                staff.clone(),
+            )
+            .await
+            .unwrap()
+            .map(|change| change.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(
+            changes,
+            vec![
+                TalkRoomChange::CardUpdated(card),
+                TalkRoomChange::Checkpoint(next_resume_token.clone()),
+            ]
+        );
+        // SSEのイベントにできる
+        for change in changes {
+            assert!(talk_room_change_event(change, staff.id).is_ok());
+        }
+        // checkpointのidをそのまま送り返すと、同じ位置から再開できる
+        assert_eq!(
+            decode_resume_token(&encode_resume_token(&next_resume_token)).unwrap(),
+            next_resume_token
+        );
+        assert!(decode_resume_token("invalid").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_listen_talk_room_changes_drops_rooms_assigned_to_others() {
+        dotenv().ok();
+        let staff = fake_staff(1, StaffRole::Pharmacist);
+        let other_staff_id = StaffId::new(2);
+        let talk_room = fake_talk_room();
+        let message = talk_room.latest_messages.clone();
+        let other_card = TalkRoomCard {
+            assignee: Some(other_staff_id),
+            ..fake_talk_room_card(Local::now())
+        };
+        let card = fake_talk_room_card(Local::now());
+        let reassigned_card = TalkRoomCard {
+            assignee: Some(other_staff_id),
+            ..card.clone()
+        };
+        let message_added = |talk_room_id: &Id<TalkRoom>| {
+            TalkRoomChange::MessageAdded(TalkRoomMessage::new(
+                talk_room_id.clone(),
+                message.clone(),
+            ))
+        };
+        let checkpoint =
+            TalkRoomChange::Checkpoint(TalkRoomChangeResumeToken::new("resume_token".to_string()));
+
+        /*
+         * ほかのスタッフが担当しているtalkRoomの変更は届けない
+         * talkRoomCardsの更新がまだ届いていないtalkRoomのメッセージは、talkRoomを取得して担当者を確かめる
+         * ほかのスタッフに担当が移った更新は、受信箱から外せるように届ける
+         */
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+        let changes = vec![
+            TalkRoomChange::CardUpdated(other_card.clone()),
+            message_added(&other_card.id),
+            message_added(&talk_room.id),
+            TalkRoomChange::CardUpdated(card.clone()),
+            message_added(&card.id),
+            TalkRoomChange::CardUpdated(reassigned_card.clone()),
+            message_added(&card.id),
+            checkpoint.clone(),
+        ];
+        talk_room_repository
+            .expect_listen_changes()
+            .once()
+            .returning(move |_| {
+                Ok(futures::stream::iter(changes.clone().into_iter().map(Ok)).boxed())
+            });
+        let assigned_talk_room = TalkRoom {
+            assignee: Some(other_staff_id),
+            ..talk_room.clone()
+        };
+        talk_room_repository
+            .expect_get_talk_room_by_id()
+            .with(predicate::eq(talk_room.id.clone()))
+            .once()
+            .returning(move |_| Ok(assigned_talk_room.clone()));
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            talk_room_repository,
+            MockSendMessageGateway::new(),
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            MockStaffRepository::new(),
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let changes = modules
+            .talk_room_usecase()
+            .listen_changes(None, staff)
+            .await
+            .unwrap()
+            .map(|change| change.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(
+            changes,
+            vec![
+                TalkRoomChange::CardUpdated(card.clone()),
+                message_added(&card.id),
+                TalkRoomChange::CardUpdated(reassigned_card),
+                checkpoint,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mark_talk_room_as_read_per_staff() {
+        dotenv().ok();
+        let talk_room = fake_talk_room();
+        let talk_room_id = talk_room.id.clone();
+        let staff = fake_staff(1, StaffRole::Pharmacist);
+        let other_staff = fake_staff(2, StaffRole::Pharmacist);
+
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_get_talk_room_by_id()
+            .with(predicate::eq(talk_room_id.clone()))
+            .once()
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+        /*
+         * 既読の位置は、既読にしたスタッフの分だけ進める
+         */
+        let now = Local::now();
+        talk_room_repository
+            .expect_mark_as_read()
+            .with(predicate::eq(talk_room_id.clone()), predicate::eq(staff.id))
+            .once()
+            .returning(move |talk_room_id, staff_id| {
+                Ok(TalkRoomReadMarker::new(talk_room_id, staff_id, 5, now))
+            });
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            talk_room_repository,
+            MockSendMessageGateway::new(),
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            MockStaffRepository::new(),
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let read_marker = modules
+            .talk_room_usecase()
+            .mark_as_read(talk_room_id.clone(), staff.id)
+            .await
+            .unwrap();
+        let response = TalkRoomReadMarkerResponse::from(read_marker);
+        assert_eq!(response.talk_room_id, talk_room_id.value.to_string());
+        assert_eq!(response.read_message_count, 5);
+
+        /*
+         * 既読にしたスタッフの未読数だけが0になり、他のスタッフの未読数は変わらない
+         */
+        let card = TalkRoomCard {
+            user_message_count: 7,
+            read_message_counts: HashMap::from([(staff.id, 5)]),
+            ..fake_talk_room_card(now)
+        };
+        let page = TalkRoomCardPage::new(vec![card.clone()], None);
+        let response = TalkRoomCardsResponse::from((page.clone(), staff.id));
+        assert_eq!(response.talk_rooms[0].unread_count, 2);
+        let response = TalkRoomCardsResponse::from((page, other_staff.id));
+        assert_eq!(response.talk_rooms[0].unread_count, 7);
+        // 既読の位置がメッセージの累計を超えていても、未読数は負にならない
+        let card = TalkRoomCard {
+            read_message_counts: HashMap::from([(staff.id, 10)]),
+            ..card
+        };
+        assert_eq!(card.unread_count(staff.id), 0);
+    }
+
+    #[tokio::test]
+    async fn test_update_talk_room_nickname_and_pinned() {
+        dotenv().ok();
+        let talk_room = fake_talk_room();
+        let talk_room_id = talk_room.id.clone();
+
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_get_talk_room_by_id()
+            .with(predicate::eq(talk_room_id.clone()))
+            .times(2)
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+        /*
+         * 呼び名は前後の空白を取り除いて、displayNameとは別に保存する
+         */
+        talk_room_repository
+            .expect_update_nickname()
+            .with(
+                predicate::eq(talk_room_id.clone()),
+                predicate::eq(Some("山田さん".to_string())),
+            )
+            .once()
+            .returning(|_, _| Ok(()));
+        talk_room_repository
+            .expect_update_pinned()
+            .with(predicate::eq(talk_room_id.clone()), predicate::eq(true))
+            .once()
+            .returning(|_, _| Ok(()));
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            talk_room_repository,
+            MockSendMessageGateway::new(),
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            MockStaffRepository::new(),
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let request = TalkRoomNicknameRequest {
+            nickname: Some(" 山田さん ".to_string()),
+        };
+        let updated_talk_room = modules
+            .talk_room_usecase()
+            .update_nickname(talk_room_id.clone(), request.nickname().unwrap())
+            .await
+            .unwrap();
+        let response = TalkRoomSettingsResponse::from(updated_talk_room);
+        assert_eq!(response.nickname, Some("山田さん".to_string()));
+        assert_eq!(response.display_name, talk_room.display_name);
+
+        let updated_talk_room = modules
+            .talk_room_usecase()
+            .update_pinned(talk_room_id, true)
+            .await
+            .unwrap();
+        assert!(updated_talk_room.pinned);
+
+        /*
+         * 空の呼び名は外す。長すぎる呼び名は400にする
+         */
+        let empty = TalkRoomNicknameRequest {
+            nickname: Some("  ".to_string()),
+        };
+        assert_eq!(empty.nickname().unwrap(), None);
+        let too_long = TalkRoomNicknameRequest {
+            nickname: Some("あ".repeat(51)),
+        };
+        assert!(too_long.nickname().is_err());
+    }
+
+    fn fake_talk_room_card(now: chrono::DateTime<Local>) -> TalkRoomCard {
+        TalkRoomCard {
+            id: Id::gen(),
+            primary_user_id: PrimaryUserId::new("primary_user_id".to_string()),
+            display_name: "display_name".to_string(),
+            nickname: None,
+            rsvp: false,
+            pinned: false,
+            follow: true,
+            unread: true,
+            assignee: None,
+            status: TalkRoomStatus::Open,
+            mode: TalkRoomMode::Bot,
+            latest_message: LatestMessagePreview::new(
+                "message_id".to_string(),
+                "text".to_string(),
+                Some("こんにちは".to_string()),
+            ),
+            latest_messaged_at: now,
+            sort_time: now,
+            created_at: now,
+            updated_at: now,
+            user_message_count: 0,
+            read_message_counts: HashMap::new(),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use crate::context::staff_auth::AuthenticatedStaff;
+use crate::model::talk_room_note::{TalkRoomNoteRequest, TalkRoomNoteResponse};
+use crate::module::ModulesExt;
+use application::usecase::UseCaseError;
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use domain::model::{
+    staff::StaffPermission, talk_room::TalkRoom, talk_room_note::TalkRoomNote, Id,
+};
+use domain::repository::RepositoryError;
+use std::sync::Arc;
+use tracing::{error, warn};
+
+/// talkRoomのメモを新しい順に返す
+/// メモはスタッフだけが見るもので、ユーザーには送られない
+#[tracing::instrument(skip(modules, staff))]
+pub async fn get_talk_room_notes_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(talk_room_id): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ViewTalkRooms)?;
+    let notes = modules
+        .talk_room_note_usecase()
+        .get_notes(id_from_path(talk_room_id)?)
+        .await
+        .map_err(|err| {
+            error!("Failed to get talk room notes: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(
+        notes
+            .into_iter()
+            .map(TalkRoomNoteResponse::from)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+#[tracing::instrument(skip(modules, staff))]
+pub async fn create_talk_room_note_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(talk_room_id): Path<String>,
+    Json(request): Json<TalkRoomNoteRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::HandleTalkRooms)?;
+    let body = request.body().map_err(|err| {
+        error!("Invalid note: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    let note = modules
+        .talk_room_note_usecase()
+        .create_note(id_from_path(talk_room_id)?, staff.0, body)
+        .await
+        .map_err(|err| {
+            error!("Failed to create talk room note: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok((StatusCode::CREATED, Json(TalkRoomNoteResponse::from(note))))
+}
+
+/// メモを書き換えられるのは、書いたスタッフと管理者だけ
+#[tracing::instrument(skip(modules, staff))]
+pub async fn update_talk_room_note_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path((talk_room_id, note_id)): Path<(String, String)>,
+    Json(request): Json<TalkRoomNoteRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::HandleTalkRooms)?;
+    let body = request.body().map_err(|err| {
+        error!("Invalid note: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    let talk_room_id: Id<TalkRoom> = id_from_path(talk_room_id)?;
+    let note_id: Id<TalkRoomNote> = id_from_path(note_id)?;
+    require_editable_note(
+        modules.as_ref(),
+        &staff,
+        talk_room_id.clone(),
+        note_id.clone(),
+    )
+    .await?;
+    let note = modules
+        .talk_room_note_usecase()
+        .update_note(talk_room_id, note_id, body)
+        .await
+        .map_err(|err| {
+            error!("Failed to update talk room note: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(TalkRoomNoteResponse::from(note)))
+}
+
+#[tracing::instrument(skip(modules, staff))]
+pub async fn delete_talk_room_note_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path((talk_room_id, note_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::HandleTalkRooms)?;
+    let talk_room_id: Id<TalkRoom> = id_from_path(talk_room_id)?;
+    let note_id: Id<TalkRoomNote> = id_from_path(note_id)?;
+    require_editable_note(
+        modules.as_ref(),
+        &staff,
+        talk_room_id.clone(),
+        note_id.clone(),
+    )
+    .await?;
+    modules
+        .talk_room_note_usecase()
+        .delete_note(talk_room_id, note_id)
+        .await
+        .map_err(|err| {
+            error!("Failed to delete talk room note: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn require_editable_note<M: ModulesExt>(
+    modules: &M,
+    staff: &AuthenticatedStaff,
+    talk_room_id: Id<TalkRoom>,
+    note_id: Id<TalkRoomNote>,
+) -> Result<(), StatusCode> {
+    let note = modules
+        .talk_room_note_usecase()
+        .get_note(talk_room_id, note_id)
+        .await
+        .map_err(|err| {
+            error!("Failed to get talk room note: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+    if !note.can_be_edited_by(&staff.0) {
+        warn!(
+            "Staff {} is not allowed to edit note {}",
+            staff.0.id.0, note.id.value
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(())
+}
+
+fn id_from_path<T>(id: String) -> Result<Id<T>, StatusCode> {
+    Id::try_from(id).map_err(|err| {
+        error!("Invalid id: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+fn status_code_from_error(err: &UseCaseError) -> StatusCode {
+    match err {
+        // talkRoomかメモがない
+        UseCaseError::Repository(RepositoryError::NotFound(_, _)) => StatusCode::NOT_FOUND,
+        // MySQLやfirestoreにつながらない。時間をおけば成功するかもしれない
+        UseCaseError::Repository(RepositoryError::Unavailable(_)) => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::module::test::{fake_staff, fake_talk_room, TestModules};
+    use domain::{
+        gateway::{
+            rich_menu::MockRichMenuGateway, send_message::MockSendMessageGateway,
+            user_auth::MockUserAuthGateway,
+        },
+        model::{staff::StaffRole, talk_room_note::TalkRoomNoteAuthor},
+        repository::{
+            business_hours::MockBusinessHoursRepository,
+            canned_response::MockCannedResponseRepository, staff::MockStaffRepository,
+            talk_room::MockTalkRoomRepository, talk_room_note::MockTalkRoomNoteRepository,
+            user::MockUserRepository, user_tag::MockUserTagRepository,
+        },
+    };
+    use dotenv::dotenv;
+    use mockall::predicate;
+
+    #[tokio::test]
+    async fn test_create_talk_room_note_and_check_editor() {
+        dotenv().ok();
+        let talk_room = fake_talk_room();
+        let talk_room_id = talk_room.id.clone();
+        let author = fake_staff(1, StaffRole::Pharmacist);
+        let request: TalkRoomNoteRequest =
+            serde_json::from_str(r#"{ "body": "  次回は薬の飲み合わせを確認する  " }"#).unwrap();
+        let body = request.body().unwrap();
+        assert_eq!(body, "次回は薬の飲み合わせを確認する");
+        // 空白だけのメモは作れない
+        let request: TalkRoomNoteRequest = serde_json::from_str(r#"{ "body": "   " }"#).unwrap();
+        assert!(request.body().is_err());
+
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+        let cloned_talk_room = talk_room.clone();
+        talk_room_repository
+            .expect_get_talk_room_by_id()
+            .with(predicate::eq(talk_room_id.clone()))
+            .once()
+            .returning(move |_| Ok(cloned_talk_room.clone()));
+        /*
+         * メモには書いたスタッフの名前を残す
+         */
+        let mut talk_room_note_repository = MockTalkRoomNoteRepository::new();
+        let expected_author = TalkRoomNoteAuthor::new(author.id, author.name.clone());
+        let expected_body = body.clone();
+        talk_room_note_repository
+            .expect_create_note()
+            .withf(move |n| n.author == expected_author && n.body == expected_body)
+            .once()
+            .returning(|n| {
+                Ok(TalkRoomNote::new(
+                    n.id,
+                    n.talk_room_id,
+                    n.author,
+                    n.body,
+                    n.created_at,
+                    n.created_at,
+                ))
+            });
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            talk_room_repository,
+            MockSendMessageGateway::new(),
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            MockStaffRepository::new(),
+            talk_room_note_repository,
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let note = modules
+            .talk_room_note_usecase()
+            .create_note(talk_room_id.clone(), author.clone(), body)
+            .await
+            .unwrap();
+        let response = TalkRoomNoteResponse::from(note.clone());
+        assert_eq!(response.talk_room_id, talk_room_id.value.to_string());
+        assert_eq!(response.author.staff_id, 1);
+        assert_eq!(response.author.name, "staff1");
+
+        /*
+         * メモを変更できるのは、書いたスタッフと管理者だけ
+         */
+        assert!(note.can_be_edited_by(&author));
+        assert!(!note.can_be_edited_by(&fake_staff(2, StaffRole::Pharmacist)));
+        assert!(note.can_be_edited_by(&fake_staff(3, StaffRole::Admin)));
+    }
+}
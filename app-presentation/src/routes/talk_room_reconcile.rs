@@ -0,0 +1,131 @@
+use crate::model::talk_room_reconcile::{TalkRoomReconcileQuery, TalkRoomReconcileReportResponse};
+use crate::module::ModulesExt;
+use axum::{
+    extract::{Extension, Query},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use std::sync::Arc;
+use tracing::error;
+
+/// MySQLのtalk_roomsとfirestoreのドキュメントを突き合わせる
+/// repair=trueのときは、自動で直せる食い違いを直す
+#[tracing::instrument(skip(modules))]
+pub async fn reconcile_talk_rooms_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Query(query): Query<TalkRoomReconcileQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let report = modules
+        .talk_room_reconcile_usecase()
+        .reconcile_talk_rooms(query.repair)
+        .await
+        .map_err(|err| {
+            error!("Failed to reconcile talk rooms: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(TalkRoomReconcileReportResponse::from(report)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::module::{test::TestModules, ModulesExt};
+    use domain::{
+        gateway::{
+            rich_menu::MockRichMenuGateway, send_message::MockSendMessageGateway,
+            user_auth::MockUserAuthGateway,
+        },
+        model::talk_room_consistency::{TalkRoomConsistencyScan, TalkRoomInconsistency},
+        repository::{
+            business_hours::MockBusinessHoursRepository,
+            canned_response::MockCannedResponseRepository, staff::MockStaffRepository,
+            talk_room::MockTalkRoomRepository, talk_room_note::MockTalkRoomNoteRepository,
+            user::MockUserRepository, user_tag::MockUserTagRepository, RepositoryError,
+        },
+    };
+    use dotenv::dotenv;
+    use mockall::predicate;
+
+    #[tokio::test]
+    async fn test_reconcile_talk_rooms_repairs_only_repairable_inconsistencies() {
+        dotenv().ok();
+        let missing_talk_room_document =
+            TalkRoomInconsistency::MissingTalkRoomDocument("talk_room_1".to_string());
+        let missing_card = TalkRoomInconsistency::MissingCard("talk_room_2".to_string());
+        let missing_latest_message = TalkRoomInconsistency::MissingLatestMessage {
+            talk_room_document_id: "talk_room_3".to_string(),
+            message_document_id: "message_1".to_string(),
+        };
+        let orphaned_with_messages = TalkRoomInconsistency::OrphanedDocuments {
+            talk_room_document_id: "talk_room_4".to_string(),
+            has_messages: true,
+        };
+        let inconsistencies = vec![
+            missing_talk_room_document.clone(),
+            missing_card.clone(),
+            missing_latest_message.clone(),
+            orphaned_with_messages,
+        ];
+
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+        let cloned_inconsistencies = inconsistencies.clone();
+        talk_room_repository
+            .expect_scan_consistency()
+            .once()
+            .returning(move || {
+                Ok(TalkRoomConsistencyScan::new(
+                    5,
+                    cloned_inconsistencies.clone(),
+                ))
+            });
+        /*
+         * メッセージが残っている孤立したドキュメントは直さない
+         * 1件直せなくても残りは直す
+         */
+        talk_room_repository
+            .expect_repair_inconsistency()
+            .with(predicate::eq(missing_talk_room_document))
+            .once()
+            .returning(|_| {
+                Err(RepositoryError::NotFound(
+                    "talk_rooms".to_string(),
+                    "talk_room_1".to_string(),
+                ))
+            });
+        talk_room_repository
+            .expect_repair_inconsistency()
+            .with(predicate::eq(missing_card))
+            .once()
+            .returning(|_| Ok(()));
+        talk_room_repository
+            .expect_repair_inconsistency()
+            .with(predicate::eq(missing_latest_message))
+            .once()
+            .returning(|_| Ok(()));
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            talk_room_repository,
+            MockSendMessageGateway::new(),
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            MockStaffRepository::new(),
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let report = modules
+            .talk_room_reconcile_usecase()
+            .reconcile_talk_rooms(true)
+            .await
+            .unwrap();
+
+        assert_eq!(report.checked_count, 5);
+        assert_eq!(report.inconsistencies, inconsistencies);
+        assert_eq!(report.repaired_count, 2);
+        assert_eq!(report.failed_count, 1);
+    }
+}
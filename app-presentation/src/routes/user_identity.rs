@@ -0,0 +1,265 @@
+use crate::context::staff_auth::AuthenticatedStaff;
+use crate::model::user_identity::{MergeUsersRequest, UserIdentityRequest, UserIdentityResponse};
+use crate::module::ModulesExt;
+use application::usecase::UseCaseError;
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use domain::model::{primary_user_id::PrimaryUserId, staff::StaffPermission};
+use domain::repository::RepositoryError;
+use std::sync::Arc;
+use tracing::error;
+
+/// メールアドレスとパスワード、またはLINEログインのIDトークンでユーザーを認証する
+/// どの認証プロバイダーで認証しても、紐づいているprimary_user_idのユーザーを返す
+#[tracing::instrument(skip(modules, request))]
+pub async fn authenticate_user_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Json(request): Json<UserIdentityRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let user = modules
+        .user_identity_usecase()
+        .authenticate(request.into())
+        .await
+        .map_err(|err| {
+            error!("Failed to authenticate user: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(Json(UserIdentityResponse::from(user)))
+}
+
+/// 既存のユーザーに、メールアドレスやLINEログインのユーザーを紐づける
+#[tracing::instrument(skip(modules, request, staff))]
+pub async fn link_user_identity_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(primary_user_id): Path<String>,
+    Json(request): Json<UserIdentityRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ManageUsers)?;
+    let user = modules
+        .user_identity_usecase()
+        .link_identity(PrimaryUserId::new(primary_user_id), request.into())
+        .await
+        .map_err(|err| {
+            error!("Failed to link user identity: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok((StatusCode::CREATED, Json(UserIdentityResponse::from(user))))
+}
+
+/// 2つのprimary_user_idを1つに統合する
+#[tracing::instrument(skip(modules, staff))]
+pub async fn merge_users_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Json(request): Json<MergeUsersRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ManageUsers)?;
+    modules
+        .user_identity_usecase()
+        .merge_users(
+            PrimaryUserId::new(request.into_primary_user_id),
+            PrimaryUserId::new(request.from_primary_user_id),
+        )
+        .await
+        .map_err(|err| {
+            error!("Failed to merge users: {:?}", err);
+            status_code_from_error(&err)
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn status_code_from_error(err: &UseCaseError) -> StatusCode {
+    match err {
+        // メールアドレスやIDトークンの形式が正しくない
+        UseCaseError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+        UseCaseError::Repository(RepositoryError::NotFound(_, _)) => StatusCode::NOT_FOUND,
+        // 同じ認証プロバイダーのユーザーが既に紐づいている
+        UseCaseError::Repository(RepositoryError::Conflict(_, _, _)) => StatusCode::CONFLICT,
+        UseCaseError::Repository(RepositoryError::NotAuthFound(_)) => StatusCode::UNAUTHORIZED,
+        // MySQLやfirestoreにつながらない。時間をおけば成功するかもしれない
+        UseCaseError::Repository(RepositoryError::Unavailable(_)) => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::user_identity::{EmailIdentityRequest, LineLoginIdentityRequest};
+    use crate::module::test::TestModules;
+    use domain::model::{
+        email_user::{EmailAddress, EmailUserProfile, PasswordHash},
+        line_login_user::{LineLoginId, LineLoginUserProfile},
+        user::{User, UserProfile},
+        user_auth::{AuthUserId, LineLoginAuthData},
+    };
+    use domain::{
+        gateway::{
+            rich_menu::MockRichMenuGateway, send_message::MockSendMessageGateway,
+            user_auth::MockUserAuthGateway,
+        },
+        repository::{
+            business_hours::MockBusinessHoursRepository,
+            canned_response::MockCannedResponseRepository, staff::MockStaffRepository,
+            talk_room::MockTalkRoomRepository, talk_room_note::MockTalkRoomNoteRepository,
+            user::MockUserRepository, user_tag::MockUserTagRepository,
+        },
+    };
+    use dotenv::dotenv;
+    use mockall::predicate;
+
+    #[tokio::test]
+    async fn test_authenticate_resolves_each_provider_to_the_same_user() {
+        dotenv().ok();
+        let primary_user_id = PrimaryUserId::new("primary_user_id".to_string());
+        let email = EmailAddress::new("user@example.com".to_string());
+        let email_user_profile =
+            EmailUserProfile::new(email.clone(), PasswordHash::from_plain("password").unwrap());
+        let line_login_user_profile = LineLoginUserProfile::new(
+            LineLoginId::new("U_line_login".to_string()),
+            "display_name".to_string(),
+            "".to_string(),
+            None,
+        );
+
+        /*
+         * LINEログインのIDトークンは、設定のLINEログインのチャネルIDで検証する
+         */
+        let mut user_auth_gateway = MockUserAuthGateway::new();
+        let cloned_line_login_user_profile = line_login_user_profile.clone();
+        user_auth_gateway
+            .expect_verify_line_login_id_token()
+            .with(predicate::eq(LineLoginAuthData::new(
+                "id_token".to_string(),
+                "1234567891".to_string(),
+            )))
+            .once()
+            .returning(move |_| Ok(cloned_line_login_user_profile.clone()));
+
+        let mut user_repository = MockUserRepository::new();
+        let cloned_primary_user_id = primary_user_id.clone();
+        user_repository
+            .expect_get_user()
+            .times(3)
+            .returning(move |auth_user_id| {
+                let user_profile = match auth_user_id {
+                    AuthUserId::Email(_) => UserProfile::Email(email_user_profile.clone()),
+                    AuthUserId::LineLogin(_) => {
+                        UserProfile::LineLogin(line_login_user_profile.clone())
+                    }
+                    AuthUserId::Line(_) => unreachable!(),
+                };
+                Ok(User::new(cloned_primary_user_id.clone(), user_profile))
+            });
+
+        let modules = TestModules::new(
+            user_auth_gateway,
+            user_repository,
+            MockTalkRoomRepository::new(),
+            MockSendMessageGateway::new(),
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            MockStaffRepository::new(),
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+
+        let user = modules
+            .user_identity_usecase()
+            .authenticate(
+                UserIdentityRequest::Email(EmailIdentityRequest {
+                    email: email.0.clone(),
+                    password: "password".to_string(),
+                })
+                .into(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(user.id, primary_user_id);
+
+        let user = modules
+            .user_identity_usecase()
+            .authenticate(
+                UserIdentityRequest::LineLogin(LineLoginIdentityRequest {
+                    id_token: "id_token".to_string(),
+                })
+                .into(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(user.id, primary_user_id);
+
+        /*
+         * パスワードが違うときは認証エラーになる
+         */
+        let err = modules
+            .user_identity_usecase()
+            .authenticate(
+                UserIdentityRequest::Email(EmailIdentityRequest {
+                    email: email.0,
+                    password: "wrong".to_string(),
+                })
+                .into(),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(status_code_from_error(&err), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_merge_users_conflicts_when_both_have_talk_rooms() {
+        dotenv().ok();
+        let into = PrimaryUserId::new("into_primary_user_id".to_string());
+        let from = PrimaryUserId::new("from_primary_user_id".to_string());
+
+        /*
+         * ユーザーの統合とtalk_roomの付け替えは、talk_roomのリポジトリが1回で行う
+         * 両方にtalk_roomがあるときは、リポジトリがConflictを返す
+         */
+        let mut talk_room_repository = MockTalkRoomRepository::new();
+        talk_room_repository
+            .expect_merge_users()
+            .with(predicate::eq(into.clone()), predicate::eq(from.clone()))
+            .once()
+            .returning(|into, _| {
+                Err(RepositoryError::Conflict(
+                    "talk_rooms".to_string(),
+                    "primary_user_id".to_string(),
+                    into.value().to_string(),
+                ))
+            });
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            talk_room_repository,
+            MockSendMessageGateway::new(),
+            MockRichMenuGateway::new(),
+            MockUserTagRepository::new(),
+            MockStaffRepository::new(),
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let err = modules
+            .user_identity_usecase()
+            .merge_users(into, from)
+            .await
+            .unwrap_err();
+
+        assert_eq!(status_code_from_error(&err), StatusCode::CONFLICT);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::context::staff_auth::AuthenticatedStaff;
+use crate::model::user_tag::UserTagsRequest;
+use crate::module::ModulesExt;
+use application::usecase::UseCaseError;
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use domain::model::staff::StaffPermission;
+use domain::repository::RepositoryError;
+use std::sync::Arc;
+use tracing::error;
+
+/// スタッフがユーザーのタグを付け外しし、リッチメニューをリンクし直す
+#[tracing::instrument(skip(modules, staff))]
+pub async fn change_user_tags_handler<M: ModulesExt>(
+    Extension(modules): Extension<Arc<M>>,
+    Extension(staff): Extension<AuthenticatedStaff>,
+    Path(primary_user_id): Path<String>,
+    Json(request): Json<UserTagsRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    staff.require(StaffPermission::ManageUsers)?;
+    let user_event = request.into_user_event(primary_user_id).map_err(|err| {
+        error!("Invalid user tags: {:?}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    modules
+        .rich_menu_assignment_usecase()
+        .handle_user_event(user_event)
+        .await
+        .map_err(|err| {
+            if let UseCaseError::Repository(RepositoryError::NotFound(_, _)) = err {
+                return StatusCode::NOT_FOUND;
+            }
+            error!("Failed to change user tags: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::module::test::TestModules;
+    use domain::{
+        gateway::{
+            rich_menu::MockRichMenuGateway, send_message::MockSendMessageGateway,
+            user_auth::MockUserAuthGateway,
+        },
+        model::{
+            primary_user_id::PrimaryUserId,
+            rich_menu::{RichMenuAlias, RichMenuAliasId, RichMenuId},
+            user_auth::LineId,
+            user_tag::{LineUserTags, UserTag},
+        },
+        repository::{
+            business_hours::MockBusinessHoursRepository,
+            canned_response::MockCannedResponseRepository, staff::MockStaffRepository,
+            talk_room::MockTalkRoomRepository, talk_room_note::MockTalkRoomNoteRepository,
+            user::MockUserRepository, user_tag::MockUserTagRepository,
+        },
+    };
+    use dotenv::dotenv;
+    use mockall::predicate;
+
+    #[tokio::test]
+    async fn test_change_user_tags_links_rich_menu_by_rule() {
+        dotenv().ok();
+        let primary_user_id = PrimaryUserId::new("primary_user_id".to_string());
+        let line_id = LineId::new("U00000000000000000000000000000000".to_string());
+        let request: UserTagsRequest = serde_json::from_str(
+            r#"{ "addedTags": ["has_appointment"], "removedTags": ["new_follower"] }"#,
+        )
+        .unwrap();
+        let user_event = request
+            .into_user_event(primary_user_id.value().to_string())
+            .unwrap();
+
+        /*
+         * タグを付け外ししたあと、タグを取得し直してルールを評価する
+         */
+        let mut user_tag_repository = MockUserTagRepository::new();
+        user_tag_repository
+            .expect_add_user_tags()
+            .with(
+                predicate::eq(primary_user_id.clone()),
+                predicate::eq(vec![UserTag::HasAppointment]),
+            )
+            .once()
+            .returning(|_, _| Ok(()));
+        user_tag_repository
+            .expect_remove_user_tags()
+            .with(
+                predicate::eq(primary_user_id.clone()),
+                predicate::eq(vec![UserTag::NewFollower]),
+            )
+            .once()
+            .returning(|_, _| Ok(()));
+        let line_user_tags = LineUserTags::new(
+            primary_user_id.clone(),
+            line_id.clone(),
+            vec![
+                UserTag::HasAppointment,
+                UserTag::MedicationReminderSubscriber,
+            ],
+        );
+        user_tag_repository
+            .expect_get_line_user_tags()
+            .with(predicate::eq(primary_user_id))
+            .once()
+            .returning(move |_| Ok(line_user_tags.clone()));
+
+        /*
+         * 優先度の高いhas-appointmentのリッチメニューがリンクされる
+         */
+        let mut rich_menu_gateway = MockRichMenuGateway::new();
+        let alias_id = RichMenuAliasId::new("has-appointment".to_string());
+        let rich_menu_id = RichMenuId::new("richmenu-0001".to_string());
+        let alias = RichMenuAlias::new(alias_id.clone(), rich_menu_id.clone());
+        rich_menu_gateway
+            .expect_get_rich_menu_alias()
+            .with(predicate::always(), predicate::eq(alias_id))
+            .once()
+            .returning(move |_, _| Ok(Some(alias.clone())));
+        rich_menu_gateway
+            .expect_link_rich_menu_to_user()
+            .with(
+                predicate::always(),
+                predicate::eq(line_id),
+                predicate::eq(rich_menu_id),
+            )
+            .once()
+            .returning(|_, _, _| Ok(()));
+
+        let modules = TestModules::new(
+            MockUserAuthGateway::new(),
+            MockUserRepository::new(),
+            MockTalkRoomRepository::new(),
+            MockSendMessageGateway::new(),
+            rich_menu_gateway,
+            user_tag_repository,
+            MockStaffRepository::new(),
+            MockTalkRoomNoteRepository::new(),
+            MockCannedResponseRepository::new(),
+            MockBusinessHoursRepository::new(),
+        )
+        .await;
+        let result = modules
+            .rich_menu_assignment_usecase()
+            .handle_user_event(user_event)
+            .await;
+
+        assert!(result.is_ok());
+    }
+}
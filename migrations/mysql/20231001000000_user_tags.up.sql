@@ -0,0 +1,9 @@
+-- tag: UserTagの値。タグが増えてもマイグレーションが不要なように文字列で持つ
+CREATE TABLE user_tags (
+  primary_user_id VARCHAR(36) NOT NULL,
+  tag VARCHAR(64) NOT NULL,
+  created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (primary_user_id, tag)
+);
+
+CREATE INDEX idx_user_tags_tag ON user_tags(tag);
@@ -0,0 +1,4 @@
+DROP INDEX idx_line_users_updated_at ON line_users;
+ALTER TABLE line_users
+  DROP COLUMN status_message,
+  DROP COLUMN language;
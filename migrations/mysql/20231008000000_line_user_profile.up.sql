@@ -0,0 +1,7 @@
+-- status_message: statusMessageの制限の記述は見つけられなかったので、長めにとっておく
+-- language: BCP 47の言語タグ（https://developers.line.biz/ja/reference/messaging-api/#get-profile）
+ALTER TABLE line_users
+  ADD COLUMN status_message VARCHAR(1024) NULL AFTER picture_url,
+  ADD COLUMN language VARCHAR(35) NULL AFTER status_message;
+
+CREATE INDEX idx_line_users_updated_at ON line_users(updated_at);
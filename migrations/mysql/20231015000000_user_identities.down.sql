@@ -0,0 +1,2 @@
+DROP TABLE email_users;
+DROP TABLE line_login_users;
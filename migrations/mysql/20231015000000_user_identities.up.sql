@@ -0,0 +1,20 @@
+-- email: メールアドレスは小文字に正規化して保存する
+-- password_hash: argon2のPHC文字列
+CREATE TABLE email_users (
+  email VARCHAR(255) NOT NULL PRIMARY KEY,
+  primary_user_id VARCHAR(36) NOT NULL UNIQUE,
+  password_hash VARCHAR(255) NOT NULL,
+  created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- line_login_id: IDトークンのsub。LINEのユーザーIDと同じ形式
+CREATE TABLE line_login_users (
+  line_login_id VARCHAR(36) NOT NULL PRIMARY KEY,
+  primary_user_id VARCHAR(36) NOT NULL UNIQUE,
+  display_name VARCHAR(255) NOT NULL DEFAULT '',
+  picture_url VARCHAR(2048) NOT NULL DEFAULT '',
+  email VARCHAR(255) NULL,
+  created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+) CHARACTER SET utf8mb4;
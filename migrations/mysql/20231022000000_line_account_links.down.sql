@@ -0,0 +1,2 @@
+DROP TABLE line_account_link_nonces;
+DROP TABLE line_account_links;
@@ -0,0 +1,16 @@
+-- nonce: 会員システムのログイン後に発行し、accountLinkイベントで照合したら削除する
+-- external_member_id: 会員システムのアカウントのID
+CREATE TABLE line_account_link_nonces (
+  nonce VARCHAR(255) NOT NULL PRIMARY KEY,
+  external_member_id VARCHAR(255) NOT NULL,
+  expires_at DATETIME NOT NULL,
+  created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 1つの会員システムのアカウントは、1人のユーザーにしか連携できない
+CREATE TABLE line_account_links (
+  primary_user_id VARCHAR(36) NOT NULL PRIMARY KEY,
+  external_member_id VARCHAR(255) NOT NULL UNIQUE,
+  created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,13 @@
+-- id: メッセージの送信者(sender)のidとしてFirestoreにも保存する
+-- role: admin, pharmacist, viewer
+-- password_hash: argon2のPHC文字列
+CREATE TABLE staffs (
+  id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+  email VARCHAR(255) NOT NULL UNIQUE,
+  name VARCHAR(255) NOT NULL,
+  picture_url VARCHAR(2048) NOT NULL DEFAULT '',
+  role VARCHAR(32) NOT NULL,
+  password_hash VARCHAR(255) NOT NULL,
+  created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+) CHARACTER SET utf8mb4;
@@ -0,0 +1 @@
+DROP TABLE canned_responses;
@@ -0,0 +1,21 @@
+DROP TABLE talk_room_changes;
+DROP TABLE messages;
+DROP INDEX idx_talk_rooms_pinned_sort_time ON talk_rooms;
+ALTER TABLE talk_rooms
+  DROP COLUMN display_name,
+  DROP COLUMN rsvp,
+  DROP COLUMN pinned,
+  DROP COLUMN follow,
+  DROP COLUMN latest_message,
+  DROP COLUMN latest_messaged_at,
+  DROP COLUMN sort_time,
+  DROP COLUMN assignee,
+  DROP COLUMN status,
+  DROP COLUMN mode,
+  DROP COLUMN unread,
+  DROP COLUMN nickname,
+  DROP COLUMN user_message_count,
+  DROP COLUMN read_message_counts,
+  DROP COLUMN away_message_off_hours_since,
+  DROP COLUMN updated_at,
+  MODIFY COLUMN created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP;
@@ -0,0 +1,51 @@
+-- TALK_ROOM_REPOSITORY=mysqlのときに、firestoreのtalkRoomCardsの代わりに使う
+-- firestoreを使うときは書き込まないので、latest_messageなどはNULLのままになる
+-- latest_message: firestoreのlatestMessageと同じ形のJSON
+-- status: open, pending, resolved
+-- mode: bot, human, botOutsideBusinessHours
+-- read_message_counts: スタッフのIDをキーにした、最後に既読にしたときのuser_message_count
+-- 並び順とカーソルに使うので、日時はマイクロ秒まで持つ
+ALTER TABLE talk_rooms
+  MODIFY COLUMN created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+  ADD COLUMN display_name VARCHAR(255) NOT NULL DEFAULT '' AFTER primary_user_id,
+  ADD COLUMN rsvp BOOLEAN NOT NULL DEFAULT FALSE AFTER display_name,
+  ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT FALSE AFTER rsvp,
+  ADD COLUMN follow BOOLEAN NOT NULL DEFAULT TRUE AFTER pinned,
+  ADD COLUMN latest_message JSON NULL AFTER follow,
+  ADD COLUMN latest_messaged_at DATETIME(6) NULL AFTER latest_message,
+  ADD COLUMN sort_time DATETIME(6) NULL AFTER latest_messaged_at,
+  ADD COLUMN assignee BIGINT NULL AFTER sort_time,
+  ADD COLUMN status VARCHAR(16) NOT NULL DEFAULT 'open' AFTER assignee,
+  ADD COLUMN mode VARCHAR(32) NOT NULL DEFAULT 'bot' AFTER status,
+  ADD COLUMN unread BOOLEAN NOT NULL DEFAULT FALSE AFTER mode,
+  ADD COLUMN nickname VARCHAR(255) NULL AFTER unread,
+  ADD COLUMN user_message_count BIGINT NOT NULL DEFAULT 0 AFTER nickname,
+  ADD COLUMN read_message_counts JSON NULL AFTER user_message_count,
+  ADD COLUMN away_message_off_hours_since DATETIME(6) NULL AFTER read_message_counts,
+  ADD COLUMN updated_at DATETIME(6) NULL AFTER created_at;
+
+CREATE INDEX idx_talk_rooms_pinned_sort_time ON talk_rooms(pinned, sort_time, document_id);
+
+-- document_id: talkRoomごとに一意。firestoreのサブコレクションのドキュメントIDと同じ
+-- event, send_message, system_event: メッセージの種類に合わせて、firestoreと同じ形のJSONをどれか1つに入れる
+CREATE TABLE messages (
+  talk_room_document_id VARCHAR(36) NOT NULL,
+  document_id VARCHAR(36) NOT NULL,
+  event JSON NULL,
+  send_message JSON NULL,
+  system_event JSON NULL,
+  created_at DATETIME(6) NOT NULL,
+  PRIMARY KEY (talk_room_document_id, document_id)
+) CHARACTER SET utf8mb4;
+
+CREATE INDEX idx_messages_talk_room_document_id_created_at ON messages(talk_room_document_id, created_at, document_id);
+CREATE INDEX idx_messages_created_at ON messages(created_at);
+
+-- listen_changesで変更を送り直せるように、talk_roomsとmessagesの変更を順番に記録する
+-- message_document_id: NULLのときはtalk_roomsの変更、それ以外はメッセージの追加
+CREATE TABLE talk_room_changes (
+  id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+  talk_room_document_id VARCHAR(36) NOT NULL,
+  message_document_id VARCHAR(36) NULL,
+  created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6)
+);
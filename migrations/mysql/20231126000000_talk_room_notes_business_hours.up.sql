@@ -0,0 +1,25 @@
+-- TALK_ROOM_REPOSITORY=mysqlのときに、firestoreのtalkRoomsのサブコレクションnotesの代わりに使う
+-- document_id: talkRoomごとに一意。firestoreのサブコレクションのドキュメントIDと同じ
+CREATE TABLE talk_room_notes (
+  talk_room_document_id VARCHAR(36) NOT NULL,
+  document_id VARCHAR(36) NOT NULL,
+  author_staff_id BIGINT NOT NULL,
+  author_name VARCHAR(255) NOT NULL,
+  body TEXT NOT NULL,
+  created_at DATETIME(6) NOT NULL,
+  updated_at DATETIME(6) NOT NULL,
+  PRIMARY KEY (talk_room_document_id, document_id)
+) CHARACTER SET utf8mb4;
+
+CREATE INDEX idx_talk_room_notes_talk_room_document_id_created_at ON talk_room_notes(talk_room_document_id, created_at);
+
+-- TALK_ROOM_REPOSITORY=mysqlのときに、firestoreのbusinessHoursCalendarsの代わりに使う
+-- weekly_hours, exceptions: firestoreと同じ形のJSON
+CREATE TABLE business_hours_calendars (
+  channel_id VARCHAR(64) NOT NULL PRIMARY KEY,
+  time_zone VARCHAR(64) NOT NULL,
+  weekly_hours JSON NOT NULL,
+  exceptions JSON NOT NULL,
+  away_message TEXT NULL,
+  updated_at DATETIME(6) NOT NULL
+) CHARACTER SET utf8mb4;
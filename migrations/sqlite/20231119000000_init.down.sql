@@ -0,0 +1,12 @@
+DROP TABLE canned_responses;
+DROP TABLE staffs;
+DROP TABLE line_account_links;
+DROP TABLE line_account_link_nonces;
+DROP TABLE line_login_users;
+DROP TABLE email_users;
+DROP TABLE user_tags;
+DROP TABLE talk_room_changes;
+DROP TABLE messages;
+DROP TABLE talk_rooms;
+DROP TABLE line_users;
+DROP TABLE primary_users;
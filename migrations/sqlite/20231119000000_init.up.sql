@@ -0,0 +1,125 @@
+-- migrations/mysqlの20231112000000_talk_room_cardsまでをまとめたもの
+-- MySQLと同じく、日時はローカルタイムの文字列で持つ
+-- JSONはTEXTに入れる
+CREATE TABLE primary_users (
+  id VARCHAR(36) NOT NULL PRIMARY KEY,
+  created_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE TABLE line_users (
+  line_id VARCHAR(36) NOT NULL PRIMARY KEY,
+  primary_user_id VARCHAR(36) NOT NULL UNIQUE,
+  display_name VARCHAR(255) NOT NULL DEFAULT '',
+  picture_url VARCHAR(2048) NOT NULL DEFAULT '',
+  status_message VARCHAR(1024) NULL,
+  language VARCHAR(35) NULL,
+  created_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime')),
+  updated_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE INDEX idx_line_users_updated_at ON line_users(updated_at);
+
+CREATE TABLE talk_rooms (
+  document_id VARCHAR(36) NOT NULL PRIMARY KEY,
+  primary_user_id VARCHAR(36) NOT NULL UNIQUE,
+  display_name VARCHAR(255) NOT NULL DEFAULT '',
+  rsvp BOOLEAN NOT NULL DEFAULT FALSE,
+  pinned BOOLEAN NOT NULL DEFAULT FALSE,
+  follow BOOLEAN NOT NULL DEFAULT TRUE,
+  latest_message TEXT NULL,
+  latest_messaged_at DATETIME NULL,
+  sort_time DATETIME NULL,
+  assignee BIGINT NULL,
+  status VARCHAR(16) NOT NULL DEFAULT 'open',
+  mode VARCHAR(32) NOT NULL DEFAULT 'bot',
+  unread BOOLEAN NOT NULL DEFAULT FALSE,
+  nickname VARCHAR(255) NULL,
+  user_message_count BIGINT NOT NULL DEFAULT 0,
+  read_message_counts TEXT NULL,
+  away_message_off_hours_since DATETIME NULL,
+  created_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime')),
+  updated_at DATETIME NULL
+);
+
+CREATE INDEX idx_talk_rooms_pinned_sort_time ON talk_rooms(pinned, sort_time, document_id);
+
+CREATE TABLE messages (
+  talk_room_document_id VARCHAR(36) NOT NULL,
+  document_id VARCHAR(36) NOT NULL,
+  event TEXT NULL,
+  send_message TEXT NULL,
+  system_event TEXT NULL,
+  created_at DATETIME NOT NULL,
+  PRIMARY KEY (talk_room_document_id, document_id)
+);
+
+CREATE INDEX idx_messages_talk_room_document_id_created_at ON messages(talk_room_document_id, created_at, document_id);
+CREATE INDEX idx_messages_created_at ON messages(created_at);
+
+CREATE TABLE talk_room_changes (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  talk_room_document_id VARCHAR(36) NOT NULL,
+  message_document_id VARCHAR(36) NULL,
+  created_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE TABLE user_tags (
+  primary_user_id VARCHAR(36) NOT NULL,
+  tag VARCHAR(64) NOT NULL,
+  created_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime')),
+  PRIMARY KEY (primary_user_id, tag)
+);
+
+CREATE INDEX idx_user_tags_tag ON user_tags(tag);
+
+CREATE TABLE email_users (
+  email VARCHAR(255) NOT NULL PRIMARY KEY,
+  primary_user_id VARCHAR(36) NOT NULL UNIQUE,
+  password_hash VARCHAR(255) NOT NULL,
+  created_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime')),
+  updated_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE TABLE line_login_users (
+  line_login_id VARCHAR(36) NOT NULL PRIMARY KEY,
+  primary_user_id VARCHAR(36) NOT NULL UNIQUE,
+  display_name VARCHAR(255) NOT NULL DEFAULT '',
+  picture_url VARCHAR(2048) NOT NULL DEFAULT '',
+  email VARCHAR(255) NULL,
+  created_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime')),
+  updated_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE TABLE line_account_link_nonces (
+  nonce VARCHAR(255) NOT NULL PRIMARY KEY,
+  external_member_id VARCHAR(255) NOT NULL,
+  expires_at DATETIME NOT NULL,
+  created_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE TABLE line_account_links (
+  primary_user_id VARCHAR(36) NOT NULL PRIMARY KEY,
+  external_member_id VARCHAR(255) NOT NULL UNIQUE,
+  created_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime')),
+  updated_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE TABLE staffs (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  email VARCHAR(255) NOT NULL UNIQUE,
+  name VARCHAR(255) NOT NULL,
+  picture_url VARCHAR(2048) NOT NULL DEFAULT '',
+  role VARCHAR(32) NOT NULL,
+  password_hash VARCHAR(255) NOT NULL,
+  created_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime')),
+  updated_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE TABLE canned_responses (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  title VARCHAR(255) NOT NULL,
+  body TEXT NOT NULL,
+  created_by BIGINT NOT NULL,
+  created_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime')),
+  updated_at DATETIME NOT NULL DEFAULT (datetime('now', 'localtime'))
+);
@@ -0,0 +1,2 @@
+DROP TABLE business_hours_calendars;
+DROP TABLE talk_room_notes;
@@ -0,0 +1,21 @@
+CREATE TABLE talk_room_notes (
+  talk_room_document_id VARCHAR(36) NOT NULL,
+  document_id VARCHAR(36) NOT NULL,
+  author_staff_id BIGINT NOT NULL,
+  author_name VARCHAR(255) NOT NULL,
+  body TEXT NOT NULL,
+  created_at DATETIME NOT NULL,
+  updated_at DATETIME NOT NULL,
+  PRIMARY KEY (talk_room_document_id, document_id)
+);
+
+CREATE INDEX idx_talk_room_notes_talk_room_document_id_created_at ON talk_room_notes(talk_room_document_id, created_at);
+
+CREATE TABLE business_hours_calendars (
+  channel_id VARCHAR(64) NOT NULL PRIMARY KEY,
+  time_zone VARCHAR(64) NOT NULL,
+  weekly_hours TEXT NOT NULL,
+  exceptions TEXT NOT NULL,
+  away_message TEXT NULL,
+  updated_at DATETIME NOT NULL
+);